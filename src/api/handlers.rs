@@ -1,26 +1,177 @@
 use axum::{
-    extract::{State, Json, Path},
+    extract::{DefaultBodyLimit, State, Json, Path, Query, Request},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Router, http::StatusCode,
-    http::Method,
+    http::{HeaderMap, Method},
 };
 use tower_http::cors::{CorsLayer, Any};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use crate::consensus::blockchain::{Blockchain, BlockchainStats};
-use crate::core::transaction::Transaction;
+use crate::consensus::blockchain::{mine_until_stale, run_blocking, Blockchain, BlockchainStats, StatsDelta, SupplyInfo};
+use tokio_util::sync::CancellationToken;
+use crate::core::transaction::{Transaction, is_valid_address};
+use crate::core::TxHash;
 use crate::crypto::wallet::QuantumWallet;
-use crate::consensus::mempool::NodeMetrics;
-use crate::core::block::Block;
-use std::sync::atomic::{AtomicBool, Ordering};
+use crate::consensus::mempool::{MempoolInfo, NodeMetrics};
+use crate::consensus::replica::ChainReplica;
+use crate::core::block::{Block, Target};
+use crate::network::peer::PeerDirection;
+use crate::rpc::types::VersionInfo;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 
 /// API state
 pub struct ApiState {
     pub blockchain: Arc<RwLock<Blockchain>>,
     pub metrics: Option<Arc<crate::consensus::mempool::MetricsCollector>>,
     pub network: Option<Arc<crate::network::Network>>,
-    pub mining_active: Arc<AtomicBool>,
+    pub mining: MiningControl,
+    pub replica: Option<ChainReplica>, // Read-replica mode: stats/supply/balance skip the blockchain lock
+    pub idempotency: IdempotencyCache,
+}
+
+/// Request body cap for the API and RPC routers; larger bodies get 413 Payload Too Large
+pub const DEFAULT_MAX_REQUEST_BODY_BYTES: usize = 512 * 1024;
+
+/// Header clients set on POST /api/transaction so retries don't create a second transaction
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+/// How long a submitted key keeps returning the original tx hash
+const IDEMPOTENCY_KEY_TTL: Duration = Duration::from_secs(10 * 60);
+/// Bound on remembered keys; when full the oldest entry is evicted
+const MAX_IDEMPOTENCY_KEYS: usize = 10_000;
+
+/// What a request is asking for; a key reused with a different request is rejected
+#[derive(Clone, PartialEq)]
+struct SubmissionFingerprint {
+    wallet_file: String,
+    recipient: String,
+    amount_microunits: u64,
+    memo: Option<Vec<u8>>,
+}
+
+struct IdempotencyEntry {
+    fingerprint: SubmissionFingerprint,
+    tx_hash: Option<TxHash>, // None while the first request is still in flight
+    created: Instant,
+}
+
+enum IdempotencyClaim {
+    New,
+    InFlight,
+    Replay(TxHash),
+    Mismatch,
+}
+
+/// Continuous mining started over the API: the running task's token, cancelled to stop it
+/// (None while stopped). Cancelling also abandons the block attempt in progress
+#[derive(Default)]
+pub struct MiningControl {
+    token: parking_lot::Mutex<Option<CancellationToken>>,
+}
+
+impl MiningControl {
+    /// Token for a new mining task, or None if one is already running
+    fn start(&self) -> Option<CancellationToken> {
+        let mut token = self.token.lock();
+        if token.is_some() {
+            return None;
+        }
+        let started = CancellationToken::new();
+        *token = Some(started.clone());
+        Some(started)
+    }
+
+    fn stop(&self) {
+        if let Some(token) = self.token.lock().take() {
+            token.cancel();
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.token.lock().is_some()
+    }
+}
+
+/// Idempotency keys seen by POST /api/transaction, remembered for IDEMPOTENCY_KEY_TTL.
+/// Only successful submissions are remembered; a failed one frees its key for a retry
+#[derive(Default)]
+pub struct IdempotencyCache {
+    entries: parking_lot::Mutex<HashMap<String, IdempotencyEntry>>,
+}
+
+impl IdempotencyCache {
+    fn claim(&self, key: &str, fingerprint: SubmissionFingerprint) -> IdempotencyClaim {
+        let mut entries = self.entries.lock();
+        let now = Instant::now();
+        entries.retain(|_, entry| now.duration_since(entry.created) < IDEMPOTENCY_KEY_TTL);
+
+        if let Some(entry) = entries.get(key) {
+            return match (&entry.tx_hash, entry.fingerprint == fingerprint) {
+                (_, false) => IdempotencyClaim::Mismatch,
+                (Some(tx_hash), true) => IdempotencyClaim::Replay(tx_hash.clone()),
+                (None, true) => IdempotencyClaim::InFlight,
+            };
+        }
+
+        if entries.len() >= MAX_IDEMPOTENCY_KEYS {
+            if let Some(oldest) = entries.iter().min_by_key(|(_, e)| e.created).map(|(k, _)| k.clone()) {
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(key.to_string(), IdempotencyEntry { fingerprint, tx_hash: None, created: now });
+        IdempotencyClaim::New
+    }
+
+    fn complete(&self, key: &str, tx_hash: TxHash) {
+        if let Some(entry) = self.entries.lock().get_mut(key) {
+            entry.tx_hash = Some(tx_hash);
+        }
+    }
+
+    fn release(&self, key: &str) {
+        let mut entries = self.entries.lock();
+        if entries.get(key).is_some_and(|entry| entry.tx_hash.is_none()) {
+            entries.remove(key);
+        }
+    }
+}
+
+/// Claimed key for one submission; released on drop unless completed, so an error
+/// return or a dropped request doesn't leave the key stuck in flight
+struct ClaimedKey<'a> {
+    cache: &'a IdempotencyCache,
+    key: Option<String>,
+}
+
+impl ClaimedKey<'_> {
+    fn complete(&mut self, tx_hash: &TxHash) {
+        if let Some(key) = self.key.take() {
+            self.cache.complete(&key, tx_hash.clone());
+        }
+    }
+}
+
+impl Drop for ClaimedKey<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key.take() {
+            self.cache.release(&key);
+        }
+    }
+}
+
+fn transaction_error(status: StatusCode, error: String) -> (StatusCode, Json<TransactionResponse>) {
+    (
+        status,
+        Json(TransactionResponse {
+            success: false,
+            tx_hash: None,
+            error: Some(error),
+        }),
+    )
 }
 
 /// Request to create a transaction
@@ -47,24 +198,58 @@ pub struct CreateTransactionRequest {
     pub wallet_password: String,
     pub recipient: String,
     pub amount_microunits: u64, // Amount in microunits (1 QUA = 1_000_000)
+    /// Optional payload bytes (invoice ID, payment reference), signed with the transaction
+    #[serde(default)]
+    pub memo: Option<Vec<u8>>,
 }
 
 /// Response with transaction hash
 #[derive(Serialize)]
 pub struct TransactionResponse {
     pub success: bool,
-    pub tx_hash: Option<String>,
+    pub tx_hash: Option<TxHash>,
     pub error: Option<String>,
 }
 
+/// Get software/protocol version, network and supported features
+async fn get_version(
+    State(state): State<Arc<ApiState>>,
+) -> Json<VersionInfo> {
+    let blockchain = state.blockchain.read().await;
+    Json(VersionInfo::new(blockchain.network(), blockchain.contracts_enabled()))
+}
+
 /// Get blockchain stats
 async fn get_stats(
     State(state): State<Arc<ApiState>>,
 ) -> Json<BlockchainStats> {
+    if let Some(ref replica) = state.replica {
+        return Json(replica.load().snapshot.stats.clone());
+    }
     let blockchain = state.blockchain.read().await;
     Json(blockchain.get_stats())
 }
 
+/// Get only what changed since a previously seen height (cheap polling)
+async fn get_stats_since(
+    State(state): State<Arc<ApiState>>,
+    Path(height): Path<u64>,
+) -> Json<StatsDelta> {
+    let blockchain = state.blockchain.read().await;
+    Json(blockchain.stats_since(height))
+}
+
+/// Get supply breakdown (total / circulating / locked / burned)
+async fn get_supply(
+    State(state): State<Arc<ApiState>>,
+) -> Json<SupplyInfo> {
+    if let Some(ref replica) = state.replica {
+        return Json(replica.load().supply.clone());
+    }
+    let blockchain = state.blockchain.read().await;
+    Json(blockchain.get_supply())
+}
+
 /// Get balance for an address
 #[derive(Deserialize)]
 pub struct BalanceRequest {
@@ -75,25 +260,104 @@ pub struct BalanceRequest {
 pub struct BalanceResponse {
     pub address: String,
     pub balance_microunits: u64, // Balance in microunits (1 QUA = 1_000_000)
+    pub locked_microunits: u64,  // Immature coinbase rewards, not yet spendable
+    pub unlock_height: u64,      // Height the next locked reward unlocks (0 = nothing locked)
 }
 
 async fn get_balance(
     State(state): State<Arc<ApiState>>,
     Json(req): Json<BalanceRequest>,
 ) -> Json<BalanceResponse> {
-    let blockchain = state.blockchain.read().await;
-    let balance = blockchain.get_balance(&req.address);
+    let (balance, locked, unlock_height) = match state.replica {
+        Some(ref replica) => {
+            let view = replica.load();
+            (view.balance(&req.address), view.locked_balance(&req.address), view.unlock_height(&req.address))
+        }
+        None => {
+            let blockchain = state.blockchain.read().await;
+            (
+                blockchain.get_balance(&req.address),
+                blockchain.get_locked_balance(&req.address),
+                blockchain.get_unlock_height(&req.address),
+            )
+        }
+    };
     Json(BalanceResponse {
         address: req.address,
         balance_microunits: balance,
+        locked_microunits: locked,
+        unlock_height,
     })
 }
 
+#[derive(Serialize)]
+pub struct NonceResponse {
+    pub address: String,
+    pub next_nonce: u64, // Counts pending mempool transactions, not just confirmed ones
+}
+
+/// Nonce for the next transaction from an address
+async fn get_next_nonce(
+    State(state): State<Arc<ApiState>>,
+    Path(address): Path<String>,
+) -> Json<NonceResponse> {
+    let blockchain = state.blockchain.read().await;
+    let next_nonce = blockchain.get_next_nonce(&address);
+    Json(NonceResponse { address, next_nonce })
+}
+
 /// Create and submit a transaction
 async fn create_transaction(
     State(state): State<Arc<ApiState>>,
+    headers: HeaderMap,
     Json(req): Json<CreateTransactionRequest>,
 ) -> (StatusCode, Json<TransactionResponse>) {
+    // Retries carrying the same Idempotency-Key get the original tx hash back
+    let key = match headers.get(IDEMPOTENCY_KEY_HEADER).map(|value| value.to_str()) {
+        None => None,
+        Some(Ok(key)) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => Some(key.to_string()),
+        Some(_) => {
+            return transaction_error(
+                StatusCode::BAD_REQUEST,
+                format!("Idempotency-Key must be 1-{} visible ASCII characters", MAX_IDEMPOTENCY_KEY_LEN),
+            );
+        }
+    };
+    let mut claimed = ClaimedKey { cache: &state.idempotency, key: None };
+    if let Some(key) = key {
+        let fingerprint = SubmissionFingerprint {
+            wallet_file: req.wallet_file.clone(),
+            recipient: req.recipient.clone(),
+            amount_microunits: req.amount_microunits,
+            memo: req.memo.clone(),
+        };
+        match state.idempotency.claim(&key, fingerprint) {
+            IdempotencyClaim::New => claimed.key = Some(key),
+            IdempotencyClaim::Replay(tx_hash) => {
+                return (
+                    StatusCode::OK,
+                    Json(TransactionResponse {
+                        success: true,
+                        tx_hash: Some(tx_hash),
+                        error: None,
+                    }),
+                );
+            }
+            IdempotencyClaim::InFlight => {
+                return transaction_error(
+                    StatusCode::CONFLICT,
+                    "A request with this Idempotency-Key is still being processed".to_string(),
+                );
+            }
+            IdempotencyClaim::Mismatch => {
+                return transaction_error(
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    "Idempotency-Key was already used for a different request".to_string(),
+                );
+            }
+        }
+    }
+
     // Load quantum-safe wallet
     let wallet = match QuantumWallet::load_quantum_safe(&req.wallet_file, &req.wallet_password) {
         Ok(w) => w,
@@ -109,35 +373,35 @@ async fn create_transaction(
         }
     };
 
-    // Get current nonce
+    // Reserve nonce, sign and submit atomically so concurrent requests from
+    // the same sender can't pick the same nonce
     let blockchain = state.blockchain.read().await;
-    let current_nonce = blockchain.get_account_state_mut().get_nonce(&wallet.address);
-    let next_nonce = current_nonce + 1;
-    drop(blockchain);
+    let submit_result = {
+        let reservation = blockchain.reserve_next_nonce(&wallet.address);
 
-    // Create transaction with microunits
-    let mut tx = Transaction::new(
-        wallet.address.clone(),
-        req.recipient,
-        req.amount_microunits,
-        chrono::Utc::now().timestamp(),
-    );
-    tx.nonce = next_nonce;
-
-    // Sign transaction
-    let signature = wallet.keypair.sign_transaction_data(&tx.get_signing_data());
-    
-    tx.signature = signature;
-    tx.public_key = wallet.keypair.public_key.clone();
+        // Create transaction with microunits
+        let mut tx = Transaction::new(
+            wallet.address.clone(),
+            req.recipient,
+            req.amount_microunits,
+            chrono::Utc::now().timestamp(),
+        );
+        tx.nonce = reservation.nonce;
+        tx.memo = req.memo;
 
-    // Submit to blockchain
-    let blockchain = state.blockchain.write().await;
-    match blockchain.add_transaction(tx.clone()) {
-        Ok(_) => {
+        // Sign with a fee at the recent fee rate for the signed size (a memo adds weight)
+        wallet.sign_transaction_with_fee(&mut tx, &blockchain.estimate_fee_rate());
+
+        blockchain.add_transaction(tx.clone()).map(|_| tx)
+    };
+    drop(blockchain);
+
+    match submit_result {
+        Ok(tx) => {
             let tx_hash = tx.hash();
+            claimed.complete(&tx_hash);
             
             // Broadcast to network if available
-            drop(blockchain);
             if let Some(ref network) = state.network {
                 network.broadcast_transaction(tx).await;
             }
@@ -181,6 +445,17 @@ async fn mine_block(
     State(state): State<Arc<ApiState>>,
     Json(req): Json<MineRequest>,
 ) -> (StatusCode, Json<MineResponse>) {
+    if !is_valid_address(&req.miner_address) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(MineResponse {
+                success: false,
+                block_index: None,
+                error: Some(format!("Invalid miner address: {}", req.miner_address)),
+            }),
+        );
+    }
+
     // 1. Create template (Lock held briefly)
     let template_res = state.blockchain.read().await.create_block_template(req.miner_address.clone());
 
@@ -194,9 +469,10 @@ async fn mine_block(
             }).await;
 
             if let Ok(mined_block) = mined_block_res {
-                 // 3. Submit (Lock held briefly to commit)
-                 let blockchain = state.blockchain.read().await;
-                 match blockchain.add_network_block(mined_block.clone()) {
+                 // 3. Submit (Lock held briefly to commit, storage writes off the runtime)
+                 let guard = Arc::clone(&state.blockchain).read_owned().await;
+                 let submitted = mined_block.clone();
+                 match run_blocking(guard, move |bc| bc.add_network_block(submitted)).await {
                      Ok(_) => {
                          let index = mined_block.index;
                          
@@ -204,7 +480,6 @@ async fn mine_block(
                          if let Some(ref network) = state.network {
                              network.broadcast_block(mined_block).await;
                          }
-                         drop(blockchain);
 
                          (
                              StatusCode::OK,
@@ -255,21 +530,25 @@ async fn start_continuous_mining(
     State(state): State<Arc<ApiState>>,
     Json(req): Json<MineRequest>,
 ) -> (StatusCode, Json<serde_json::Value>) {
-    if state.mining_active.load(Ordering::Relaxed) {
+    if !is_valid_address(&req.miner_address) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "status": "error", "message": format!("Invalid miner address: {}", req.miner_address) }))
+        );
+    }
+
+    let Some(stop) = state.mining.start() else {
         return (
             StatusCode::OK,
             Json(serde_json::json!({ "status": "already_running", "message": "Mining already active" }))
         );
-    }
-    
-    state.mining_active.store(true, Ordering::Relaxed);
+    };
     let blockchain = state.blockchain.clone();
     let network = state.network.clone();
-    let mining_active = state.mining_active.clone();
     let miner_address = req.miner_address.clone();
     
     tokio::spawn(async move {
-        while mining_active.load(Ordering::Relaxed) {
+        while !stop.is_cancelled() {
             // Check if there are transactions to mine
             let has_txs = {
                 let bc = blockchain.read().await;
@@ -282,27 +561,40 @@ async fn start_continuous_mining(
                 tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
                 continue;
             }
+
+            // Too few peers to trust our tip (min_peers_to_mine)
+            if let Some(ref net) = network {
+                if !net.ready_to_mine().await {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+            }
             
             // 1. Create template
-            let template_res = blockchain.read().await.create_block_template(miner_address.clone());
+            let (template_res, tip) = {
+                let bc = blockchain.read().await;
+                (bc.create_block_template(miner_address.clone()), bc.subscribe_tip())
+            };
             
             match template_res {
-                Ok(mut block) => {
-                    // 2. Mine (NO LOCK)
-                    let mined_block_res = tokio::task::spawn_blocking(move || {
-                        block.mine();
-                        block
-                    }).await;
+                Ok(block) => {
+                    // 2. Mine (NO LOCK), abandoning the attempt if a new tip arrives
+                    let mined_block_res = mine_until_stale(block, tip, &stop).await;
                     
                     if let Ok(mined_block) = mined_block_res {
                         // Check if still active
-                        if !mining_active.load(Ordering::Relaxed) {
+                        if stop.is_cancelled() {
                             break;
                         }
+                        let Some(mined_block) = mined_block else {
+                            tracing::info!("Chain tip moved while mining, restarting on the new tip");
+                            continue;
+                        };
                         
                         // 3. Submit
-                        let bc = blockchain.read().await;
-                        match bc.add_network_block(mined_block.clone()) {
+                        let bc = Arc::clone(&blockchain).read_owned().await;
+                        let submitted = mined_block.clone();
+                        match run_blocking(bc, move |bc| bc.add_network_block(submitted)).await {
                             Ok(_) => {
                                 if let Some(ref net) = network {
                                     net.broadcast_block(mined_block).await;
@@ -336,7 +628,7 @@ async fn start_continuous_mining(
 async fn stop_continuous_mining(
     State(state): State<Arc<ApiState>>,
 ) -> Json<serde_json::Value> {
-    state.mining_active.store(false, Ordering::Relaxed);
+    state.mining.stop();
     Json(serde_json::json!({ "status": "stopped", "message": "Continuous mining stopped" }))
 }
 
@@ -350,7 +642,7 @@ async fn get_mining_status(
     State(state): State<Arc<ApiState>>,
 ) -> Json<MiningStatus> {
     Json(MiningStatus {
-        active: state.mining_active.load(Ordering::Relaxed),
+        active: state.mining.is_active(),
     })
 }
 
@@ -381,7 +673,11 @@ pub struct PeerInfoResponse {
     pub address: String,
     pub node_id: String,
     pub height: u64,
-    pub connected_for: i64,
+    pub connected_for: u64, // seconds
+    pub direction: PeerDirection,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub encrypted: bool,
 }
 
 async fn get_peers(
@@ -389,13 +685,18 @@ async fn get_peers(
 ) -> Json<PeersResponse> {
     if let Some(ref network) = state.network {
         let peers_info = network.get_peers_info().await;
+        let now = chrono::Utc::now().timestamp();
         let peers: Vec<PeerInfoResponse> = peers_info
             .into_iter()
             .map(|p| PeerInfoResponse {
                 address: p.address.to_string(),
+                connected_for: p.connected_for(now),
                 node_id: p.node_id,
                 height: p.height,
-                connected_for: chrono::Utc::now().timestamp() - p.connected_at,
+                direction: p.direction,
+                bytes_sent: p.bytes_sent,
+                bytes_received: p.bytes_received,
+                encrypted: p.remote_static_key.is_some(),
             })
             .collect();
         
@@ -422,38 +723,218 @@ async fn get_metrics(
     }
 }
 
+/// Transaction as returned by the API: all fields plus the canonical hash and a
+/// readable type name, so clients never recompute the hash themselves
+#[derive(Serialize)]
+pub struct TransactionView {
+    pub hash: TxHash,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    #[serde(flatten)]
+    pub transaction: Transaction,
+}
+
+impl From<Transaction> for TransactionView {
+    fn from(transaction: Transaction) -> Self {
+        Self {
+            hash: transaction.hash(),
+            kind: transaction.tx_type.name(),
+            transaction,
+        }
+    }
+}
+
+/// Block as returned by the API (transactions carry their hashes)
+#[derive(Serialize)]
+pub struct BlockView {
+    pub index: u64,
+    pub timestamp: i64,
+    pub transactions: Vec<TransactionView>,
+    pub previous_hash: String,
+    pub nonce: u64,
+    pub hash: String,
+    pub difficulty: u32,
+    pub target: Target,
+    pub merkle_root: String,
+}
+
+impl From<Block> for BlockView {
+    fn from(block: Block) -> Self {
+        Self {
+            target: block.target(),
+            index: block.index,
+            timestamp: block.timestamp,
+            transactions: block.transactions.into_iter().map(TransactionView::from).collect(),
+            previous_hash: block.previous_hash,
+            nonce: block.nonce,
+            hash: block.hash,
+            difficulty: block.difficulty,
+            merkle_root: block.merkle_root,
+        }
+    }
+}
+
 /// Get specific block by height
 async fn get_block(
     State(state): State<Arc<ApiState>>,
     Path(height): Path<u64>,
-) -> Result<Json<Block>, StatusCode> {
+) -> Result<Json<BlockView>, StatusCode> {
     let blockchain = state.blockchain.read().await;
     let block = blockchain.get_chain().get(height as usize).cloned();
     drop(blockchain);
     
     if let Some(block) = block {
-        Ok(Json(block))
+        Ok(Json(block.into()))
     } else {
         Err(StatusCode::NOT_FOUND)
     }
 }
 
+/// Transaction with the height of its block (None while pending)
+#[derive(Serialize)]
+pub struct TransactionLookup {
+    pub transaction: TransactionView,
+    pub block_height: Option<u64>,
+}
+
+/// Get a transaction by hash, mined or still in the mempool
+async fn get_transaction(
+    State(state): State<Arc<ApiState>>,
+    Path(hash): Path<String>,
+) -> Result<Json<TransactionLookup>, StatusCode> {
+    let blockchain = state.blockchain.read().await;
+    let pending = blockchain.get_pending_transactions().iter().find(|tx| tx.hash() == hash).cloned();
+    let found = pending
+        .map(|tx| (tx, None))
+        .or_else(|| blockchain.get_transaction(&hash).map(|(tx, height)| (tx, Some(height))));
+    drop(blockchain);
+
+    match found {
+        Some((tx, block_height)) => Ok(Json(TransactionLookup { transaction: tx.into(), block_height })),
+        None => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+/// Default and maximum page size for /api/mempool
+const MEMPOOL_DEFAULT_LIMIT: usize = 100;
+const MEMPOOL_MAX_LIMIT: usize = 1000;
+
+/// Mempool query parameters (?offset=&limit=&full=)
+#[derive(Deserialize, Default)]
+pub struct MempoolQuery {
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+    /// Include public keys and signatures (default: summaries only)
+    #[serde(default)]
+    pub full: bool,
+}
+
+/// Compact mempool entry without key/signature bytes
+#[derive(Serialize)]
+pub struct TransactionSummary {
+    pub hash: TxHash,
+    #[serde(rename = "type")]
+    pub kind: &'static str,
+    pub sender: String,
+    pub recipient: String,
+    pub amount: u64,
+    pub fee: u64,
+    pub nonce: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub memo: Option<Vec<u8>>,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+pub enum MempoolEntry {
+    Summary(TransactionSummary),
+    Full(TransactionView),
+}
+
 /// Get mempool transactions
 #[derive(Serialize)]
 pub struct MempoolResponse {
     pub transaction_count: usize,
-    pub transactions: Vec<Transaction>,
+    pub offset: usize,
+    pub limit: usize,
+    pub transactions: Vec<MempoolEntry>,
+}
+
+/// Build one page of the mempool listing
+fn build_mempool_page(pending: &[Transaction], query: &MempoolQuery) -> MempoolResponse {
+    let offset = query.offset.unwrap_or(0);
+    let limit = query.limit.unwrap_or(MEMPOOL_DEFAULT_LIMIT).min(MEMPOOL_MAX_LIMIT);
+
+    let transactions = pending
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .map(|tx| {
+            if query.full {
+                MempoolEntry::Full(tx.clone().into())
+            } else {
+                MempoolEntry::Summary(TransactionSummary {
+                    hash: tx.hash(),
+                    kind: tx.tx_type.name(),
+                    sender: tx.sender.clone(),
+                    recipient: tx.recipient.clone(),
+                    amount: tx.amount,
+                    fee: tx.fee,
+                    nonce: tx.nonce,
+                    memo: tx.memo.clone(),
+                })
+            }
+        })
+        .collect();
+
+    MempoolResponse {
+        transaction_count: pending.len(),
+        offset,
+        limit,
+        transactions,
+    }
 }
 
 async fn get_mempool(
     State(state): State<Arc<ApiState>>,
+    Query(query): Query<MempoolQuery>,
 ) -> Json<MempoolResponse> {
     let blockchain = state.blockchain.read().await;
-    let transactions = blockchain.get_pending_transactions().clone();
+    let pending = blockchain.get_pending_transactions();
     
-    Json(MempoolResponse {
-        transaction_count: transactions.len(),
-        transactions,
+    Json(build_mempool_page(&pending, &query))
+}
+
+/// Aggregate mempool stats (count, bytes, fees, fee rates, oldest age)
+async fn get_mempool_info(
+    State(state): State<Arc<ApiState>>,
+) -> Json<MempoolInfo> {
+    let blockchain = state.blockchain.read().await;
+    Json(blockchain.mempool_info())
+}
+
+/// Fee estimate query (?target=N blocks, default 1)
+#[derive(Deserialize)]
+pub struct FeeEstimateQuery {
+    pub target: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct FeeEstimateResponse {
+    pub target_blocks: u32,
+    pub fee_microunits: u64,
+}
+
+/// Recommended fee to be confirmed within `target` blocks
+async fn get_fee_estimate(
+    State(state): State<Arc<ApiState>>,
+    Query(query): Query<FeeEstimateQuery>,
+) -> Json<FeeEstimateResponse> {
+    let target_blocks = query.target.unwrap_or(1).max(1);
+    let blockchain = state.blockchain.read().await;
+    Json(FeeEstimateResponse {
+        target_blocks,
+        fee_microunits: blockchain.estimate_fee(target_blocks),
     })
 }
 
@@ -495,17 +976,44 @@ async fn health_check(
     })
 }
 
+/// Cap requests in flight; beyond `limit` respond 503 immediately instead of queueing
+/// /health bypasses the cap so probes stay responsive under load
+fn with_concurrency_limit<S: Clone + Send + Sync + 'static>(router: Router<S>, limit: usize) -> Router<S> {
+    let permits = Arc::new(tokio::sync::Semaphore::new(limit.max(1)));
+    router.layer(middleware::from_fn(move |req: Request, next: Next| {
+        let permits = Arc::clone(&permits);
+        async move {
+            if req.uri().path() == "/health" {
+                return next.run(req).await;
+            }
+            match permits.try_acquire_owned() {
+                Ok(_permit) => next.run(req).await,
+                Err(_) => too_busy(),
+            }
+        }
+    }))
+}
+
+fn too_busy() -> Response {
+    (StatusCode::SERVICE_UNAVAILABLE, "Too many concurrent requests").into_response()
+}
+
 /// Create the API router with rate limiting (DOS protection)
 pub fn create_router(
     blockchain: Arc<RwLock<Blockchain>>,
     metrics: Option<Arc<crate::consensus::mempool::MetricsCollector>>,
     network: Option<Arc<crate::network::Network>>,
+    max_concurrent_requests: usize,
+    max_body_bytes: usize,
+    replica: Option<ChainReplica>,
 ) -> Router {
     let state = Arc::new(ApiState { 
         blockchain,
         metrics,
         network,
-        mining_active: Arc::new(AtomicBool::new(false)),
+        mining: MiningControl::default(),
+        replica,
+        idempotency: IdempotencyCache::default(),
     });
 
     // Configure CORS to allow requests from any origin
@@ -518,10 +1026,14 @@ pub fn create_router(
     // TODO: Implement rate limiting using axum-compatible middleware
     // Consider using tower::limit::RateLimitLayer or axum-specific rate limiting
 
-    Router::new()
+    let routes = Router::new()
         .route("/health", get(health_check))
+        .route("/api/version", get(get_version))
         .route("/api/stats", get(get_stats))
+        .route("/api/stats/since/:height", get(get_stats_since))
+        .route("/api/supply", get(get_supply))
         .route("/api/balance", post(get_balance))
+        .route("/api/nonce/:address", get(get_next_nonce))
         .route("/api/transaction", post(create_transaction))
         .route("/api/mine", post(mine_block))
         .route("/api/mine/start", post(start_continuous_mining))
@@ -531,7 +1043,13 @@ pub fn create_router(
         .route("/api/peers", get(get_peers))
         .route("/api/metrics", get(get_metrics))
         .route("/api/block/:height", get(get_block))
+        .route("/api/tx/:hash", get(get_transaction))
         .route("/api/mempool", get(get_mempool))
+        .route("/api/mempool/info", get(get_mempool_info))
+        .route("/api/fee-estimate", get(get_fee_estimate));
+
+    with_concurrency_limit(routes, max_concurrent_requests)
+        .layer(DefaultBodyLimit::max(max_body_bytes))
         .layer(cors)
         .with_state(state)
 }
@@ -542,22 +1060,35 @@ pub async fn start_server(
     port: u16,
     metrics: Option<Arc<crate::consensus::mempool::MetricsCollector>>,
     network: Option<Arc<crate::network::Network>>,
+    max_concurrent_requests: usize,
+    max_body_bytes: usize,
+    replica: Option<ChainReplica>,
 ) {
-    let app = create_router(blockchain, metrics, network);
+    let read_replica = replica.is_some();
+    let app = create_router(blockchain, metrics, network, max_concurrent_requests, max_body_bytes, replica);
     let addr = format!("0.0.0.0:{}", port);
     
     tracing::info!("QUANTA API server starting on {}", addr);
+    if read_replica {
+        tracing::info!("Read replica mode: stats, supply and balance served from the last published block");
+    }
     tracing::info!("Endpoints:");
     tracing::info!("   GET  /health - Health check");
+    tracing::info!("   GET  /api/version - Get node version, protocol and network");
     tracing::info!("   GET  /api/stats - Get blockchain statistics");
+    tracing::info!("   GET  /api/stats/since/:height - Get changes since a chain height");
+    tracing::info!("   GET  /api/supply - Get supply breakdown");
     tracing::info!("   POST /api/balance - Get address balance");
+    tracing::info!("   GET  /api/nonce/:address - Get next nonce (including pending txs)");
     tracing::info!("   POST /api/transaction - Create transaction");
     tracing::info!("   POST /api/mine - Mine a block");
     tracing::info!("   GET  /api/validate - Validate blockchain");
     tracing::info!("   GET  /api/peers - Get connected peers");
     tracing::info!("   GET  /api/metrics - Get node metrics");
     tracing::info!("   GET  /api/block/:height - Get specific block");
-    tracing::info!("   GET  /api/mempool - Get pending transactions");
+    tracing::info!("   GET  /api/tx/:hash - Get transaction (mined or pending)");
+    tracing::info!("   GET  /api/mempool?offset=&limit=&full= - Get pending transactions");
+    tracing::info!("   GET  /api/mempool/info - Get mempool summary stats");
     tracing::info!("   POST /api/merkle/proof - Get Merkle proof for transaction");
     
     let listener = tokio::net::TcpListener::bind(&addr)
@@ -568,3 +1099,377 @@ pub async fn start_server(
         .await
         .expect("Server error");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pending_txs(count: u64) -> Vec<Transaction> {
+        (0..count)
+            .map(|i| {
+                let mut tx = Transaction::new(
+                    "0x1111111111111111111111111111111111111111".to_string(),
+                    "0x2222222222222222222222222222222222222222".to_string(),
+                    1_000 + i,
+                    1_700_000_000,
+                );
+                tx.nonce = i + 1;
+                tx.public_key = vec![7u8; 897];
+                tx.signature = vec![9u8; 666];
+                tx
+            })
+            .collect()
+    }
+
+    #[test]
+    fn mempool_summary_omits_heavy_fields() {
+        let txs = pending_txs(3);
+        let page = build_mempool_page(&txs, &MempoolQuery::default());
+        let json = serde_json::to_value(&page).unwrap();
+
+        let first = &json["transactions"][0];
+        assert!(first.get("public_key").is_none());
+        assert!(first.get("signature").is_none());
+        assert_eq!(first["hash"], txs[0].hash().as_str());
+
+        let full = build_mempool_page(&txs, &MempoolQuery { full: true, ..Default::default() });
+        let json = serde_json::to_value(&full).unwrap();
+        assert!(json["transactions"][0].get("signature").is_some());
+    }
+
+    #[test]
+    fn api_transactions_carry_canonical_hash() {
+        let txs = pending_txs(2);
+        let full = build_mempool_page(&txs, &MempoolQuery { full: true, ..Default::default() });
+        let json = serde_json::to_value(&full).unwrap();
+        for (entry, tx) in json["transactions"].as_array().unwrap().iter().zip(&txs) {
+            assert_eq!(entry["hash"], tx.hash().as_str());
+            assert_eq!(entry["type"], "transfer");
+            assert_eq!(entry["nonce"], tx.nonce); // original fields are still there
+        }
+
+        let mut block = Block::new(1, txs.clone(), "00".repeat(32), 4);
+        block.transactions.push(Transaction::new_call_contract(
+            txs[0].sender.clone(),
+            txs[0].recipient.clone(),
+            "f".to_string(),
+            vec![1],
+            1_700_000_000,
+            3,
+        ));
+        let json = serde_json::to_value(BlockView::from(block.clone())).unwrap();
+        assert_eq!(json["hash"], block.hash);
+        assert_eq!(json["merkle_root"], block.merkle_root);
+        for (entry, tx) in json["transactions"].as_array().unwrap().iter().zip(&block.transactions) {
+            assert_eq!(entry["hash"], tx.hash().as_str());
+            assert_eq!(entry["type"], tx.tx_type.name());
+        }
+        assert_eq!(json["transactions"][2]["type"], "call_contract");
+    }
+
+    #[test]
+    fn mempool_pagination() {
+        let txs = pending_txs(5);
+        let query = MempoolQuery { offset: Some(1), limit: Some(2), full: false };
+        let page = build_mempool_page(&txs, &query);
+
+        assert_eq!(page.transaction_count, 5);
+        assert_eq!(page.transactions.len(), 2);
+        let json = serde_json::to_value(&page).unwrap();
+        assert_eq!(json["transactions"][0]["nonce"], 2);
+        assert_eq!(json["transactions"][1]["nonce"], 3);
+
+        // Past the end yields an empty page
+        let query = MempoolQuery { offset: Some(10), limit: None, full: false };
+        assert!(build_mempool_page(&txs, &query).transactions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn excess_concurrent_requests_get_503() {
+        async fn slow() -> &'static str {
+            tokio::time::sleep(std::time::Duration::from_millis(500)).await;
+            "done"
+        }
+        let app: Router = with_concurrency_limit(
+            Router::new()
+                .route("/slow", get(slow))
+                .route("/health", get(|| async { "ok" })),
+            1,
+        );
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let first = tokio::spawn(reqwest::get(format!("{}/slow", base)));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let second = reqwest::get(format!("{}/slow", base)).await.unwrap();
+        assert_eq!(second.status(), reqwest::StatusCode::SERVICE_UNAVAILABLE);
+
+        let health = reqwest::get(format!("{}/health", base)).await.unwrap();
+        assert_eq!(health.status(), reqwest::StatusCode::OK);
+
+        assert_eq!(first.await.unwrap().unwrap().status(), reqwest::StatusCode::OK);
+    }
+
+    #[test]
+    fn stopping_mining_cancels_the_running_task() {
+        let mining = MiningControl::default();
+        let stop = mining.start().unwrap();
+        assert!(mining.is_active());
+        assert!(mining.start().is_none());
+
+        mining.stop();
+        assert!(stop.is_cancelled());
+        assert!(!mining.is_active());
+
+        // A restart gets a fresh token; the old task stays stopped
+        let restarted = mining.start().unwrap();
+        assert!(!restarted.is_cancelled());
+        assert!(stop.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn version_endpoint_reports_crate_version() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet)
+            .unwrap()
+            .with_contracts_enabled(false);
+        let app = create_router(Arc::new(RwLock::new(blockchain)), None, None, 16, DEFAULT_MAX_REQUEST_BODY_BYTES, None);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let info: VersionInfo = reqwest::get(format!("{}/api/version", base))
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(info.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(info.protocol_version, crate::network::protocol::PROTOCOL_VERSION);
+        assert_eq!(info.network, crate::core::ChainNetwork::Testnet);
+        assert!(!info.features.iter().any(|f| f == "contracts"));
+    }
+
+    #[tokio::test]
+    async fn retried_submission_with_same_idempotency_key_returns_same_hash() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+
+        let wallet = QuantumWallet::new();
+        let wallet_file = dir.path().join("wallet.qua").to_string_lossy().into_owned();
+        wallet.save_quantum_safe(&wallet_file, "password").unwrap();
+        let credit = Transaction::new("TREASURY".to_string(), wallet.address.to_string(), 1_000_000_000, 0);
+        blockchain.get_account_state_mut().credit_account(&credit, 0, 0);
+
+        let blockchain = Arc::new(RwLock::new(blockchain));
+        let app = create_router(Arc::clone(&blockchain), None, None, 16, DEFAULT_MAX_REQUEST_BODY_BYTES, None);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/api/transaction", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let submit = |key: &'static str, amount: u64| {
+            client
+                .post(&url)
+                .header(IDEMPOTENCY_KEY_HEADER, key)
+                .json(&serde_json::json!({
+                    "wallet_file": wallet_file,
+                    "wallet_password": "password",
+                    "recipient": "0x2222222222222222222222222222222222222222",
+                    "amount_microunits": amount,
+                }))
+                .send()
+        };
+
+        let first = submit("retry-1", 5_000).await.unwrap();
+        assert_eq!(first.status(), reqwest::StatusCode::OK);
+        let first: serde_json::Value = first.json().await.unwrap();
+        let retry: serde_json::Value = submit("retry-1", 5_000).await.unwrap().json().await.unwrap();
+        assert!(first["tx_hash"].is_string());
+        assert_eq!(retry["tx_hash"], first["tx_hash"]);
+        assert_eq!(blockchain.read().await.get_pending_transactions().len(), 1);
+
+        // Same key for a different request is refused rather than replayed
+        let mismatch = submit("retry-1", 6_000).await.unwrap();
+        assert_eq!(mismatch.status(), reqwest::StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(blockchain.read().await.get_pending_transactions().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn balance_reports_locked_coinbase_reward() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+        let miner = "0x3333333333333333333333333333333333333333";
+        blockchain.mine_pending_transactions(miner.to_string()).unwrap();
+        let locked = blockchain.get_locked_balance(miner);
+        let unlock_height = blockchain.get_unlock_height(miner);
+        assert!(locked > 0);
+        assert_eq!(unlock_height, 1 + 100);
+
+        let replica = blockchain.replica();
+        let blockchain = Arc::new(RwLock::new(blockchain));
+        let client = reqwest::Client::new();
+        // Same answer with and without the read replica
+        for replica in [None, Some(replica)] {
+            let app = create_router(Arc::clone(&blockchain), None, None, 16, DEFAULT_MAX_REQUEST_BODY_BYTES, replica);
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let url = format!("http://{}/api/balance", listener.local_addr().unwrap());
+            tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+            let balance = |address: &'static str| client.post(&url).json(&serde_json::json!({ "address": address })).send();
+            let mined: serde_json::Value = balance(miner).await.unwrap().json().await.unwrap();
+            assert_eq!(mined["locked_microunits"], locked);
+            assert_eq!(mined["unlock_height"], unlock_height);
+            assert_eq!(mined["balance_microunits"], 0); // coinbase rewards start fully locked
+
+            // Unknown addresses report zeros rather than an error
+            let unknown = balance("0x4444444444444444444444444444444444444444").await.unwrap();
+            assert_eq!(unknown.status(), reqwest::StatusCode::OK);
+            let unknown: serde_json::Value = unknown.json().await.unwrap();
+            for field in ["balance_microunits", "locked_microunits", "unlock_height"] {
+                assert_eq!(unknown[field], 0);
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn memo_round_trips_through_create_transaction_and_mempool() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+
+        let wallet = QuantumWallet::new();
+        let wallet_file = dir.path().join("wallet.qua").to_string_lossy().into_owned();
+        wallet.save_quantum_safe(&wallet_file, "password").unwrap();
+        let credit = Transaction::new("TREASURY".to_string(), wallet.address.to_string(), 1_000_000_000, 0);
+        blockchain.get_account_state_mut().credit_account(&credit, 0, 0);
+
+        let blockchain = Arc::new(RwLock::new(blockchain));
+        let app = create_router(Arc::clone(&blockchain), None, None, 16, DEFAULT_MAX_REQUEST_BODY_BYTES, None);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = reqwest::Client::new();
+        let submit = |memo: Vec<u8>| {
+            client
+                .post(format!("{}/api/transaction", base))
+                .json(&serde_json::json!({
+                    "wallet_file": wallet_file,
+                    "wallet_password": "password",
+                    "recipient": "0x2222222222222222222222222222222222222222",
+                    "amount_microunits": 5_000,
+                    "memo": memo,
+                }))
+                .send()
+        };
+
+        let memo = b"invoice #2024-0117".to_vec();
+        let created: serde_json::Value = submit(memo.clone()).await.unwrap().json().await.unwrap();
+        let mempool: serde_json::Value =
+            client.get(format!("{}/api/mempool?full=true", base)).send().await.unwrap().json().await.unwrap();
+        let listed = &mempool["transactions"][0];
+        assert_eq!(listed["hash"], created["tx_hash"]);
+        assert_eq!(listed["memo"], serde_json::json!(memo));
+
+        // The memo is signed: the pooled transaction verifies, and changing the memo changes the hash
+        let mut pooled = blockchain.read().await.get_pending_transactions()[0].clone();
+        assert!(pooled.verify());
+        pooled.memo = Some(b"invoice #2024-0118".to_vec());
+        assert!(!pooled.verify());
+        assert_ne!(pooled.hash(), created["tx_hash"].as_str().unwrap());
+
+        let oversized = submit(vec![b'x'; crate::core::transaction::MAX_MEMO_BYTES + 1]).await.unwrap();
+        assert_eq!(oversized.status(), reqwest::StatusCode::BAD_REQUEST);
+        let oversized: serde_json::Value = oversized.json().await.unwrap();
+        assert!(oversized["error"].as_str().unwrap().contains("Memo too large"));
+    }
+
+    #[tokio::test]
+    async fn transaction_lookup_finds_pending_then_mined() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+
+        let wallet = QuantumWallet::new();
+        let credit = Transaction::new("TREASURY".to_string(), wallet.address.to_string(), 1_000_000_000, 0);
+        blockchain.get_account_state_mut().credit_account(&credit, 0, 0);
+        let mut tx = Transaction::builder()
+            .sender(wallet.address.to_string())
+            .recipient("0x2222222222222222222222222222222222222222")
+            .amount(5_000)
+            .fee(5_000)
+            .nonce(1)
+            .build();
+        tx.public_key = wallet.keypair.public_key.clone();
+        tx.signature = wallet.keypair.sign(&tx.get_signing_data());
+        let hash = tx.hash();
+        blockchain.add_transaction(tx).unwrap();
+
+        let blockchain = Arc::new(RwLock::new(blockchain));
+        let app = create_router(Arc::clone(&blockchain), None, None, 16, DEFAULT_MAX_REQUEST_BODY_BYTES, None);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let lookup = |hash: String| reqwest::get(format!("{}/api/tx/{}", base, hash));
+
+        let pending: serde_json::Value = lookup(hash.to_string()).await.unwrap().json().await.unwrap();
+        assert_eq!(pending["transaction"]["hash"], hash.as_str());
+        assert!(pending["block_height"].is_null());
+
+        blockchain.read().await.mine_pending_transactions("0x1111111111111111111111111111111111111111".to_string()).unwrap();
+        let mined: serde_json::Value = lookup(hash.to_string()).await.unwrap().json().await.unwrap();
+        assert_eq!(mined["transaction"]["hash"], hash.as_str());
+        assert_eq!(mined["block_height"], 1);
+
+        let unknown = lookup("ab".repeat(32)).await.unwrap();
+        assert_eq!(unknown.status(), reqwest::StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn replica_reads_do_not_contend_with_writes() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+        let replica = blockchain.replica();
+        let blockchain = Arc::new(RwLock::new(blockchain));
+        let app = create_router(Arc::clone(&blockchain), None, None, 64, DEFAULT_MAX_REQUEST_BODY_BYTES, Some(replica.clone()));
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        // A writer holds the chain lock (as block import does); reads are still served
+        let writer = blockchain.write().await;
+        let client = reqwest::Client::new();
+        let reads = (0..32).map(|_| {
+            let client = client.clone();
+            let base = base.clone();
+            async move {
+                let stats: BlockchainStats = client.get(format!("{}/api/stats", base)).send().await?.json().await?;
+                client.get(format!("{}/api/supply", base)).send().await?.error_for_status()?;
+                Ok::<_, reqwest::Error>(stats)
+            }
+        });
+        let results = tokio::time::timeout(std::time::Duration::from_secs(10), futures::future::join_all(reads))
+            .await
+            .expect("replica reads must not wait for the writer");
+        assert!(results.iter().all(|r| r.as_ref().unwrap().chain_length == 1));
+
+        // Readers holding views don't hold up the write; they keep their consistent view
+        let held = replica.load();
+        writer.mine_pending_transactions("0x1111111111111111111111111111111111111111".to_string()).unwrap();
+        drop(writer);
+        assert_eq!(held.snapshot.height, 1);
+        assert_eq!(replica.load().snapshot.height, 2);
+        let stats: BlockchainStats = reqwest::get(format!("{}/api/stats", base)).await.unwrap().json().await.unwrap();
+        assert_eq!(stats.chain_length, 2);
+    }
+}
@@ -0,0 +1,214 @@
+use super::QuantaConfig;
+use crate::core::block::Block;
+use crate::core::ChainNetwork;
+use crate::storage::{BlockchainStorage, StorageError};
+use std::net::TcpListener;
+use std::path::Path;
+
+/// Outcome of one `doctor` check
+#[derive(Debug, Clone)]
+pub struct DoctorCheck {
+    pub name: String,
+    pub passed: bool,
+    pub detail: String,
+    /// What the operator should change when the check fails
+    pub hint: Option<String>,
+}
+
+impl DoctorCheck {
+    fn pass(name: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self { name: name.into(), passed: true, detail: detail.into(), hint: None }
+    }
+
+    fn fail(name: impl Into<String>, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self { name: name.into(), passed: false, detail: detail.into(), hint: Some(hint.into()) }
+    }
+}
+
+/// Pass/fail report produced by `quanta doctor`
+#[derive(Debug, Clone, Default)]
+pub struct DoctorReport {
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl DoctorReport {
+    pub fn passed(&self) -> bool {
+        self.checks.iter().all(|check| check.passed)
+    }
+
+    #[cfg(test)]
+    pub fn check(&self, name: &str) -> Option<&DoctorCheck> {
+        self.checks.iter().find(|check| check.name == name)
+    }
+
+    pub fn print(&self) {
+        for check in &self.checks {
+            println!("[{}] {}: {}", if check.passed { "PASS" } else { "FAIL" }, check.name, check.detail);
+            if let Some(ref hint) = check.hint {
+                println!("       -> {}", hint);
+            }
+        }
+        let failed = self.checks.iter().filter(|check| !check.passed).count();
+        if failed == 0 {
+            println!("\nAll {} checks passed", self.checks.len());
+        } else {
+            println!("\n{} of {} checks failed", failed, self.checks.len());
+        }
+    }
+}
+
+/// Check that `cfg` can start a node on this machine: config sanity, free ports,
+/// a writable and unlocked data directory, and a stored chain from the configured network
+pub fn run(cfg: &QuantaConfig) -> DoctorReport {
+    let mut report = DoctorReport::default();
+
+    report.checks.push(match cfg.validate() {
+        Ok(()) => DoctorCheck::pass("config", "configuration is valid"),
+        Err(e) => DoctorCheck::fail("config", e, "fix the value in quanta.toml (or the QUANTA__* environment override)"),
+    });
+
+    // Same bind addresses the servers use at startup
+    let mut ports = vec![("api port", "0.0.0.0", cfg.node.api_port), ("rpc port", "127.0.0.1", cfg.node.rpc_port)];
+    if !cfg.node.no_network {
+        ports.push(("p2p port", "0.0.0.0", cfg.node.network_port));
+    }
+    for (i, &(name, host, port)) in ports.iter().enumerate() {
+        if let Some(&(other, _, _)) = ports[..i].iter().find(|&&(_, _, p)| p == port) {
+            report.checks.push(DoctorCheck::fail(
+                name,
+                format!("{} is also configured as the {}", port, other),
+                "give each server its own port in [node]",
+            ));
+            continue;
+        }
+        report.checks.push(match TcpListener::bind((host, port)) {
+            Ok(_) => DoctorCheck::pass(name, format!("{}:{} is available", host, port)),
+            Err(e) => DoctorCheck::fail(
+                name,
+                format!("cannot bind {}:{}: {}", host, port, e),
+                "stop the process using the port (is a node already running?) or choose another port",
+            ),
+        });
+    }
+
+    let db_path = Path::new(&cfg.node.db_path);
+    if !db_path.exists() {
+        report.checks.push(DoctorCheck::pass(
+            "data directory",
+            format!("{} does not exist yet; it will be created on first start", cfg.node.db_path),
+        ));
+        return report;
+    }
+    report.checks.push(check_writable(db_path));
+
+    // sled holds an exclusive lock on the directory while a node has it open
+    let storage = match BlockchainStorage::new(db_path) {
+        Ok(storage) => {
+            report.checks.push(DoctorCheck::pass("database lock", "database opened (no other node is using it)"));
+            storage
+        }
+        // Reading it needs the passphrase, so the genesis check is skipped
+        Err(StorageError::Encrypted) if cfg.node.encrypt_db => {
+            report.checks.push(DoctorCheck::pass("database lock", "encrypted database opened (no other node is using it)"));
+            return report;
+        }
+        Err(StorageError::Encrypted) => {
+            report.checks.push(DoctorCheck::fail(
+                "database lock",
+                format!("{} is encrypted", cfg.node.db_path),
+                "set node.encrypt_db = true and supply the passphrase via QUANTA_DB_PASSPHRASE",
+            ));
+            return report;
+        }
+        Err(e) => {
+            report.checks.push(DoctorCheck::fail(
+                "database lock",
+                format!("cannot open {}: {}", cfg.node.db_path, e),
+                "stop the node using this data directory, or point --db at another one",
+            ));
+            return report;
+        }
+    };
+
+    report.checks.push(check_genesis(&storage, cfg.network_type));
+    report
+}
+
+fn check_writable(db_path: &Path) -> DoctorCheck {
+    let probe = db_path.join(".doctor_write_probe");
+    match std::fs::write(&probe, b"ok").and_then(|_| std::fs::remove_file(&probe)) {
+        Ok(()) => DoctorCheck::pass("data directory", format!("{} is writable", db_path.display())),
+        Err(e) => DoctorCheck::fail(
+            "data directory",
+            format!("{} is not writable: {}", db_path.display(), e),
+            "fix the directory permissions or run the node as its owner",
+        ),
+    }
+}
+
+fn check_genesis(storage: &BlockchainStorage, network: ChainNetwork) -> DoctorCheck {
+    let stored = match storage.load_block(0) {
+        Ok(block) => block,
+        Err(_) => return DoctorCheck::pass("genesis", "no stored chain yet; genesis will be created on first start"),
+    };
+    let expected = Block::genesis(network);
+    if stored.hash == expected.hash {
+        return DoctorCheck::pass("genesis", format!("stored genesis matches {:?}", network));
+    }
+
+    let (other, flag) = match network {
+        ChainNetwork::Mainnet => (ChainNetwork::Testnet, "testnet"),
+        ChainNetwork::Testnet => (ChainNetwork::Mainnet, "mainnet"),
+    };
+    let hint = if stored.hash == Block::genesis(other).hash {
+        format!("this data directory holds a {:?} chain; start with --network {} or use a different --db", other, flag)
+    } else {
+        "the stored chain is from an unknown network; use a different --db or remove the data directory".to_string()
+    };
+    DoctorCheck::fail(
+        "genesis",
+        format!("stored genesis {} does not match {:?} genesis {}", stored.hash, network, expected.hash),
+        hint,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flags_port_conflict_and_genesis_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("db");
+        let storage = BlockchainStorage::new(&db_path).unwrap();
+        storage.save_block(&Block::genesis(ChainNetwork::Testnet)).unwrap();
+        drop(storage);
+
+        let busy = TcpListener::bind("0.0.0.0:0").unwrap();
+        let mut cfg = QuantaConfig { network_type: ChainNetwork::Mainnet, ..Default::default() };
+        cfg.node.api_port = busy.local_addr().unwrap().port();
+        cfg.node.db_path = db_path.to_string_lossy().into_owned();
+
+        let report = run(&cfg);
+        assert!(!report.passed());
+        assert!(report.check("config").unwrap().passed);
+        assert!(!report.check("api port").unwrap().passed);
+        assert!(report.check("data directory").unwrap().passed);
+        assert!(report.check("database lock").unwrap().passed);
+        let genesis = report.check("genesis").unwrap();
+        assert!(!genesis.passed);
+        assert!(genesis.hint.as_deref().unwrap().contains("--network testnet"));
+
+        // Two servers configured on the same port is caught without binding
+        drop(busy);
+        cfg.node.rpc_port = cfg.node.api_port;
+        assert!(!run(&cfg).check("rpc port").unwrap().passed);
+
+        // Checking a fresh data directory writes nothing into it, so it can still be encrypted
+        let fresh = dir.path().join("fresh");
+        drop(BlockchainStorage::new(&fresh).unwrap());
+        cfg.node.db_path = fresh.to_string_lossy().into_owned();
+        run(&cfg);
+        assert!(BlockchainStorage::open_encrypted(&fresh, "pass").is_ok());
+    }
+}
@@ -1,3 +1,4 @@
+pub mod doctor;
 pub mod types;
 
 pub use types::QuantaConfig;
@@ -2,6 +2,7 @@ use serde::{Deserialize, Serialize};
 use std::path::Path;
 use config::{Config, ConfigError, File};
 use crate::core::ChainNetwork;
+use crate::network::noise::EncryptionMode;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct QuantaConfig {
@@ -22,6 +23,59 @@ pub struct NodeConfig {
     pub rpc_port: u16,
     pub db_path: String,
     pub no_network: bool,
+    /// Accept DeployContract/CallContract transactions (false = payments-only node)
+    #[serde(default = "default_contracts_enabled")]
+    pub contracts_enabled: bool,
+    /// On a stored height / on-disk chain mismatch at startup, repair instead of refusing to start
+    #[serde(default)]
+    pub repair_chain_height: bool,
+    /// Encrypt database values at rest with a key derived from a passphrase given at startup
+    /// (QUANTA_DB_PASSPHRASE or a prompt); only for a new data directory
+    #[serde(default)]
+    pub encrypt_db: bool,
+    /// Recent blocks sampled when estimating fee rates for wallets
+    #[serde(default = "default_fee_estimate_window_blocks")]
+    pub fee_estimate_window_blocks: usize,
+    /// Recent block intervals averaged for the block time / schedule display
+    #[serde(default = "default_block_time_window_blocks")]
+    pub block_time_window_blocks: usize,
+    /// Serve API stats/supply/balance reads from a replica published after each block
+    /// instead of locking the chain (reads may trail the mempool until the next block)
+    #[serde(default)]
+    pub api_read_replica: bool,
+    /// Log output: "text" (human-readable) or "json" (one object per line, for log aggregators)
+    #[serde(default)]
+    pub log_format: LogFormat,
+}
+
+/// Log line format (config: node.log_format, CLI: start --log-format)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl LogFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LogFormat::Text => "text",
+            LogFormat::Json => "json",
+        }
+    }
+}
+
+fn default_contracts_enabled() -> bool {
+    true
+}
+
+fn default_fee_estimate_window_blocks() -> usize {
+    crate::consensus::fee_estimator::DEFAULT_FEE_ESTIMATE_WINDOW
+}
+
+fn default_block_time_window_blocks() -> usize {
+    crate::consensus::blockchain::DEFAULT_BLOCK_TIME_WINDOW
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,18 +83,91 @@ pub struct NetworkConfig {
     pub max_peers: usize,
     pub bootstrap_nodes: Vec<String>,
     pub dns_seeds: Vec<String>,
+    /// Max inbound connections accepted per second from a single IP
+    #[serde(default = "default_max_inbound_per_ip_per_sec")]
+    pub max_inbound_per_ip_per_sec: u32,
+    /// Max inbound connections accepted per second overall
+    #[serde(default = "default_max_inbound_per_sec")]
+    pub max_inbound_per_sec: u32,
+    /// Fast-sync: fetch a state snapshot verified against a hardcoded checkpoint
+    #[serde(default)]
+    pub snapshot_sync: bool,
+    /// Noise encryption of P2P connections: "disabled", "optional" or "required"
+    #[serde(default)]
+    pub p2p_encryption: EncryptionMode,
+    /// File holding this node's Noise static key, created on first start
+    #[serde(default = "default_p2p_noise_key_file")]
+    pub p2p_noise_key_file: String,
+    /// Expected Noise static keys (hex) of outbound peers, by "ip:port"
+    #[serde(default)]
+    pub peer_static_keys: std::collections::HashMap<String, String>,
+    /// Parent requests a single peer's orphan blocks may have outstanding; peers
+    /// that keep sending orphans past this are marked failed
+    #[serde(default = "default_max_orphan_requests_per_peer")]
+    pub max_orphan_requests_per_peer: u32,
+    /// Pause mining while fewer peers than this are connected (0 = never pause;
+    /// ignored with no_network)
+    #[serde(default)]
+    pub min_peers_to_mine: usize,
 }
 
-/// Consensus-critical configuration (MUST match across all nodes)
+fn default_p2p_noise_key_file() -> String {
+    "p2p_noise.key".to_string()
+}
+
+fn default_max_inbound_per_ip_per_sec() -> u32 {
+    5
+}
+
+fn default_max_inbound_per_sec() -> u32 {
+    50
+}
+
+fn default_max_orphan_requests_per_peer() -> u32 {
+    8
+}
+
+/// Consensus-critical configuration (MUST match across all nodes, except the fields documented as mempool policy)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConsensusConfig {
     pub max_block_transactions: usize,
     pub max_block_size_bytes: usize,
+    /// Mempool fee floor for transfers (relay policy; blocks use the consensus constants)
     pub min_transaction_fee_microunits: u64,
+    /// Mempool fee floor for contract deployments (relay policy; blocks use the consensus constants)
+    #[serde(default = "default_min_deploy_fee_microunits")]
+    pub min_deploy_fee_microunits: u64,
+    /// Mempool fee floor for contract calls (relay policy; blocks use the consensus constants)
+    #[serde(default = "default_min_call_fee_microunits")]
+    pub min_call_fee_microunits: u64,
+    /// Mempool fee-rate floor: microunits per 1000 bytes of serialized transaction weight.
+    /// Relay policy only; blocks are held to the built-in 500 from its activation height
+    #[serde(default = "default_min_fee_per_kb_microunits")]
+    pub min_fee_per_kb_microunits: u64,
+    /// Mempool cap on contract call instruction data (function name + args) in bytes.
+    /// Relay policy only, and never above the built-in 16 KiB blocks are held to
+    #[serde(default = "default_max_call_data_bytes")]
+    pub max_call_data_bytes: usize,
     pub transaction_expiry_blocks: u64,
     pub coinbase_maturity: u64,
 }
 
+fn default_min_deploy_fee_microunits() -> u64 {
+    10_000
+}
+
+fn default_min_call_fee_microunits() -> u64 {
+    5_000
+}
+
+fn default_min_fee_per_kb_microunits() -> u64 {
+    500
+}
+
+fn default_max_call_data_bytes() -> usize {
+    16 * 1024
+}
+
 /// Node-local security preferences (can differ between nodes)
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SecurityConfig {
@@ -54,6 +181,68 @@ pub struct SecurityConfig {
     pub enable_peer_banning: bool,
     /// Require TLS for API (PRODUCTION: true)
     pub require_tls: bool,
+    /// Truncate addresses in info/warn logs (full values only at debug level)
+    #[serde(default)]
+    pub redact_log_addresses: bool,
+    /// Max API requests in flight; extra requests get 503 (/health is exempt)
+    #[serde(default = "default_max_concurrent_api_requests")]
+    pub max_concurrent_api_requests: usize,
+    /// Max API/RPC request body size in bytes; larger requests get 413
+    #[serde(default = "default_max_request_body_bytes")]
+    pub max_request_body_bytes: usize,
+    /// Allow admin RPC methods (add_peer, remove_peer, ban_peer, get_account_state, sign_message) on the local RPC port
+    #[serde(default)]
+    pub enable_admin_rpc: bool,
+    /// Max blocks held while waiting for their parent (least recently seen evicted first)
+    #[serde(default = "default_max_orphan_blocks")]
+    pub max_orphan_blocks: usize,
+    /// Max mempool transactions from a single sender; further ones are rejected
+    #[serde(default = "default_max_pending_per_sender")]
+    pub max_pending_per_sender: usize,
+    /// Failures after which a peer whose reputation is below peer_ban_strike_reputation is banned
+    #[serde(default = "default_peer_ban_strike_failures")]
+    pub peer_ban_strike_failures: u32,
+    /// Reputation threshold for the strike rule (each failure costs 5, each success gains 1)
+    #[serde(default = "default_peer_ban_strike_reputation")]
+    pub peer_ban_strike_reputation: i32,
+    /// Failures after which a peer is banned regardless of reputation
+    #[serde(default = "default_peer_ban_max_failures")]
+    pub peer_ban_max_failures: u32,
+    /// Ban length per peer_ban_strike_failures failures, in seconds
+    #[serde(default = "default_peer_ban_duration_secs")]
+    pub peer_ban_duration_secs: i64,
+}
+
+fn default_max_concurrent_api_requests() -> usize {
+    256
+}
+
+fn default_max_request_body_bytes() -> usize {
+    crate::api::handlers::DEFAULT_MAX_REQUEST_BODY_BYTES
+}
+
+fn default_max_orphan_blocks() -> usize {
+    100
+}
+
+fn default_max_pending_per_sender() -> usize {
+    crate::consensus::blockchain::DEFAULT_MAX_PENDING_PER_SENDER
+}
+
+fn default_peer_ban_strike_failures() -> u32 {
+    3
+}
+
+fn default_peer_ban_strike_reputation() -> i32 {
+    -20
+}
+
+fn default_peer_ban_max_failures() -> u32 {
+    10
+}
+
+fn default_peer_ban_duration_secs() -> i64 {
+    3600
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -100,6 +289,13 @@ impl Default for QuantaConfig {
                 rpc_port: 7782,
                 db_path: "./quanta_data".to_string(),
                 no_network: false,
+                contracts_enabled: default_contracts_enabled(),
+                repair_chain_height: false,
+                encrypt_db: false,
+                fee_estimate_window_blocks: default_fee_estimate_window_blocks(),
+                block_time_window_blocks: default_block_time_window_blocks(),
+                api_read_replica: false,
+                log_format: LogFormat::default(),
             },
             network: NetworkConfig {
                 max_peers: 125,
@@ -110,11 +306,23 @@ impl Default for QuantaConfig {
                     // "seed2.quanta.network".to_string(),
                     // "seed3.quanta.network".to_string(),
                 ],
+                max_inbound_per_ip_per_sec: default_max_inbound_per_ip_per_sec(),
+                max_inbound_per_sec: default_max_inbound_per_sec(),
+                snapshot_sync: false,
+                p2p_encryption: EncryptionMode::default(),
+                p2p_noise_key_file: default_p2p_noise_key_file(),
+                peer_static_keys: Default::default(),
+                max_orphan_requests_per_peer: default_max_orphan_requests_per_peer(),
+                min_peers_to_mine: 0,
             },
             consensus: ConsensusConfig {
                 max_block_transactions: 2000,
                 max_block_size_bytes: 1_048_576,
                 min_transaction_fee_microunits: 100, // 0.0001 QUA
+                min_deploy_fee_microunits: default_min_deploy_fee_microunits(),
+                min_call_fee_microunits: default_min_call_fee_microunits(),
+                min_fee_per_kb_microunits: default_min_fee_per_kb_microunits(),
+                max_call_data_bytes: default_max_call_data_bytes(),
                 transaction_expiry_blocks: 8640, // ~24 hours at 10s blocks
                 coinbase_maturity: 100,
             },
@@ -125,6 +333,16 @@ impl Default for QuantaConfig {
                 rate_limit_per_minute: 60,   // 60 requests/min per IP
                 enable_peer_banning: true,   // Auto-ban malicious peers
                 require_tls: false,          // Set true for public nodes
+                redact_log_addresses: false, // Set true for public nodes
+                max_concurrent_api_requests: default_max_concurrent_api_requests(),
+                max_request_body_bytes: default_max_request_body_bytes(),
+                enable_admin_rpc: false,
+                max_orphan_blocks: default_max_orphan_blocks(),
+                max_pending_per_sender: default_max_pending_per_sender(),
+                peer_ban_strike_failures: default_peer_ban_strike_failures(),
+                peer_ban_strike_reputation: default_peer_ban_strike_reputation(),
+                peer_ban_max_failures: default_peer_ban_max_failures(),
+                peer_ban_duration_secs: default_peer_ban_duration_secs(),
             },
             mining: MiningConfig {
                 year_1_reward_microunits: 100_000_000, // 100 QUA
@@ -254,6 +472,20 @@ impl QuantaConfig {
         if self.consensus.min_transaction_fee_microunits == 0 {
             return Err("Minimum transaction fee must be > 0 (prevents spam)".into());
         }
+        if self.consensus.min_deploy_fee_microunits < self.consensus.min_transaction_fee_microunits
+            || self.consensus.min_call_fee_microunits < self.consensus.min_transaction_fee_microunits
+        {
+            return Err("Contract deploy/call minimum fees must be >= the transfer minimum".into());
+        }
+        if self.node.fee_estimate_window_blocks == 0 {
+            return Err("Fee estimate window must be > 0 blocks".into());
+        }
+        if self.node.block_time_window_blocks == 0 {
+            return Err("Block time window must be > 0 blocks".into());
+        }
+        if self.consensus.max_call_data_bytes == 0 {
+            return Err("Max call data bytes must be > 0".into());
+        }
         if self.consensus.transaction_expiry_blocks == 0 {
             return Err("Transaction expiry blocks must be > 0".into());
         }
@@ -282,11 +514,38 @@ impl QuantaConfig {
         if self.security.max_mempool_size == 0 {
             return Err("Max mempool size must be > 0".into());
         }
+        if self.security.max_request_body_bytes == 0 {
+            return Err("Max request body bytes must be > 0".into());
+        }
+        if self.security.max_pending_per_sender == 0 {
+            return Err("Max pending transactions per sender must be > 0".into());
+        }
+        if self.security.peer_ban_strike_failures == 0 {
+            return Err("Peer ban strike failures must be > 0".into());
+        }
+        if self.security.peer_ban_max_failures < self.security.peer_ban_strike_failures {
+            return Err("Peer ban max failures must be >= strike failures".into());
+        }
+        if self.security.peer_ban_strike_reputation >= 0 {
+            return Err("Peer ban strike reputation must be negative".into());
+        }
+        if self.security.peer_ban_duration_secs <= 0 {
+            return Err("Peer ban duration must be > 0 seconds".into());
+        }
         
         // Network sanity
         if self.network.max_peers == 0 {
             return Err("Max peers must be > 0 (unless running solo)".into());
         }
+        if self.network.max_inbound_per_ip_per_sec == 0 || self.network.max_inbound_per_sec == 0 {
+            return Err("Inbound connection rate limits must be > 0".into());
+        }
+        if self.network.max_orphan_requests_per_peer == 0 {
+            return Err("Max orphan requests per peer must be > 0".into());
+        }
+        if self.network.min_peers_to_mine > self.network.max_peers {
+            return Err("Min peers to mine cannot exceed max peers".into());
+        }
         
         Ok(())
     }
@@ -301,13 +560,33 @@ impl QuantaConfig {
         tracing::info!("  Network Port: {}", self.node.network_port);
         tracing::info!("  DB Path: {}", self.node.db_path);
         tracing::info!("  Network Disabled: {}", self.node.no_network);
+        tracing::info!("  Contracts Enabled: {}", self.node.contracts_enabled);
+        tracing::info!("  Repair Chain Height: {}", self.node.repair_chain_height);
+        tracing::info!("  Encrypt DB: {}", self.node.encrypt_db);
+        tracing::info!("  Fee Estimate Window: {} blocks", self.node.fee_estimate_window_blocks);
+        tracing::info!("  Block Time Window: {} blocks", self.node.block_time_window_blocks);
+        tracing::info!("  API Read Replica: {}", self.node.api_read_replica);
+        tracing::info!("  Log Format: {}", self.node.log_format.as_str());
         tracing::info!("Network:");
         tracing::info!("  Max Peers: {}", self.network.max_peers);
         tracing::info!("  Bootstrap Nodes: {:?}", self.network.bootstrap_nodes);
+        tracing::info!("  Inbound Rate: {}/s per IP, {}/s total",
+            self.network.max_inbound_per_ip_per_sec, self.network.max_inbound_per_sec);
+        tracing::info!("  Snapshot Sync: {}", self.network.snapshot_sync);
+        tracing::info!("  P2P Encryption: {:?}", self.network.p2p_encryption);
+        tracing::info!("  Max Orphan Requests Per Peer: {}", self.network.max_orphan_requests_per_peer);
+        tracing::info!("  Min Peers To Mine: {}", self.network.min_peers_to_mine);
         tracing::info!("Consensus (MUST match network):");
         tracing::info!("  Max Block Size: {} bytes", self.consensus.max_block_size_bytes);
         tracing::info!("  Max Block Txs: {}", self.consensus.max_block_transactions);
-        tracing::info!("  Min Fee: {} microunits", self.consensus.min_transaction_fee_microunits);
+        tracing::info!(
+            "  Min Fee: {} microunits (deploy {}, call {})",
+            self.consensus.min_transaction_fee_microunits,
+            self.consensus.min_deploy_fee_microunits,
+            self.consensus.min_call_fee_microunits
+        );
+        tracing::info!("  Min Fee Rate: {} microunits/KB", self.consensus.min_fee_per_kb_microunits);
+        tracing::info!("  Max Call Data: {} bytes", self.consensus.max_call_data_bytes);
         tracing::info!("  Tx Expiry: {} blocks", self.consensus.transaction_expiry_blocks);
         tracing::info!("  Coinbase Maturity: {} blocks", self.consensus.coinbase_maturity);
         tracing::info!("Mining:");
@@ -322,6 +601,19 @@ impl QuantaConfig {
         tracing::info!("  Difficulty Adjustment: {} blocks", self.mining.difficulty_adjustment_interval);
         tracing::info!("Security:");
         tracing::info!("  Max Mempool: {} txs", self.security.max_mempool_size);
+        tracing::info!("  Redact Log Addresses: {}", self.security.redact_log_addresses);
+        tracing::info!("  Max Concurrent API Requests: {}", self.security.max_concurrent_api_requests);
+        tracing::info!("  Max Request Body: {} bytes", self.security.max_request_body_bytes);
+        tracing::info!("  Admin RPC: {}", self.security.enable_admin_rpc);
+        tracing::info!("  Max Orphan Blocks: {}", self.security.max_orphan_blocks);
+        tracing::info!("  Max Pending Per Sender: {} txs", self.security.max_pending_per_sender);
+        tracing::info!(
+            "  Peer Ban: {} failures below reputation {}, or {} failures; {}s per strike",
+            self.security.peer_ban_strike_failures,
+            self.security.peer_ban_strike_reputation,
+            self.security.peer_ban_max_failures,
+            self.security.peer_ban_duration_secs
+        );
         tracing::info!("Metrics:");
         tracing::info!("  Enabled: {}", self.metrics.enabled);
         tracing::info!("  Port: {}", self.metrics.port);
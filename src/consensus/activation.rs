@@ -0,0 +1,78 @@
+use crate::core::block::{HEADER_HASH_BLOCK_VERSION, LEGACY_BLOCK_VERSION};
+use crate::core::ChainNetwork;
+
+/// Mainnet height at which the block rules below take effect (~late November 2026 at 10s blocks)
+const MAINNET_UPGRADE_HEIGHT: u64 = 3_000_000;
+/// Testnet height for the same rules
+const TESTNET_UPGRADE_HEIGHT: u64 = 3_000_000;
+
+/// Heights at which block-validity rules added after launch take effect
+///
+/// CONSENSUS-CRITICAL: a block below a rule's height is validated as nodes without the
+/// rule validate it, so upgraded nodes keep accepting the chain older nodes build and
+/// the two only diverge once the height is reached. Every node on a network must use
+/// the same heights; they are constants, never node configuration.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ActivationHeights {
+    /// User transactions must be in canonical (sender, nonce) order
+    pub canonical_order: u64,
+    /// Contract deployments and calls must pay MIN_DEPLOY_FEE and MIN_CALL_FEE
+    pub type_min_fees: u64,
+    /// Fees must cover the transaction's weight at MIN_FEE_PER_KB
+    pub fee_rate_floor: u64,
+    /// Contract call data must fit in MAX_CALL_DATA_BYTES
+    pub call_data_cap: u64,
+    /// Addresses, function names and call args must fit MAX_ADDRESS_LEN,
+    /// MAX_FUNCTION_NAME_LEN and MAX_CALL_ARGS_BYTES (Transaction::check_field_bounds)
+    pub field_bounds: u64,
+    /// The coinbase timestamp must equal the block timestamp
+    pub coinbase_timestamp: u64,
+    /// Deployments carry no value and calls pay the called contract (check_contract_value)
+    pub contract_value: u64,
+    /// Each coinbase reward unlocks at its own maturity height, instead of all locked
+    /// rewards unlocking together with the newest
+    pub coinbase_tranches: u64,
+    /// Transactions older than TRANSACTION_EXPIRY_SECONDS before the block timestamp are invalid
+    pub transaction_expiry: u64,
+    /// Transactions more than MAX_FUTURE_TRANSACTION_TIME after the block timestamp are invalid
+    pub future_transaction_time: u64,
+    /// Blocks are HEADER_HASH_BLOCK_VERSION: their hash covers the header fields only, so
+    /// header sync can recompute it before downloading the body
+    pub header_hash: u64,
+}
+
+impl ActivationHeights {
+    /// Every rule active from `height`
+    pub const fn all_at(height: u64) -> Self {
+        Self {
+            canonical_order: height,
+            type_min_fees: height,
+            fee_rate_floor: height,
+            call_data_cap: height,
+            field_bounds: height,
+            coinbase_timestamp: height,
+            contract_value: height,
+            coinbase_tranches: height,
+            transaction_expiry: height,
+            future_transaction_time: height,
+            header_hash: height,
+        }
+    }
+
+    /// Version a block at `height` must carry
+    pub fn block_version(&self, height: u64) -> u32 {
+        if height >= self.header_hash {
+            HEADER_HASH_BLOCK_VERSION
+        } else {
+            LEGACY_BLOCK_VERSION
+        }
+    }
+
+    /// Heights in force on `network`
+    pub fn for_network(network: ChainNetwork) -> Self {
+        match network {
+            ChainNetwork::Mainnet => Self::all_at(MAINNET_UPGRADE_HEIGHT),
+            ChainNetwork::Testnet => Self::all_at(TESTNET_UPGRADE_HEIGHT),
+        }
+    }
+}
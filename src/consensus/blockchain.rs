@@ -1,11 +1,23 @@
-use crate::core::block::Block;
-use crate::core::ChainNetwork;
-use crate::core::transaction::{Transaction, AccountState};
+use crate::consensus::activation::ActivationHeights;
+use crate::consensus::fee_estimator::{FeeEstimate, DEFAULT_FEE_ESTIMATE_WINDOW};
+use crate::consensus::mempool::{FeeHistogram, Mempool, MempoolInfo};
+use crate::consensus::orphan_pool::OrphanPool;
+use crate::consensus::replica::{ChainReplica, ChainView};
+use crate::core::block::{Block, BlockHeader};
+use crate::core::snapshot::{SnapshotError, StateSnapshot, SNAPSHOT_CHECKPOINTS};
+use crate::core::{ChainNetwork, TxHash};
+use crate::core::transaction::{Transaction, TransactionType, AccountState, AccountBalance, is_valid_address, MAX_MEMO_BYTES};
+use crate::core::units::{format_qua, MICROUNITS_PER_QUA};
+use crate::core::redact::redact_address;
 use crate::storage::{BlockchainStorage, StorageError};
 use serde::{Serialize, Deserialize};
 use parking_lot::RwLock;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::watch;
+use tokio_util::sync::CancellationToken;
 use dashmap::DashMap;
 
 #[derive(Error, Debug)]
@@ -24,26 +36,56 @@ pub enum BlockchainError {
     InvalidBlock,
     #[error("Mempool full: {0} transactions")]
     MempoolFull(usize),
+    #[error("Sender already has {0} pending transactions (per-sender limit)")]
+    SenderMempoolLimit(usize),
+    #[error("Replacement fee too low: at least {min_fee} microunits required to replace the pending transaction")]
+    ReplacementUnderpriced { min_fee: u64 },
     #[error("Fee too low: {fee} microunits, minimum: {min} microunits")]
     FeeTooLow { fee: u64, min: u64 },
     #[error("Transaction expired")]
     TransactionExpired,
+    #[error("Transaction timestamp is {0}s in the future")]
+    TransactionFromFuture(i64),
+    #[error("Transaction locked until height {not_before} (block height {height})")]
+    TransactionLocked { not_before: u64, height: u64 },
     #[error("Block too large: {size} bytes")]
     BlockTooLarge { size: usize },
     #[error("Invalid coinbase reward: {actual} != {expected}")]
     InvalidCoinbaseReward { actual: u64, expected: u64 },
     #[error("Invalid block difficulty")]
     InvalidDifficulty,
+    #[error("Invalid block version {got} (expected {expected})")]
+    InvalidBlockVersion { expected: u32, got: u32 },
+    #[error("Invalid address: {0}")]
+    InvalidAddress(String),
+    #[error("Contract transactions are disabled on this node")]
+    ContractsDisabled,
+    #[error("Invalid contract transaction: {0}")]
+    InvalidContractValue(String),
+    #[error("Transaction field too large: {0}")]
+    FieldTooLarge(String),
+    #[error("Memo too large: {size} bytes (max {max})")]
+    MemoTooLarge { size: usize, max: usize },
+    #[error("System clock is {0}s behind the chain tip")]
+    ClockBehindTip(i64),
+    #[error("Snapshot rejected: {0}")]
+    Snapshot(#[from] SnapshotError),
+    #[error("Chain tip moved while the block was validated")]
+    StaleTip,
 }
 
 const TARGET_BLOCK_TIME: u64 = 10; // 10 seconds
 const DIFFICULTY_ADJUSTMENT_INTERVAL: u64 = 10; // Adjust every 10 blocks
+pub const DEFAULT_BLOCK_TIME_WINDOW: usize = 20; // Block intervals averaged for pace display
+pub const DEFAULT_MAX_PENDING_PER_SENDER: usize = 64; // Mempool slots one sender may hold
+const REPLACEMENT_FEE_BUMP_PERCENT: u64 = 10; // Minimum fee increase for replace-by-fee
 
 // MODERN ADAPTIVE TOKENOMICS (Option 3 - Solana-style)
 const YEAR_1_REWARD: u64 = 100_000_000; // 100 QUA in microunits
 const ANNUAL_REDUCTION_PERCENT: u64 = 15; // 15% reduction per year (faster value creation)
 const MIN_REWARD: u64 = 5_000_000; // 5 QUA floor (reached after ~20 years)
 const BLOCKS_PER_YEAR: u64 = 3_153_600; // 365.25 days * 86400 / 10 seconds
+const REWARD_FLOOR_YEARS: u64 = 100; // Well past the year the reduction hits MIN_REWARD
 
 // UNIQUE FEATURES - Early Adopter Incentives
 const EARLY_ADOPTER_BONUS_BLOCKS: u64 = 100_000; // First ~11.5 days
@@ -68,18 +110,27 @@ const MINING_REWARD_LOCK_BLOCKS: u64 = 157_680; // 6 months vesting (182.5 days)
 const MAX_MEMPOOL_SIZE: usize = 5000; // Maximum pending transactions
 const MAX_BLOCK_TRANSACTIONS: usize = 2000; // Maximum transactions per block
 const MAX_BLOCK_SIZE_BYTES: usize = 1_048_576; // 1 MB max block size
-const MAX_ORPHAN_BLOCKS: usize = 100; // Maximum orphaned blocks (prevents memory exhaustion)
+const MAX_ORPHAN_BLOCKS: usize = 100; // Default orphan pool cap (prevents memory exhaustion)
+const REORG_UNDO_DEPTH: usize = 10; // Account states kept for rollback; deeper reorgs replay from genesis
 const MAX_TRANSACTION_SIZE_BYTES: usize = 102400; // 100KB max per transaction (prevents DOS)
 const MIN_TRANSACTION_FEE: u64 = 100; // 0.0001 QUA in microunits
+const MIN_DEPLOY_FEE: u64 = 10_000; // 0.01 QUA: deployments add code to every node's storage
+const MIN_CALL_FEE: u64 = 5_000; // 0.005 QUA: calls cost execution on every node
+const MAX_CALL_DATA_BYTES: usize = 16 * 1024; // Contract call instruction data (function + args)
+const MIN_FEE_PER_KB: u64 = 500; // Consensus fee-rate floor: a ~1.7KB signed transfer needs ~850 microunits
 const TRANSACTION_EXPIRY_SECONDS: i64 = 86400; // 24 hours
 const COINBASE_MATURITY: u64 = 100; // Blocks before coinbase can be spent
 const MAX_FUTURE_BLOCK_TIME: i64 = 7200; // 2 hours maximum future timestamp
+const MAX_FUTURE_TRANSACTION_TIME: i64 = 600; // Clock-skew tolerance; later timestamps would dodge expiry
+
+// Genesis distribution credited to the zero address at chain creation
+const GENESIS_ALLOCATION: u64 = 1_000_000_000; // 1000 QUA in microunits
 
 // CONSENSUS-CRITICAL: Genesis block hash (prevents chain split attacks)
 // Generated from Block::genesis() with timestamp 1735689600 (2026-01-01 00:00:00 UTC)
 // Difficulty: 6 (PRODUCTION)
 //  VERIFIED: 2026-01-04 - Hash regenerated with correct parameters
-const GENESIS_HASH: &str = "2c8490a8bfd4d8bbef7315fcf47bab8fa8b3a1d1c8ed2239512ad5191e0ddc22";
+const GENESIS_HASH: &str = "527a8a6ad3292c9b42c40f3d71fd3b89cdd79415106ce0b8d9f7f6690a96433d";
 
 // CHECKPOINT SYSTEM: Hardcoded checkpoints prevent deep reorganizations
 // Format: (block_height, block_hash)
@@ -93,18 +144,55 @@ const CHECKPOINTS: &[(u64, &str)] = &[
 ];
 
 /// Thread-safe blockchain with persistent storage
+///
+/// Lock order: chain -> account_state -> pending_transactions. Never take an earlier lock
+/// while holding a later one (parking_lot locks are writer-fair, so a queued writer turns
+/// an inverted pair of readers into a deadlock)
 pub struct Blockchain {
     chain: Arc<RwLock<Vec<Block>>>,
     pending_transactions: Arc<RwLock<Vec<Transaction>>>,
     account_state: Arc<RwLock<AccountState>>,
+    tx_index: Arc<RwLock<HashMap<TxHash, u64>>>, // Hash -> height of the block holding each mined transaction
+    undo_states: Arc<RwLock<VecDeque<(String, AccountState)>>>, // Account state after each of the blocks just below the tip, by block hash
     pending_nonces: Arc<DashMap<String, u64>>, // ATOMIC: Track highest pending nonce (fixes race condition)
     storage: Arc<BlockchainStorage>,
-    orphaned_blocks: Arc<RwLock<Vec<Block>>>, // Store competing chain blocks for fork resolution
+    orphan_pool: Arc<RwLock<OrphanPool>>, // Blocks awaiting their parent, plus competing blocks for fork resolution
+    nonce_reservation: parking_lot::Mutex<()>, // Serializes reserve-nonce → sign → submit for local senders
+    contracts_enabled: bool, // Node-local: mempool admission of DeployContract/CallContract
+    min_fees: MinFees, // Node-local: mempool fee floor per transaction type
+    min_fee_per_kb: u64, // Node-local: mempool fee-rate floor in microunits per 1000 bytes of weight
+    max_call_data_bytes: usize, // Node-local: mempool cap on CallContract instruction data
+    fee_estimate_window: usize, // Node-local: recent blocks sampled by estimate_fee_rate
+    block_time_window: usize, // Node-local: block intervals averaged by block_timing
+    max_pending_per_sender: usize, // Node-local: mempool transactions one sender may hold
+    activations: ActivationHeights, // Consensus: heights at which post-launch block rules apply
+    network: ChainNetwork,
+    replica: once_cell::sync::OnceCell<ChainReplica>, // Published chain view for lock-free reads (created on first use)
+    tip: watch::Sender<String>, // Tip hash, so miners can abandon work on a stale parent
+    generation: AtomicU64, // Bumped under the chain write lock on every tip change; orders replica views
+    fast_sync: RwLock<Option<StateSnapshot>>, // Verified snapshot ahead of the tip that blocks up to its height are matched against
+}
+
+/// Held while a locally created transaction is built and submitted with a reserved nonce
+pub struct NonceReservation<'a> {
+    pub nonce: u64,
+    _guard: parking_lot::MutexGuard<'a, ()>,
+}
+
+/// Chain and account-state inputs to transaction validation, read before the mempool
+/// lock is taken (see the lock order on Blockchain)
+struct ValidationInputs {
+    height: u64,
+    chain_nonce: u64,
+    balance: u64,
 }
 
 impl Blockchain {
     /// Create or load blockchain from storage
     pub fn new(storage: Arc<BlockchainStorage>, network: ChainNetwork) -> Result<Self, BlockchainError> {
+        // Refuse to start on a height/chain mismatch (repair first via check_chain_height(true))
+        storage.check_chain_height(false)?;
+
         // Try to load existing chain
         let chain = storage.load_chain()?;
         let account_state = storage.load_account_state()?.unwrap_or_else(AccountState::new);
@@ -126,22 +214,7 @@ impl Blockchain {
                 tracing::info!("Testnet Genesis Hash: {}", genesis.hash);
             }
             
-            let mut account_state = AccountState::new();
-            
-            // Genesis distribution
-            let genesis_address = "0x0000000000000000000000000000000000000000";
-            let genesis_tx = Transaction {
-                sender: "COINBASE".to_string(),
-                recipient: genesis_address.to_string(),
-                amount: 1_000_000_000, // 1000 QUA in microunits
-                timestamp: genesis.timestamp,
-                signature: vec![],
-                public_key: vec![],
-                fee: 0,
-                nonce: 0,
-                tx_type: crate::core::transaction::TransactionType::Transfer,
-            };
-            account_state.credit_account(&genesis_tx, 0, COINBASE_MATURITY);
+            let account_state = Self::genesis_state(&genesis);
             
             storage.save_block(&genesis)?;
             storage.set_chain_height(1)?;
@@ -161,17 +234,287 @@ impl Blockchain {
             let difficulty = chain.last().map(|b| b.difficulty).unwrap_or(4);
             (chain, account_state, difficulty)
         };
+        let tip_hash = chain.last().map(|b| b.hash.clone()).unwrap_or_default();
+        // Resume a snapshot sync that was cut short (the snapshot is re-verified)
+        let fast_sync = match storage.load_snapshot()? {
+            Some(snapshot) => match Self::check_snapshot(&chain, &snapshot) {
+                Ok(()) if snapshot.height >= chain.len() as u64 => {
+                    tracing::info!("Resuming snapshot sync to height {}", snapshot.height);
+                    Some(snapshot)
+                }
+                Ok(()) => None,
+                Err(e) => {
+                    tracing::warn!("Ignoring stored state snapshot: {}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let tx_index = chain
+            .iter()
+            .flat_map(|block| block.transactions.iter().map(|tx| (tx.hash(), block.index)))
+            .collect();
 
         Ok(Self {
             chain: Arc::new(RwLock::new(chain)),
             pending_transactions: Arc::new(RwLock::new(Vec::new())),
             account_state: Arc::new(RwLock::new(account_state)),
+            tx_index: Arc::new(RwLock::new(tx_index)),
+            undo_states: Arc::new(RwLock::new(VecDeque::new())),
             pending_nonces: Arc::new(DashMap::new()), // Concurrent HashMap - no lock needed
             storage,
-            orphaned_blocks: Arc::new(RwLock::new(Vec::new())),
+            orphan_pool: Arc::new(RwLock::new(OrphanPool::new(MAX_ORPHAN_BLOCKS))),
+            nonce_reservation: parking_lot::Mutex::new(()),
+            contracts_enabled: true,
+            min_fees: MinFees::default(),
+            min_fee_per_kb: MIN_FEE_PER_KB,
+            max_call_data_bytes: MAX_CALL_DATA_BYTES,
+            fee_estimate_window: DEFAULT_FEE_ESTIMATE_WINDOW,
+            block_time_window: DEFAULT_BLOCK_TIME_WINDOW,
+            max_pending_per_sender: DEFAULT_MAX_PENDING_PER_SENDER,
+            activations: ActivationHeights::for_network(network),
+            network,
+            replica: once_cell::sync::OnceCell::new(),
+            tip: watch::channel(tip_hash).0,
+            generation: AtomicU64::new(0),
+            fast_sync: RwLock::new(fast_sync),
+        })
+    }
+
+    /// Watch the tip hash (updated whenever a block is connected)
+    pub fn subscribe_tip(&self) -> watch::Receiver<String> {
+        self.tip.subscribe()
+    }
+
+    /// Network this chain was opened for
+    pub fn network(&self) -> ChainNetwork {
+        self.network
+    }
+
+    /// Whether contract transactions are admitted to the mempool
+    pub fn contracts_enabled(&self) -> bool {
+        self.contracts_enabled
+    }
+
+    /// Account state right after genesis (the genesis allocation)
+    fn genesis_state(genesis: &Block) -> AccountState {
+        let mut account_state = AccountState::new();
+        let genesis_address = "0x0000000000000000000000000000000000000000";
+        let genesis_tx = Transaction {
+            sender: "COINBASE".to_string(),
+            recipient: genesis_address.to_string(),
+            amount: GENESIS_ALLOCATION,
+            timestamp: genesis.timestamp,
+            signature: vec![],
+            public_key: vec![],
+            fee: 0,
+            nonce: 0,
+            tx_type: crate::core::transaction::TransactionType::Transfer,
+            not_before_height: None,
+            memo: None,
+        };
+        account_state.credit_account(&genesis_tx, 0, COINBASE_MATURITY);
+        account_state
+    }
+
+    /// Apply a block's transactions to `state` (nonce, balance, credits, coinbase unlock)
+    fn apply_block(&self, state: &mut AccountState, block: &Block) -> Result<(), BlockchainError> {
+        // Unlock any mature coinbase rewards
+        state.unlock_mature_coinbase(block.index);
+
+        for tx in &block.transactions {
+            if !tx.is_coinbase() && tx.sender != "TREASURY" {
+                // Nonce must follow the evolving state (debit below bumps it)
+                let expected_nonce = state.get_nonce(&tx.sender) + 1;
+                if tx.nonce != expected_nonce {
+                    tracing::warn!("Network block has invalid tx: nonce {} from {}, expected {}",
+                        tx.nonce, tx.sender, expected_nonce);
+                    return Err(BlockchainError::InvalidNonce {
+                        expected: expected_nonce,
+                        actual: tx.nonce,
+                    });
+                }
+
+                let total = tx.amount.saturating_add(tx.fee);
+                if !state.debit_account(&tx.sender, total) {
+                    tracing::warn!("Network block has invalid tx: insufficient balance");
+                    return Err(BlockchainError::InvalidBlock);
+                }
+            }
+            state.credit_account(tx, block.index, COINBASE_MATURITY);
+            if tx.is_coinbase() && block.index < self.activations.coinbase_tranches {
+                state.merge_locked_tranches(&tx.recipient);
+            }
+        }
+        Ok(())
+    }
+
+    /// Override the rule activation heights (tests exercise rules at low heights)
+    #[cfg(test)]
+    pub fn with_activations(mut self, activations: ActivationHeights) -> Self {
+        self.activations = activations;
+        self
+    }
+
+    /// Heights at which post-launch block rules apply on this chain
+    pub fn activations(&self) -> ActivationHeights {
+        self.activations
+    }
+
+    /// Override the per-type mempool fee floors (node-local; blocks are held to the constants)
+    pub fn with_min_fees(mut self, min_fees: MinFees) -> Self {
+        self.min_fees = min_fees;
+        self
+    }
+
+    /// Override the mempool fee-rate floor (relay policy: blocks are held to MIN_FEE_PER_KB)
+    pub fn with_min_fee_per_kb(mut self, min_fee_per_kb: u64) -> Self {
+        self.min_fee_per_kb = min_fee_per_kb;
+        self
+    }
+
+    /// Override the mempool call data cap (relay policy: blocks are held to MAX_CALL_DATA_BYTES)
+    pub fn with_max_call_data_bytes(mut self, max_call_data_bytes: usize) -> Self {
+        self.max_call_data_bytes = max_call_data_bytes;
+        self
+    }
+
+    /// Reject CallContract transactions whose instruction data exceeds `max` bytes
+    fn check_call_data(tx: &Transaction, max: usize) -> Result<(), BlockchainError> {
+        match tx.call_data_len() {
+            Some(len) if len > max => Err(BlockchainError::FieldTooLarge(format!(
+                "call data is {} bytes (max {})",
+                len, max
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reject memos over MAX_MEMO_BYTES
+    fn check_memo(tx: &Transaction) -> Result<(), BlockchainError> {
+        match tx.memo {
+            Some(ref memo) if memo.len() > MAX_MEMO_BYTES => {
+                Err(BlockchainError::MemoTooLarge { size: memo.len(), max: MAX_MEMO_BYTES })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Minimum fee for `tx` to enter the mempool: the floor for its type or its weight at
+    /// the node's fee-rate floor, and never less than the next block must pay
+    pub fn required_fee(&self, tx: &Transaction) -> u64 {
+        self.required_fee_at(tx, self.get_height())
+    }
+
+    /// required_fee with the chain height given explicitly
+    fn required_fee_at(&self, tx: &Transaction, height: u64) -> u64 {
+        let by_weight = Self::fee_for_weight_at(tx, self.min_fee_per_kb);
+        self.min_fees
+            .for_type(&tx.tx_type)
+            .max(by_weight)
+            .max(self.block_min_fee(tx, height))
+    }
+
+    /// Minimum fee for `tx` in a block at `height`. The per-type and fee-rate floors are the
+    /// MinFees::default and MIN_FEE_PER_KB constants from their activation heights, never the
+    /// node's configured mempool floors
+    fn block_min_fee(&self, tx: &Transaction, height: u64) -> u64 {
+        let mut min_fee = MIN_TRANSACTION_FEE;
+        if height >= self.activations.type_min_fees {
+            min_fee = MinFees::default().for_type(&tx.tx_type);
+        }
+        if height >= self.activations.fee_rate_floor {
+            min_fee = min_fee.max(Self::fee_for_weight_at(tx, MIN_FEE_PER_KB));
+        }
+        min_fee
+    }
+
+    /// Fee for `tx`'s weight at `fee_per_kb` microunits per 1000 bytes
+    fn fee_for_weight_at(tx: &Transaction, fee_per_kb: u64) -> u64 {
+        (tx.weight() as u64).saturating_mul(fee_per_kb).div_ceil(1000)
+    }
+
+    /// Fee rate (microunits per byte of weight) and flat fee paid by recently confirmed
+    /// transactions, floored at the consensus minimums
+    pub fn estimate_fee_rate(&self) -> FeeEstimate {
+        let chain = self.chain.read();
+        let start = chain.len().saturating_sub(self.fee_estimate_window);
+        FeeEstimate::from_transactions(
+            chain[start..].iter().flat_map(|block| block.transactions.iter()),
+            self.min_fees.transfer,
+            self.min_fee_per_kb,
+        )
+    }
+
+    /// Override the orphan pool cap (node-local memory bound)
+    pub fn with_max_orphan_blocks(self, max_orphan_blocks: usize) -> Self {
+        *self.orphan_pool.write() = OrphanPool::new(max_orphan_blocks);
+        self
+    }
+
+    /// Whether `hash` is held in the orphan pool waiting for its parent
+    pub fn has_orphan(&self, hash: &str) -> bool {
+        self.orphan_pool.read().contains(hash)
+    }
+
+    /// Number of blocks held in the orphan pool
+    #[cfg(test)]
+    pub fn orphan_count(&self) -> usize {
+        self.orphan_pool.read().len()
+    }
+
+    /// Override how many recent block intervals block_timing averages
+    pub fn with_block_time_window(mut self, intervals: usize) -> Self {
+        self.block_time_window = intervals;
+        self
+    }
+
+    /// Smoothed pace of recent blocks against the target block time
+    /// None until at least one interval after genesis exists
+    pub fn block_timing(&self) -> Option<BlockTiming> {
+        Self::block_timing_for(&self.chain.read(), self.block_time_window)
+    }
+
+    /// Pace over the last `window` intervals of `chain`; the genesis interval is skipped
+    /// (genesis has a fixed historical timestamp)
+    fn block_timing_for(chain: &[Block], window: usize) -> Option<BlockTiming> {
+        let mined = chain.get(1..)?;
+        let intervals = window.min(mined.len().saturating_sub(1));
+        if intervals == 0 {
+            return None;
+        }
+        let last = mined.last()?;
+        let first = &mined[mined.len() - 1 - intervals];
+
+        let actual = (last.timestamp - first.timestamp) as f64;
+        let expected = (TARGET_BLOCK_TIME * intervals as u64) as f64;
+        Some(BlockTiming {
+            window_blocks: intervals as u64,
+            avg_block_time_secs: actual / intervals as f64,
+            target_block_time_secs: TARGET_BLOCK_TIME,
+            blocks_ahead_of_target: (expected - actual) / TARGET_BLOCK_TIME as f64,
         })
     }
 
+    /// Override how many recent blocks estimate_fee_rate samples
+    pub fn with_fee_estimate_window(mut self, blocks: usize) -> Self {
+        self.fee_estimate_window = blocks;
+        self
+    }
+
+    /// Cap the mempool transactions a single sender may hold (node-local fairness policy)
+    pub fn with_max_pending_per_sender(mut self, limit: usize) -> Self {
+        self.max_pending_per_sender = limit;
+        self
+    }
+
+    /// Enable or disable mempool admission of contract transactions
+    /// Node-local policy: blocks from peers containing contract txs are still accepted
+    pub fn with_contracts_enabled(mut self, enabled: bool) -> Self {
+        self.contracts_enabled = enabled;
+        self
+    }
+
     /// Validate block against checkpoints (prevents deep reorgs)
     fn validate_checkpoint(&self, height: u64, hash: &str) -> bool {
         for (checkpoint_height, checkpoint_hash) in CHECKPOINTS {
@@ -195,70 +538,82 @@ impl Blockchain {
         self.chain.read().last().unwrap().clone()
     }
 
-    /// Add a new transaction to the mempool
-    pub fn add_transaction(&self, transaction: Transaction) -> Result<(), BlockchainError> {
-        // Skip validation for coinbase transactions
-        if transaction.is_coinbase() {
-            self.pending_transactions.write().push(transaction);
-            return Ok(());
-        }
+    /// Validate a transaction against current chain + mempool state without side effects
+    /// Covers fee, expiry, address format, size, signature, nonce and balance
+    #[allow(dead_code)]
+    pub fn validate_transaction(&self, transaction: &Transaction) -> Result<(), BlockchainError> {
+        let inputs = self.validation_inputs(&transaction.sender);
+        self.check_transaction(transaction, &inputs)?;
+        Self::check_nonce(transaction, self.next_nonce_after(&transaction.sender, inputs.chain_nonce))
+    }
 
-        // Check mempool size limit
-        let pending_count = self.pending_transactions.read().len();
-        if pending_count >= MAX_MEMPOOL_SIZE {
-            return Err(BlockchainError::MempoolFull(pending_count));
+    /// Height and the sender's confirmed nonce and balance, for check_transaction
+    fn validation_inputs(&self, sender: &str) -> ValidationInputs {
+        let height = self.get_height();
+        let state = self.account_state.read();
+        ValidationInputs {
+            height,
+            chain_nonce: state.get_nonce(sender),
+            balance: state.get_balance(sender),
         }
+    }
 
-        // Validate minimum fee
-        if transaction.fee < MIN_TRANSACTION_FEE {
+    /// Every validate_transaction rule except the nonce, which depends on the mempool
+    /// Takes no lock, so the signature check never runs under the mempool lock
+    fn check_transaction(&self, transaction: &Transaction, inputs: &ValidationInputs) -> Result<(), BlockchainError> {
+        // Bound variable-length fields before any hashing or signature work
+        transaction.check_field_bounds().map_err(BlockchainError::FieldTooLarge)?;
+        Self::check_memo(transaction)?;
+        Self::check_call_data(transaction, self.max_call_data_bytes.min(MAX_CALL_DATA_BYTES))?;
+
+        // Validate minimum fee (absolute floor and fee rate by weight)
+        let required_fee = self.required_fee_at(transaction, inputs.height);
+        if transaction.fee < required_fee {
             return Err(BlockchainError::FeeTooLow {
                 fee: transaction.fee,
-                min: MIN_TRANSACTION_FEE,
+                min: required_fee,
             });
         }
 
+        // Payments-only nodes refuse contract transactions outright
+        if !self.contracts_enabled && !matches!(transaction.tx_type, TransactionType::Transfer) {
+            return Err(BlockchainError::ContractsDisabled);
+        }
+        transaction.check_contract_value().map_err(BlockchainError::InvalidContractValue)?;
+
         // Check transaction expiry (replay protection)
         let current_time = chrono::Utc::now().timestamp();
         if transaction.timestamp < current_time - TRANSACTION_EXPIRY_SECONDS {
             return Err(BlockchainError::TransactionExpired);
         }
+        if transaction.timestamp > current_time + MAX_FUTURE_TRANSACTION_TIME {
+            return Err(BlockchainError::TransactionFromFuture(transaction.timestamp - current_time));
+        }
 
-        // Verify signature
-        if !transaction.verify() {
-            return Err(BlockchainError::InvalidSignature);
+        // Address format (deployments have no recipient yet)
+        if !is_valid_address(&transaction.sender) {
+            return Err(BlockchainError::InvalidAddress(transaction.sender.clone()));
         }
-        
-        // Validate nonce (account-based model) - ATOMIC OPERATION (no race condition)
-        let chain_nonce = self.account_state.read().get_nonce(&transaction.sender);
-        
-        // CRITICAL FIX: Atomic check-and-increment using DashMap
-        // This prevents two parallel txs from using the same nonce
-        let expected_nonce = self.pending_nonces
-            .entry(transaction.sender.clone())
-            .or_insert(chain_nonce)
-            .value()
-            .max(&chain_nonce) + 1;
-        
-        if transaction.nonce != expected_nonce {
-            return Err(BlockchainError::InvalidNonce {
-                expected: expected_nonce,
-                actual: transaction.nonce,
-            });
+        if !matches!(transaction.tx_type, TransactionType::DeployContract { .. })
+            && !is_valid_address(&transaction.recipient)
+        {
+            return Err(BlockchainError::InvalidAddress(transaction.recipient.clone()));
         }
-        
-        // ATOMIC: Update pending nonce (no race - single map entry lock)
-        self.pending_nonces.insert(transaction.sender.clone(), transaction.nonce);
 
         // Check transaction size limit (DOS protection - prevents huge DeployContract)
-        let tx_size = bincode::serialize(&transaction).map_err(|_| BlockchainError::InvalidBlock)?.len();
+        let tx_size = bincode::serialize(transaction).map_err(|_| BlockchainError::InvalidBlock)?.len();
         if tx_size > MAX_TRANSACTION_SIZE_BYTES {
             return Err(BlockchainError::BlockTooLarge { size: tx_size }); // Reuse error type
         }
 
+        // Verify signature
+        if !transaction.verify() {
+            return Err(BlockchainError::InvalidSignature);
+        }
+
         // Check sender has sufficient balance (amount + fee)
         let total_required = transaction.amount.saturating_add(transaction.fee);
-        let available = self.account_state.read().get_balance(&transaction.sender);
-        
+        let available = inputs.balance;
         if available < total_required {
             return Err(BlockchainError::InsufficientBalance {
                 required: total_required,
@@ -266,50 +621,173 @@ impl Blockchain {
             });
         }
 
+        Ok(())
+    }
+
+    /// Validate nonce (account-based model); a replacement reuses the nonce of the pending
+    /// transaction it evicts, anything else must carry the sender's next nonce
+    fn check_nonce(transaction: &Transaction, expected_nonce: u64) -> Result<(), BlockchainError> {
+        if transaction.nonce != expected_nonce {
+            return Err(BlockchainError::InvalidNonce {
+                expected: expected_nonce,
+                actual: transaction.nonce,
+            });
+        }
+        Ok(())
+    }
+
+    /// Add a new transaction to the mempool
+    pub fn add_transaction(&self, transaction: Transaction) -> Result<(), BlockchainError> {
+        // Skip validation for coinbase transactions
+        if transaction.is_coinbase() {
+            self.pending_transactions.write().push(transaction);
+            return Ok(());
+        }
+
+        // Everything but the nonce first, without the mempool lock: field bounds, fee floor,
+        // signature and balance don't depend on what else is pending (and chain and account
+        // inputs must be read before it, per the lock order)
+        let inputs = self.validation_inputs(&transaction.sender);
+        self.check_transaction(&transaction, &inputs)?;
+
+        // Hold the mempool lock across the nonce check + insert so two parallel txs
+        // cannot both pass the nonce check for the same slot
+        let mut pending = self.pending_transactions.write();
+
+        // Same (sender, nonce) as a pending transaction: replace-by-fee, which takes no new slot
+        if let Some(index) = self.replaceable_index(&pending, &transaction) {
+            let existing = &pending[index];
+            let min_fee = existing
+                .fee
+                .saturating_add(existing.fee.saturating_mul(REPLACEMENT_FEE_BUMP_PERCENT).div_ceil(100).max(1));
+            if transaction.fee < min_fee {
+                return Err(BlockchainError::ReplacementUnderpriced { min_fee });
+            }
+
+            tracing::info!(
+                "Replaced mempool transaction {} (fee {} -> {})",
+                existing.hash(), existing.fee, transaction.fee
+            );
+            let sender = transaction.sender.clone();
+            pending[index] = transaction;
+            self.resync_pending_nonce(&sender, &pending);
+            return Ok(());
+        }
+
+        // Check mempool size limit
+        if pending.len() >= MAX_MEMPOOL_SIZE {
+            return Err(BlockchainError::MempoolFull(pending.len()));
+        }
+
+        Self::check_nonce(&transaction, self.next_nonce_after(&transaction.sender, inputs.chain_nonce))?;
+
         // Check for duplicate by hash (not sender - multiple txs from same sender OK if nonces differ)
         let tx_hash = transaction.hash();
-        let pending = self.pending_transactions.read();
-        for pending_tx in pending.iter() {
-            if pending_tx.hash() == tx_hash {
-                return Err(BlockchainError::DuplicateTransaction);
-            }
+        if pending.iter().any(|pending_tx| pending_tx.hash() == tx_hash) {
+            return Err(BlockchainError::DuplicateTransaction);
+        }
+
+        // One sender can't crowd out everyone else with a long run of sequential nonces
+        let from_sender = pending.iter().filter(|tx| tx.sender == transaction.sender).count();
+        if from_sender >= self.max_pending_per_sender {
+            return Err(BlockchainError::SenderMempoolLimit(from_sender));
         }
-        drop(pending);
 
-        self.pending_transactions.write().push(transaction);
+        self.pending_nonces.insert(transaction.sender.clone(), transaction.nonce);
+        pending.push(transaction);
         tracing::info!("Transaction added to mempool");
         Ok(())
     }
 
+    /// Position of the pending transaction `transaction` would replace: same sender and a
+    /// nonce at or below the sender's tracked pending nonce (an identical resubmission is
+    /// not a replacement and fails the nonce check as before)
+    fn replaceable_index(&self, pending: &[Transaction], transaction: &Transaction) -> Option<usize> {
+        let pending_nonce = *self.pending_nonces.get(&transaction.sender)?.value();
+        if transaction.nonce > pending_nonce {
+            return None;
+        }
+        pending
+            .iter()
+            .position(|tx| tx.sender == transaction.sender && tx.nonce == transaction.nonce)
+            .filter(|&index| pending[index].hash() != transaction.hash())
+    }
+
+    /// Remove mempool transactions matching `predicate`, plus any later-nonce txs from the
+    /// same senders (they can no longer be mined), and resync those senders' pending nonces.
+    /// Every removal path other than block inclusion should go through here.
+    pub fn drop_pending_transactions<F: Fn(&Transaction) -> bool>(&self, predicate: F) -> usize {
+        let mut pending = self.pending_transactions.write();
+
+        // Lowest dropped nonce per sender
+        let mut cutoff: BTreeMap<String, u64> = BTreeMap::new();
+        for tx in pending.iter().filter(|tx| !tx.is_coinbase() && predicate(tx)) {
+            let nonce = cutoff.entry(tx.sender.clone()).or_insert(tx.nonce);
+            *nonce = (*nonce).min(tx.nonce);
+        }
+
+        let before = pending.len();
+        pending.retain(|tx| cutoff.get(&tx.sender).is_none_or(|&min| tx.nonce < min));
+        for sender in cutoff.keys() {
+            self.resync_pending_nonce(sender, &pending);
+        }
+        before - pending.len()
+    }
+
+    /// Drop transactions older than the expiry window
+    pub fn prune_expired_transactions(&self) -> usize {
+        let cutoff = chrono::Utc::now().timestamp() - TRANSACTION_EXPIRY_SECONDS;
+        let dropped = self.drop_pending_transactions(|tx| tx.timestamp < cutoff);
+        if dropped > 0 {
+            tracing::info!("Pruned {} expired transaction(s) from mempool", dropped);
+        }
+        dropped
+    }
+
+    /// Set the tracked pending nonce for `sender` from what is actually in `pending`
+    fn resync_pending_nonce(&self, sender: &str, pending: &[Transaction]) {
+        match pending.iter().filter(|tx| tx.sender == sender).map(|tx| tx.nonce).max() {
+            Some(nonce) => {
+                self.pending_nonces.insert(sender.to_string(), nonce);
+            }
+            None => {
+                self.pending_nonces.remove(sender);
+            }
+        }
+    }
+
     /// Create a block template for mining (does not mine or save)
     pub fn create_block_template(&self, miner_address: String) -> Result<Block, BlockchainError> {
+        self.create_block_template_at(miner_address, chrono::Utc::now().timestamp())
+    }
+
+    /// Block template stamped from a clock reading of `now`
+    /// A clock slightly behind the tip is clamped to tip + 1 (otherwise our own block would
+    /// fail the timestamp rule); one behind by more than MAX_FUTURE_BLOCK_TIME is an error
+    fn create_block_template_at(&self, miner_address: String, now: i64) -> Result<Block, BlockchainError> {
+        // A malformed coinbase recipient would lock the reward forever
+        if !is_valid_address(&miner_address) {
+            return Err(BlockchainError::InvalidAddress(miner_address));
+        }
+
+        let previous = self.get_latest_block();
+        let timestamp = now.max(previous.timestamp + 1);
+        if timestamp > now + MAX_FUTURE_BLOCK_TIME {
+            return Err(BlockchainError::ClockBehindTip(previous.timestamp - now));
+        }
+        if now < previous.timestamp {
+            tracing::warn!("Clock is {}s behind the chain tip; stamping block at tip + 1", previous.timestamp - now);
+        }
+        
+        // Expired txs would fail validation; drop them (and their nonce reservations) first
+        self.prune_expired_transactions();
+
         let reward = self.get_mining_reward();
         let difficulty = self.calculate_next_difficulty();
         
-        // Get pending transactions sorted by fee (highest first) with size limits
-        let mut pending_txs = self.pending_transactions.write();
-        
-        // Sort by fee descending (highest fee first)
-        let mut sorted_txs = pending_txs.clone();
-        sorted_txs.sort_by(|a, b| b.fee.cmp(&a.fee));
-        
-        let mut transactions = Vec::new();
-        let mut block_size = 0usize;
-        
         // Select transactions that fit in block limits (prioritize high fees)
-        for tx in sorted_txs.iter() {
-            if transactions.len() >= MAX_BLOCK_TRANSACTIONS {
-                break;
-            }
-            
-            let tx_size = bincode::serialize(tx).unwrap_or_default().len();
-            if block_size + tx_size > MAX_BLOCK_SIZE_BYTES {
-                break;
-            }
-            
-            transactions.push(tx.clone());
-            block_size += tx_size;
-        }
+        let index = self.chain.read().len() as u64;
+        let transactions = self.next_block_transactions(index);
         
         // Create coinbase transaction with fee distribution
         let total_fees: u64 = transactions.iter().map(|tx| tx.fee).sum();
@@ -329,8 +807,8 @@ impl Blockchain {
         
         tracing::info!(
             "Mining Economics: Reward={} QUA, Treasury={} QUA, Fees Burned={} QUA, Locked={} QUA",
-            reward / 1_000_000, treasury_allocation / 1_000_000,
-            fee_burned / 1_000_000, locked_reward / 1_000_000
+            format_qua(reward), format_qua(treasury_allocation),
+            format_qua(fee_burned), format_qua(locked_reward)
         );
         
         // Coinbase transaction (immediate + fees to miner)
@@ -339,12 +817,14 @@ impl Blockchain {
             sender: "COINBASE".to_string(),
             recipient: miner_address.clone(),
             amount: coinbase_amount,
-            timestamp: chrono::Utc::now().timestamp(),
+            timestamp,
             signature: vec![],
             public_key: vec![],
             fee: 0,
             nonce: 0,
             tx_type: crate::core::transaction::TransactionType::Transfer,
+            not_before_height: None,
+            memo: None,
         };
         
         // Treasury allocation transaction (if any)
@@ -355,27 +835,163 @@ impl Blockchain {
                 sender: "TREASURY".to_string(),
                 recipient: TREASURY_ADDRESS.to_string(),
                 amount: treasury_allocation.saturating_add(fee_to_treasury),
-                timestamp: chrono::Utc::now().timestamp(),
+                timestamp,
                 signature: vec![],
                 public_key: vec![],
                 fee: 0,
                 nonce: 0,
                 tx_type: crate::core::transaction::TransactionType::Transfer,
+                not_before_height: None,
+                memo: None,
             };
             all_transactions.push(treasury_tx);
         }
         
         all_transactions.extend(transactions);
 
+        // Consensus requires canonical order; the selected set is always orderable
+        let mut state = self.account_state.read().clone();
+        state.unlock_mature_coinbase(index);
+        if let Some(ordered) = Self::canonical_order(&all_transactions, &state, index) {
+            all_transactions = ordered;
+        }
+
         // Create new block (unmined)
-        let previous_hash = self.get_latest_block().hash.clone();
-        let index = self.chain.read().len() as u64;
-        let new_block = Block::new(index, all_transactions, previous_hash, difficulty);
+        let mut new_block = Block::try_new(index, all_transactions, previous.hash, difficulty).map_err(|e| {
+            tracing::error!("{}", e);
+            BlockchainError::InvalidBlock
+        })?;
+        new_block.timestamp = timestamp;
+        new_block.version = self.activations.block_version(index);
+        new_block.hash = new_block.calculate_hash().into();
         
         // Don't mine or save here. Just return the template.
         Ok(new_block)
     }
 
+    /// Pending transactions the block at `index` would carry, highest fee first
+    fn next_block_transactions(&self, index: u64) -> Vec<Transaction> {
+        // Expired txs can't be mined (templates prune them first; previews just skip them)
+        let cutoff = chrono::Utc::now().timestamp() - TRANSACTION_EXPIRY_SECONDS;
+        let mut sorted_txs: Vec<Transaction> = self
+            .pending_transactions
+            .read()
+            .iter()
+            .filter(|tx| tx.timestamp >= cutoff)
+            .cloned()
+            .collect();
+        sorted_txs.sort_by(|a, b| b.fee.cmp(&a.fee));
+        self.select_block_transactions(sorted_txs, index)
+    }
+
+    /// Transactions the next block template would include, in block order, with their
+    /// total fees. Same selection as mining (fee order, nonce and balance dependencies,
+    /// size/count limits) but nothing is pruned, built or mined
+    pub fn preview_next_block(&self) -> BlockPreview {
+        let index = self.chain.read().len() as u64;
+        let selected = self.next_block_transactions(index);
+        let mut state = self.account_state.read().clone();
+        state.unlock_mature_coinbase(index);
+        let transactions = Self::canonical_order(&selected, &state, index).unwrap_or(selected);
+        BlockPreview {
+            height: index,
+            total_fees: transactions.iter().map(|tx| tx.fee).sum(),
+            transactions,
+        }
+    }
+
+    /// Pick transactions for the block at `index` in fee order, respecting intra-block
+    /// dependencies: a tx is only placed once its nonce is next and the sender can pay
+    /// given everything placed before it (e.g. funds received earlier in the same block)
+    fn select_block_transactions(&self, mut remaining: Vec<Transaction>, index: u64) -> Vec<Transaction> {
+        let mut state = self.account_state.read().clone();
+        state.unlock_mature_coinbase(index);
+
+        let mut transactions = Vec::new();
+        let mut block_size = 0usize;
+
+        // Each pass places every tx that became ready; stop when a pass places nothing
+        loop {
+            let mut deferred = Vec::new();
+            let mut placed_any = false;
+
+            for tx in remaining {
+                if transactions.len() >= MAX_BLOCK_TRANSACTIONS {
+                    return transactions;
+                }
+
+                // Locktimed txs (and later nonces from the same sender) wait in the mempool
+                let ready = tx.is_final_at(index)
+                    && tx.nonce == state.get_nonce(&tx.sender) + 1
+                    && state.get_balance(&tx.sender) >= tx.amount.saturating_add(tx.fee);
+                if !ready {
+                    deferred.push(tx);
+                    continue;
+                }
+
+                let tx_size = bincode::serialize(&tx).unwrap_or_default().len();
+                if block_size + tx_size > MAX_BLOCK_SIZE_BYTES {
+                    return transactions;
+                }
+
+                state.debit_account(&tx.sender, tx.amount.saturating_add(tx.fee));
+                state.credit_account(&tx, index, COINBASE_MATURITY);
+                transactions.push(tx);
+                block_size += tx_size;
+                placed_any = true;
+            }
+
+            if !placed_any || deferred.is_empty() {
+                return transactions;
+            }
+            remaining = deferred;
+        }
+    }
+
+    /// Canonical block order: coinbase, treasury, then user txs where each step takes the
+    /// lowest (sender, nonce) tx that is valid against the state so far. Equivalent to
+    /// sorting by sender then nonce, except a spend of funds received in the same block
+    /// waits for the receipt. None if some tx can never become valid.
+    fn canonical_order(transactions: &[Transaction], state: &AccountState, index: u64) -> Option<Vec<Transaction>> {
+        let mut state = state.clone();
+        let (mut ordered, user): (Vec<_>, Vec<_>) = transactions
+            .iter()
+            .cloned()
+            .partition(|tx| tx.is_coinbase() || tx.sender == "TREASURY");
+        ordered.sort_by_key(|tx| !tx.is_coinbase()); // stable: coinbase, then treasury
+
+        // Per-sender queues in nonce order; BTreeMap visits senders in address order
+        let mut queues: BTreeMap<String, VecDeque<Transaction>> = BTreeMap::new();
+        for tx in user {
+            queues.entry(tx.sender.clone()).or_default().push_back(tx);
+        }
+        for queue in queues.values_mut() {
+            queue.make_contiguous().sort_by_key(|tx| tx.nonce);
+        }
+
+        while !queues.is_empty() {
+            let sender = queues
+                .iter()
+                .find(|(sender, queue)| {
+                    let tx = &queue[0];
+                    tx.nonce == state.get_nonce(sender) + 1
+                        && state.get_balance(sender) >= tx.amount.saturating_add(tx.fee)
+                })
+                .map(|(sender, _)| sender.clone())?;
+
+            let queue = queues.get_mut(&sender)?;
+            let tx = queue.pop_front()?;
+            if queue.is_empty() {
+                queues.remove(&sender);
+            }
+            state.debit_account(&tx.sender, tx.amount.saturating_add(tx.fee));
+            state.credit_account(&tx, index, COINBASE_MATURITY);
+            ordered.push(tx);
+        }
+
+        Some(ordered)
+    }
+
     /// Mine a new block with pending transactions (BLOCKING - for CLI use)
     pub fn mine_pending_transactions(&self, miner_address: String) -> Result<(), BlockchainError> {
         // Create template and mine synchronously
@@ -386,15 +1002,13 @@ impl Blockchain {
 
     /// Get current mining reward with adaptive model (u64 microunits)
     fn get_mining_reward(&self) -> u64 {
-        let chain_len = self.chain.read().len() as u64;
-        
-        // Calculate base reward with annual reduction
-        let years_elapsed = chain_len / BLOCKS_PER_YEAR;
-        let reduction_factor = (100 - ANNUAL_REDUCTION_PERCENT) as f64 / 100.0;
-        let base_reward = (YEAR_1_REWARD as f64 * reduction_factor.powi(years_elapsed as i32)).round() as u64;
-        
-        // Apply minimum floor
-        let base_reward = base_reward.max(MIN_REWARD);
+        Self::mining_reward_for(&self.chain.read())
+    }
+
+    /// Mining reward for the block following `chain`
+    fn mining_reward_for(chain: &[Block]) -> u64 {
+        let chain_len = chain.len() as u64;
+        let base_reward = Self::base_reward_at(chain_len);
         
         // UNIQUE FEATURE 1: Early adopter bonus (first 100k blocks)
         let reward_with_bonus = if chain_len < EARLY_ADOPTER_BONUS_BLOCKS {
@@ -406,7 +1020,7 @@ impl Blockchain {
         // UNIQUE FEATURE 2: Network usage adjustment during bootstrap
         let final_reward = if chain_len < BOOTSTRAP_PHASE_BLOCKS {
             // During bootstrap, adjust based on transaction activity
-            let usage_factor = self.get_usage_factor();
+            let usage_factor = Self::usage_factor_for(chain);
             let adjusted = (reward_with_bonus as f64 * usage_factor).round() as u64;
             // Clamp between base and 2x base (encourages transaction activity)
             adjusted.clamp(reward_with_bonus, reward_with_bonus * 2)
@@ -419,13 +1033,12 @@ impl Blockchain {
     
     /// Calculate network usage factor (1.0 = baseline, up to 2.0 during high activity)
     /// ANTI-SPAM: Weighted by TOTAL FEES PAID, not transaction count
-    fn get_usage_factor(&self) -> f64 {
-        let recent_blocks = 100.min(self.chain.read().len());
+    fn usage_factor_for(chain: &[Block]) -> f64 {
+        let recent_blocks = 100.min(chain.len());
         if recent_blocks < 10 {
             return 1.0; // Not enough data
         }
         
-        let chain = self.chain.read();
         let start_idx = chain.len().saturating_sub(recent_blocks);
         let recent = &chain[start_idx..];
         
@@ -437,21 +1050,19 @@ impl Blockchain {
             .sum();
         
         // Average fees per block (in QUA)
-        let avg_fees_per_block = (total_fees as f64 / recent_blocks as f64) / 1_000_000.0; // Convert to QUA
+        let avg_fees_per_block = (total_fees as f64 / recent_blocks as f64) / MICROUNITS_PER_QUA as f64; // Convert to QUA
         
         // Factor based on economic activity (fee spending), not spam count
         // 0 fees → 1.0x, 50 QUA avg fees/block → 2.0x
         // This makes spam UNPROFITABLE (must pay real fees to boost rewards)
         (1.0 + (avg_fees_per_block / 50.0).min(1.0)).min(2.0)
     }
-    
-    /// Get current difficulty (DERIVED FROM CHAIN, not local memory)
-    fn get_current_difficulty(&self) -> u32 {
-        self.chain.read().last().map(|b| b.difficulty).unwrap_or(4)
-    }
 
     /// Validate block against consensus rules (CRITICAL for network blocks)
-    fn validate_block_consensus(&self, block: &Block, previous: &Block) -> Result<(), BlockchainError> {
+    ///
+    /// `chain` is the chain the block would extend and `state` the account state at its tip
+    fn validate_block_consensus(&self, block: &Block, chain: &[Block], state: &AccountState) -> Result<(), BlockchainError> {
+        let previous = chain.last().ok_or(BlockchainError::InvalidBlock)?;
         // 0. Block size limit (DoS protection)
         let block_size = bincode::serialize(block).map_err(|_| BlockchainError::InvalidBlock)?.len();
         if block_size > MAX_BLOCK_SIZE_BYTES {
@@ -483,10 +1094,17 @@ impl Blockchain {
         
         // 3. Difficulty must match expected
         // Calculate expected difficulty considering adjustments
-        let expected_difficulty = self.calculate_next_difficulty();
+        let expected_difficulty = Self::next_difficulty_for(chain);
         if block.difficulty != expected_difficulty {
             return Err(BlockchainError::InvalidDifficulty);
         }
+
+        // Hash format is fixed by height (from the header_hash activation, hashes commit
+        // to the header fields only)
+        let expected_version = self.activations.block_version(block.index);
+        if block.version != expected_version {
+            return Err(BlockchainError::InvalidBlockVersion { expected: expected_version, got: block.version });
+        }
         
         // 4. Coinbase validation - Must account for fee distribution
         let coinbase_txs: Vec<_> = block.transactions.iter().filter(|tx| tx.is_coinbase()).collect();
@@ -501,6 +1119,17 @@ impl Blockchain {
             .collect();
         
         let coinbase = coinbase_txs[0];
+        // Coinbase timestamp is part of its hash; pin it to the block so it is deterministic
+        if block.index >= self.activations.coinbase_timestamp && coinbase.timestamp != block.timestamp {
+            tracing::warn!("Coinbase timestamp {} does not match block timestamp {}",
+                coinbase.timestamp, block.timestamp);
+            return Err(BlockchainError::InvalidBlock);
+        }
+        // System transactions never carry a memo (keeps the supply math over plain transfers)
+        if coinbase.memo.is_some() || treasury_txs.iter().any(|tx| tx.memo.is_some()) {
+            tracing::warn!("Block has a coinbase or treasury transaction with a memo");
+            return Err(BlockchainError::InvalidBlock);
+        }
         let expected_reward = self.calculate_reward_at_height(block.index);
         let total_fees: u64 = block.transactions.iter()
             .filter(|tx| !tx.is_coinbase() && tx.sender != "TREASURY")
@@ -547,7 +1176,8 @@ impl Blockchain {
             }
             
             if treasury_tx.recipient != TREASURY_ADDRESS {
-                tracing::warn!("Treasury transaction sent to wrong address: {}", treasury_tx.recipient);
+                tracing::warn!("Treasury transaction sent to wrong address: {}", redact_address(&treasury_tx.recipient));
+                tracing::debug!("Treasury transaction recipient (full): {}", treasury_tx.recipient);
                 return Err(BlockchainError::InvalidBlock);
             }
         } else if !treasury_txs.is_empty() {
@@ -557,20 +1187,53 @@ impl Blockchain {
         
         // 5. All non-coinbase txs must have valid signatures and nonces
         // CRITICAL: Build temporary state to validate balances and nonces
-        let mut temp_state = self.account_state.read().clone();
+        let mut temp_state = state.clone();
+        temp_state.unlock_mature_coinbase(block.index); // Same starting point as block application
+        let start_state = temp_state.clone();
         
         for tx in &block.transactions {
             // Exclude Coinbase AND Treasury (system) transactions
             if !tx.is_coinbase() && tx.sender != "TREASURY" {
+                if block.index >= self.activations.field_bounds {
+                    tx.check_field_bounds().map_err(BlockchainError::FieldTooLarge)?;
+                }
+                Self::check_memo(tx)?;
+                if block.index >= self.activations.call_data_cap {
+                    Self::check_call_data(tx, MAX_CALL_DATA_BYTES)?;
+                }
+                if block.index >= self.activations.contract_value {
+                    tx.check_contract_value().map_err(BlockchainError::InvalidContractValue)?;
+                }
                 if !tx.verify() {
                     return Err(BlockchainError::InvalidSignature);
                 }
+                if !tx.is_final_at(block.index) {
+                    return Err(BlockchainError::TransactionLocked {
+                        not_before: tx.not_before_height.unwrap_or_default(),
+                        height: block.index,
+                    });
+                }
+                // Same expiry window as the mempool, measured from the block's own timestamp
+                // so every node reaches the same verdict (and old txs can't be mined later)
+                if block.index >= self.activations.transaction_expiry
+                    && tx.timestamp < block.timestamp - TRANSACTION_EXPIRY_SECONDS
+                {
+                    tracing::warn!("Expired transaction {} in block {}", tx.hash(), block.index);
+                    return Err(BlockchainError::TransactionExpired);
+                }
+                if block.index >= self.activations.future_transaction_time
+                    && tx.timestamp > block.timestamp + MAX_FUTURE_TRANSACTION_TIME
+                {
+                    tracing::warn!("Future-dated transaction {} in block {}", tx.hash(), block.index);
+                    return Err(BlockchainError::TransactionFromFuture(tx.timestamp - block.timestamp));
+                }
                 
-                // Fee must meet minimum
-                if tx.fee < MIN_TRANSACTION_FEE {
+                // Fee must meet minimum (absolute floor and, once active, fee rate by weight)
+                let required_fee = self.block_min_fee(tx, block.index);
+                if tx.fee < required_fee {
                     return Err(BlockchainError::FeeTooLow {
                         fee: tx.fee,
-                        min: MIN_TRANSACTION_FEE,
+                        min: required_fee,
                     });
                 }
                 
@@ -601,21 +1264,42 @@ impl Blockchain {
                 if !temp_state.debit_account(&tx.sender, total_required) {
                     return Err(BlockchainError::InvalidBlock);
                 }
+                // debit_account bumps the sender nonce
                 temp_state.credit_account(tx, block.index, COINBASE_MATURITY);
-                temp_state.increment_nonce(&tx.sender);
             }
         }
+
+        // 6. Transactions must be in canonical order (one valid ordering per tx set)
+        if block.index < self.activations.canonical_order {
+            return Ok(());
+        }
+        let canonical = Self::canonical_order(&block.transactions, &start_state, block.index)
+            .ok_or(BlockchainError::InvalidBlock)?;
+        if canonical.iter().map(|tx| tx.hash()).ne(block.transactions.iter().map(|tx| tx.hash())) {
+            tracing::warn!("Block {} transactions are not in canonical order", block.index);
+            return Err(BlockchainError::InvalidBlock);
+        }
         
         Ok(())
     }
     
-    /// Calculate reward at specific height (for validation)
-    fn calculate_reward_at_height(&self, height: u64) -> u64 {
-        // Use same logic as get_mining_reward but with specified height
+    /// Reward with annual reduction applied, before bonuses (never below MIN_REWARD)
+    fn base_reward_at(height: u64) -> u64 {
         let years_elapsed = height / BLOCKS_PER_YEAR;
+        // The reduction reaches the floor after ~20 years; past this, skip the float math
+        // (powi takes an i32, which a huge year count would wrap into a huge reward)
+        if years_elapsed >= REWARD_FLOOR_YEARS {
+            return MIN_REWARD;
+        }
         let reduction_factor = (100 - ANNUAL_REDUCTION_PERCENT) as f64 / 100.0;
         let base_reward = (YEAR_1_REWARD as f64 * reduction_factor.powi(years_elapsed as i32)).round() as u64;
-        let base_reward = base_reward.max(MIN_REWARD);
+        base_reward.max(MIN_REWARD)
+    }
+
+    /// Calculate reward at specific height (for validation)
+    fn calculate_reward_at_height(&self, height: u64) -> u64 {
+        // Use same logic as get_mining_reward but with specified height
+        let base_reward = Self::base_reward_at(height);
         
         // Apply early adopter bonus if applicable
         if height < EARLY_ADOPTER_BONUS_BLOCKS {
@@ -628,43 +1312,24 @@ impl Blockchain {
 
     /// Calculate next difficulty (pure function, deterministic)
     fn calculate_next_difficulty(&self) -> u32 {
-        let chain = self.chain.read();
-        let chain_len = chain.len();
-        
-        // Not enough blocks yet - use initial difficulty
-        if chain_len < DIFFICULTY_ADJUSTMENT_INTERVAL as usize {
-            return chain.last().unwrap().difficulty;
-        }
-        
-        // Only adjust at intervals
-        if chain_len % DIFFICULTY_ADJUSTMENT_INTERVAL as usize != 0 {
-            return chain.last().unwrap().difficulty;
-        }
-        
+        Self::next_difficulty_for(&self.chain.read())
+    }
+
+    /// Difficulty required of the block following `chain`
+    fn next_difficulty_for(chain: &[Block]) -> u32 {
         let latest_block = chain.last().unwrap();
-        let adjustment_start = chain_len.saturating_sub(DIFFICULTY_ADJUSTMENT_INTERVAL as usize);
-        let start_block = &chain[adjustment_start];
-        
-        // Calculate actual time taken for last N blocks
-        let actual_time = latest_block.timestamp - start_block.timestamp;
-        let expected_time = (TARGET_BLOCK_TIME * DIFFICULTY_ADJUSTMENT_INTERVAL) as i64;
-        
-        // SECURITY: Limit adjustment range to prevent manipulation (Bitcoin-style: 4x max)
-        let actual_time_clamped = actual_time.max(expected_time / 4).min(expected_time * 4);
-        
-        let current_difficulty = latest_block.difficulty as i64;
-        
-        // Adjust difficulty proportionally (clamped to ±25% per adjustment)
-        let new_difficulty_raw = (current_difficulty * expected_time) / actual_time_clamped;
-        let new_difficulty = new_difficulty_raw
-            .max(current_difficulty * 3 / 4)  // Max decrease 25%
-            .min(current_difficulty * 5 / 4)  // Max increase 25%
-            .max(4)                           // Minimum difficulty
-            .min(32) as u32;                  // Maximum difficulty (prevents overflow)
-        
-        tracing::info!("Difficulty adjustment: {} -> {} (actual time: {}s, expected: {}s)",
-            current_difficulty, new_difficulty, actual_time, expected_time);
-        
+        let start_block = &chain[chain.len().saturating_sub(DIFFICULTY_ADJUSTMENT_INTERVAL as usize)];
+        let new_difficulty = retarget(
+            chain.len() as u64,
+            latest_block.difficulty,
+            latest_block.timestamp - start_block.timestamp,
+        );
+
+        if new_difficulty != latest_block.difficulty {
+            tracing::info!("Difficulty adjustment: {} -> {} (actual time: {}s, expected: {}s)",
+                latest_block.difficulty, new_difficulty, latest_block.timestamp - start_block.timestamp,
+                TARGET_BLOCK_TIME * DIFFICULTY_ADJUSTMENT_INTERVAL);
+        }
         new_difficulty
     }
 
@@ -692,30 +1357,191 @@ impl Blockchain {
 
     /// Get blockchain statistics
     pub fn get_stats(&self) -> BlockchainStats {
+        self.snapshot().stats
+    }
+
+    /// Consistent view of tip + stats, taken under a single chain read lock
+    /// Use this instead of several separate getters when fields must agree
+    pub fn snapshot(&self) -> ChainSnapshot {
         let chain = self.chain.read();
+        let account_count = self.account_state.read().account_count();
+        let pending_transactions = self.pending_transactions.read().len();
+        Self::snapshot_of(&chain, account_count, pending_transactions)
+    }
+
+    fn snapshot_of(chain: &[Block], account_count: usize, pending_transactions: usize) -> ChainSnapshot {
+        let tip = chain.last().expect("chain always contains genesis");
+
         let total_transactions: usize = chain.iter().map(|b| b.transactions.len()).sum();
-        let total_supply = self.calculate_total_supply();
-        let pending = self.pending_transactions.read();
-        
-        BlockchainStats {
-            chain_length: chain.len(),
-            total_transactions,
-            current_difficulty: self.get_current_difficulty(),
-            mining_reward: self.get_mining_reward(),
-            total_supply,
-            pending_transactions: pending.len(),
+        let total_supply = chain
+            .iter()
+            .flat_map(|block| &block.transactions)
+            .filter(|tx| tx.is_coinbase())
+            .map(|tx| tx.amount)
+            .sum();
+
+        ChainSnapshot {
+            height: chain.len() as u64,
+            tip_hash: tip.hash.clone(),
+            tip_timestamp: tip.timestamp,
+            stats: BlockchainStats {
+                chain_length: chain.len(),
+                total_transactions,
+                current_difficulty: tip.difficulty,
+                mining_reward: Self::mining_reward_for(chain),
+                total_supply,
+                pending_transactions,
+                account_count,
+            },
         }
     }
 
-    /// Calculate total coin supply (u64 microunits)
-    fn calculate_total_supply(&self) -> u64 {
+    /// Changes since a chain height previously seen by the caller (chain_length)
+    /// Lets frequent pollers skip re-fetching full stats when nothing moved
+    pub fn stats_since(&self, since_height: u64) -> StatsDelta {
         let chain = self.chain.read();
-        chain
+        let current_height = chain.len() as u64;
+        let from = (since_height.min(current_height)) as usize;
+
+        let supply_delta = chain[from..]
             .iter()
             .flat_map(|block| &block.transactions)
             .filter(|tx| tx.is_coinbase())
             .map(|tx| tx.amount)
-            .sum()
+            .sum();
+        let new_blocks = current_height.saturating_sub(since_height);
+
+        StatsDelta {
+            since_height,
+            current_height,
+            new_blocks,
+            supply_delta,
+            current_difficulty: chain.last().map(|b| b.difficulty).unwrap_or(0),
+            changed: new_blocks > 0,
+        }
+    }
+
+    /// Reserve the next nonce for a locally created transaction from `address`
+    /// Other reservations block until this one is dropped, so build, sign and
+    /// add_transaction before releasing it
+    pub fn reserve_next_nonce(&self, address: &str) -> NonceReservation<'_> {
+        let guard = self.nonce_reservation.lock();
+        NonceReservation {
+            nonce: self.get_next_nonce(address),
+            _guard: guard,
+        }
+    }
+
+    /// Nonce the next transaction from `address` must use: after both its confirmed
+    /// transactions and any still in the mempool
+    pub fn get_next_nonce(&self, address: &str) -> u64 {
+        self.next_nonce_after(address, self.account_state.read().get_nonce(address))
+    }
+
+    /// get_next_nonce given the address's confirmed nonce
+    fn next_nonce_after(&self, address: &str, chain_nonce: u64) -> u64 {
+        let pending_nonce = self.pending_nonces
+            .get(address)
+            .map(|n| *n.value())
+            .unwrap_or(chain_nonce);
+        pending_nonce.max(chain_nonce) + 1
+    }
+
+    /// Get supply breakdown (u64 microunits)
+    /// Issued/burned come from replaying block fee distribution,
+    /// circulating/locked from the current account state
+    pub fn get_supply(&self) -> SupplyInfo {
+        let chain = self.chain.read();
+        let state = self.account_state.read();
+        Self::supply_of(&chain, &state)
+    }
+
+    /// Up to `limit` accounts ordered by address, starting after `after`
+    /// Totals cover the whole state so each page can be checked against get_supply
+    pub fn account_state_page(&self, after: Option<&str>, limit: usize) -> AccountStatePage {
+        let chain = self.chain.read();
+        let state = self.account_state.read();
+
+        let mut remaining: Vec<&AccountBalance> = state
+            .iter()
+            .filter(|(address, _)| after.is_none_or(|after| *address > after))
+            .map(|(_, account)| account)
+            .collect();
+        remaining.sort_unstable_by(|a, b| a.address.cmp(&b.address));
+        let has_more = remaining.len() > limit;
+        remaining.truncate(limit);
+
+        let accounts: Vec<AccountStateEntry> = remaining
+            .into_iter()
+            .map(|account| AccountStateEntry {
+                address: account.address.clone(),
+                balance: account.balance,
+                locked: account.locked_balance,
+                nonce: account.nonce,
+            })
+            .collect();
+        let tip = chain.last().expect("chain always contains genesis");
+
+        AccountStatePage {
+            height: chain.len() as u64,
+            tip_hash: tip.hash.clone(),
+            account_count: state.account_count(),
+            total_spendable: state.total_spendable(),
+            total_locked: state.total_locked(),
+            next_after: if has_more { accounts.last().map(|entry| entry.address.clone()) } else { None },
+            accounts,
+        }
+    }
+
+    fn supply_of(chain: &[Block], state: &AccountState) -> SupplyInfo {
+        let mut total_issued = GENESIS_ALLOCATION;
+        let mut burned = 0u64;
+
+        for block in chain.iter() {
+            let system_amount: u64 = block.transactions.iter()
+                .filter(|tx| tx.is_coinbase() || tx.sender == "TREASURY")
+                .map(|tx| tx.amount)
+                .sum();
+            let total_fees: u64 = block.transactions.iter()
+                .filter(|tx| !tx.is_coinbase() && tx.sender != "TREASURY")
+                .map(|tx| tx.fee)
+                .sum();
+
+            // Fee shares paid back out via coinbase/treasury are not new issuance
+            let fee_redistributed = (total_fees * FEE_VALIDATOR_PERCENT) / 100
+                + (total_fees * FEE_TREASURY_PERCENT) / 100;
+            total_issued += system_amount.saturating_sub(fee_redistributed);
+            burned += total_fees - fee_redistributed;
+        }
+
+        SupplyInfo {
+            total_issued,
+            circulating: state.total_spendable(),
+            locked: state.total_locked(),
+            burned,
+        }
+    }
+
+    /// Read-only replica for API reads, published again after every block
+    /// Readers load it without locking this Blockchain (or the lock around it)
+    pub fn replica(&self) -> ChainReplica {
+        let replica = self.replica.get_or_init(|| ChainReplica::new(self.chain_view()));
+        // A block committed while the replica was being created may not be in it yet
+        replica.publish(self.chain_view());
+        replica.clone()
+    }
+
+    /// Consistent view for the replica: stats, supply and accounts at one tip
+    fn chain_view(&self) -> ChainView {
+        let chain = self.chain.read();
+        let state = self.account_state.read();
+        let pending_transactions = self.pending_transactions.read().len();
+        ChainView {
+            generation: self.generation.load(Ordering::Acquire),
+            snapshot: Self::snapshot_of(&chain, state.account_count(), pending_transactions),
+            supply: Self::supply_of(&chain, &state),
+            account_state: state.clone(),
+        }
     }
 
     /// Get balance for an address (u64 microunits)
@@ -723,6 +1549,16 @@ impl Blockchain {
         self.account_state.read().get_balance(address)
     }
 
+    /// Locked balance for an address: immature coinbase rewards (u64 microunits)
+    pub fn get_locked_balance(&self, address: &str) -> u64 {
+        self.account_state.read().get_locked_balance(address)
+    }
+
+    /// Height the address's next locked reward unlocks (0 = nothing locked)
+    pub fn get_unlock_height(&self, address: &str) -> u64 {
+        self.account_state.read().get_unlock_height(address)
+    }
+
     /// Get the blockchain (for network sync)
     pub fn get_chain(&self) -> parking_lot::RwLockReadGuard<Vec<Block>> {
         self.chain.read()
@@ -738,6 +1574,57 @@ impl Blockchain {
         self.pending_transactions.read()
     }
 
+    /// Aggregate stats over the pending transactions
+    pub fn mempool_info(&self) -> MempoolInfo {
+        MempoolInfo::from_transactions(&self.pending_transactions.read(), chrono::Utc::now().timestamp())
+    }
+
+    /// Pending transactions bucketed by fee rate (see FeeHistogram::check_boundaries),
+    /// walked in fee order through a Mempool index of the pool
+    pub fn fee_histogram(&self, boundaries: &[f64]) -> FeeHistogram {
+        Mempool::from_transactions(&self.pending_transactions.read()).fee_histogram(boundaries)
+    }
+
+    /// Recommended fee (microunits) for a signed transfer to be confirmed within
+    /// `target_blocks` blocks (0 counts as 1): the median fee estimate_fee_rate finds in
+    /// recent blocks, scaled up when the mempool already holds more than `target_blocks`
+    /// blocks' worth of transactions. Never below what a typical transfer needs to enter
+    /// the mempool, which is also the answer when the mempool is empty
+    pub fn estimate_fee(&self, target_blocks: u32) -> u64 {
+        let floor = self.required_fee(&Self::typical_transfer());
+        let (pending_count, pending_bytes) = self
+            .pending_transactions
+            .read()
+            .iter()
+            .filter(|tx| !tx.is_coinbase())
+            .fold((0usize, 0usize), |(count, bytes), tx| (count + 1, bytes.saturating_add(tx.weight())));
+        if pending_count == 0 {
+            return floor;
+        }
+
+        let median = self.estimate_fee_rate().flat_fee;
+        Self::scale_fee_for_congestion(median, pending_count, pending_bytes, target_blocks).max(floor)
+    }
+
+    /// Stand-in for a signed transfer when quoting fees (a Falcon-512 signed message is
+    /// about 700 bytes)
+    fn typical_transfer() -> Transaction {
+        let address = format!("0x{}", "0".repeat(40));
+        let mut tx = Transaction::new(address.clone(), address, 1, 0);
+        tx.public_key = vec![0u8; 897];
+        tx.signature = vec![0u8; 700];
+        tx
+    }
+
+    /// `base` times the number of target windows the pending transactions would fill
+    /// (by count or size, whichever binds first), never below MIN_TRANSACTION_FEE
+    fn scale_fee_for_congestion(base: u64, pending_count: usize, pending_bytes: usize, target_blocks: u32) -> u64 {
+        let blocks_to_clear = (pending_count as f64 / MAX_BLOCK_TRANSACTIONS as f64)
+            .max(pending_bytes as f64 / MAX_BLOCK_SIZE_BYTES as f64);
+        let congestion = (blocks_to_clear / target_blocks.max(1) as f64).max(1.0);
+        ((base as f64 * congestion).ceil() as u64).max(MIN_TRANSACTION_FEE)
+    }
+
     /// Get mutable pending transactions
     #[allow(dead_code)]
     pub fn get_pending_transactions_mut(&self) -> parking_lot::RwLockWriteGuard<'_, Vec<Transaction>> {
@@ -763,39 +1650,31 @@ impl Blockchain {
         // 2. FORK DETECTION: Check if this block builds on our chain
         if block.previous_hash == latest.hash && block.index == latest.index + 1 {
             // Normal case: extends our chain
-            return self.add_block_to_main_chain(block);
+            let hash = block.hash.clone();
+            match self.add_block_to_main_chain(block.clone()) {
+                // Another block connected first: place this one against the new tip
+                Err(BlockchainError::StaleTip) => return self.add_network_block(block),
+                result => result?,
+            }
+            self.connect_orphans(hash);
+            return Ok(());
         } else if block.index > latest.index {
-            // Potential fork: block is ahead of us
-            tracing::warn!("Fork detected: Block {} at height {}, we're at {}", 
+            // Parent unknown: hold it until the parent arrives (caller requests the gap).
+            // It may also extend a pooled competing branch past our tip
+            tracing::warn!("Orphan block {} at height {}, we're at {}", 
                 &block.hash[..8], block.index, latest.index);
-            
-            // Store as orphaned block (with size limit)
-            let mut orphans = self.orphaned_blocks.write();
-            if orphans.len() >= MAX_ORPHAN_BLOCKS {
-                tracing::warn!("Max orphan blocks reached, dropping oldest");
-                orphans.remove(0);
-            }
-            orphans.push(block.clone());
-            drop(orphans);
-            
-            // Try to resolve fork by fetching missing blocks
-            // (This would trigger sync - simplified for now)
-            tracing::info!("Stored orphaned block, need to sync");
+            let hash = block.hash.clone();
+            self.orphan_pool.write().insert(block);
+            self.resolve_fork(&hash);
             return Ok(());
-        } else if block.index == latest.index {
-            // Competing block at same height - apply longest chain rule
+        } else if block.index == latest.index || self.has_block(&block.previous_hash) {
+            // Competing block at or below our height: keep our branch unless its branch
+            // carries more work (pooled, so later blocks can extend it)
             tracing::warn!("Competing block at height {}: {} vs {}", 
                 block.index, &block.hash[..8], &latest.hash[..8]);
-            
-            // For now, keep our block (in production: compare total work)
-            // TODO: Implement total difficulty comparison
-            let mut orphans = self.orphaned_blocks.write();
-            if orphans.len() >= MAX_ORPHAN_BLOCKS {
-                tracing::warn!("Max orphan blocks reached, dropping oldest");
-                orphans.remove(0);
-            }
-            orphans.push(block);
-            drop(orphans);
+            let hash = block.hash.clone();
+            self.orphan_pool.write().insert(block);
+            self.resolve_fork(&hash);
             return Ok(());
         } else {
             // Block is behind our chain - likely stale
@@ -804,6 +1683,191 @@ impl Blockchain {
             return Ok(());
         }
     }
+
+    /// Switch to the pooled branch ending at `hash` if it forks from our chain and
+    /// outweighs it (see handle_fork)
+    fn resolve_fork(&self, hash: &str) {
+        let mut branch: Vec<Block> = {
+            let pool = self.orphan_pool.read();
+            // Pooled hashes are unverified, so bound the walk (a crafted cycle would loop)
+            std::iter::successors(pool.get(hash), |block| pool.get(&block.previous_hash))
+                .take(pool.len())
+                .cloned()
+                .collect()
+        };
+        branch.reverse();
+        // Still waiting for an ancestor
+        if !branch.first().is_some_and(|first| self.has_block(&first.previous_hash)) {
+            return;
+        }
+
+        let hashes: Vec<String> = branch.iter().map(|block| block.hash.clone()).collect();
+        match self.handle_fork(branch) {
+            // Lighter branches stay pooled in case they grow
+            Ok(false) => {}
+            Ok(true) => {
+                let mut pool = self.orphan_pool.write();
+                hashes.iter().for_each(|hash| pool.remove(hash));
+                drop(pool);
+                self.connect_orphans(self.get_latest_block().hash);
+            }
+            Err(e) => {
+                tracing::warn!("Dropping invalid competing branch ending at {}: {}", &hash[..8.min(hash.len())], e);
+                let mut pool = self.orphan_pool.write();
+                hashes.iter().for_each(|hash| pool.remove(hash));
+            }
+        }
+    }
+
+    /// Reorganize onto a competing branch if it carries more cumulative work than ours
+    ///
+    /// `blocks` must be consecutive, starting right after a block on our chain (the
+    /// common ancestor). Work is the sum of `16^difficulty` over each side's blocks past
+    /// the ancestor; ties keep our chain. A heavier branch is validated against the
+    /// account state replayed from genesis to the ancestor, then replaces our blocks in
+    /// memory and (in one batch) on disk. Transactions from the dropped blocks that the
+    /// branch didn't include go back to the mempool. Returns whether the chain switched.
+    pub fn handle_fork(&self, blocks: Vec<Block>) -> Result<bool, BlockchainError> {
+        let Some(first) = blocks.first() else {
+            return Ok(false);
+        };
+        let chain = self.chain.read().clone();
+        // Replay needs genesis; the branch must fork from a block we hold
+        if chain.first().map(|genesis| genesis.index) != Some(0) {
+            return Err(BlockchainError::InvalidBlock);
+        }
+        let ancestor = chain
+            .iter()
+            .position(|block| block.hash == first.previous_hash)
+            .ok_or(BlockchainError::InvalidBlock)?;
+
+        let ours = Self::cumulative_work(&chain[ancestor + 1..]);
+        let theirs = Self::cumulative_work(&blocks);
+        if theirs <= ours {
+            tracing::debug!("Keeping our chain: branch from height {} has work {} vs our {}", ancestor, theirs, ours);
+            return Ok(false);
+        }
+
+        // Roll back to the ancestor (a recent state if we kept it, else replay from
+        // genesis), then validate the branch on top of it
+        let mut state = if ancestor + 1 == chain.len() {
+            self.account_state.read().clone()
+        } else if let Some((_, kept)) = self.undo_states.read().iter().find(|(hash, _)| *hash == first.previous_hash) {
+            kept.clone()
+        } else {
+            let mut replayed = Self::genesis_state(&chain[0]);
+            for block in &chain[1..=ancestor] {
+                self.apply_block(&mut replayed, block)?;
+            }
+            replayed
+        };
+        let mut candidate = chain[..=ancestor].to_vec();
+        let mut branch_states = Vec::with_capacity(blocks.len());
+        for block in &blocks {
+            if !self.validate_checkpoint(block.index, &block.hash) || !block.is_valid(candidate.last()) {
+                return Err(BlockchainError::InvalidBlock);
+            }
+            self.validate_block_consensus(block, &candidate, &state)?;
+            branch_states.push((block.previous_hash.clone(), state.clone()));
+            self.apply_block(&mut state, block)?;
+            candidate.push(block.clone());
+        }
+
+        // COMMIT: unless a block was connected while we validated
+        let mut current = self.chain.write();
+        if current.last().map(|block| &block.hash) != chain.last().map(|block| &block.hash) {
+            tracing::warn!("Chain tip moved during reorganization; keeping our chain");
+            return Ok(false);
+        }
+        self.storage.replace_chain_suffix(ancestor as u64 + 1, &blocks, &state)?;
+        let dropped = current.split_off(ancestor + 1);
+        current.extend(blocks.iter().cloned());
+        *self.account_state.write() = state;
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        let tip_hash = current.last().map(|block| block.hash.clone()).unwrap_or_default();
+        drop(current);
+
+        let mut undo_states = self.undo_states.write();
+        undo_states.retain(|(hash, _)| !dropped.iter().any(|block| block.hash == *hash));
+        for (hash, kept) in branch_states {
+            Self::push_undo_state(&mut undo_states, hash, kept);
+        }
+        drop(undo_states);
+
+        let mut tx_index = self.tx_index.write();
+        for tx in dropped.iter().flat_map(|block| &block.transactions) {
+            tx_index.remove(tx.hash().as_str());
+        }
+        tx_index.extend(blocks.iter().flat_map(|block| block.transactions.iter().map(|tx| (tx.hash(), block.index))));
+        drop(tx_index);
+
+        // Rebuild the mempool against the new state: unmined txs from the dropped blocks
+        // plus what was pending, minus what the branch mined (nonce order keeps each
+        // sender's run sequential; anything no longer valid is discarded)
+        let mined: HashSet<TxHash> = blocks.iter().flat_map(|block| block.transactions.iter().map(|tx| tx.hash())).collect();
+        let mut requeue: Vec<Transaction> = dropped
+            .iter()
+            .flat_map(|block| block.transactions.iter())
+            .filter(|tx| !tx.is_coinbase() && tx.sender != "TREASURY")
+            .cloned()
+            .chain(std::mem::take(&mut *self.pending_transactions.write()))
+            .filter(|tx| !mined.contains(tx.hash().as_str()))
+            .collect();
+        requeue.sort_by_key(|tx| tx.nonce);
+        self.pending_nonces.clear();
+        let requeued = requeue.into_iter().filter(|tx| self.add_transaction(tx.clone()).is_ok()).count();
+
+        if let Some(replica) = self.replica.get() {
+            replica.publish(self.chain_view());
+        }
+        self.tip.send_replace(tip_hash);
+
+        tracing::warn!(
+            "Reorganized at height {}: replaced {} block(s) with {} (work {} -> {}), {} transaction(s) in mempool",
+            ancestor, dropped.len(), blocks.len(), ours, theirs, requeued
+        );
+        Ok(true)
+    }
+
+    /// Keep `state` (the account state after block `hash`) for rollback, dropping the oldest
+    fn push_undo_state(undo_states: &mut VecDeque<(String, AccountState)>, hash: String, state: AccountState) {
+        undo_states.retain(|(kept, _)| *kept != hash);
+        undo_states.push_back((hash, state));
+        while undo_states.len() > REORG_UNDO_DEPTH {
+            undo_states.pop_front();
+        }
+    }
+
+    /// Cumulative proof-of-work of `blocks`, counting `16^difficulty` per block
+    /// (difficulty is leading zero hex digits, so each one is 4 bits of expected work)
+    fn cumulative_work(blocks: &[Block]) -> u128 {
+        blocks
+            .iter()
+            .map(|block| 1u128.checked_shl(block.difficulty.saturating_mul(4)).unwrap_or(u128::MAX))
+            .fold(0, u128::saturating_add)
+    }
+
+    /// Attach pooled orphans that descend from the newly added `parent_hash`
+    fn connect_orphans(&self, parent_hash: String) {
+        if self.orphan_pool.read().is_empty() {
+            return;
+        }
+        let mut parents = vec![parent_hash];
+        while let Some(parent) = parents.pop() {
+            let children = self.orphan_pool.write().take_children(&parent);
+            for child in children {
+                let hash = child.hash.clone();
+                match self.add_block_to_main_chain(child) {
+                    Ok(()) => {
+                        tracing::info!("Connected orphan block {}", &hash[..8]);
+                        parents.push(hash);
+                    }
+                    // A sibling connected first, or the orphan is invalid
+                    Err(e) => tracing::debug!("Dropping orphan block {}: {}", &hash[..8], e),
+                }
+            }
+        }
+    }
     
     /// Add block to main chain (internal helper)
     fn add_block_to_main_chain(&self, block: Block) -> Result<(), BlockchainError> {
@@ -820,48 +1884,77 @@ impl Blockchain {
             return Err(BlockchainError::InvalidBlock);
         }
         
-        // Consensus rules validation
-        self.validate_block_consensus(&block, &latest)?;
-        let mut new_state = self.account_state.read().clone();
-        
-        // Unlock any mature coinbase rewards
-        new_state.unlock_mature_coinbase(block.index);
-
-        // 5. Apply all transactions
-        for tx in &block.transactions {
-            if !tx.is_coinbase() && tx.sender != "TREASURY" {
-                let total = tx.amount.saturating_add(tx.fee);
-                if !new_state.debit_account(&tx.sender, total) {
-                    tracing::warn!("Network block has invalid tx: insufficient balance");
+        let fast_sync = self.fast_sync.read();
+        let new_state = match fast_sync.as_ref().filter(|snapshot| block.index <= snapshot.height) {
+            // Snapshot sync: up to the snapshot's height a block only has to match its
+            // verified header. Transactions are not validated or applied; the snapshot's
+            // account state is adopted with the checkpoint block
+            Some(snapshot) => {
+                if snapshot.headers[block.index as usize].hash != block.hash {
                     return Err(BlockchainError::InvalidBlock);
                 }
+                if block.index == snapshot.height {
+                    snapshot.account_state.clone()
+                } else {
+                    self.account_state.read().clone()
+                }
             }
-            new_state.credit_account(tx, block.index, COINBASE_MATURITY);
-        }
+            None => {
+                // Consensus rules validation
+                self.validate_block_consensus(&block, &self.chain.read(), &self.account_state.read())?;
+                let mut new_state = self.account_state.read().clone();
 
-        // 6. COMMIT: Add to chain
-        self.chain.write().push(block.clone());
+                // 5. Apply all transactions
+                self.apply_block(&mut new_state, &block)?;
+                new_state
+            }
+        };
+        let reached_snapshot = fast_sync.as_ref().is_some_and(|snapshot| block.index == snapshot.height);
+        drop(fast_sync);
+
+        // 6. COMMIT: unless another block was connected while this one was validated
+        // (the chain lock is held until the state matches the new tip)
+        let mut chain = self.chain.write();
+        if chain.last().map(|tip| &tip.hash) != Some(&latest.hash) {
+            return Err(BlockchainError::StaleTip);
+        }
+        chain.push(block.clone());
         
         // 7. COMMIT: Save to storage
         self.storage.save_block(&block)?;
-        self.storage.set_chain_height(self.get_latest_block().index + 1)?;
+        self.storage.set_chain_height(block.index + 1)?;
         self.storage.save_account_state(&new_state)?;
         
-        // 8. COMMIT: Update state
-        *self.account_state.write() = new_state;
+        // 8. COMMIT: Update state (the previous one stays available for a reorg)
+        let previous_state = std::mem::replace(&mut *self.account_state.write(), new_state);
+        self.generation.fetch_add(1, Ordering::AcqRel);
+        drop(chain);
+        Self::push_undo_state(&mut self.undo_states.write(), latest.hash.clone(), previous_state);
+        self.tx_index
+            .write()
+            .extend(block.transactions.iter().map(|tx| (tx.hash(), block.index)));
 
         // 9. Remove mined transactions from pending
         let mut pending = self.pending_transactions.write();
         pending.retain(|tx| !block.transactions.iter().any(|btx| btx.hash() == tx.hash()));
-        drop(pending);
         
-        // 10. Clear pending nonces for mined txs (DashMap - concurrent safe)
+        // 10. Resync pending nonces for the block's senders (they may still have later txs queued)
         for tx in &block.transactions {
             if !tx.is_coinbase() {
-                self.pending_nonces.remove(&tx.sender);
+                self.resync_pending_nonce(&tx.sender, &pending);
             }
         }
+        drop(pending);
+
+        if let Some(replica) = self.replica.get() {
+            replica.publish(self.chain_view());
+        }
+        self.tip.send_replace(block.hash.clone());
 
+        if reached_snapshot {
+            *self.fast_sync.write() = None;
+            tracing::info!("Snapshot sync reached height {}; validating blocks in full from here", block.index);
+        }
         tracing::info!(" Network block {} accepted", block.index);
         Ok(())
     }
@@ -873,20 +1966,179 @@ impl Blockchain {
         chain.iter().any(|b| b.hash == hash)
     }
 
-    /// Get block by height
-    #[allow(dead_code)]
+    /// Get block by height (None if beyond the tip or below the earliest held block)
     pub fn get_block_by_height(&self, height: u64) -> Option<Block> {
         let chain = self.chain.read();
-        chain.get(height as usize).cloned()
+        let offset = height.checked_sub(chain.first()?.index)?;
+        chain.get(offset as usize).cloned()
+    }
+
+    /// Mined transaction by hash, with the height of its block (see the pending
+    /// transactions for ones not yet mined)
+    pub fn get_transaction(&self, tx_hash: &str) -> Option<(Transaction, u64)> {
+        let height = *self.tx_index.read().get(tx_hash)?;
+        let chain = self.chain.read();
+        let offset = height.checked_sub(chain.first()?.index)?;
+        let tx = chain.get(offset as usize)?.transactions.iter().find(|tx| tx.hash() == tx_hash)?.clone();
+        Some((tx, height))
+    }
+
+    /// Snapshot at a snapshot checkpoint height: account state replayed from genesis
+    /// through block `height`, plus headers 0..=height. None for other heights or
+    /// if we don't have the block (only checkpoints are served, bounding replay work)
+    pub fn state_snapshot(&self, height: u64) -> Option<StateSnapshot> {
+        if !SNAPSHOT_CHECKPOINTS.iter().any(|checkpoint| checkpoint.height == height) {
+            return None;
+        }
+        self.build_state_snapshot(height)
+    }
+
+    fn build_state_snapshot(&self, height: u64) -> Option<StateSnapshot> {
+        let chain = self.chain.read();
+        if chain.first()?.index != 0 || height >= chain.len() as u64 {
+            return None;
+        }
+        let blocks = &chain[..=height as usize];
+
+        let mut account_state = Self::genesis_state(&blocks[0]);
+        for block in &blocks[1..] {
+            self.apply_block(&mut account_state, block).ok()?;
+        }
+
+        Some(StateSnapshot {
+            height,
+            headers: blocks.iter().map(BlockHeader::from).collect(),
+            account_state,
+        })
+    }
+
+    /// Verify a snapshot from a peer against the hardcoded checkpoints, store it and
+    /// sync from it: blocks up to its height are then matched against its headers
+    /// instead of being validated in full (ignored once the tip is past its height)
+    pub fn accept_snapshot(&self, snapshot: &StateSnapshot) -> Result<(), BlockchainError> {
+        Self::check_snapshot(&self.chain.read(), snapshot)?;
+        if snapshot.height < self.get_height() {
+            tracing::info!("Ignoring state snapshot at height {}: already synced past it", snapshot.height);
+            return Ok(());
+        }
+        self.storage.save_snapshot(snapshot)?;
+        *self.fast_sync.write() = Some(snapshot.clone());
+        tracing::info!("Verified state snapshot at height {} stored; syncing from it", snapshot.height);
+        Ok(())
+    }
+
+    /// Snapshot verified against its checkpoint and starting from our genesis block
+    fn check_snapshot(chain: &[Block], snapshot: &StateSnapshot) -> Result<(), SnapshotError> {
+        snapshot.verify_known(next_difficulty_after)?;
+        if chain.first().is_none_or(|genesis| snapshot.headers[0].hash != genesis.hash) {
+            return Err(SnapshotError::BrokenLink(0));
+        }
+        Ok(())
+    }
+
+    /// Lowest block height this node still holds and can serve to peers
+    /// (0 until pruning drops old blocks)
+    pub fn earliest_available_height(&self) -> u64 {
+        self.chain.read().first().map(|b| b.index).unwrap_or(0)
     }
 
     /// Get current chain height
     pub fn get_height(&self) -> u64 {
         self.chain.read().len() as u64
     }
+
+    /// Headers of our last adjustment window up to the tip (from genesis on a shorter
+    /// chain): what next_difficulty_after needs to check the headers that follow
+    pub fn recent_headers(&self) -> Vec<BlockHeader> {
+        let chain = self.chain.read();
+        chain[chain.len().saturating_sub(DIFFICULTY_ADJUSTMENT_INTERVAL as usize)..]
+            .iter()
+            .map(BlockHeader::from)
+            .collect()
+    }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// Difficulty required of the block after `headers`, a contiguous header run ending at
+/// its parent that starts at genesis or holds at least one adjustment window
+/// (same schedule as block validation, for headers whose bodies we don't have)
+pub fn next_difficulty_after(headers: &[BlockHeader]) -> u32 {
+    let latest = headers.last().expect("header run is never empty");
+    let start = &headers[headers.len().saturating_sub(DIFFICULTY_ADJUSTMENT_INTERVAL as usize)];
+    retarget(latest.index + 1, latest.difficulty, latest.timestamp - start.timestamp)
+}
+
+/// Difficulty after a chain of `chain_len` blocks whose tip has `current_difficulty`,
+/// where the last adjustment window took `actual_time` seconds (pure, deterministic)
+fn retarget(chain_len: u64, current_difficulty: u32, actual_time: i64) -> u32 {
+    // Not enough blocks yet, or not at an interval: keep the current difficulty
+    if chain_len < DIFFICULTY_ADJUSTMENT_INTERVAL || !chain_len.is_multiple_of(DIFFICULTY_ADJUSTMENT_INTERVAL) {
+        return current_difficulty;
+    }
+
+    let expected_time = (TARGET_BLOCK_TIME * DIFFICULTY_ADJUSTMENT_INTERVAL) as i64;
+
+    // SECURITY: Limit adjustment range to prevent manipulation (Bitcoin-style: 4x max)
+    let actual_time_clamped = actual_time.max(expected_time / 4).min(expected_time * 4);
+
+    let current_difficulty = current_difficulty as i64;
+
+    // Adjust difficulty proportionally (clamped to ±25% per adjustment)
+    let new_difficulty_raw = (current_difficulty * expected_time) / actual_time_clamped;
+    new_difficulty_raw
+        .max(current_difficulty * 3 / 4)  // Max decrease 25%
+        .min(current_difficulty * 5 / 4)  // Max increase 25%
+        .max(4)                           // Minimum difficulty
+        .min(32) as u32                   // Maximum difficulty (prevents overflow)
+}
+
+/// Run a chain operation that writes to storage (block commit, sled flush) on
+/// tokio's blocking pool so async tasks keep running during disk I/O.
+///
+/// Pass an owned guard (`read_owned` / `write_owned`): the async lock is taken
+/// by the caller before the task starts and released when it finishes, so the
+/// lock order stays tokio RwLock -> chain locks -> sled, same as a direct call.
+pub async fn run_blocking<G, F, R>(guard: G, f: F) -> R
+where
+    G: std::ops::Deref<Target = Blockchain> + Send + 'static,
+    F: FnOnce(&Blockchain) -> R + Send + 'static,
+    R: Send + 'static,
+{
+    match tokio::task::spawn_blocking(move || f(&guard)).await {
+        Ok(result) => result,
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+/// Mine a block template off the async runtime, abandoning it as soon as the chain
+/// tip moves past its parent (the result would be stale) or `stop` fires.
+///
+/// Ok(None) means the attempt was abandoned; build a fresh template to continue.
+pub async fn mine_until_stale(
+    mut block: Block,
+    mut tip: watch::Receiver<String>,
+    stop: &CancellationToken,
+) -> Result<Option<Block>, tokio::task::JoinError> {
+    let attempt = stop.child_token();
+    let parent = block.previous_hash.clone();
+    let watcher = {
+        let attempt = attempt.clone();
+        tokio::spawn(async move {
+            // Also catches a tip that moved before we subscribed
+            while *tip.borrow_and_update() == parent {
+                if tip.changed().await.is_err() {
+                    break;
+                }
+            }
+            attempt.cancel();
+        })
+    };
+
+    let mined = tokio::task::spawn_blocking(move || block.mine_cancellable(&attempt).then_some(block)).await;
+    watcher.abort();
+    mined
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BlockchainStats {
     pub chain_length: usize,
     pub total_transactions: usize,
@@ -894,5 +2146,1610 @@ pub struct BlockchainStats {
     pub mining_reward: u64,      // microunits
     pub total_supply: u64,       // microunits
     pub pending_transactions: usize,
+    #[serde(default)]
+    pub account_count: usize,    // distinct addresses in the account state
+}
+
+/// Recent block pace vs the target block time (see Blockchain::block_timing)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlockTiming {
+    pub window_blocks: u64,          // intervals averaged (fewer than configured on a young chain)
+    pub avg_block_time_secs: f64,
+    pub target_block_time_secs: u64,
+    pub blocks_ahead_of_target: f64, // >0: blocks arriving faster than target (difficulty lagging hashrate)
+}
+
+/// Absolute minimum fee per transaction type, in microunits (see Blockchain::required_fee)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MinFees {
+    pub transfer: u64,
+    pub deploy: u64,
+    pub call: u64,
+}
+
+impl Default for MinFees {
+    fn default() -> Self {
+        Self {
+            transfer: MIN_TRANSACTION_FEE,
+            deploy: MIN_DEPLOY_FEE,
+            call: MIN_CALL_FEE,
+        }
+    }
+}
+
+impl MinFees {
+    pub fn for_type(&self, tx_type: &TransactionType) -> u64 {
+        match tx_type {
+            TransactionType::Transfer => self.transfer,
+            TransactionType::DeployContract { .. } => self.deploy,
+            TransactionType::CallContract { .. } => self.call,
+        }
+    }
 }
 
+/// Stats changes since a given height (see Blockchain::stats_since)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StatsDelta {
+    pub since_height: u64,
+    pub current_height: u64,
+    pub new_blocks: u64,
+    pub supply_delta: u64,       // microunits (coinbase issued since)
+    pub current_difficulty: u32,
+    pub changed: bool,
+}
+
+/// Point-in-time chain view (see Blockchain::snapshot)
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChainSnapshot {
+    pub height: u64,
+    pub tip_hash: String,
+    pub tip_timestamp: i64,
+    pub stats: BlockchainStats,
+}
+
+/// One account in an account state dump (see Blockchain::account_state_page)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountStateEntry {
+    pub address: String,
+    pub balance: u64,            // microunits (spendable)
+    pub locked: u64,             // microunits
+    pub nonce: u64,
+}
+
+/// Would-be contents of the block at `height` (see preview_next_block)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockPreview {
+    pub height: u64,
+    pub transactions: Vec<Transaction>, // user transactions, without coinbase/treasury
+    pub total_fees: u64,                // microunits
+}
+
+/// Page of the account state at `height`; pass `next_after` back to continue
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountStatePage {
+    pub height: u64,
+    pub tip_hash: String,
+    pub account_count: usize,
+    pub total_spendable: u64,    // microunits, all accounts
+    pub total_locked: u64,       // microunits, all accounts
+    pub accounts: Vec<AccountStateEntry>,
+    pub next_after: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupplyInfo {
+    pub total_issued: u64,       // microunits
+    pub circulating: u64,        // microunits (spendable)
+    pub locked: u64,             // microunits (immature coinbase + vesting)
+    pub burned: u64,             // microunits (fee burn)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::crypto::FalconKeypair;
+
+    const MINER: &str = "0x1111111111111111111111111111111111111111";
+    const RECIPIENT: &str = "0x2222222222222222222222222222222222222222";
+
+    /// Fresh testnet chain (difficulty 4) in a temp directory
+    fn test_blockchain() -> (tempfile::TempDir, Blockchain) {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, ChainNetwork::Testnet)
+            .unwrap()
+            .with_activations(ActivationHeights::all_at(0));
+        (dir, blockchain)
+    }
+
+    /// Credit an address with immediately spendable funds
+    fn fund(blockchain: &Blockchain, address: &str, amount: u64) {
+        let credit = Transaction::new("TREASURY".to_string(), address.to_string(), amount, 0);
+        blockchain.get_account_state_mut().credit_account(&credit, 0, COINBASE_MATURITY);
+    }
+
+    /// Build a transfer signed by `keypair`
+    fn signed_transfer(keypair: &FalconKeypair, recipient: &str, amount: u64, fee: u64, nonce: u64) -> Transaction {
+        let mut tx = Transaction::new(
+            keypair.get_address(),
+            recipient.to_string(),
+            amount,
+            chrono::Utc::now().timestamp(),
+        );
+        tx.fee = fee;
+        tx.nonce = nonce;
+        tx.public_key = keypair.public_key.clone();
+        tx.signature = keypair.sign(&tx.get_signing_data());
+        tx
+    }
+
+    #[test]
+    fn contract_transactions_follow_contracts_enabled() {
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+        let signed_call = || {
+            let mut tx = Transaction::new_call_contract(
+                sender.clone(),
+                RECIPIENT.to_string(),
+                "transfer".to_string(),
+                vec![1, 2, 3],
+                chrono::Utc::now().timestamp(),
+                1,
+            );
+            tx.public_key = keypair.public_key.clone();
+            tx.signature = keypair.sign(&tx.get_signing_data());
+            tx
+        };
+
+        let (_dir, enabled) = test_blockchain();
+        fund(&enabled, &sender, 1_000_000);
+        assert!(enabled.add_transaction(signed_call()).is_ok());
+
+        let (_dir2, disabled) = test_blockchain();
+        let disabled = disabled.with_contracts_enabled(false);
+        fund(&disabled, &sender, 1_000_000);
+        assert!(matches!(
+            disabled.add_transaction(signed_call()),
+            Err(BlockchainError::ContractsDisabled)
+        ));
+        // Plain transfers are unaffected
+        assert!(disabled.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1)).is_ok());
+    }
+
+    #[test]
+    fn block_assembly_places_receive_before_dependent_spend() {
+        let (_dir, blockchain) = test_blockchain();
+        let alice = FalconKeypair::generate();
+        let bob = FalconKeypair::generate();
+        fund(&blockchain, &alice.get_address(), 1_000_000);
+
+        // Alice pays Bob, then sends a second (higher fee) tx with the next nonce
+        let receive = signed_transfer(&alice, &bob.get_address(), 100_000, 1_000, 1);
+        let alice_next = signed_transfer(&alice, RECIPIENT, 1_000, 9_000, 2);
+        blockchain.add_transaction(receive.clone()).unwrap();
+        blockchain.add_transaction(alice_next.clone()).unwrap();
+
+        // Bob spends the unconfirmed funds with the highest fee; admission checks
+        // confirmed balances only, so place it in the pool directly
+        let spend = signed_transfer(&bob, RECIPIENT, 50_000, 10_000, 1);
+        blockchain.pending_transactions.write().push(spend.clone());
+
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+
+        let block = blockchain.get_latest_block();
+        let position = |tx: &Transaction| block.transactions.iter().position(|t| t.hash() == tx.hash()).unwrap();
+        assert!(position(&receive) < position(&spend));
+        assert!(position(&receive) < position(&alice_next));
+        assert_eq!(blockchain.get_balance(&bob.get_address()), 100_000 - 50_000 - 10_000);
+    }
+
+    #[test]
+    fn preview_matches_next_mined_block() {
+        let (_dir, blockchain) = test_blockchain();
+        let alice = FalconKeypair::generate();
+        let bob = FalconKeypair::generate();
+        let carol = FalconKeypair::generate();
+        fund(&blockchain, &alice.get_address(), 1_000_000);
+        fund(&blockchain, &carol.get_address(), 1_000_000);
+
+        let receive = signed_transfer(&alice, &bob.get_address(), 100_000, 1_000, 1);
+        blockchain.add_transaction(receive).unwrap();
+        blockchain.add_transaction(signed_transfer(&alice, RECIPIENT, 1_000, 9_000, 2)).unwrap();
+        blockchain.add_transaction(signed_transfer(&carol, RECIPIENT, 1_000, 5_000, 1)).unwrap();
+        // Spends funds received in the same block, and a nonce gap that can't be mined yet
+        blockchain.pending_transactions.write().push(signed_transfer(&bob, RECIPIENT, 50_000, 10_000, 1));
+        blockchain.pending_transactions.write().push(signed_transfer(&carol, RECIPIENT, 1_000, 20_000, 3));
+
+        let preview = blockchain.preview_next_block();
+        assert_eq!(preview.height, 1);
+        assert_eq!(preview.transactions.len(), 4);
+        assert_eq!(preview.total_fees, 25_000);
+        // Nothing was mined or dropped
+        assert_eq!(blockchain.get_height(), 1);
+        assert_eq!(blockchain.get_pending_transactions().len(), 5);
+
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        let block = blockchain.get_latest_block();
+        let mined: Vec<String> = block
+            .transactions
+            .iter()
+            .filter(|tx| !tx.is_coinbase() && tx.sender != "TREASURY")
+            .map(|tx| tx.hash().to_string())
+            .collect();
+        let previewed: Vec<String> = preview.transactions.iter().map(|tx| tx.hash().to_string()).collect();
+        assert_eq!(mined, previewed);
+        assert_eq!(block.get_total_fees(), preview.total_fees);
+    }
+
+    #[test]
+    fn larger_transactions_need_proportionally_higher_fees() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        // A plain transfer clears the fee-rate floor at the default 0.001 QUA fee
+        let transfer = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1);
+        assert!(blockchain.required_fee(&transfer) <= 1_000);
+
+        let call_with_fee = |fee: u64| {
+            let mut tx = Transaction::new_call_contract(
+                keypair.get_address(),
+                RECIPIENT.to_string(),
+                "store".to_string(),
+                vec![0u8; 12 * 1024],
+                chrono::Utc::now().timestamp(),
+                1,
+            );
+            tx.fee = fee;
+            tx.public_key = keypair.public_key.clone();
+            tx.signature = keypair.sign(&tx.get_signing_data());
+            tx
+        };
+
+        // ~14KB call: the flat fee that covers a transfer is no longer enough
+        let cheap = call_with_fee(1_000);
+        let required = blockchain.required_fee(&cheap);
+        assert!(required > 1_000);
+        assert_eq!(required, (cheap.weight() as u64 * MIN_FEE_PER_KB).div_ceil(1000));
+        assert!(matches!(
+            blockchain.validate_transaction(&cheap),
+            Err(BlockchainError::FeeTooLow { fee: 1_000, min }) if min == required
+        ));
+
+        // Paying the rate clears it (small margin: Falcon signature length varies slightly)
+        let paid = call_with_fee(required + 50);
+        assert!(blockchain.validate_transaction(&paid).is_ok());
+    }
+
+    #[test]
+    fn block_fee_rate_floor_is_a_consensus_constant() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        let call_with_fee = |fee: u64| {
+            let mut tx = Transaction::new_call_contract(
+                keypair.get_address(),
+                RECIPIENT.to_string(),
+                "store".to_string(),
+                vec![0u8; 12 * 1024],
+                chrono::Utc::now().timestamp(),
+                1,
+            );
+            tx.fee = fee;
+            tx.public_key = keypair.public_key.clone();
+            tx.signature = keypair.sign(&tx.get_signing_data());
+            tx
+        };
+        let consensus_rate = (call_with_fee(0).weight() as u64 * MIN_FEE_PER_KB).div_ceil(1000) + 50;
+
+        // A node with a stricter relay floor keeps the call out of its mempool...
+        let blockchain = blockchain.with_min_fee_per_kb(MIN_FEE_PER_KB * 10);
+        assert!(matches!(
+            blockchain.validate_transaction(&call_with_fee(consensus_rate)),
+            Err(BlockchainError::FeeTooLow { .. })
+        ));
+        // ...but still accepts a block paying only the consensus rate
+        let block = mine_template_with(&blockchain, call_with_fee(consensus_rate));
+        blockchain.add_network_block(block).unwrap();
+        assert_eq!(blockchain.get_height(), 2);
+
+        // Below the activation height only the type minimum applies
+        let (_dir, blockchain) = test_blockchain();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+        let under_rate = mine_template_with(&blockchain, call_with_fee(MIN_CALL_FEE));
+        assert!(matches!(
+            blockchain.add_network_block(under_rate.clone()),
+            Err(BlockchainError::FeeTooLow { .. })
+        ));
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            fee_rate_floor: under_rate.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        blockchain.add_network_block(under_rate).unwrap();
+        assert_eq!(blockchain.get_height(), 2);
+    }
+
+    #[test]
+    fn contract_transactions_pay_their_type_minimum() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        let signed = |builder: crate::core::transaction::TransactionBuilder| {
+            let mut tx = builder.sender(keypair.get_address()).nonce(1).build();
+            tx.public_key = keypair.public_key.clone();
+            tx.signature = keypair.sign(&tx.get_signing_data());
+            tx
+        };
+        let deploy = |fee| signed(Transaction::builder().fee(fee).deploy_contract(vec![0u8; 64]));
+
+        // The same fee clears the transfer floor but not the deploy floor
+        let transfer = signed(Transaction::builder().recipient(RECIPIENT).amount(1_000).fee(2_000));
+        assert!(blockchain.validate_transaction(&transfer).is_ok());
+        assert!(matches!(
+            blockchain.validate_transaction(&deploy(2_000)),
+            Err(BlockchainError::FeeTooLow { fee: 2_000, min: MIN_DEPLOY_FEE })
+        ));
+        assert!(blockchain.validate_transaction(&deploy(MIN_DEPLOY_FEE)).is_ok());
+
+        // Blocks from peers are held to the same floor
+        let underpaid = deploy(2_000);
+        let block = mine_template_with(&blockchain, underpaid);
+        assert!(matches!(blockchain.add_network_block(block), Err(BlockchainError::FeeTooLow { .. })));
+        assert_eq!(blockchain.get_height(), 1);
+
+        // Mempool floors are configurable per type, but blocks keep the constant floors
+        let blockchain = blockchain.with_min_fees(MinFees { transfer: 3_000, deploy: 50_000, ..MinFees::default() });
+        assert!(matches!(
+            blockchain.validate_transaction(&transfer),
+            Err(BlockchainError::FeeTooLow { fee: 2_000, min: 3_000 })
+        ));
+        assert!(blockchain.validate_transaction(&deploy(MIN_DEPLOY_FEE)).is_err());
+        let block = mine_template_with(&blockchain, deploy(MIN_DEPLOY_FEE));
+        assert!(blockchain.add_network_block(block).is_ok());
+
+        // Below activation, blocks only need the transfer floor
+        let (_dir, blockchain) = test_blockchain();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+        let block = mine_template_with(&blockchain, deploy(2_000));
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            type_min_fees: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        assert!(blockchain.add_network_block(block).is_ok());
+    }
+
+    #[test]
+    fn deploy_moves_no_value_and_call_pays_its_contract() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+        let signed = |tx: Transaction| {
+            let mut tx = tx;
+            tx.public_key = keypair.public_key.clone();
+            tx.signature = keypair.sign(&tx.get_signing_data());
+            tx
+        };
+        let builder = || Transaction::builder().sender(keypair.get_address()).nonce(1);
+
+        // A deployment carrying value is rejected, in the mempool and in peer blocks
+        let paid_deploy = signed(builder().amount(5_000).fee(MIN_DEPLOY_FEE).deploy_contract(vec![0u8; 64]).build());
+        assert!(matches!(
+            blockchain.add_transaction(paid_deploy.clone()),
+            Err(BlockchainError::InvalidContractValue(_))
+        ));
+        let block = mine_template_with(&blockchain, paid_deploy.clone());
+        assert!(matches!(blockchain.add_network_block(block), Err(BlockchainError::InvalidContractValue(_))));
+
+        // Blocks below the rule's activation height still take it
+        let (_other_dir, other) = test_blockchain();
+        fund(&other, &keypair.get_address(), 1_000_000);
+        let block = mine_template_with(&other, paid_deploy);
+        let other = other.with_activations(ActivationHeights {
+            contract_value: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        assert!(other.add_network_block(block).is_ok());
+
+        // So is a call whose value would go somewhere other than the called contract
+        let mut misdirected = builder().amount(5_000).fee(MIN_CALL_FEE).call_contract(RECIPIENT, "f", vec![]).build();
+        misdirected.recipient = MINER.to_string();
+        assert!(matches!(
+            blockchain.add_transaction(signed(misdirected)),
+            Err(BlockchainError::InvalidContractValue(_))
+        ));
+
+        // A call's amount is credited to the contract
+        let paid_call = signed(builder().amount(5_000).fee(MIN_CALL_FEE).call_contract(RECIPIENT, "f", vec![]).build());
+        blockchain.add_transaction(paid_call).unwrap();
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        assert_eq!(blockchain.get_balance(RECIPIENT), 5_000);
+        assert_eq!(blockchain.get_balance(&keypair.get_address()), 1_000_000 - 5_000 - MIN_CALL_FEE);
+    }
+
+    #[test]
+    fn locktimed_transaction_waits_for_its_height() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+        fund(&blockchain, &sender, 1_000_000);
+
+        let locked = {
+            let mut tx = Transaction::builder()
+                .sender(sender.clone())
+                .recipient(RECIPIENT)
+                .amount(1_000)
+                .nonce(1)
+                .not_before_height(3)
+                .build();
+            tx.public_key = keypair.public_key.clone();
+            tx.signature = keypair.sign(&tx.get_signing_data());
+            tx
+        };
+        let mut unlocked = locked.clone();
+        unlocked.not_before_height = None;
+        assert_ne!(locked.hash(), unlocked.hash()); // locktime is covered by the signature
+
+        // Including it early is a consensus failure
+        let early = mine_template_with(&blockchain, locked.clone());
+        assert!(matches!(
+            blockchain.add_network_block(early),
+            Err(BlockchainError::TransactionLocked { not_before: 3, height: 1 })
+        ));
+
+        // Accepted into the mempool; the sender's next nonce queues behind it
+        blockchain.add_transaction(locked.clone()).unwrap();
+        blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 2)).unwrap();
+        for height in 1..3 {
+            blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+            assert_eq!(blockchain.get_block_by_height(height).unwrap().transactions.len(), 2);
+            assert_eq!(blockchain.get_pending_transactions().len(), 2);
+        }
+
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        let block = blockchain.get_block_by_height(3).unwrap();
+        assert!(block.transactions.iter().any(|tx| tx.hash() == locked.hash()));
+        assert!(blockchain.get_pending_transactions().is_empty());
+        assert_eq!(blockchain.get_account_state_mut().get_nonce(&sender), 2);
+    }
+
+    #[test]
+    fn transaction_dated_an_hour_ahead_is_rejected() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        let mut future = Transaction::new(keypair.get_address(), RECIPIENT.to_string(), 1_000,
+            chrono::Utc::now().timestamp() + 3600);
+        future.nonce = 1;
+        future.public_key = keypair.public_key.clone();
+        future.signature = keypair.sign(&future.get_signing_data());
+
+        assert!(matches!(
+            blockchain.add_transaction(future.clone()),
+            Err(BlockchainError::TransactionFromFuture(ahead)) if ahead >= 3600 - 5
+        ));
+        let block = mine_template_with(&blockchain, future.clone());
+        assert!(matches!(blockchain.add_network_block(block), Err(BlockchainError::TransactionFromFuture(_))));
+        assert_eq!(blockchain.get_height(), 1);
+
+        // Blocks below the rule's activation height still take it
+        let (_other_dir, other) = test_blockchain();
+        fund(&other, &keypair.get_address(), 1_000_000);
+        let block = mine_template_with(&other, future);
+        let other = other.with_activations(ActivationHeights {
+            future_transaction_time: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        assert!(other.add_network_block(block).is_ok());
+    }
+
+    #[test]
+    fn block_with_expired_transaction_is_rejected() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        // Validly signed with a correct nonce, but older than the expiry window
+        let mut expired = Transaction::new(keypair.get_address(), RECIPIENT.to_string(), 1_000,
+            chrono::Utc::now().timestamp() - TRANSACTION_EXPIRY_SECONDS - 60);
+        expired.nonce = 1;
+        expired.public_key = keypair.public_key.clone();
+        expired.signature = keypair.sign(&expired.get_signing_data());
+
+        let block = mine_template_with(&blockchain, expired.clone());
+        assert!(matches!(blockchain.add_network_block(block), Err(BlockchainError::TransactionExpired)));
+        assert_eq!(blockchain.get_height(), 1);
+
+        // Blocks below the rule's activation height still take it
+        let (_other_dir, other) = test_blockchain();
+        fund(&other, &keypair.get_address(), 1_000_000);
+        let block = mine_template_with(&other, expired);
+        let other = other.with_activations(ActivationHeights {
+            transaction_expiry: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        assert!(other.add_network_block(block).is_ok());
+
+        // A fresh transaction with the same nonce is still accepted
+        let block = mine_template_with(&blockchain, signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1));
+        blockchain.add_network_block(block).unwrap();
+    }
+
+    #[test]
+    fn sender_at_pending_limit_is_rejected_but_others_are_not() {
+        let (_dir, blockchain) = test_blockchain();
+        let blockchain = blockchain.with_max_pending_per_sender(2);
+        let (busy, other) = (FalconKeypair::generate(), FalconKeypair::generate());
+        fund(&blockchain, &busy.get_address(), 1_000_000);
+        fund(&blockchain, &other.get_address(), 1_000_000);
+
+        blockchain.add_transaction(signed_transfer(&busy, RECIPIENT, 1_000, 1_000, 1)).unwrap();
+        blockchain.add_transaction(signed_transfer(&busy, RECIPIENT, 1_000, 1_000, 2)).unwrap();
+        assert!(matches!(
+            blockchain.add_transaction(signed_transfer(&busy, RECIPIENT, 1_000, 1_000, 3)),
+            Err(BlockchainError::SenderMempoolLimit(2))
+        ));
+        blockchain.add_transaction(signed_transfer(&other, RECIPIENT, 1_000, 1_000, 1)).unwrap();
+
+        // Once a block drains the sender's queue, it may submit again
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        blockchain.add_transaction(signed_transfer(&busy, RECIPIENT, 1_000, 1_000, 3)).unwrap();
+    }
+
+    #[test]
+    fn fee_estimate_follows_recent_fees_and_congestion() {
+        let (_dir, blockchain) = test_blockchain();
+        let floor = blockchain.required_fee(&Blockchain::typical_transfer());
+        assert!(floor > MIN_TRANSACTION_FEE && floor < 2_000);
+        assert_eq!(blockchain.estimate_fee(1), floor);
+
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+        for (nonce, fee) in (1..).zip([1_000, 3_000, 2_000]) {
+            blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, fee, nonce)).unwrap();
+        }
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+
+        // Empty mempool: the floor, whatever recent blocks paid
+        assert_eq!(blockchain.estimate_fee(1), floor);
+
+        // Something waiting but room to spare: the median recent fee
+        blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 4)).unwrap();
+        assert_eq!(blockchain.estimate_fee(1), 2_000);
+        assert_eq!(blockchain.estimate_fee(0), 2_000);
+
+        // A quote the mempool would refuse is raised to its floor
+        let (_strict_dir, strict) = test_blockchain();
+        let strict = strict.with_min_fee_per_kb(5_000);
+        let strict_floor = strict.required_fee(&Blockchain::typical_transfer());
+        assert!(strict_floor > 2_000);
+        assert_eq!(strict.estimate_fee(1), strict_floor);
+
+        // Three blocks' worth waiting: 3x for the next block, no premium over three blocks
+        let backlog = 3 * MAX_BLOCK_SIZE_BYTES;
+        assert_eq!(Blockchain::scale_fee_for_congestion(2_000, 10, backlog, 1), 6_000);
+        assert_eq!(Blockchain::scale_fee_for_congestion(2_000, 10, backlog, 3), 2_000);
+        assert_eq!(Blockchain::scale_fee_for_congestion(2_000, 3 * MAX_BLOCK_TRANSACTIONS, 0, 1), 6_000);
+        assert_eq!(Blockchain::scale_fee_for_congestion(10, 1, 0, 1), MIN_TRANSACTION_FEE);
+    }
+
+    #[test]
+    fn replace_by_fee_needs_a_ten_percent_bump() {
+        let (_dir, blockchain) = test_blockchain();
+        let blockchain = blockchain.with_max_pending_per_sender(2);
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        let stuck = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1);
+        blockchain.add_transaction(stuck.clone()).unwrap();
+        blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 2)).unwrap();
+
+        // Under 10% more is refused and leaves the original in place
+        assert!(matches!(
+            blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_099, 1)),
+            Err(BlockchainError::ReplacementUnderpriced { min_fee: 1_100 })
+        ));
+        assert!(blockchain.get_pending_transactions().iter().any(|tx| tx.hash() == stuck.hash()));
+
+        // A sufficient bump evicts it, even with the sender at its pending limit
+        let bumped = signed_transfer(&keypair, RECIPIENT, 1_000, 1_100, 1);
+        blockchain.add_transaction(bumped.clone()).unwrap();
+        {
+            let pending = blockchain.get_pending_transactions();
+            assert_eq!(pending.len(), 2);
+            assert!(pending.iter().all(|tx| tx.hash() != stuck.hash()));
+            assert!(pending.iter().any(|tx| tx.hash() == bumped.hash()));
+        }
+        assert_eq!(blockchain.get_next_nonce(&keypair.get_address()), 3);
+
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        assert!(blockchain.get_transaction(&bumped.hash()).is_some());
+    }
+
+    #[test]
+    fn account_count_includes_new_recipients() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+        let before = blockchain.get_stats().account_count;
+
+        let recipients: Vec<String> = (1..=3u64).map(|i| format!("0x{:040x}", 0xabc0 + i)).collect();
+        for (nonce, recipient) in (1..).zip(&recipients) {
+            blockchain.add_transaction(signed_transfer(&keypair, recipient, 1_000, 1_000, nonce)).unwrap();
+        }
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+
+        // The miner and treasury accounts are created by the same block
+        let stats = blockchain.get_stats();
+        let state = blockchain.account_state.read();
+        assert_eq!(stats.account_count, state.account_count());
+        assert!(stats.account_count >= before + recipients.len());
+        assert!(recipients.iter().all(|r| state.iter().any(|(address, _)| address == r)));
+    }
+
+    #[test]
+    fn oversized_fields_are_rejected() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        let long_recipient = format!("0x{}", "a".repeat(4096));
+        let oversized_address = signed_transfer(&keypair, &long_recipient, 1_000, 1_000, 1);
+        assert!(matches!(
+            blockchain.validate_transaction(&oversized_address),
+            Err(BlockchainError::FieldTooLarge(_))
+        ));
+
+        let mut oversized_args = Transaction::new_call_contract(
+            keypair.get_address(),
+            RECIPIENT.to_string(),
+            "transfer".to_string(),
+            vec![0u8; crate::core::transaction::MAX_CALL_ARGS_BYTES + 1],
+            chrono::Utc::now().timestamp(),
+            1,
+        );
+        oversized_args.public_key = keypair.public_key.clone();
+        oversized_args.signature = keypair.sign(&oversized_args.get_signing_data());
+        assert!(matches!(
+            blockchain.add_transaction(oversized_args),
+            Err(BlockchainError::FieldTooLarge(_))
+        ));
+    }
+
+    #[test]
+    fn validate_transaction_rejection_matrix() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+        fund(&blockchain, &sender, 1_000_000);
+
+        // Valid transfer passes
+        let valid = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1);
+        assert!(blockchain.validate_transaction(&valid).is_ok());
+
+        // Fee below minimum
+        let low_fee = signed_transfer(&keypair, RECIPIENT, 1_000, MIN_TRANSACTION_FEE - 1, 1);
+        assert!(matches!(
+            blockchain.validate_transaction(&low_fee),
+            Err(BlockchainError::FeeTooLow { .. })
+        ));
+
+        // Expired timestamp
+        let mut expired = Transaction::new(sender.clone(), RECIPIENT.to_string(), 1_000,
+            chrono::Utc::now().timestamp() - TRANSACTION_EXPIRY_SECONDS - 60);
+        expired.nonce = 1;
+        expired.public_key = keypair.public_key.clone();
+        expired.signature = keypair.sign(&expired.get_signing_data());
+        assert!(matches!(
+            blockchain.validate_transaction(&expired),
+            Err(BlockchainError::TransactionExpired)
+        ));
+
+        // Malformed recipient address
+        let bad_recipient = signed_transfer(&keypair, "0xNOTHEX", 1_000, 1_000, 1);
+        assert!(matches!(
+            blockchain.validate_transaction(&bad_recipient),
+            Err(BlockchainError::InvalidAddress(_))
+        ));
+
+        // Tampered amount breaks the signature
+        let mut tampered = valid.clone();
+        tampered.amount += 1;
+        assert!(matches!(
+            blockchain.validate_transaction(&tampered),
+            Err(BlockchainError::InvalidSignature)
+        ));
+
+        // Nonce gap
+        let bad_nonce = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 2);
+        assert!(matches!(
+            blockchain.validate_transaction(&bad_nonce),
+            Err(BlockchainError::InvalidNonce { expected: 1, actual: 2 })
+        ));
+
+        // Amount + fee exceeds balance
+        let overspend = signed_transfer(&keypair, RECIPIENT, 1_000_000, 1_000, 1);
+        assert!(matches!(
+            blockchain.validate_transaction(&overspend),
+            Err(BlockchainError::InsufficientBalance { required: 1_001_000, available: 1_000_000 })
+        ));
+
+        // Validation alone has no side effects
+        assert!(blockchain.get_pending_transactions().is_empty());
+        assert!(blockchain.validate_transaction(&valid).is_ok());
+    }
+
+    #[test]
+    fn add_transaction_applies_validate_rules() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+
+        let first = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1);
+        blockchain.add_transaction(first.clone()).unwrap();
+        assert_eq!(blockchain.get_pending_transactions().len(), 1);
+
+        // Mempool nonce is now reserved, so the same slot is rejected in both paths
+        assert!(matches!(
+            blockchain.validate_transaction(&first),
+            Err(BlockchainError::InvalidNonce { expected: 2, actual: 1 })
+        ));
+        assert!(matches!(
+            blockchain.add_transaction(first),
+            Err(BlockchainError::InvalidNonce { expected: 2, actual: 1 })
+        ));
+
+        let second = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 2);
+        blockchain.add_transaction(second).unwrap();
+        assert_eq!(blockchain.get_pending_transactions().len(), 2);
+    }
+
+    #[test]
+    fn add_transaction_checks_signatures_before_taking_the_mempool_lock() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 1_000_000);
+        let mut forged = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1);
+        forged.amount += 1;
+
+        // While a reader holds the mempool, a transaction failing a stateless check is
+        // still rejected straight away instead of queueing for the write lock
+        let pending = blockchain.pending_transactions.read();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        std::thread::scope(|scope| {
+            scope.spawn(|| sender.send(blockchain.add_transaction(forged)).unwrap());
+            let result = receiver.recv_timeout(std::time::Duration::from_secs(10));
+            drop(pending);
+            assert!(matches!(result, Ok(Err(BlockchainError::InvalidSignature))));
+        });
+    }
+
+    /// Build a template from the current mempool and mine it
+    fn mine_template(blockchain: &Blockchain) -> Block {
+        let mut block = blockchain.create_block_template(MINER.to_string()).unwrap();
+        block.mine();
+        block
+    }
+
+    /// Mine the template with `extra` appended, bypassing block assembly's filtering
+    fn mine_template_with(blockchain: &Blockchain, extra: Transaction) -> Block {
+        let template = blockchain.create_block_template(MINER.to_string()).unwrap();
+        let mut transactions = template.transactions;
+        // Keep the coinbase/treasury fee shares consistent with the added fee
+        transactions[0].amount += extra.fee * FEE_VALIDATOR_PERCENT / 100;
+        transactions[1].amount += extra.fee * FEE_TREASURY_PERCENT / 100;
+        transactions.push(extra);
+        let mut block = Block::new(template.index, transactions, template.previous_hash, template.difficulty);
+        block.timestamp = template.timestamp;
+        block.version = template.version;
+        block.mine();
+        block
+    }
+
+    #[test]
+    fn network_block_with_sequential_nonces_is_accepted() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+        fund(&blockchain, &sender, 1_000_000);
+
+        blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1)).unwrap();
+        blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 2)).unwrap();
+
+        blockchain.add_network_block(mine_template(&blockchain)).unwrap();
+        assert_eq!(blockchain.get_height(), 2);
+        assert_eq!(blockchain.get_account_state_mut().get_nonce(&sender), 2);
+    }
+
+    #[test]
+    fn block_with_non_canonical_order_is_rejected() {
+        let (_dir, blockchain) = test_blockchain();
+        let first = FalconKeypair::generate();
+        let second = FalconKeypair::generate();
+        for keypair in [&first, &second] {
+            fund(&blockchain, &keypair.get_address(), 1_000_000);
+            blockchain.add_transaction(signed_transfer(keypair, RECIPIENT, 1_000, 1_000, 1)).unwrap();
+        }
+
+        // Template lists independent txs by sender address after coinbase + treasury
+        let template = blockchain.create_block_template(MINER.to_string()).unwrap();
+        let senders: Vec<_> = template.transactions[2..].iter().map(|tx| tx.sender.clone()).collect();
+        let mut sorted = senders.clone();
+        sorted.sort();
+        assert_eq!(senders, sorted);
+
+        let mut transactions = template.transactions;
+        transactions.swap(2, 3);
+        let mut block = Block::new(template.index, transactions, template.previous_hash, template.difficulty);
+        block.timestamp = template.timestamp;
+        block.version = template.version;
+        block.mine();
+
+        assert!(matches!(blockchain.add_network_block(block.clone()), Err(BlockchainError::InvalidBlock)));
+        assert_eq!(blockchain.get_height(), 1);
+
+        // Below the activation height the order is not checked, as on nodes without the rule
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            canonical_order: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        blockchain.add_network_block(block).unwrap();
+        assert_eq!(blockchain.get_height(), 2);
+    }
+
+    #[test]
+    fn coinbase_rewards_unlock_together_below_tranche_activation() {
+        let (_dir, blockchain) = test_blockchain();
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            coinbase_tranches: 3,
+            ..ActivationHeights::all_at(0)
+        });
+        let mut state = AccountState::new();
+        for index in 1..=3 {
+            let coinbase = Transaction::new("COINBASE".to_string(), MINER.to_string(), 100, 0);
+            let block = Block::try_new(index, vec![coinbase], "ab".repeat(32), 1).unwrap();
+            blockchain.apply_block(&mut state, &block).unwrap();
+        }
+
+        // Block 2's reward relocked block 1's; block 3's reward unlocks on its own
+        state.unlock_mature_coinbase(1 + COINBASE_MATURITY);
+        assert_eq!(state.get_balance(MINER), 0);
+        state.unlock_mature_coinbase(2 + COINBASE_MATURITY);
+        assert_eq!(state.get_balance(MINER), 200);
+        assert_eq!(state.get_locked_balance(MINER), 100);
+        state.unlock_mature_coinbase(3 + COINBASE_MATURITY);
+        assert_eq!(state.get_balance(MINER), 300);
+    }
+
+    #[test]
+    fn coinbase_timestamp_must_match_block() {
+        let (_dir, blockchain) = test_blockchain();
+        let template = blockchain.create_block_template(MINER.to_string()).unwrap();
+        assert_eq!(template.transactions[0].timestamp, template.timestamp);
+
+        let mut transactions = template.transactions.clone();
+        transactions[0].timestamp -= 1;
+        let mut block = Block::new(template.index, transactions, template.previous_hash.clone(), template.difficulty);
+        block.timestamp = template.timestamp;
+        block.version = template.version;
+        block.mine();
+
+        assert!(matches!(blockchain.add_network_block(block.clone()), Err(BlockchainError::InvalidBlock)));
+        assert_eq!(blockchain.get_height(), 1);
+
+        let mut template = template;
+        template.mine();
+        blockchain.add_network_block(template).unwrap();
+        assert_eq!(blockchain.get_height(), 2);
+
+        // Below the activation height any coinbase timestamp is accepted
+        let (_dir, blockchain) = test_blockchain();
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            coinbase_timestamp: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        blockchain.add_network_block(block).unwrap();
+        assert_eq!(blockchain.get_height(), 2);
+    }
+
+    #[test]
+    fn orphan_connects_when_parent_arrives_and_pool_is_capped() {
+        let (_dir, source) = test_blockchain();
+        for _ in 0..3 {
+            source.mine_pending_transactions(MINER.to_string()).unwrap();
+        }
+        let blocks: Vec<Block> = source.get_chain()[1..].to_vec();
+
+        let (_dir2, blockchain) = test_blockchain();
+        blockchain.add_network_block(blocks[1].clone()).unwrap();
+        assert!(blockchain.has_orphan(&blocks[1].hash));
+        assert_eq!(blockchain.get_height(), 1);
+
+        blockchain.add_network_block(blocks[0].clone()).unwrap();
+        assert_eq!(blockchain.get_height(), 3);
+        assert_eq!(blockchain.get_latest_block().hash, blocks[1].hash);
+        assert_eq!(blockchain.orphan_count(), 0);
+
+        // Capped pool: the older orphan is evicted, so only its sibling's parent connects
+        let (_dir3, capped) = test_blockchain();
+        let capped = capped.with_max_orphan_blocks(1);
+        capped.add_network_block(blocks[1].clone()).unwrap();
+        capped.add_network_block(blocks[2].clone()).unwrap();
+        assert_eq!(capped.orphan_count(), 1);
+        assert!(!capped.has_orphan(&blocks[1].hash));
+
+        capped.add_network_block(blocks[0].clone()).unwrap();
+        assert_eq!(capped.get_height(), 2);
+        assert!(capped.has_orphan(&blocks[2].hash));
+    }
+
+    #[test]
+    fn network_block_with_out_of_order_nonce_is_rejected() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+        fund(&blockchain, &sender, 1_000_000);
+
+        // Bypass mempool and assembly checks: nonce 2 with no nonce 1 before it
+        let out_of_order = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 2);
+
+        let result = blockchain.add_network_block(mine_template_with(&blockchain, out_of_order));
+        assert!(matches!(
+            result,
+            Err(BlockchainError::InvalidNonce { expected: 1, actual: 2 })
+        ));
+        assert_eq!(blockchain.get_height(), 1);
+        assert_eq!(blockchain.get_balance(&sender), 1_000_000);
+    }
+
+    #[test]
+    fn mining_to_malformed_address_is_rejected() {
+        let (_dir, blockchain) = test_blockchain();
+
+        for bad in ["", "miner", "0x1234", "0xZZ11111111111111111111111111111111111111"] {
+            assert!(matches!(
+                blockchain.mine_pending_transactions(bad.to_string()),
+                Err(BlockchainError::InvalidAddress(_))
+            ));
+        }
+        assert_eq!(blockchain.get_height(), 1);
+    }
+
+    #[test]
+    fn concurrent_local_submissions_get_unique_nonces() {
+        let (_dir, blockchain) = test_blockchain();
+        let blockchain = Arc::new(blockchain);
+        let keypair = Arc::new(FalconKeypair::generate());
+        fund(&blockchain, &keypair.get_address(), 10_000_000);
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let blockchain = Arc::clone(&blockchain);
+                let keypair = Arc::clone(&keypair);
+                std::thread::spawn(move || {
+                    let reservation = blockchain.reserve_next_nonce(&keypair.get_address());
+                    let tx = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, reservation.nonce);
+                    blockchain.add_transaction(tx)
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            handle.join().unwrap().unwrap();
+        }
+
+        let mut nonces: Vec<u64> = blockchain.get_pending_transactions().iter().map(|tx| tx.nonce).collect();
+        nonces.sort_unstable();
+        assert_eq!(nonces, (1..=8).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn reward_at_extreme_heights_stays_at_floor() {
+        let (_dir, blockchain) = test_blockchain();
+        assert_eq!(Blockchain::base_reward_at(0), YEAR_1_REWARD);
+        assert_eq!(Blockchain::base_reward_at(BLOCKS_PER_YEAR), YEAR_1_REWARD * (100 - ANNUAL_REDUCTION_PERCENT) / 100);
+
+        // The floor is reached before the shortcut kicks in, so it doesn't change any reward
+        assert_eq!(Blockchain::base_reward_at((REWARD_FLOOR_YEARS - 1) * BLOCKS_PER_YEAR), MIN_REWARD);
+        for height in [REWARD_FLOOR_YEARS * BLOCKS_PER_YEAR, (i32::MAX as u64 + 1) * BLOCKS_PER_YEAR, u64::MAX] {
+            assert_eq!(Blockchain::base_reward_at(height), MIN_REWARD);
+            assert_eq!(blockchain.calculate_reward_at_height(height), MIN_REWARD);
+        }
+    }
+
+    #[test]
+    fn snapshot_matches_tip() {
+        let (_dir, blockchain) = test_blockchain();
+        let snapshot = blockchain.snapshot();
+        let tip = blockchain.get_latest_block();
+
+        assert_eq!(snapshot.height, 1);
+        assert_eq!(snapshot.tip_hash, tip.hash);
+        assert_eq!(snapshot.stats.chain_length, 1);
+        assert_eq!(snapshot.stats.current_difficulty, tip.difficulty);
+    }
+
+    #[test]
+    fn stats_since_current_height_reports_no_change() {
+        let (_dir, blockchain) = test_blockchain();
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+
+        let current = blockchain.get_height();
+        let delta = blockchain.stats_since(current);
+        assert!(!delta.changed);
+        assert_eq!(delta.new_blocks, 0);
+        assert_eq!(delta.supply_delta, 0);
+
+        // From one block back, the mined block's coinbase shows up
+        let delta = blockchain.stats_since(current - 1);
+        assert!(delta.changed);
+        assert_eq!(delta.new_blocks, 1);
+        assert!(delta.supply_delta > 0);
+    }
+
+    #[test]
+    fn block_timing_smooths_over_window() {
+        let at = |timestamp: i64| {
+            let mut block = Block::genesis(ChainNetwork::Testnet);
+            block.timestamp = timestamp;
+            block
+        };
+        // Genesis far in the past, then 5s, 5s, 5s, 25s intervals (target is 10s)
+        let chain: Vec<Block> = [0, 1_000, 1_005, 1_010, 1_015, 1_040].into_iter().map(at).collect();
+
+        let timing = Blockchain::block_timing_for(&chain, 4).unwrap();
+        assert_eq!(timing.window_blocks, 4);
+        assert_eq!(timing.avg_block_time_secs, 10.0);
+        assert_eq!(timing.blocks_ahead_of_target, 0.0);
+
+        // Without the slow last block the chain is running ahead of schedule
+        let timing = Blockchain::block_timing_for(&chain[..5], 20).unwrap();
+        assert_eq!(timing.window_blocks, 3);
+        assert_eq!(timing.avg_block_time_secs, 5.0);
+        assert_eq!(timing.blocks_ahead_of_target, 1.5);
+
+        // Only the slow interval: one block behind
+        let timing = Blockchain::block_timing_for(&chain, 1).unwrap();
+        assert_eq!(timing.blocks_ahead_of_target, -1.5);
+
+        assert!(Blockchain::block_timing_for(&chain[..2], 20).is_none());
+    }
+
+    #[test]
+    fn supply_breakdown_after_mining() {
+        let (_dir, blockchain) = test_blockchain();
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+
+        let supply = blockchain.get_supply();
+
+        // Fresh coinbase is immature, so part of the supply is locked
+        assert!(supply.locked > 0);
+        assert!(supply.circulating < supply.total_issued);
+        assert_eq!(supply.circulating + supply.locked + supply.burned, supply.total_issued);
+    }
+
+    #[tokio::test]
+    async fn runtime_stays_responsive_during_large_block_save() {
+        use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        let mut block = blockchain.get_latest_block();
+        let tx = signed_transfer(&keypair, RECIPIENT, 1_000, MIN_TRANSACTION_FEE, 0);
+        block.transactions = vec![tx; 2_000]; // several MB of JSON plus a flush
+
+        let saving = Arc::new(AtomicBool::new(true));
+        let ticks = Arc::new(AtomicU64::new(0));
+        // Single-threaded runtime: the ticker can only run while the save is off-thread
+        let ticker = tokio::spawn({
+            let (saving, ticks) = (saving.clone(), ticks.clone());
+            async move {
+                while saving.load(Ordering::SeqCst) {
+                    ticks.fetch_add(1, Ordering::SeqCst);
+                    tokio::task::yield_now().await;
+                }
+            }
+        });
+
+        let guard = Arc::new(blockchain);
+        run_blocking(guard, move |bc| bc.storage.save_block(&block)).await.unwrap();
+        saving.store(false, Ordering::SeqCst);
+        ticker.await.unwrap();
+
+        assert!(ticks.load(Ordering::SeqCst) > 1, "ticker starved during save");
+    }
+
+    #[test]
+    fn height_mismatch_is_detected_and_repaired() {
+        let (_dir, blockchain) = test_blockchain();
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        let storage = blockchain.storage.clone();
+        assert_eq!(storage.check_chain_height(false).unwrap(), 2);
+
+        // Crash between save_block and set_chain_height: a stray block past the height
+        let mut stray = blockchain.get_latest_block();
+        stray.index = 2;
+        storage.save_block(&stray).unwrap();
+        assert!(matches!(
+            Blockchain::new(storage.clone(), ChainNetwork::Testnet),
+            Err(BlockchainError::Storage(StorageError::HeightMismatch { stored: 2, found: 3 }))
+        ));
+        assert_eq!(storage.check_chain_height(true).unwrap(), 2);
+        assert!(storage.load_block(2).is_err());
+
+        // Height pointing past the last block on disk
+        storage.set_chain_height(5).unwrap();
+        assert!(matches!(
+            storage.check_chain_height(false),
+            Err(StorageError::HeightMismatch { stored: 5, found: 2 })
+        ));
+        assert_eq!(storage.check_chain_height(true).unwrap(), 2);
+        assert_eq!(Blockchain::new(storage, ChainNetwork::Testnet).unwrap().get_height(), 2);
+    }
+
+    #[test]
+    fn state_is_identical_after_restart() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || {
+            let storage = Arc::new(BlockchainStorage::new(dir.path()).unwrap());
+            Blockchain::new(storage, ChainNetwork::Testnet).unwrap()
+        };
+
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+        let addresses = [sender.as_str(), RECIPIENT, MINER, "TREASURY"];
+
+        let blockchain = open();
+        fund(&blockchain, &sender, 1_000_000);
+        let mut mined = Vec::new();
+        for nonce in 1..=4 {
+            let tx = signed_transfer(&keypair, RECIPIENT, 10_000, 1_000, nonce);
+            mined.push(tx.hash());
+            blockchain.add_transaction(tx).unwrap();
+            blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        }
+        assert_eq!(blockchain.get_transaction(&mined[2]).map(|(tx, height)| (tx.nonce, height)), Some((3, 3)));
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+
+        let tip = blockchain.get_latest_block();
+        let next_difficulty = blockchain.calculate_next_difficulty();
+        let supply = serde_json::to_value(blockchain.get_supply()).unwrap();
+        let accounts: Vec<_> = {
+            let state = blockchain.get_account_state_mut();
+            addresses
+                .iter()
+                .map(|a| (state.get_balance(a), state.get_total_balance(a), state.get_nonce(a)))
+                .collect()
+        };
+        assert_eq!(accounts[0].2, 4);
+        drop(blockchain);
+
+        let reopened = open();
+        assert_eq!(reopened.get_height(), 6);
+        assert_eq!(reopened.get_latest_block().hash, tip.hash);
+        assert_eq!(reopened.get_latest_block().difficulty, tip.difficulty);
+        assert_eq!(reopened.calculate_next_difficulty(), next_difficulty);
+        assert_eq!(serde_json::to_value(reopened.get_supply()).unwrap(), supply);
+        let state = reopened.get_account_state_mut();
+        for (address, expected) in addresses.iter().zip(&accounts) {
+            let actual = (state.get_balance(address), state.get_total_balance(address), state.get_nonce(address));
+            assert_eq!(&actual, expected, "{}", address);
+        }
+        drop(state);
+        assert!(reopened.is_valid());
+
+        // The transaction index is rebuilt from the stored blocks
+        for (i, hash) in mined.iter().enumerate() {
+            assert_eq!(reopened.get_transaction(hash).unwrap().1, i as u64 + 1);
+        }
+        assert!(reopened.get_transaction(&"ab".repeat(32)).is_none());
+
+        // The reloaded chain keeps extending from the same tip
+        reopened.add_transaction(signed_transfer(&keypair, RECIPIENT, 10_000, 1_000, 5)).unwrap();
+        reopened.mine_pending_transactions(MINER.to_string()).unwrap();
+        assert_eq!(reopened.get_latest_block().previous_hash, tip.hash);
+    }
+
+    #[test]
+    fn template_timestamp_is_clamped_when_clock_is_behind_tip() {
+        let (_dir, blockchain) = test_blockchain();
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        let tip = blockchain.get_latest_block();
+
+        let mut block = blockchain.create_block_template_at(MINER.to_string(), tip.timestamp - 30).unwrap();
+        assert_eq!(block.timestamp, tip.timestamp + 1);
+        assert!(block.transactions.iter().all(|tx| tx.timestamp == block.timestamp));
+        block.mine();
+        blockchain.add_network_block(block).unwrap();
+
+        // A clock hours behind the tip is not papered over
+        let tip = blockchain.get_latest_block();
+        assert!(matches!(
+            blockchain.create_block_template_at(MINER.to_string(), tip.timestamp - 3 * 3600),
+            Err(BlockchainError::ClockBehindTip(_))
+        ));
+    }
+
+    #[test]
+    fn dropped_transaction_releases_its_nonce() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 10_000_000);
+
+        let first = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1);
+        blockchain.add_transaction(first.clone()).unwrap();
+        let second = signed_transfer(&keypair, RECIPIENT, 2_000, 1_000, 2);
+        blockchain.add_transaction(second).unwrap();
+
+        // Dropping nonce 1 also drops the now-unminable nonce 2
+        assert_eq!(blockchain.drop_pending_transactions(|tx| tx.hash() == first.hash()), 2);
+        assert!(blockchain.get_pending_transactions().is_empty());
+
+        let fresh = signed_transfer(&keypair, RECIPIENT, 3_000, 1_000, 1);
+        blockchain.add_transaction(fresh).unwrap();
+    }
+
+    #[test]
+    fn expired_transactions_are_pruned_with_their_nonce() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 10_000_000);
+
+        let tx = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1);
+        blockchain.add_transaction(tx).unwrap();
+        blockchain.get_pending_transactions_mut()[0].timestamp -= TRANSACTION_EXPIRY_SECONDS + 60;
+
+        assert_eq!(blockchain.prune_expired_transactions(), 1);
+        blockchain.add_transaction(signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, 1)).unwrap();
+    }
+
+    #[test]
+    fn call_data_over_configured_cap_is_rejected() {
+        let (_dir, blockchain) = test_blockchain();
+        let blockchain = blockchain.with_max_call_data_bytes(1024);
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 10_000_000);
+
+        let call = |args_len: usize, nonce: u64| {
+            let mut tx = Transaction::builder()
+                .sender(keypair.get_address())
+                .fee(100_000)
+                .nonce(nonce)
+                .call_contract(RECIPIENT, "transfer", vec![0u8; args_len])
+                .build();
+            tx.public_key = keypair.public_key.clone();
+            tx.signature = keypair.sign(&tx.get_signing_data());
+            tx
+        };
+
+        // "transfer" + NUL + args: 1024 bytes fits exactly, one more does not
+        assert!(blockchain.validate_transaction(&call(1024 - 9, 1)).is_ok());
+        let oversized = call(1024 - 8, 1);
+        assert!(matches!(
+            blockchain.add_transaction(oversized.clone()),
+            Err(BlockchainError::FieldTooLarge(_))
+        ));
+
+        // The configured cap is relay policy: blocks are held to the built-in one
+        blockchain.add_network_block(mine_template_with(&blockchain, oversized)).unwrap();
+        let over_consensus = mine_template_with(&blockchain, call(MAX_CALL_DATA_BYTES - 8, 2));
+        assert!(matches!(
+            blockchain.add_network_block(over_consensus.clone()),
+            Err(BlockchainError::FieldTooLarge(_))
+        ));
+
+        // Below the activation height blocks carry any call data size
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            call_data_cap: over_consensus.index + 1,
+            field_bounds: over_consensus.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        blockchain.add_network_block(over_consensus).unwrap();
+    }
+
+    #[test]
+    fn block_version_follows_the_header_hash_activation() {
+        use crate::core::block::{HEADER_HASH_BLOCK_VERSION, LEGACY_BLOCK_VERSION};
+
+        // From the activation height templates hash their header fields only
+        let (_dir, blockchain) = test_blockchain();
+        let block = mine_template(&blockchain);
+        assert_eq!(block.version, HEADER_HASH_BLOCK_VERSION);
+        assert_eq!(BlockHeader::from(&block).calculate_hash().unwrap(), block.hash);
+
+        let mut legacy = block.clone();
+        legacy.version = LEGACY_BLOCK_VERSION;
+        legacy.mine();
+        assert!(BlockHeader::from(&legacy).calculate_hash().is_none());
+        assert!(matches!(
+            blockchain.add_network_block(legacy.clone()),
+            Err(BlockchainError::InvalidBlockVersion { expected: HEADER_HASH_BLOCK_VERSION, got: LEGACY_BLOCK_VERSION })
+        ));
+
+        // Below it blocks keep the legacy format
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            header_hash: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        assert!(matches!(
+            blockchain.add_network_block(block),
+            Err(BlockchainError::InvalidBlockVersion { expected: LEGACY_BLOCK_VERSION, got: HEADER_HASH_BLOCK_VERSION })
+        ));
+        blockchain.add_network_block(legacy).unwrap();
+    }
+
+    #[test]
+    fn field_bounds_apply_to_blocks_from_activation() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        fund(&blockchain, &keypair.get_address(), 10_000_000);
+
+        // Well inside the call data cap, but the function name is over MAX_FUNCTION_NAME_LEN
+        let mut call = Transaction::builder()
+            .sender(keypair.get_address())
+            .fee(100_000)
+            .nonce(1)
+            .call_contract(RECIPIENT, "f".repeat(crate::core::transaction::MAX_FUNCTION_NAME_LEN + 1), vec![])
+            .build();
+        call.public_key = keypair.public_key.clone();
+        call.signature = keypair.sign(&call.get_signing_data());
+
+        // Mempool admission always enforces the bounds
+        assert!(matches!(
+            blockchain.add_transaction(call.clone()),
+            Err(BlockchainError::FieldTooLarge(_))
+        ));
+
+        let block = mine_template_with(&blockchain, call);
+        assert!(matches!(
+            blockchain.add_network_block(block.clone()),
+            Err(BlockchainError::FieldTooLarge(_))
+        ));
+
+        // Below the activation height blocks are held only to the rules older nodes apply
+        let blockchain = blockchain.with_activations(ActivationHeights {
+            field_bounds: block.index + 1,
+            ..ActivationHeights::all_at(0)
+        });
+        blockchain.add_network_block(block).unwrap();
+    }
+
+    #[test]
+    fn snapshot_matching_checkpoint_is_accepted_and_tampering_rejected() {
+        use crate::core::snapshot::SnapshotCheckpoint;
+
+        let (_dir, blockchain) = test_blockchain();
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        blockchain.add_network_block(mine_template(&blockchain)).unwrap();
+
+        // Replay from genesis reproduces the live state at the tip
+        let snapshot = blockchain.build_state_snapshot(2).unwrap();
+        let state_hash = snapshot.account_state.state_hash();
+        assert_eq!(state_hash, blockchain.account_state.read().state_hash());
+
+        let tip_hash = blockchain.get_latest_block().hash;
+        let checkpoint = SnapshotCheckpoint {
+            height: 2,
+            block_hash: Box::leak(tip_hash.into_boxed_str()),
+            state_hash: Box::leak(state_hash.into_boxed_str()),
+        };
+        snapshot.verify(&checkpoint, next_difficulty_after).unwrap();
+
+        let mut inflated = snapshot.clone();
+        let credit = Transaction::new("TREASURY".to_string(), MINER.to_string(), 1, 0);
+        inflated.account_state.credit_account(&credit, 0, COINBASE_MATURITY);
+        assert_eq!(inflated.verify(&checkpoint, next_difficulty_after), Err(SnapshotError::StateHashMismatch));
+
+        let mut forged = snapshot.clone();
+        forged.headers[1].hash = "0".repeat(64);
+        assert_eq!(forged.verify(&checkpoint, next_difficulty_after), Err(SnapshotError::BrokenLink(2)));
+
+        // A header claiming less work than the retarget schedule requires
+        let mut easier = snapshot.clone();
+        easier.headers[2].difficulty -= 1;
+        assert_eq!(easier.verify(&checkpoint, next_difficulty_after), Err(SnapshotError::UnexpectedDifficulty(2)));
+
+        let mut truncated = snapshot;
+        truncated.headers.pop();
+        assert!(matches!(truncated.verify(&checkpoint, next_difficulty_after), Err(SnapshotError::HeaderCount { .. })));
+
+        // Not a hardcoded checkpoint: neither served nor accepted
+        assert!(blockchain.state_snapshot(2).is_none());
+        assert!(matches!(
+            blockchain.accept_snapshot(&forged),
+            Err(BlockchainError::Snapshot(SnapshotError::UnknownCheckpoint(2)))
+        ));
+    }
+
+    #[test]
+    fn snapshot_sync_matches_headers_and_adopts_state_at_its_height() {
+        let (_dir, source) = test_blockchain();
+        for _ in 0..3 {
+            source.mine_pending_transactions(MINER.to_string()).unwrap();
+        }
+        let snapshot = source.build_state_snapshot(2).unwrap();
+        let blocks: Vec<Block> = source.get_chain()[1..].to_vec();
+
+        let (_dir2, target) = test_blockchain();
+        let genesis_state = target.account_state.read().state_hash();
+        *target.fast_sync.write() = Some(snapshot.clone());
+
+        // A block off the snapshot's header chain is refused
+        let mut stray = target.create_block_template(RECIPIENT.to_string()).unwrap();
+        stray.mine();
+        assert!(matches!(target.add_network_block(stray), Err(BlockchainError::InvalidBlock)));
+
+        // Below the snapshot height transactions are not applied...
+        target.add_network_block(blocks[0].clone()).unwrap();
+        assert_eq!(target.account_state.read().state_hash(), genesis_state);
+        // ...and the snapshot's state is adopted with the checkpoint block
+        target.add_network_block(blocks[1].clone()).unwrap();
+        assert_eq!(target.account_state.read().state_hash(), snapshot.account_state.state_hash());
+        assert!(target.fast_sync.read().is_none());
+
+        // Past it, blocks are validated and applied in full
+        target.add_network_block(blocks[2].clone()).unwrap();
+        assert_eq!(target.account_state.read().state_hash(), source.account_state.read().state_hash());
+    }
+
+    #[test]
+    fn next_nonce_counts_pending_transactions() {
+        let (_dir, blockchain) = test_blockchain();
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+        fund(&blockchain, &sender, 1_000_000);
+        assert_eq!(blockchain.get_next_nonce(&sender), 1);
+
+        // Two transactions built back-to-back, neither confirmed yet
+        let first = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, blockchain.get_next_nonce(&sender));
+        blockchain.add_transaction(first).unwrap();
+        let second = signed_transfer(&keypair, RECIPIENT, 1_000, 1_000, blockchain.get_next_nonce(&sender));
+        assert_eq!(second.nonce, 2);
+        blockchain.add_transaction(second).unwrap();
+        assert_eq!(blockchain.get_pending_transactions().len(), 2);
+
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        assert_eq!(blockchain.get_account_state_mut().get_nonce(&sender), 2);
+        assert_eq!(blockchain.get_next_nonce(&sender), 3);
+    }
+
+    #[tokio::test]
+    async fn mining_abandons_template_when_tip_moves() {
+        let (_dir, blockchain) = test_blockchain();
+        let mut stale = blockchain.create_block_template(MINER.to_string()).unwrap();
+        stale.difficulty = 64; // never completes on its own
+        let tip = blockchain.subscribe_tip();
+        let attempt = tokio::spawn(async move { mine_until_stale(stale, tip, &CancellationToken::new()).await });
+
+        // A competing block lands on the same parent
+        let blockchain = tokio::task::spawn_blocking(move || {
+            blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+            blockchain
+        })
+        .await
+        .unwrap();
+        let mined = tokio::time::timeout(std::time::Duration::from_secs(5), attempt)
+            .await
+            .expect("mining must stop once the tip moves");
+        assert!(mined.unwrap().unwrap().is_none());
+        assert_eq!(blockchain.get_height(), 2);
+    }
+
+    #[test]
+    fn mainnet_genesis_matches_the_hardcoded_hash() {
+        assert_eq!(Block::genesis(ChainNetwork::Mainnet).hash, GENESIS_HASH);
+        let dir = tempfile::tempdir().unwrap();
+        let mainnet = Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Mainnet);
+        assert_eq!(mainnet.unwrap().get_latest_block().hash, GENESIS_HASH);
+    }
+
+    #[test]
+    fn replica_keeps_the_newest_view_whatever_its_height() {
+        let (_dir, blockchain) = test_blockchain();
+        let replica = blockchain.replica();
+        let before = blockchain.chain_view();
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        let tip = blockchain.get_latest_block();
+        assert_eq!(replica.load().snapshot.tip_hash, tip.hash);
+
+        // A view taken before the block but published after it is ignored
+        replica.publish(before);
+        assert_eq!(replica.load().snapshot.tip_hash, tip.hash);
+
+        // A newer view is published even at a lower height (as after a reorg)
+        let mut reorged = blockchain.chain_view();
+        reorged.generation += 1;
+        reorged.snapshot.height -= 1;
+        replica.publish(reorged);
+        assert_eq!(replica.load().snapshot.height, tip.index);
+    }
+
+    #[test]
+    fn heavier_fork_replaces_chain_and_requeues_dropped_transactions() {
+        let dir = tempfile::tempdir().unwrap();
+        let open = || {
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet)
+                .unwrap()
+                .with_activations(ActivationHeights::all_at(0))
+        };
+        let keypair = FalconKeypair::generate();
+        let sender = keypair.get_address();
+
+        // Both nodes share block 1
+        let blockchain = open();
+        let (_dir, rival) = test_blockchain();
+        fund(&blockchain, &sender, 1_000_000);
+        blockchain.mine_pending_transactions(MINER.to_string()).unwrap();
+        rival.add_network_block(blockchain.get_latest_block()).unwrap();
+
+        // Ours: one block carrying a transfer. Theirs: two empty blocks
+        let transfer = signed_transfer(&keypair, RECIPIENT, 10_000, 1_000, 1);
+        blockchain.add_network_block(mine_template_with(&blockchain, transfer.clone())).unwrap();
+        for _ in 0..2 {
+            rival.mine_pending_transactions(MINER.to_string()).unwrap();
+        }
+        let branch: Vec<Block> = rival.get_chain()[2..].to_vec();
+        let original = blockchain.get_latest_block();
+
+        // Equal work (one block each) keeps our chain, and the competing block is pooled
+        assert!(!blockchain.handle_fork(branch[..1].to_vec()).unwrap());
+        blockchain.add_network_block(branch[0].clone()).unwrap();
+        assert_eq!(blockchain.get_latest_block().hash, original.hash);
+        assert!(blockchain.has_orphan(&branch[0].hash));
+
+        // The longer branch outweighs ours once its second block arrives
+        blockchain.add_network_block(branch[1].clone()).unwrap();
+        assert_eq!(blockchain.get_height(), 4);
+        assert_eq!(blockchain.get_latest_block().hash, branch[1].hash);
+        assert_eq!(blockchain.orphan_count(), 0);
+        assert_eq!(blockchain.get_balance(RECIPIENT), 0);
+        assert_eq!(blockchain.get_account_state_mut().get_nonce(&sender), 0);
+        assert!(blockchain.get_transaction(&transfer.hash()).is_none());
+        assert_eq!(blockchain.get_pending_transactions().iter().map(|tx| tx.hash()).collect::<Vec<_>>(), vec![transfer.hash()]);
+        assert_eq!(blockchain.get_next_nonce(&sender), 2);
+
+        // The shorter original branch can't switch it back
+        assert!(!blockchain.handle_fork(vec![original]).unwrap());
+
+        // The switch was persisted: same tip and state after a restart
+        let balance = blockchain.get_balance(&sender);
+        drop(blockchain);
+        let reopened = open();
+        assert_eq!(reopened.get_height(), 4);
+        assert_eq!(reopened.get_latest_block().hash, branch[1].hash);
+        assert_eq!(reopened.get_balance(&sender), balance);
+    }
+
+    #[test]
+    fn shorter_branch_with_more_work_wins() {
+        let (_dir, blockchain) = test_blockchain();
+        let (_rival_dir, rival) = test_blockchain();
+        let genesis = blockchain.get_latest_block().timestamp;
+        let mine_at = |chain: &Blockchain, timestamp: i64| {
+            let mut block = chain.create_block_template_at(MINER.to_string(), timestamp).unwrap();
+            block.mine();
+            chain.add_network_block(block).unwrap();
+        };
+
+        // Ours: 11 slow blocks, so the retarget at height 10 stays at the minimum
+        for height in 1..=11 {
+            mine_at(&blockchain, genesis + 100 * height);
+        }
+        // Theirs: 10 blocks from genesis, the first 9 fast enough to raise block 10's difficulty
+        for height in 1..=10 {
+            mine_at(&rival, genesis + height);
+        }
+        let branch = rival.get_chain()[1..].to_vec();
+        assert_eq!(branch.last().unwrap().difficulty, 5);
+        assert_eq!(blockchain.get_latest_block().difficulty, 4);
+
+        // 9·16^4 + 16^5 outweighs 11·16^4 (counting 2^difficulty, the two would tie)
+        assert!(Blockchain::cumulative_work(&branch) > Blockchain::cumulative_work(&blockchain.get_chain()[1..]));
+        assert!(blockchain.handle_fork(branch.clone()).unwrap());
+        assert_eq!(blockchain.get_height(), 11);
+        assert_eq!(blockchain.get_latest_block().hash, branch.last().unwrap().hash);
+    }
+}
@@ -0,0 +1,107 @@
+use crate::core::transaction::Transaction;
+use serde::{Deserialize, Serialize};
+
+/// Recent blocks sampled for fee estimates by default
+pub const DEFAULT_FEE_ESTIMATE_WINDOW: usize = 20;
+
+/// Fee recommendation derived from recently confirmed transactions
+///
+/// Falcon signatures make transaction sizes vary a lot (a contract call can be several
+/// times a plain transfer), so wallets should size fees with `fee_for_weight` rather
+/// than reuse `flat_fee`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FeeEstimate {
+    /// Median fee rate in microunits per byte of weight, never below the fee-rate floor
+    pub fee_per_byte: f64,
+    /// Median absolute fee in microunits, never below the absolute minimum
+    pub flat_fee: u64,
+    /// Absolute minimum fee accepted for any transaction
+    pub min_fee: u64,
+    /// Confirmed transactions the estimate is based on (0 = floors only)
+    pub samples: usize,
+}
+
+impl FeeEstimate {
+    /// Estimate from confirmed user transactions (coinbase/treasury are skipped)
+    pub fn from_transactions<'a>(
+        txs: impl IntoIterator<Item = &'a Transaction>,
+        min_fee: u64,
+        min_fee_per_kb: u64,
+    ) -> Self {
+        let (mut rates, mut fees): (Vec<f64>, Vec<u64>) = txs
+            .into_iter()
+            .filter(|tx| !tx.is_coinbase() && tx.sender != "TREASURY")
+            .map(|tx| (tx.fee as f64 / tx.weight().max(1) as f64, tx.fee))
+            .unzip();
+        rates.sort_by(f64::total_cmp);
+        fees.sort_unstable();
+
+        let floor_per_byte = min_fee_per_kb as f64 / 1000.0;
+        Self {
+            fee_per_byte: rates.get(rates.len() / 2).copied().unwrap_or(0.0).max(floor_per_byte),
+            flat_fee: fees.get(fees.len() / 2).copied().unwrap_or(0).max(min_fee),
+            min_fee,
+            samples: fees.len(),
+        }
+    }
+
+    /// Recommended fee for a transaction of `weight` bytes (see Transaction::weight)
+    pub fn fee_for_weight(&self, weight: usize) -> u64 {
+        ((weight as f64 * self.fee_per_byte).ceil() as u64).max(self.min_fee)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx_with(fee: u64, call_args: usize) -> Transaction {
+        let mut tx = Transaction::new_call_contract(
+            "0x1111111111111111111111111111111111111111".to_string(),
+            "0x2222222222222222222222222222222222222222".to_string(),
+            "f".to_string(),
+            vec![0u8; call_args],
+            1_700_000_000,
+            1,
+        );
+        tx.fee = fee;
+        tx.signature = vec![0u8; 666];
+        tx.public_key = vec![0u8; 897];
+        tx
+    }
+
+    #[test]
+    fn rate_tracks_fee_per_byte_across_sizes() {
+        // Three txs paying ~2 microunits/byte regardless of size, one cheap outlier
+        let sizes = [0usize, 2_000, 8_000];
+        let mut txs: Vec<_> = sizes
+            .iter()
+            .map(|&args| {
+                let weight = tx_with(0, args).weight() as u64;
+                tx_with(weight * 2, args)
+            })
+            .collect();
+        txs.push(tx_with(100, 4_000));
+
+        let estimate = FeeEstimate::from_transactions(&txs, 100, 500);
+        assert_eq!(estimate.samples, 4);
+        assert!((estimate.fee_per_byte - 2.0).abs() < 1e-9);
+
+        // A large tx is quoted proportionally more than a small one
+        let small = tx_with(0, 0).weight();
+        let large = tx_with(0, 8_000).weight();
+        assert_eq!(estimate.fee_for_weight(small), small as u64 * 2);
+        assert_eq!(estimate.fee_for_weight(large), large as u64 * 2);
+        assert!(estimate.flat_fee > estimate.min_fee);
+    }
+
+    #[test]
+    fn empty_window_falls_back_to_floors() {
+        let estimate = FeeEstimate::from_transactions(&[], 100, 500);
+        assert_eq!(estimate.samples, 0);
+        assert_eq!(estimate.fee_per_byte, 0.5);
+        assert_eq!(estimate.flat_fee, 100);
+        assert_eq!(estimate.fee_for_weight(10), 100);
+        assert_eq!(estimate.fee_for_weight(2_000), 1_000);
+    }
+}
@@ -1,4 +1,5 @@
 use crate::core::transaction::Transaction;
+use crate::core::types::TxHash;
 use serde::{Deserialize, Serialize};
 use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
@@ -8,11 +9,11 @@ use tokio::sync::RwLock;
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Mempool {
     // Transactions indexed by hash
-    transactions: HashMap<String, Transaction>,
+    transactions: HashMap<TxHash, Transaction>,
     // Transactions sorted by fee (descending) using integer microunits
-    by_fee: BTreeMap<u64, Vec<String>>, // fee_microunits -> [tx_hashes] (reversed iteration for desc order)
+    by_fee: BTreeMap<u64, Vec<TxHash>>, // fee_microunits -> [tx_hashes] (reversed iteration for desc order)
     // Index: tx_hash -> fee_microunits for O(1) removal
-    hash_to_fee: HashMap<String, u64>,
+    hash_to_fee: HashMap<TxHash, u64>,
     // Max size limit
     max_size: usize,
 }
@@ -28,6 +29,15 @@ impl Mempool {
         }
     }
 
+    /// Index `txs` by fee, holding all of them
+    pub fn from_transactions(txs: &[Transaction]) -> Self {
+        let mut mempool = Self::new(txs.len());
+        for tx in txs {
+            let _ = mempool.add(tx.clone()); // Only a duplicate is refused
+        }
+        mempool
+    }
+
     /// Add a transaction to the mempool
     pub fn add(&mut self, tx: Transaction) -> Result<(), String> {
         if self.transactions.len() >= self.max_size {
@@ -151,6 +161,124 @@ impl Mempool {
     pub fn contains(&self, tx_hash: &str) -> bool {
         self.transactions.contains_key(tx_hash)
     }
+
+    /// Pending counts and sizes by fee-rate bucket, walking the pool in fee order
+    pub fn fee_histogram(&self, boundaries: &[f64]) -> FeeHistogram {
+        let by_fee = self.by_fee.values().rev().flatten().filter_map(|hash| self.transactions.get(hash));
+        FeeHistogram::from_transactions(by_fee, boundaries)
+    }
+}
+
+/// Default get_fee_histogram bucket boundaries, in microunits per byte of weight
+/// (the lowest is the MIN_FEE_PER_KB floor of 500 per 1000 bytes)
+pub const DEFAULT_FEE_HISTOGRAM_BOUNDARIES: &[f64] = &[0.5, 1.0, 2.0, 5.0, 10.0, 20.0, 50.0, 100.0];
+const MAX_FEE_HISTOGRAM_BOUNDARIES: usize = 64;
+
+/// Pending transactions whose fee rate falls in [min_fee_rate, max_fee_rate)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeeHistogramBucket {
+    pub min_fee_rate: f64,
+    pub max_fee_rate: Option<f64>, // None for the open-ended top bucket
+    pub tx_count: usize,
+    pub total_bytes: usize,
+    /// Bytes pending at this bucket's fee rates or higher (what a new tx here queues behind)
+    pub cumulative_bytes: usize,
+}
+
+/// Mempool congestion by fee rate, for fee-selection UIs
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct FeeHistogram {
+    pub tx_count: usize,
+    pub total_bytes: usize,
+    pub buckets: Vec<FeeHistogramBucket>, // highest fee rates first
+}
+
+impl FeeHistogram {
+    /// Boundaries must be finite, non-negative and strictly increasing
+    pub fn check_boundaries(boundaries: &[f64]) -> Result<(), String> {
+        if boundaries.len() > MAX_FEE_HISTOGRAM_BOUNDARIES {
+            return Err(format!("At most {} bucket boundaries", MAX_FEE_HISTOGRAM_BOUNDARIES));
+        }
+        if boundaries.iter().any(|b| !b.is_finite() || *b < 0.0) {
+            return Err("Bucket boundaries must be finite and non-negative".to_string());
+        }
+        if boundaries.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err("Bucket boundaries must be strictly increasing".to_string());
+        }
+        Ok(())
+    }
+
+    /// Bucket `txs` by fee rate: below the first boundary, between each pair, and above the last
+    pub fn from_transactions<'a>(txs: impl IntoIterator<Item = &'a Transaction>, boundaries: &[f64]) -> Self {
+        let mut counts = vec![(0usize, 0usize); boundaries.len() + 1];
+        for tx in txs {
+            let weight = tx.weight();
+            let rate = tx.fee as f64 / weight.max(1) as f64;
+            let slot = &mut counts[boundaries.partition_point(|&b| b <= rate)];
+            slot.0 += 1;
+            slot.1 += weight;
+        }
+
+        let mut cumulative_bytes = 0;
+        let buckets: Vec<FeeHistogramBucket> = counts
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(i, &(tx_count, total_bytes))| {
+                cumulative_bytes += total_bytes;
+                FeeHistogramBucket {
+                    min_fee_rate: if i == 0 { 0.0 } else { boundaries[i - 1] },
+                    max_fee_rate: boundaries.get(i).copied(),
+                    tx_count,
+                    total_bytes,
+                    cumulative_bytes,
+                }
+            })
+            .collect();
+
+        Self {
+            tx_count: counts.iter().map(|c| c.0).sum(),
+            total_bytes: cumulative_bytes,
+            buckets,
+        }
+    }
+}
+
+/// Aggregate mempool statistics (cheap alternative to downloading the whole pool)
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct MempoolInfo {
+    pub tx_count: usize,
+    pub total_bytes: usize,
+    pub total_fees: u64, // microunits
+    /// Fee rates in microunits per byte of weight (0 when the pool is empty)
+    pub min_fee_rate: f64,
+    pub max_fee_rate: f64,
+    pub median_fee_rate: f64,
+    /// Seconds since the oldest pending transaction was created (None when empty)
+    pub oldest_tx_age_secs: Option<i64>,
+}
+
+impl MempoolInfo {
+    /// Summarize `txs` as of unix time `now`
+    pub fn from_transactions(txs: &[Transaction], now: i64) -> Self {
+        let weights: Vec<usize> = txs.iter().map(|tx| tx.weight()).collect();
+        let mut rates: Vec<f64> = txs
+            .iter()
+            .zip(&weights)
+            .map(|(tx, &weight)| tx.fee as f64 / weight.max(1) as f64)
+            .collect();
+        rates.sort_by(f64::total_cmp);
+
+        Self {
+            tx_count: txs.len(),
+            total_bytes: weights.iter().sum(),
+            total_fees: txs.iter().map(|tx| tx.fee).fold(0u64, u64::saturating_add),
+            min_fee_rate: rates.first().copied().unwrap_or(0.0),
+            max_fee_rate: rates.last().copied().unwrap_or(0.0),
+            median_fee_rate: rates.get(rates.len() / 2).copied().unwrap_or(0.0),
+            oldest_tx_age_secs: txs.iter().map(|tx| tx.timestamp).min().map(|oldest| (now - oldest).max(0)),
+        }
+    }
 }
 
 /// Node metrics for monitoring
@@ -238,3 +366,73 @@ impl MetricsCollector {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(fee: u64, timestamp: i64, signature_len: usize) -> Transaction {
+        let mut tx = Transaction::new(
+            "0x1111111111111111111111111111111111111111".to_string(),
+            "0x2222222222222222222222222222222222222222".to_string(),
+            1_000,
+            timestamp,
+        );
+        tx.fee = fee;
+        tx.signature = vec![0u8; signature_len];
+        tx
+    }
+
+    #[test]
+    fn info_summarizes_known_pool() {
+        let txs = vec![tx(1_000, 1_000, 900), tx(3_000, 1_100, 900), tx(9_000, 1_050, 900)];
+        let weight = txs[0].weight();
+        let info = MempoolInfo::from_transactions(&txs, 1_600);
+
+        assert_eq!(info.tx_count, 3);
+        assert_eq!(info.total_bytes, weight * 3);
+        assert_eq!(info.total_fees, 13_000);
+        assert_eq!(info.min_fee_rate, 1_000.0 / weight as f64);
+        assert_eq!(info.median_fee_rate, 3_000.0 / weight as f64);
+        assert_eq!(info.max_fee_rate, 9_000.0 / weight as f64);
+        assert_eq!(info.oldest_tx_age_secs, Some(600));
+
+        let empty = MempoolInfo::from_transactions(&[], 1_600);
+        assert_eq!((empty.tx_count, empty.total_fees, empty.oldest_tx_age_secs), (0, 0, None));
+    }
+
+    #[test]
+    fn fee_histogram_buckets_sum_to_pool() {
+        let mut mempool = Mempool::new(100);
+        let fees = [0, 400, 900, 2_500, 2_600, 15_000, 40_000, 500_000];
+        for (i, &fee) in fees.iter().enumerate() {
+            mempool.add(tx(fee, 1_000 + i as i64, 900)).unwrap();
+        }
+        let weight = tx(0, 0, 900).weight();
+
+        let histogram = mempool.fee_histogram(DEFAULT_FEE_HISTOGRAM_BOUNDARIES);
+        assert_eq!(histogram.buckets.len(), DEFAULT_FEE_HISTOGRAM_BOUNDARIES.len() + 1);
+        assert_eq!(histogram.tx_count, fees.len());
+        assert_eq!(histogram.total_bytes, weight * fees.len());
+        assert_eq!(histogram.buckets.iter().map(|b| b.tx_count).sum::<usize>(), mempool.len());
+        assert_eq!(histogram.buckets.iter().map(|b| b.total_bytes).sum::<usize>(), histogram.total_bytes);
+        assert_eq!(histogram.buckets.last().unwrap().cumulative_bytes, histogram.total_bytes);
+
+        // Highest rates first; each transaction sits in the bucket covering its rate
+        let top = &histogram.buckets[0];
+        assert_eq!((top.min_fee_rate, top.max_fee_rate), (100.0, None));
+        assert_eq!(top.tx_count, 1);
+        let bottom = histogram.buckets.last().unwrap();
+        assert_eq!((bottom.min_fee_rate, bottom.max_fee_rate), (0.0, Some(0.5)));
+        assert_eq!(bottom.tx_count, fees.iter().filter(|&&fee| (fee as f64 / weight as f64) < 0.5).count());
+
+        // Custom boundaries, and ones a caller can get wrong
+        let halves = mempool.fee_histogram(&[10.0]);
+        let at_least_10 = fees.iter().filter(|&&fee| fee as f64 / weight as f64 >= 10.0).count();
+        assert_eq!(
+            halves.buckets.iter().map(|b| b.tx_count).collect::<Vec<_>>(),
+            vec![at_least_10, fees.len() - at_least_10]
+        );
+        assert!(FeeHistogram::check_boundaries(&[2.0, 1.0]).is_err());
+        assert!(FeeHistogram::check_boundaries(&[f64::NAN]).is_err());
+    }
+}
@@ -1,5 +1,9 @@
+pub mod activation;
 pub mod blockchain;
+pub mod fee_estimator;
 pub mod mempool;
+pub mod orphan_pool;
+pub mod replica;
 
 pub use blockchain::Blockchain;
 pub use mempool::{Mempool, MetricsCollector};
@@ -0,0 +1,133 @@
+use crate::core::block::Block;
+use std::collections::{HashMap, VecDeque};
+
+/// Blocks whose parent we don't have yet, waiting to be connected
+///
+/// Bounded: once full, the least recently seen orphan is evicted. Indexed by
+/// previous_hash so an arriving parent can pull in its children directly.
+pub struct OrphanPool {
+    max_size: usize,
+    blocks: HashMap<String, Block>,          // block hash -> block
+    by_parent: HashMap<String, Vec<String>>, // previous_hash -> orphan hashes
+    recency: VecDeque<String>,               // least recently seen at the front
+}
+
+impl OrphanPool {
+    pub fn new(max_size: usize) -> Self {
+        Self {
+            max_size,
+            blocks: HashMap::new(),
+            by_parent: HashMap::new(),
+            recency: VecDeque::new(),
+        }
+    }
+
+    /// Add an orphan (or mark an existing one as recently seen); returns false if already present
+    pub fn insert(&mut self, block: Block) -> bool {
+        if self.blocks.contains_key(&block.hash) {
+            self.touch(&block.hash);
+            return false;
+        }
+        if self.max_size == 0 {
+            return false;
+        }
+        while self.blocks.len() >= self.max_size {
+            let Some(oldest) = self.recency.front().cloned() else { break };
+            tracing::warn!("Orphan pool full ({}), evicting {}", self.max_size, &oldest[..8.min(oldest.len())]);
+            self.remove(&oldest);
+        }
+
+        self.by_parent.entry(block.previous_hash.clone()).or_default().push(block.hash.clone());
+        self.recency.push_back(block.hash.clone());
+        self.blocks.insert(block.hash.clone(), block);
+        true
+    }
+
+    /// Remove and return every orphan that builds on `parent_hash`
+    pub fn take_children(&mut self, parent_hash: &str) -> Vec<Block> {
+        let hashes = self.by_parent.remove(parent_hash).unwrap_or_default();
+        hashes
+            .iter()
+            .filter_map(|hash| {
+                self.recency.retain(|h| h != hash);
+                self.blocks.remove(hash)
+            })
+            .collect()
+    }
+
+    pub fn get(&self, hash: &str) -> Option<&Block> {
+        self.blocks.get(hash)
+    }
+
+    pub fn contains(&self, hash: &str) -> bool {
+        self.blocks.contains_key(hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    fn touch(&mut self, hash: &str) {
+        if let Some(pos) = self.recency.iter().position(|h| h == hash) {
+            if let Some(h) = self.recency.remove(pos) {
+                self.recency.push_back(h);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, hash: &str) {
+        self.recency.retain(|h| h != hash);
+        if let Some(block) = self.blocks.remove(hash) {
+            if let Some(children) = self.by_parent.get_mut(&block.previous_hash) {
+                children.retain(|h| h != hash);
+                if children.is_empty() {
+                    self.by_parent.remove(&block.previous_hash);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(index: u64, hash: &str, previous_hash: &str) -> Block {
+        let mut block = Block::new(index, vec![], previous_hash.to_string(), 1);
+        block.hash = hash.to_string();
+        block
+    }
+
+    #[test]
+    fn caps_at_limit_evicting_least_recently_seen() {
+        let mut pool = OrphanPool::new(2);
+        assert!(pool.insert(block(5, "a", "p1")));
+        assert!(pool.insert(block(6, "b", "p2")));
+        assert!(!pool.insert(block(5, "a", "p1"))); // seen again: "b" is now the oldest
+        assert!(pool.insert(block(7, "c", "p3")));
+
+        assert_eq!(pool.len(), 2);
+        assert!(pool.contains("a") && pool.contains("c"));
+        assert!(!pool.contains("b"));
+        assert!(pool.take_children("p2").is_empty());
+    }
+
+    #[test]
+    fn take_children_indexes_by_parent() {
+        let mut pool = OrphanPool::new(10);
+        pool.insert(block(5, "a", "p"));
+        pool.insert(block(5, "b", "p"));
+        pool.insert(block(6, "c", "a"));
+
+        let mut children: Vec<_> = pool.take_children("p").into_iter().map(|b| b.hash).collect();
+        children.sort();
+        assert_eq!(children, vec!["a", "b"]);
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.take_children("a")[0].hash, "c");
+        assert!(pool.is_empty());
+    }
+}
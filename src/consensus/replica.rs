@@ -0,0 +1,64 @@
+use crate::consensus::blockchain::{ChainSnapshot, SupplyInfo};
+use crate::core::transaction::AccountState;
+use arc_swap::ArcSwap;
+use std::sync::Arc;
+
+/// Immutable chain view as of one block (see Blockchain::replica)
+///
+/// Every field was taken at the same tip, so a reader never sees stats from one
+/// block and balances from another.
+#[derive(Debug)]
+pub struct ChainView {
+    pub generation: u64, // Tip changes made before the view was taken; a later view has a higher one
+    pub snapshot: ChainSnapshot, // pending_transactions is the mempool size when published
+    pub supply: SupplyInfo,
+    pub account_state: AccountState,
+}
+
+impl ChainView {
+    pub fn balance(&self, address: &str) -> u64 {
+        self.account_state.get_balance(address)
+    }
+
+    pub fn locked_balance(&self, address: &str) -> u64 {
+        self.account_state.get_locked_balance(address)
+    }
+
+    pub fn unlock_height(&self, address: &str) -> u64 {
+        self.account_state.get_unlock_height(address)
+    }
+}
+
+/// Read-only replica of the chain for API reads
+///
+/// The writer publishes a fresh `ChainView` after each block; readers load the
+/// current one without taking any lock, so heavy read traffic never waits on
+/// mining or block import (and never delays it).
+#[derive(Clone)]
+pub struct ChainReplica {
+    view: Arc<ArcSwap<ChainView>>,
+}
+
+impl ChainReplica {
+    pub fn new(view: ChainView) -> Self {
+        Self { view: Arc::new(ArcSwap::from_pointee(view)) }
+    }
+
+    /// Current view; stays valid (and unchanged) while held, even across new blocks
+    pub fn load(&self) -> Arc<ChainView> {
+        self.view.load_full()
+    }
+
+    /// Replace the view unless a newer one is already published (publishers may race).
+    /// Newer means a higher generation, not a greater height: a reorg may lower the height
+    pub(crate) fn publish(&self, view: ChainView) {
+        let view = Arc::new(view);
+        self.view.rcu(|current| {
+            if view.generation >= current.generation {
+                Arc::clone(&view)
+            } else {
+                Arc::clone(current)
+            }
+        });
+    }
+}
@@ -1,8 +1,99 @@
 use serde::{Serialize, Deserialize};
 use crate::core::transaction::Transaction;
+use crate::core::BlockHash;
 use crate::crypto::double_sha3;
 use crate::core::merkle::MerkleTree;
 use chrono::Utc;
+use tokio_util::sync::CancellationToken;
+
+/// Hashes between cancellation checks while mining (keeps the check off the hot path)
+const MINE_CANCEL_CHECK_INTERVAL: u64 = 1024;
+
+/// Block hash format before the header_hash activation height: the hash covers the
+/// transaction hash list, so it can only be recomputed from the full block
+pub const LEGACY_BLOCK_VERSION: u32 = 1;
+/// Block hash format from the header_hash activation height: the hash covers the header
+/// fields only, with the merkle root committing to the transactions
+pub const HEADER_HASH_BLOCK_VERSION: u32 = 2;
+
+/// Version of blocks and headers stored or sent before the field existed
+fn legacy_block_version() -> u32 {
+    LEGACY_BLOCK_VERSION
+}
+
+/// Hash of a HEADER_HASH_BLOCK_VERSION block's header fields, double SHA3-256
+fn header_hash(index: u64, timestamp: i64, previous_hash: &str, nonce: u64, difficulty: u32, merkle_root: &str) -> BlockHash {
+    let data = format!(
+        "{}:{}:{}:{}:{}:{}:{}",
+        HEADER_HASH_BLOCK_VERSION, index, timestamp, previous_hash, nonce, difficulty, merkle_root
+    );
+    BlockHash::new_unchecked(double_sha3(data.as_bytes()))
+}
+
+/// Previous-hash marker carried only by the genesis block
+pub const GENESIS_PREVIOUS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Check a previous_hash for a block at `index`: genesis carries the all-zero marker,
+/// every other block a 64-char lowercase hex SHA3 hash that is not the marker
+pub fn is_valid_previous_hash(index: u64, previous_hash: &str) -> bool {
+    if index == 0 {
+        return previous_hash == GENESIS_PREVIOUS_HASH;
+    }
+    previous_hash.len() == 64
+        && previous_hash.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        && previous_hash != GENESIS_PREVIOUS_HASH
+}
+
+/// Proof-of-work target as a 256-bit big-endian number: a block is valid when its
+/// hash, read as a number, is <= the target. Serialized as 64 hex chars.
+///
+/// Difficulty counts leading zero hex digits of the hash, so difficulty d is the
+/// target with the top 4*d bits clear and every other bit set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target([u8; 32]);
+
+impl Target {
+    pub fn from_difficulty(difficulty: u32) -> Self {
+        let mut target = [0xffu8; 32];
+        let zero_bits = (difficulty as usize * 4).min(256);
+        target[..zero_bits / 8].fill(0);
+        if !zero_bits.is_multiple_of(8) {
+            target[zero_bits / 8] = 0x0f;
+        }
+        Self(target)
+    }
+
+    /// Whether a hex block hash is at or below this target
+    pub fn is_met_by(&self, hash: &str) -> bool {
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(hash, &mut bytes).is_ok() && bytes <= self.0
+    }
+
+    pub fn to_hex(self) -> String {
+        hex::encode(self.0)
+    }
+}
+
+impl std::fmt::Display for Target {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_hex())
+    }
+}
+
+impl Serialize for Target {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_hex())
+    }
+}
+
+impl<'de> Deserialize<'de> for Target {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let hex_str = String::deserialize(deserializer)?;
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice(&hex_str, &mut bytes).map_err(serde::de::Error::custom)?;
+        Ok(Self(bytes))
+    }
+}
 
 /// Block structure
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -15,6 +106,10 @@ pub struct Block {
     pub hash: String,
     pub difficulty: u32,
     pub merkle_root: String,
+    /// Hash format: LEGACY_BLOCK_VERSION or HEADER_HASH_BLOCK_VERSION (set by consensus
+    /// from the block's height; blocks stored before the field existed are legacy)
+    #[serde(default = "legacy_block_version")]
+    pub version: u32,
 }
 
 impl Block {
@@ -40,11 +135,25 @@ impl Block {
             hash: String::new(),
             difficulty,
             merkle_root,
+            version: LEGACY_BLOCK_VERSION,
         };
-        block.hash = block.calculate_hash();
+        block.hash = block.calculate_hash().into();
         block
     }
 
+    /// Create a new block (unmined), rejecting a malformed previous_hash
+    pub fn try_new(
+        index: u64,
+        transactions: Vec<Transaction>,
+        previous_hash: String,
+        difficulty: u32,
+    ) -> Result<Self, String> {
+        if !is_valid_previous_hash(index, &previous_hash) {
+            return Err(format!("Invalid previous hash for block {}: {:?}", index, previous_hash));
+        }
+        Ok(Self::new(index, transactions, previous_hash, difficulty))
+    }
+
     /// Create the genesis block (first block in chain)
     pub fn genesis(network: crate::core::ChainNetwork) -> Self {
         // CONSENSUS-CRITICAL: Genesis block parameters
@@ -60,22 +169,34 @@ impl Block {
             index: 0,
             timestamp, // Set based on network type
             transactions: vec![],
-            previous_hash: "0".repeat(64),
+            previous_hash: GENESIS_PREVIOUS_HASH.to_string(),
             nonce,
             hash: String::new(),
             difficulty,
             merkle_root: "0".repeat(64),
+            version: LEGACY_BLOCK_VERSION,
         };
-        genesis.hash = genesis.calculate_hash();
+        genesis.hash = genesis.calculate_hash().into();
         genesis
     }
 
-    /// Calculate block hash using SHA3-256
-    pub fn calculate_hash(&self) -> String {
+    /// Calculate block hash using SHA3-256, in the format of the block's version
+    pub fn calculate_hash(&self) -> BlockHash {
+        if self.version == HEADER_HASH_BLOCK_VERSION {
+            return header_hash(
+                self.index,
+                self.timestamp,
+                &self.previous_hash,
+                self.nonce,
+                self.difficulty,
+                &self.merkle_root,
+            );
+        }
+
         let transactions_str = self
             .transactions
             .iter()
-            .map(|tx| tx.hash())
+            .map(|tx| String::from(tx.hash()))
             .collect::<Vec<String>>()
             .join(",");
 
@@ -90,7 +211,12 @@ impl Block {
             self.merkle_root
         );
 
-        double_sha3(data.as_bytes())
+        BlockHash::new_unchecked(double_sha3(data.as_bytes()))
+    }
+
+    /// Exact threshold this block's hash must not exceed
+    pub fn target(&self) -> Target {
+        Target::from_difficulty(self.difficulty)
     }
 
     /// Check if block hash meets difficulty target
@@ -101,6 +227,12 @@ impl Block {
 
     /// Mine the block by finding a valid nonce
     pub fn mine(&mut self) {
+        self.mine_cancellable(&CancellationToken::new());
+    }
+
+    /// Mine until a valid nonce is found or `cancel` fires
+    /// Returns false if cancelled (the block is left unmined)
+    pub fn mine_cancellable(&mut self, cancel: &CancellationToken) -> bool {
         println!(
             "Mining block {} with difficulty {}...",
             self.index, self.difficulty
@@ -110,7 +242,7 @@ impl Block {
         let mut hash_count = 0u64;
         
         loop {
-            self.hash = self.calculate_hash();
+            self.hash = self.calculate_hash().into();
             hash_count += 1;
             
             if self.has_valid_hash() {
@@ -120,10 +252,15 @@ impl Block {
                     "Block mined! Nonce: {}, Hashes: {}, Time: {:.2}s, Hashrate: {:.0} H/s",
                     self.nonce, hash_count, elapsed, hashrate
                 );
-                break;
+                return true;
             }
             
             self.nonce += 1;
+
+            if hash_count.is_multiple_of(MINE_CANCEL_CHECK_INTERVAL) && cancel.is_cancelled() {
+                println!("\nMining block {} cancelled after {} hashes", self.index, hash_count);
+                return false;
+            }
             
             // Progress indicator every 100k hashes
             if hash_count % 100_000 == 0 {
@@ -142,6 +279,12 @@ impl Block {
             return false;
         }
 
+        // Previous hash must be well-formed (genesis uses the all-zero marker)
+        if !is_valid_previous_hash(self.index, &self.previous_hash) {
+            println!("Invalid previous hash format");
+            return false;
+        }
+
         // Check proof-of-work
         if !self.has_valid_hash() {
             println!("Invalid proof-of-work");
@@ -180,7 +323,6 @@ impl Block {
     }
 
     /// Get total transaction fees in block (u64 microunits)
-    #[allow(dead_code)]
     pub fn get_total_fees(&self) -> u64 {
         self.transactions
             .iter()
@@ -190,13 +332,60 @@ impl Block {
     }
 }
 
+/// Simplified block header for efficient sync
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BlockHeader {
+    pub index: u64,
+    pub timestamp: i64,
+    pub previous_hash: String,
+    pub hash: String,
+    pub nonce: u64,
+    pub difficulty: u32,
+    #[serde(default)]
+    pub merkle_root: String,
+    #[serde(default = "legacy_block_version")]
+    pub version: u32,
+}
+
+impl BlockHeader {
+    /// Whether the stated hash is at or below the target for the stated difficulty
+    /// Proof of work only once the hash is known to be the block's (calculate_hash);
+    /// a legacy header's hash is the sender's word until the body arrives
+    pub fn meets_difficulty(&self) -> bool {
+        Target::from_difficulty(self.difficulty).is_met_by(&self.hash)
+    }
+
+    /// Recompute the block hash from the header fields
+    /// None for LEGACY_BLOCK_VERSION headers, whose hash covers the transaction list
+    pub fn calculate_hash(&self) -> Option<BlockHash> {
+        (self.version == HEADER_HASH_BLOCK_VERSION).then(|| {
+            header_hash(self.index, self.timestamp, &self.previous_hash, self.nonce, self.difficulty, &self.merkle_root)
+        })
+    }
+}
+
+impl From<&Block> for BlockHeader {
+    fn from(block: &Block) -> Self {
+        Self {
+            index: block.index,
+            timestamp: block.timestamp,
+            previous_hash: block.previous_hash.clone(),
+            hash: block.hash.clone(),
+            nonce: block.nonce,
+            difficulty: block.difficulty,
+            merkle_root: block.merkle_root.clone(),
+            version: block.version,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn verify_genesis_hash() {
-        let genesis = Block::genesis();
+        let genesis = Block::genesis(crate::core::ChainNetwork::Mainnet);
         
         // CONSENSUS-CRITICAL: Genesis block must have these exact parameters
         assert_eq!(genesis.index, 0);
@@ -209,14 +398,30 @@ mod tests {
         // CRITICAL: Hash must match hardcoded value in blockchain.rs
         assert_eq!(
             genesis.hash,
-            "2c8490a8bfd4d8bbef7315fcf47bab8fa8b3a1d1c8ed2239512ad5191e0ddc22",
+            "527a8a6ad3292c9b42c40f3d71fd3b89cdd79415106ce0b8d9f7f6690a96433d",
             "Genesis hash mismatch! This will cause chain splits."
         );
     }
 
+    #[test]
+    fn malformed_previous_hash_is_rejected() {
+        assert!(Block::try_new(1, vec![], String::new(), 1).is_err());
+        assert!(Block::try_new(1, vec![], "abc".to_string(), 1).is_err());
+        assert!(Block::try_new(1, vec![], GENESIS_PREVIOUS_HASH.to_string(), 1).is_err());
+        assert!(Block::try_new(0, vec![], "ab".repeat(32), 1).is_err());
+
+        let genesis = Block::genesis(crate::core::ChainNetwork::Testnet);
+        assert!(Block::try_new(1, vec![], genesis.hash.clone(), 1).is_ok());
+
+        // Blocks built through new() are still caught by is_valid()
+        let mut block = Block::new(1, vec![], String::new(), 1);
+        block.mine();
+        assert!(!block.is_valid(None));
+    }
+
     #[test]
     fn genesis_hash_recalculation() {
-        let genesis = Block::genesis();
+        let genesis = Block::genesis(crate::core::ChainNetwork::Mainnet);
         let recalculated = genesis.calculate_hash();
         
         assert_eq!(
@@ -224,4 +429,102 @@ mod tests {
             "Genesis hash calculation must be deterministic"
         );
     }
+
+    #[test]
+    fn target_matches_difficulty() {
+        assert_eq!(Target::from_difficulty(0).to_hex(), "f".repeat(64));
+        assert_eq!(Target::from_difficulty(3).to_hex(), format!("000{}", "f".repeat(61)));
+        assert_eq!(Target::from_difficulty(64).to_hex(), "0".repeat(64));
+
+        let mut block = Block::new(1, vec![], "ab".repeat(32), 2);
+        block.mine();
+        let target = block.target();
+        assert!(target.is_met_by(&block.hash));
+        assert_eq!(&target.to_hex()[..3], "00f");
+        // Exactly the hashes has_valid_hash accepts
+        assert!(target.is_met_by(&format!("00{}", "f".repeat(62))));
+        assert!(!target.is_met_by(&format!("010{}", "0".repeat(61))));
+
+        let json = serde_json::to_string(&target).unwrap();
+        assert_eq!(json, format!("\"{}\"", target));
+        assert_eq!(serde_json::from_str::<Target>(&json).unwrap(), target);
+    }
+
+    #[test]
+    fn mining_stops_promptly_when_cancelled() {
+        // Unreachable target: only cancellation can end the loop
+        let genesis = Block::genesis(crate::core::ChainNetwork::Testnet);
+        let mut block = Block::new(1, vec![], genesis.hash, 64);
+        let cancel = CancellationToken::new();
+
+        let canceller = {
+            let cancel = cancel.clone();
+            std::thread::spawn(move || {
+                std::thread::sleep(std::time::Duration::from_millis(50));
+                cancel.cancel();
+                std::time::Instant::now()
+            })
+        };
+        assert!(!block.mine_cancellable(&cancel));
+        let cancelled_at = canceller.join().unwrap();
+        assert!(cancelled_at.elapsed() < std::time::Duration::from_secs(1));
+        assert!(!block.has_valid_hash());
+    }
+
+    #[test]
+    fn header_hash_test_vector() {
+        // CONSENSUS-CRITICAL: HEADER_HASH_BLOCK_VERSION hashes
+        // version:index:timestamp:previous_hash:nonce:difficulty:merkle_root, double SHA3-256
+        let mut block = Block::new(7, vec![], "cd".repeat(32), 3);
+        block.timestamp = 1_700_000_600;
+        block.version = HEADER_HASH_BLOCK_VERSION;
+        block.mine();
+        assert_eq!(
+            (block.nonce, block.hash.as_str()),
+            (7810, "0000c66bffef5acca3951eec3a35ec3939231e61d64e3e3ac5a92b1dc2c57d76")
+        );
+        assert!(block.is_valid(None));
+
+        let preimage = format!("2:7:1700000600:{}:{}:3:{}", "cd".repeat(32), block.nonce, "0".repeat(64));
+        assert_eq!(double_sha3(preimage.as_bytes()), block.hash);
+
+        // The header alone reproduces it, whatever the transactions
+        let header = BlockHeader::from(&block);
+        assert_eq!(header.calculate_hash().unwrap(), block.hash);
+        let legacy = BlockHeader { version: LEGACY_BLOCK_VERSION, ..header };
+        assert_eq!(legacy.calculate_hash(), None);
+    }
+
+    #[test]
+    fn proof_of_work_test_vectors() {
+        // CONSENSUS-CRITICAL: (index, timestamp, previous_hash, difficulty) -> first valid nonce and hash.
+        // If these change, every node running the old code rejects the new blocks
+        let vectors = [
+            (1, 1_700_000_000, "ab".repeat(32), 3, 5970, "000d71fb2e7a68ca0112ddb88639aa18574bbec70ab84390683be4d449e0bc42"),
+            (7, 1_700_000_600, "cd".repeat(32), 4, 32359, "000041712c156643d0bab2684842df69f6731148ada2f9c3b71f2579d6e0ab68"),
+        ];
+
+        for (index, timestamp, previous_hash, difficulty, nonce, hash) in vectors {
+            let mut block = Block::new(index, vec![], previous_hash.clone(), difficulty);
+            block.timestamp = timestamp;
+            block.mine();
+            assert_eq!((block.nonce, block.hash.as_str()), (nonce, hash));
+            assert!(block.is_valid(None));
+
+            // Preimage: index:timestamp:tx_hashes:previous_hash:nonce:difficulty:merkle_root, double SHA3-256
+            let preimage = format!("{}:{}::{}:{}:{}:{}", index, timestamp, previous_hash, nonce, difficulty, "0".repeat(64));
+            assert_eq!(double_sha3(preimage.as_bytes()), hash);
+            assert!(block.target().is_met_by(hash));
+
+            // Neighbouring nonces don't meet the target, and the found hash doesn't carry over to them
+            for other in [nonce - 1, nonce + 1] {
+                let mut tampered = block.clone();
+                tampered.nonce = other;
+                assert!(!tampered.is_valid(None));
+                tampered.hash = tampered.calculate_hash().into();
+                assert!(!tampered.has_valid_hash());
+                assert!(!tampered.is_valid(None));
+            }
+        }
+    }
 }
@@ -1,10 +1,15 @@
 pub mod block;
 pub mod transaction;
 pub mod merkle;
+pub mod redact;
+pub mod snapshot;
+pub mod units;
+pub mod types;
 
 pub use block::Block;
 pub use transaction::{Transaction, TransactionType, AccountState, AccountBalance};
 pub use merkle::MerkleTree;
+pub use types::{Address, BlockHash, TxHash};
 
 use serde::{Serialize, Deserialize};
 
@@ -0,0 +1,43 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// Characters kept when an address is redacted ("0x" + 8 hex)
+pub const REDACTED_ADDRESS_PREFIX_LEN: usize = 10;
+
+/// Process-wide switch, set once from config at node startup
+static REDACT_ADDRESSES: AtomicBool = AtomicBool::new(false);
+
+/// Enable or disable address redaction for info/warn log lines
+pub fn set_address_redaction(enabled: bool) {
+    REDACT_ADDRESSES.store(enabled, Ordering::Relaxed);
+}
+
+/// Address as it should appear in info/warn logs
+/// Log the full value at debug level when it is needed for troubleshooting
+pub fn redact_address(address: &str) -> String {
+    redact_address_with(address, REDACT_ADDRESSES.load(Ordering::Relaxed))
+}
+
+/// Truncate to a short prefix when `enabled`, otherwise return the address unchanged
+pub fn redact_address_with(address: &str, enabled: bool) -> String {
+    if !enabled || address.chars().count() <= REDACTED_ADDRESS_PREFIX_LEN {
+        return address.to_string();
+    }
+    let prefix: String = address.chars().take(REDACTED_ADDRESS_PREFIX_LEN).collect();
+    format!("{}…", prefix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redaction_truncates_only_when_enabled() {
+        let address = "0x1234567890abcdef1234567890abcdef12345678";
+
+        assert_eq!(redact_address_with(address, true), "0x12345678…");
+        assert_eq!(redact_address_with(address, false), address);
+
+        // Short or special senders are left alone
+        assert_eq!(redact_address_with("COINBASE", true), "COINBASE");
+    }
+}
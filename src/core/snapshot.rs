@@ -0,0 +1,107 @@
+use crate::core::block::BlockHeader;
+use crate::core::transaction::AccountState;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Trusted anchor for snapshot sync: block hash and account state hash
+/// after applying the block at `height`
+#[derive(Debug, Clone, Copy)]
+pub struct SnapshotCheckpoint {
+    pub height: u64,
+    pub block_hash: &'static str,
+    pub state_hash: &'static str,
+}
+
+/// Heights a new node may fast-sync to instead of replaying from genesis
+/// Add entries as the network matures (see CHECKPOINTS in consensus/blockchain.rs):
+/// SnapshotCheckpoint { height: 100_000, block_hash: "<hash>", state_hash: "<AccountState::state_hash>" },
+pub const SNAPSHOT_CHECKPOINTS: &[SnapshotCheckpoint] = &[];
+
+/// Highest snapshot checkpoint at or below `height`
+pub fn latest_checkpoint_at(height: u64) -> Option<&'static SnapshotCheckpoint> {
+    SNAPSHOT_CHECKPOINTS
+        .iter()
+        .filter(|checkpoint| checkpoint.height <= height)
+        .max_by_key(|checkpoint| checkpoint.height)
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SnapshotError {
+    #[error("No snapshot checkpoint at height {0}")]
+    UnknownCheckpoint(u64),
+    #[error("Expected {expected} headers, got {actual}")]
+    HeaderCount { expected: u64, actual: usize },
+    #[error("Header chain broken at height {0}")]
+    BrokenLink(u64),
+    #[error("Header {0} does not meet its difficulty")]
+    InsufficientWork(u64),
+    #[error("Header {0} difficulty is off the retarget schedule")]
+    UnexpectedDifficulty(u64),
+    #[error("Checkpoint block hash mismatch")]
+    BlockHashMismatch,
+    #[error("Account state hash mismatch")]
+    StateHashMismatch,
+}
+
+/// Account state after block `height` plus the header chain 0..=height
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct StateSnapshot {
+    pub height: u64,
+    pub headers: Vec<BlockHeader>,
+    pub account_state: AccountState,
+}
+
+impl StateSnapshot {
+    /// Check the snapshot against a trusted checkpoint
+    /// Headers must link up to the checkpoint hash, each with the difficulty
+    /// `next_difficulty` gives after the headers before it and a hash at or below
+    /// that target; the account state must hash to the checkpoint's state hash
+    pub fn verify(
+        &self,
+        checkpoint: &SnapshotCheckpoint,
+        next_difficulty: impl Fn(&[BlockHeader]) -> u32,
+    ) -> Result<(), SnapshotError> {
+        if self.height != checkpoint.height {
+            return Err(SnapshotError::UnknownCheckpoint(self.height));
+        }
+        if self.headers.len() as u64 != self.height + 1 {
+            return Err(SnapshotError::HeaderCount { expected: self.height + 1, actual: self.headers.len() });
+        }
+
+        for (i, header) in self.headers.iter().enumerate() {
+            let height = i as u64;
+            if header.index != height {
+                return Err(SnapshotError::BrokenLink(height));
+            }
+            if i == 0 {
+                continue;
+            }
+            if header.previous_hash != self.headers[i - 1].hash {
+                return Err(SnapshotError::BrokenLink(height));
+            }
+            if header.difficulty != next_difficulty(&self.headers[..i]) {
+                return Err(SnapshotError::UnexpectedDifficulty(height));
+            }
+            if !header.meets_difficulty() {
+                return Err(SnapshotError::InsufficientWork(height));
+            }
+        }
+
+        if self.headers[self.height as usize].hash != checkpoint.block_hash {
+            return Err(SnapshotError::BlockHashMismatch);
+        }
+        if self.account_state.state_hash() != checkpoint.state_hash {
+            return Err(SnapshotError::StateHashMismatch);
+        }
+        Ok(())
+    }
+
+    /// Verify against the hardcoded checkpoint at this snapshot's height
+    pub fn verify_known(&self, next_difficulty: impl Fn(&[BlockHeader]) -> u32) -> Result<(), SnapshotError> {
+        let checkpoint = SNAPSHOT_CHECKPOINTS
+            .iter()
+            .find(|checkpoint| checkpoint.height == self.height)
+            .ok_or(SnapshotError::UnknownCheckpoint(self.height))?;
+        self.verify(checkpoint, next_difficulty)
+    }
+}
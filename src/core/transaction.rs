@@ -1,5 +1,7 @@
 use serde::{Serialize, Deserialize};
-use crate::crypto::verify_signature;
+use crate::crypto::{hash_with, verify_signature, HashAlgorithm};
+use crate::core::redact::redact_address;
+use crate::core::types::TxHash;
 use std::collections::HashMap;
 
 /// Transaction structure with Falcon signature
@@ -15,6 +17,39 @@ pub struct Transaction {
     pub fee: u64,                 // Transaction fee in microunits
     pub nonce: u64,               // Nonce for replay protection
     pub tx_type: TransactionType, // Transaction type
+    /// Earliest block height this transaction may be included at (None = any)
+    #[serde(default)]
+    pub not_before_height: Option<u64>,
+    /// Arbitrary payload such as an invoice ID (at most MAX_MEMO_BYTES; always None for coinbase)
+    #[serde(default)]
+    pub memo: Option<Vec<u8>>,
+}
+
+// Bounds on variable-length fields (checked before hashing or signature work)
+pub const MAX_ADDRESS_LEN: usize = 42; // 0x + 40 hex chars
+pub const MAX_FUNCTION_NAME_LEN: usize = 64;
+pub const MAX_CALL_ARGS_BYTES: usize = 16 * 1024; // 16KB
+pub const MAX_MEMO_BYTES: usize = 256;
+
+/// First byte of every transaction preimage; bump on any change to its layout
+/// (1 was the unversioned layout without length prefixes or presence bytes)
+pub const TX_HASH_FORMAT_VERSION: u8 = 2;
+
+/// Append `bytes` preceded by its length as a little-endian u64
+fn push_length_prefixed(preimage: &mut Vec<u8>, bytes: &[u8]) {
+    preimage.extend_from_slice(&(bytes.len() as u64).to_le_bytes()); // LITTLE-ENDIAN
+    preimage.extend_from_slice(bytes);
+}
+
+/// Check address format: 0x + 40 lowercase hex chars (20-byte SHA3 prefix)
+pub fn is_valid_address(address: &str) -> bool {
+    match address.strip_prefix("0x") {
+        Some(hex_part) => {
+            hex_part.len() == 40
+                && hex_part.chars().all(|c| c.is_ascii_digit() || ('a'..='f').contains(&c))
+        }
+        None => false,
+    }
 }
 
 /// Transaction types
@@ -25,12 +60,23 @@ pub enum TransactionType {
     CallContract { contract: String, function: String, args: Vec<u8> },
 }
 
+impl TransactionType {
+    /// Stable lowercase name for APIs and logs
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransactionType::Transfer => "transfer",
+            TransactionType::DeployContract { .. } => "deploy_contract",
+            TransactionType::CallContract { .. } => "call_contract",
+        }
+    }
+}
+
 impl Transaction {
     /// Create a new transaction (unsigned) - amounts in microunits
-    pub fn new(sender: String, recipient: String, amount: u64, timestamp: i64) -> Self {
+    pub fn new(sender: impl Into<String>, recipient: impl Into<String>, amount: u64, timestamp: i64) -> Self {
         Self {
-            sender,
-            recipient,
+            sender: sender.into(),
+            recipient: recipient.into(),
             amount,
             timestamp,
             signature: vec![],
@@ -38,14 +84,16 @@ impl Transaction {
             fee: 1000, // 0.001 QUA = 1000 microunits
             nonce: 0,
             tx_type: TransactionType::Transfer,
+            not_before_height: None,
+            memo: None,
         }
     }
     
     /// Create deploy contract transaction
     #[allow(dead_code)]
-    pub fn new_deploy_contract(sender: String, code: Vec<u8>, timestamp: i64, nonce: u64) -> Self {
+    pub fn new_deploy_contract(sender: impl Into<String>, code: Vec<u8>, timestamp: i64, nonce: u64) -> Self {
         Self {
-            sender,
+            sender: sender.into(),
             recipient: String::new(),
             amount: 0,
             timestamp,
@@ -54,13 +102,15 @@ impl Transaction {
             fee: 10_000, // 0.01 QUA for deployment
             nonce,
             tx_type: TransactionType::DeployContract { code },
+            not_before_height: None,
+            memo: None,
         }
     }
     
     /// Create call contract transaction
     #[allow(dead_code)]
     pub fn new_call_contract(
-        sender: String,
+        sender: impl Into<String>,
         contract: String,
         function: String,
         args: Vec<u8>,
@@ -68,7 +118,7 @@ impl Transaction {
         nonce: u64,
     ) -> Self {
         Self {
-            sender,
+            sender: sender.into(),
             recipient: contract.clone(),
             amount: 0,
             timestamp,
@@ -77,45 +127,127 @@ impl Transaction {
             fee: 5000, // 0.005 QUA for calls
             nonce,
             tx_type: TransactionType::CallContract { contract, function, args },
+            not_before_height: None,
+            memo: None,
+        }
+    }
+
+    /// Start building an unsigned transaction (sign with QuantumWallet::sign_transaction)
+    pub fn builder() -> TransactionBuilder {
+        TransactionBuilder::default()
+    }
+
+    /// Serialized size in bytes (bincode), the basis for fee-rate checks
+    /// Falcon public key + signature alone are ~1.5KB of every signed transaction
+    pub fn weight(&self) -> usize {
+        bincode::serialized_size(self).map(|size| size as usize).unwrap_or(usize::MAX)
+    }
+
+    /// Size of the contract call instruction data (function name, NUL separator, args)
+    /// None for anything but CallContract
+    pub fn call_data_len(&self) -> Option<usize> {
+        match &self.tx_type {
+            TransactionType::CallContract { function, args, .. } => Some(function.len() + 1 + args.len()),
+            _ => None,
+        }
+    }
+
+    /// Reject oversized address, function name and args fields
+    /// Cheap length checks only; run on untrusted input before hashing or verifying
+    pub fn check_field_bounds(&self) -> Result<(), String> {
+        let check = |field: &str, len: usize, max: usize| {
+            if len > max {
+                Err(format!("{} is {} bytes (max {})", field, len, max))
+            } else {
+                Ok(())
+            }
+        };
+
+        check("sender", self.sender.len(), MAX_ADDRESS_LEN)?;
+        check("recipient", self.recipient.len(), MAX_ADDRESS_LEN)?;
+        if let TransactionType::CallContract { contract, function, args } = &self.tx_type {
+            check("contract", contract.len(), MAX_ADDRESS_LEN)?;
+            check("function", function.len(), MAX_FUNCTION_NAME_LEN)?;
+            check("args", args.len(), MAX_CALL_ARGS_BYTES)?;
+        }
+        Ok(())
+    }
+
+    /// Value semantics of contract transactions (consensus rule)
+    /// A deployment moves no value (there is no contract account to credit yet); a call's
+    /// amount is paid to the called contract, so its recipient must be that contract
+    pub fn check_contract_value(&self) -> Result<(), String> {
+        match &self.tx_type {
+            TransactionType::DeployContract { .. } if self.amount != 0 => {
+                Err(format!("deployment carries amount {} (must be 0)", self.amount))
+            }
+            TransactionType::DeployContract { .. } if !self.recipient.is_empty() => {
+                Err(format!("deployment has recipient {} (must be empty)", self.recipient))
+            }
+            TransactionType::CallContract { contract, .. } if self.recipient != *contract => {
+                Err(format!("call recipient {} is not the called contract {}", self.recipient, contract))
+            }
+            _ => Ok(()),
         }
     }
 
-    /// Get transaction data for signing - MUST match hash calculation
-    /// Everything except signature itself
-    /// 
+    /// Bytes committed to by both the signature and the hash: every field except the signature
+    ///
     /// CONSENSUS RULES (FROZEN FOREVER):
+    /// - Starts with TX_HASH_FORMAT_VERSION
     /// - All integers are LITTLE-ENDIAN (to_le_bytes)
     /// - Public key is included (binds signature to key, prevents key substitution)
     /// - Strings are UTF-8 bytes
-    pub fn get_signing_data(&self) -> Vec<u8> {
-        use sha3::{Digest, Sha3_256};
-        let mut hasher = Sha3_256::new();
-        
-        // CRITICAL: This must match hash() exactly (except signature)
-        hasher.update(self.sender.as_bytes());
-        hasher.update(self.recipient.as_bytes());
-        hasher.update(&self.amount.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.timestamp.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.fee.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.nonce.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.public_key);
-        
-        // Include tx_type
+    /// - Variable-length fields carry a u64 length prefix and optional fields a presence
+    ///   byte, so no field's bytes can be moved into a neighbour without changing the hash
+    pub fn consensus_preimage(&self) -> Vec<u8> {
+        let mut preimage = Vec::with_capacity(128 + self.public_key.len());
+
+        preimage.push(TX_HASH_FORMAT_VERSION);
+        push_length_prefixed(&mut preimage, self.sender.as_bytes());
+        push_length_prefixed(&mut preimage, self.recipient.as_bytes());
+        preimage.extend_from_slice(&self.amount.to_le_bytes()); // LITTLE-ENDIAN
+        preimage.extend_from_slice(&self.timestamp.to_le_bytes()); // LITTLE-ENDIAN
+        preimage.extend_from_slice(&self.fee.to_le_bytes()); // LITTLE-ENDIAN
+        preimage.extend_from_slice(&self.nonce.to_le_bytes()); // LITTLE-ENDIAN
+        push_length_prefixed(&mut preimage, &self.public_key);
+
+        // Include tx_type discriminant
         match &self.tx_type {
-            TransactionType::Transfer => hasher.update(&[0u8]),
+            TransactionType::Transfer => preimage.push(0u8),
             TransactionType::DeployContract { code } => {
-                hasher.update(&[1u8]);
-                hasher.update(code);
+                preimage.push(1u8);
+                push_length_prefixed(&mut preimage, code);
             }
             TransactionType::CallContract { contract, function, args } => {
-                hasher.update(&[2u8]);
-                hasher.update(contract.as_bytes());
-                hasher.update(function.as_bytes());
-                hasher.update(args);
+                preimage.push(2u8);
+                push_length_prefixed(&mut preimage, contract.as_bytes());
+                push_length_prefixed(&mut preimage, function.as_bytes());
+                push_length_prefixed(&mut preimage, args);
             }
         }
-        
-        hasher.finalize().to_vec()
+        match self.not_before_height {
+            None => preimage.push(0u8),
+            Some(height) => {
+                preimage.push(1u8);
+                preimage.extend_from_slice(&height.to_le_bytes()); // LITTLE-ENDIAN
+            }
+        }
+        match &self.memo {
+            None => preimage.push(0u8),
+            Some(memo) => {
+                preimage.push(1u8);
+                push_length_prefixed(&mut preimage, memo);
+            }
+        }
+
+        preimage
+    }
+
+    /// Get transaction data for signing: SHA3-256 of the consensus preimage
+    /// (the same digest hash() hex-encodes)
+    pub fn get_signing_data(&self) -> Vec<u8> {
+        hash_with(HashAlgorithm::CONSENSUS, &self.consensus_preimage()).to_vec()
     }
 
     /// Verify the Falcon signature AND sender matches public_key
@@ -133,7 +265,12 @@ impl Transaction {
         // CRITICAL: Verify sender matches the public key
         let derived_address = self.derive_address_from_pubkey();
         if self.sender != derived_address {
-            tracing::warn!("Sender mismatch: {} != {}", self.sender, derived_address);
+            tracing::warn!(
+                "Sender mismatch: {} != {}",
+                redact_address(&self.sender),
+                redact_address(&derived_address)
+            );
+            tracing::debug!("Sender mismatch (full): {} != {}", self.sender, derived_address);
             return false;
         }
         
@@ -143,47 +280,20 @@ impl Transaction {
     
     /// Derive address from public key (must match sender)
     fn derive_address_from_pubkey(&self) -> String {
-        use sha3::{Digest, Sha3_256};
-        let hash = Sha3_256::digest(&self.public_key);
-        format!("0x{}", hex::encode(&hash[..20])) // 0x + 40 hex chars = 42 total
+        crate::crypto::signatures::address_from_public_key(&self.public_key)
     }
 
     /// Calculate transaction hash - includes ALL fields except signature
     /// This prevents hash collisions and replay attacks
-    /// 
-    /// CONSENSUS RULES (FROZEN FOREVER):
-    /// - All integers are LITTLE-ENDIAN
-    /// - Public key included (prevents key substitution attacks)
-    /// - Signature NOT included (can't sign the signature)
-    pub fn hash(&self) -> String {
-        use sha3::{Digest, Sha3_256};
-        let mut hasher = Sha3_256::new();
-        
-        // Include all transaction data EXCEPT signature (signature signs the hash)
-        hasher.update(self.sender.as_bytes());
-        hasher.update(self.recipient.as_bytes());
-        hasher.update(&self.amount.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.timestamp.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.fee.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.nonce.to_le_bytes()); // LITTLE-ENDIAN
-        hasher.update(&self.public_key);
-        
-        // Include tx_type discriminant
-        match &self.tx_type {
-            TransactionType::Transfer => hasher.update(&[0u8]),
-            TransactionType::DeployContract { code } => {
-                hasher.update(&[1u8]);
-                hasher.update(code);
-            }
-            TransactionType::CallContract { contract, function, args } => {
-                hasher.update(&[2u8]);
-                hasher.update(contract.as_bytes());
-                hasher.update(function.as_bytes());
-                hasher.update(args);
-            }
-        }
-        
-        hex::encode(hasher.finalize())
+    ///
+    /// Hex of the signing data, so a signature always commits to exactly this hash
+    pub fn hash(&self) -> TxHash {
+        TxHash::new_unchecked(hex::encode(self.get_signing_data()))
+    }
+
+    /// Whether a block at `height` may include this transaction (locktime has passed)
+    pub fn is_final_at(&self, height: u64) -> bool {
+        self.not_before_height.is_none_or(|not_before| height >= not_before)
     }
 
     /// Check if this is a coinbase transaction (mining reward)
@@ -192,15 +302,212 @@ impl Transaction {
     }
 }
 
+/// Fluent constructor for unsigned transactions
+/// Defaults: transfer, fee 0.001 QUA, nonce 0, timestamp = now
+#[derive(Clone, Debug)]
+pub struct TransactionBuilder {
+    sender: String,
+    recipient: String,
+    amount: u64,
+    fee: u64,
+    nonce: u64,
+    timestamp: Option<i64>,
+    tx_type: TransactionType,
+    not_before_height: Option<u64>,
+    memo: Option<Vec<u8>>,
+}
+
+impl Default for TransactionBuilder {
+    fn default() -> Self {
+        Self {
+            sender: String::new(),
+            recipient: String::new(),
+            amount: 0,
+            fee: 1000, // 0.001 QUA = 1000 microunits
+            nonce: 0,
+            timestamp: None,
+            tx_type: TransactionType::Transfer,
+            not_before_height: None,
+            memo: None,
+        }
+    }
+}
+
+impl TransactionBuilder {
+    /// Sender address (left empty, it is filled in by the signing wallet)
+    pub fn sender(mut self, sender: impl Into<String>) -> Self {
+        self.sender = sender.into();
+        self
+    }
+
+    pub fn recipient(mut self, recipient: impl Into<String>) -> Self {
+        self.recipient = recipient.into();
+        self
+    }
+
+    /// Amount in microunits
+    pub fn amount(mut self, amount: u64) -> Self {
+        self.amount = amount;
+        self
+    }
+
+    /// Fee in microunits
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn nonce(mut self, nonce: u64) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Unix timestamp (defaults to the time build() is called)
+    pub fn timestamp(mut self, timestamp: i64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// Don't allow inclusion before block `height` (the tx waits in the mempool until then)
+    pub fn not_before_height(mut self, height: u64) -> Self {
+        self.not_before_height = Some(height);
+        self
+    }
+
+    /// Attach a memo (invoice ID, payment reference; at most MAX_MEMO_BYTES)
+    pub fn memo(mut self, memo: impl Into<Vec<u8>>) -> Self {
+        self.memo = Some(memo.into());
+        self
+    }
+
+    /// Plain value transfer (the default)
+    pub fn transfer(mut self) -> Self {
+        self.tx_type = TransactionType::Transfer;
+        self
+    }
+
+    /// Contract deployment (no recipient)
+    pub fn deploy_contract(mut self, code: Vec<u8>) -> Self {
+        self.recipient = String::new();
+        self.tx_type = TransactionType::DeployContract { code };
+        self
+    }
+
+    /// Contract call (recipient is the contract address)
+    pub fn call_contract(mut self, contract: impl Into<String>, function: impl Into<String>, args: Vec<u8>) -> Self {
+        let contract = contract.into();
+        self.recipient = contract.clone();
+        self.tx_type = TransactionType::CallContract { contract, function: function.into(), args };
+        self
+    }
+
+    /// Produce the unsigned transaction
+    pub fn build(self) -> Transaction {
+        Transaction {
+            sender: self.sender,
+            recipient: self.recipient,
+            amount: self.amount,
+            timestamp: self.timestamp.unwrap_or_else(|| chrono::Utc::now().timestamp()),
+            signature: vec![],
+            public_key: vec![],
+            fee: self.fee,
+            nonce: self.nonce,
+            tx_type: self.tx_type,
+            not_before_height: self.not_before_height,
+            memo: self.memo,
+        }
+    }
+}
+
 /// Account balance tracking (account-based model, not UTXO)
 /// This is simpler and works better with smart contracts
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(from = "StoredAccountBalance")]
 pub struct AccountBalance {
     pub address: String,
     pub balance: u64,        // in microunits (spendable)
     pub nonce: u64,          // for replay protection
-    pub locked_balance: u64, // coinbase rewards locked until maturity
-    pub unlock_height: u64,  // block height when locked_balance becomes spendable
+    pub locked_balance: u64, // total of locked_tranches (coinbase / vesting not yet spendable)
+    pub unlock_height: u64,  // height the next tranche unlocks (0 = nothing locked)
+    pub locked_tranches: Vec<LockedTranche>, // ascending unlock_height, each unlocks on its own
+}
+
+/// Locked funds that become spendable at `unlock_height`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockedTranche {
+    pub amount: u64,
+    pub unlock_height: u64,
+}
+
+/// AccountBalance as stored; state saved before tranches has a single lock and no list
+#[derive(Deserialize)]
+struct StoredAccountBalance {
+    address: String,
+    balance: u64,
+    nonce: u64,
+    locked_balance: u64,
+    unlock_height: u64,
+    #[serde(default)]
+    locked_tranches: Vec<LockedTranche>,
+}
+
+impl From<StoredAccountBalance> for AccountBalance {
+    fn from(stored: StoredAccountBalance) -> Self {
+        let mut account = AccountBalance {
+            address: stored.address,
+            balance: stored.balance,
+            nonce: stored.nonce,
+            locked_balance: stored.locked_balance,
+            unlock_height: stored.unlock_height,
+            locked_tranches: stored.locked_tranches,
+        };
+        let tracked: u64 = account.locked_tranches.iter().map(|t| t.amount).sum();
+        if stored.locked_balance > tracked {
+            account.locked_balance = tracked;
+            account.lock(stored.locked_balance - tracked, stored.unlock_height);
+        }
+        account
+    }
+}
+
+impl AccountBalance {
+    fn new(address: &str) -> Self {
+        Self {
+            address: address.to_string(),
+            balance: 0,
+            nonce: 0,
+            locked_balance: 0,
+            unlock_height: 0,
+            locked_tranches: Vec::new(),
+        }
+    }
+
+    /// Lock `amount` until `unlock_height` (merged with a tranche at the same height)
+    fn lock(&mut self, amount: u64, unlock_height: u64) {
+        match self.locked_tranches.binary_search_by_key(&unlock_height, |t| t.unlock_height) {
+            Ok(i) => self.locked_tranches[i].amount = self.locked_tranches[i].amount.saturating_add(amount),
+            Err(i) => self.locked_tranches.insert(i, LockedTranche { amount, unlock_height }),
+        }
+        self.locked_balance = self.locked_balance.saturating_add(amount);
+        self.unlock_height = self.locked_tranches[0].unlock_height;
+    }
+
+    /// Collapse the tranches into one that unlocks with the last of them
+    fn merge_tranches(&mut self) {
+        if let Some(last) = self.locked_tranches.last().map(|t| t.unlock_height) {
+            self.locked_tranches = vec![LockedTranche { amount: self.locked_balance, unlock_height: last }];
+            self.unlock_height = last;
+        }
+    }
+
+    /// Move every tranche due at `current_height` into the spendable balance
+    fn unlock_due(&mut self, current_height: u64) {
+        let due = self.locked_tranches.partition_point(|t| t.unlock_height <= current_height);
+        let released: u64 = self.locked_tranches.drain(..due).map(|t| t.amount).sum();
+        self.balance = self.balance.saturating_add(released);
+        self.locked_balance = self.locked_balance.saturating_sub(released);
+        self.unlock_height = self.locked_tranches.first().map_or(0, |t| t.unlock_height);
+    }
 }
 
 /// Account state database (account-based model, NOT UTXO)
@@ -225,18 +532,13 @@ impl AccountState {
             return; // Skip zero-amount txs (like contract calls)
         }
         
-        let account = self.accounts.entry(tx.recipient.clone()).or_insert(AccountBalance {
-            address: tx.recipient.clone(),
-            balance: 0,
-            nonce: 0,
-            locked_balance: 0,
-            unlock_height: 0,
-        });
+        let account = self.accounts
+            .entry(tx.recipient.clone())
+            .or_insert_with(|| AccountBalance::new(&tx.recipient));
         
         if tx.is_coinbase() {
-            // Coinbase rewards are locked until maturity
-            account.locked_balance = account.locked_balance.saturating_add(tx.amount);
-            account.unlock_height = current_height + coinbase_maturity;
+            // Coinbase rewards are locked until maturity (each reward on its own schedule)
+            account.lock(tx.amount, current_height + coinbase_maturity);
         } else {
             // Regular transactions are immediately spendable
             account.balance = account.balance.saturating_add(tx.amount);
@@ -263,27 +565,26 @@ impl AccountState {
     pub fn unlock_mature_coinbase(&mut self, current_height: u64) {
         for account in self.accounts.values_mut() {
             if account.locked_balance > 0 && current_height >= account.unlock_height {
-                account.balance = account.balance.saturating_add(account.locked_balance);
-                account.locked_balance = 0;
-                account.unlock_height = 0;
+                account.unlock_due(current_height);
             }
         }
     }
     
+    /// Make `address`'s locked funds unlock together, with the latest of its tranches
+    /// (how locks behaved before per-tranche unlocking, see ActivationHeights)
+    pub fn merge_locked_tranches(&mut self, address: &str) {
+        if let Some(account) = self.accounts.get_mut(address) {
+            account.merge_tranches();
+        }
+    }
+
     /// Add locked balance for mining reward vesting (ANTI-DUMP mechanism)
     /// Used for 50% of mining rewards locked for 6 months
     pub fn add_locked_balance(&mut self, address: &str, amount: u64, unlock_height: u64) {
-        let account = self.accounts.entry(address.to_string()).or_insert(AccountBalance {
-            address: address.to_string(),
-            balance: 0,
-            nonce: 0,
-            locked_balance: 0,
-            unlock_height: 0,
-        });
-        
-        // Add to locked balance with max unlock height
-        account.locked_balance = account.locked_balance.saturating_add(amount);
-        account.unlock_height = account.unlock_height.max(unlock_height);
+        let account = self.accounts
+            .entry(address.to_string())
+            .or_insert_with(|| AccountBalance::new(address));
+        account.lock(amount, unlock_height);
     }
 
     /// Get balance for an address (spendable only)
@@ -291,6 +592,16 @@ impl AccountState {
         self.accounts.get(address).map(|acc| acc.balance).unwrap_or(0)
     }
     
+    /// Get locked balance (immature coinbase + vesting, not yet spendable)
+    pub fn get_locked_balance(&self, address: &str) -> u64 {
+        self.accounts.get(address).map(|acc| acc.locked_balance).unwrap_or(0)
+    }
+
+    /// Height the next locked tranche unlocks (0 = nothing locked)
+    pub fn get_unlock_height(&self, address: &str) -> u64 {
+        self.accounts.get(address).map(|acc| acc.unlock_height).unwrap_or(0)
+    }
+
     /// Get total balance (spendable + locked)
     pub fn get_total_balance(&self, address: &str) -> u64 {
         self.accounts.get(address).map(|acc| acc.balance + acc.locked_balance).unwrap_or(0)
@@ -301,22 +612,6 @@ impl AccountState {
         self.accounts.get(address).map(|acc| acc.nonce).unwrap_or(0)
     }
     
-    /// Increment nonce for account (CRITICAL for transaction ordering)
-    pub fn increment_nonce(&mut self, address: &str) {
-        if let Some(acc) = self.accounts.get_mut(address) {
-            acc.nonce += 1;
-        } else {
-            // Create account with nonce 1 if doesn't exist
-            self.accounts.insert(address.to_string(), AccountBalance {
-                address: address.to_string(),
-                balance: 0,
-                nonce: 1,
-                locked_balance: 0,
-                unlock_height: 0,
-            });
-        }
-    }
-    
     /// Verify transaction nonce matches account nonce
     pub fn verify_nonce(&self, address: &str, tx_nonce: u64) -> bool {
         let account_nonce = self.get_nonce(address);
@@ -328,9 +623,201 @@ impl AccountState {
         self.get_balance(address) >= amount
     }
 
+    /// Canonical commitment to every account: hex SHA3-256 of the accounts sorted by address
+    /// (snapshot sync compares this against a hardcoded checkpoint)
+    pub fn state_hash(&self) -> String {
+        let mut accounts: Vec<&AccountBalance> = self.accounts.values().collect();
+        accounts.sort_by(|a, b| a.address.cmp(&b.address));
+        let data = serde_json::to_vec(&accounts).unwrap_or_default();
+        hex::encode(hash_with(HashAlgorithm::CONSENSUS, &data))
+    }
+
     /// Get all account addresses
     pub fn get_accounts(&self) -> Vec<String> {
         self.accounts.keys().cloned().collect()
     }
+
+    /// Number of distinct accounts with state (balance, locked funds or nonce)
+    pub fn account_count(&self) -> usize {
+        self.accounts.len()
+    }
+
+    /// Iterate over (address, balance) pairs in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &AccountBalance)> {
+        self.accounts.iter().map(|(address, balance)| (address.as_str(), balance))
+    }
+
+    /// Sum of spendable balances across all accounts
+    pub fn total_spendable(&self) -> u64 {
+        self.accounts.values().map(|acc| acc.balance).sum()
+    }
+
+    /// Sum of locked (immature coinbase / vesting) balances across all accounts
+    pub fn total_locked(&self) -> u64 {
+        self.accounts.values().map(|acc| acc.locked_balance).sum()
+    }
+}
+
+impl<'a> IntoIterator for &'a AccountState {
+    type Item = (&'a String, &'a AccountBalance);
+    type IntoIter = std::collections::hash_map::Iter<'a, String, AccountBalance>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.accounts.iter()
+    }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MINER: &str = "0x1111111111111111111111111111111111111111";
+
+    fn coinbase(amount: u64) -> Transaction {
+        Transaction::new("COINBASE".to_string(), MINER.to_string(), amount, 1_700_000_000)
+    }
+
+    #[test]
+    fn coinbase_tranches_unlock_independently() {
+        let mut state = AccountState::new();
+        state.credit_account(&coinbase(50), 10, 100); // unlocks at 110
+        state.credit_account(&coinbase(30), 20, 100); // unlocks at 120, must not delay the first
+        assert_eq!(state.get_balance(MINER), 0);
+        assert_eq!(state.get_total_balance(MINER), 80);
+
+        state.unlock_mature_coinbase(109);
+        assert_eq!(state.get_balance(MINER), 0);
+        state.unlock_mature_coinbase(110);
+        assert_eq!(state.get_balance(MINER), 50);
+        assert_eq!(state.total_locked(), 30);
+        state.unlock_mature_coinbase(120);
+        assert_eq!(state.get_balance(MINER), 80);
+        assert_eq!(state.total_locked(), 0);
+
+        // State saved before tranches (one lock, no list) keeps its lock
+        let legacy = format!(
+            r#"{{"address":"{}","balance":5,"nonce":0,"locked_balance":40,"unlock_height":130}}"#,
+            MINER
+        );
+        let mut account: AccountBalance = serde_json::from_str(&legacy).unwrap();
+        assert_eq!(account.locked_tranches, vec![LockedTranche { amount: 40, unlock_height: 130 }]);
+        account.unlock_due(130);
+        assert_eq!((account.balance, account.locked_balance, account.unlock_height), (45, 0, 0));
+    }
+
+    #[test]
+    fn signing_data_and_hash_share_one_preimage() {
+        use sha3::{Digest, Sha3_256};
+
+        let base = || {
+            Transaction::builder()
+                .sender(MINER)
+                .recipient("0x2222222222222222222222222222222222222222")
+                .amount(5_000)
+                .fee(2_000)
+                .nonce(7)
+                .timestamp(1_700_000_000)
+        };
+        let mut transfer = base().build();
+        transfer.public_key = vec![9u8; 4];
+        let mut with_extras = base().not_before_height(42).memo(b"invoice".to_vec()).build();
+        with_extras.public_key = vec![9u8; 4];
+        let call = Transaction::builder()
+            .sender(MINER)
+            .call_contract("0x3333333333333333333333333333333333333333", "transfer", vec![1, 2, 3])
+            .nonce(1)
+            .timestamp(1_700_000_000)
+            .build();
+
+        // Pinned for TX_HASH_FORMAT_VERSION 2: a layout change must bump the version
+        let vectors = [
+            (&transfer, "165c4841a026e4653b8ea1185f7856e1519ccd75bf75f5d3825801cdf1e5e23f"),
+            (&with_extras, "aab39959727deae1d8fe6703617c90e1d890245afda6c9916ad096b094a0bf38"),
+            (&call, "f257b56d447143f6c896409e27ae1660f16a2a79d28e75aa69623d45e7b44ca5"),
+        ];
+        for (tx, expected) in vectors {
+            let digest = Sha3_256::digest(tx.consensus_preimage()).to_vec();
+            assert_eq!(tx.get_signing_data(), digest);
+            assert_eq!(tx.hash(), hex::encode(&digest));
+            assert_eq!(tx.hash(), expected);
+        }
+
+        // The signature is outside the preimage; every other field is inside it
+        let mut signed = with_extras.clone();
+        signed.signature = vec![1u8; 8];
+        assert_eq!(signed.consensus_preimage(), with_extras.consensus_preimage());
+        signed.memo = None;
+        assert_ne!(signed.consensus_preimage(), with_extras.consensus_preimage());
+    }
+
+    #[test]
+    fn locktime_cannot_be_folded_into_trailing_call_data() {
+        const CONTRACT: &str = "0x3333333333333333333333333333333333333333";
+        // The old preimage appended `0xff || height` after unprefixed args/code, so these
+        // pairs shared one hash and the locktime could be stripped without the signature noticing
+        let folded = |data: &[u8]| {
+            let mut data = data.to_vec();
+            data.push(0xff);
+            data.extend_from_slice(&500u64.to_le_bytes());
+            data
+        };
+        let call = |args: Vec<u8>| {
+            Transaction::builder()
+                .sender(MINER)
+                .call_contract(CONTRACT, "transfer", args)
+                .nonce(1)
+                .timestamp(1_700_000_000)
+        };
+        let locked = call(vec![1, 2, 3]).not_before_height(500).build();
+        let stripped = call(folded(&[1, 2, 3])).build();
+        assert_ne!(locked.consensus_preimage(), stripped.consensus_preimage());
+        assert_ne!(locked.hash(), stripped.hash());
+
+        let deploy = |code: Vec<u8>| {
+            Transaction::builder().sender(MINER).deploy_contract(code).nonce(1).timestamp(1_700_000_000)
+        };
+        let locked = deploy(vec![0xaa]).not_before_height(500).build();
+        let stripped = deploy(folded(&[0xaa])).build();
+        assert_ne!(locked.hash(), stripped.hash());
+
+        // Field boundaries inside a call are committed to as well
+        let split_one_way = Transaction::builder()
+            .sender(MINER)
+            .call_contract(CONTRACT, "ab", vec![b'c'])
+            .timestamp(1_700_000_000)
+            .build();
+        let split_other_way = Transaction::builder()
+            .sender(MINER)
+            .call_contract(CONTRACT, "a", b"bc".to_vec())
+            .timestamp(1_700_000_000)
+            .build();
+        assert_ne!(split_one_way.hash(), split_other_way.hash());
+    }
+
+    #[test]
+    fn memo_cannot_move_into_trailing_call_data() {
+        const CONTRACT: &str = "0x3333333333333333333333333333333333333333";
+        let call = |args: Vec<u8>| {
+            Transaction::builder()
+                .sender(MINER)
+                .call_contract(CONTRACT, "transfer", args)
+                .nonce(1)
+                .timestamp(1_700_000_000)
+        };
+        // The old preimage appended `0xfe || len || memo` after unprefixed args, so moving
+        // that tail between args and memo gave two transactions one TxHash
+        let mut args_with_memo = vec![1, 2, 3, 0xfe];
+        args_with_memo.extend_from_slice(&7u64.to_le_bytes());
+        args_with_memo.extend_from_slice(b"invoice");
+        let with_memo = call(vec![1, 2, 3]).memo(b"invoice".to_vec()).build();
+        let memo_in_args = call(args_with_memo).build();
+        assert_ne!(with_memo.consensus_preimage(), memo_in_args.consensus_preimage());
+        assert_ne!(with_memo.hash(), memo_in_args.hash());
+
+        // An empty memo is still distinct from no memo
+        let empty_memo = call(vec![1, 2, 3]).memo(Vec::new()).build();
+        let no_memo = call(vec![1, 2, 3]).build();
+        assert_ne!(empty_memo.hash(), no_memo.hash());
+    }
+}
@@ -0,0 +1,177 @@
+use crate::core::transaction::is_valid_address;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::fmt;
+use std::ops::Deref;
+use std::str::FromStr;
+use thiserror::Error;
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ParseIdError {
+    #[error("Invalid address {0:?} (expected 0x + 40 lowercase hex chars)")]
+    Address(String),
+    #[error("Invalid hash {0:?} (expected 64 lowercase hex chars)")]
+    Hash(String),
+}
+
+/// 64 lowercase hex chars: the SHA3-256 digests used for transaction and block hashes
+fn is_valid_hash(s: &str) -> bool {
+    s.len() == 64 && s.bytes().all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// String-backed identifier: validated on parse, serialized as a plain JSON string,
+/// and usable wherever a `&str` is expected
+macro_rules! string_id {
+    ($(#[$doc:meta])* $name:ident, $is_valid:path, $error:path) => {
+        $(#[$doc])*
+        #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+        #[serde(try_from = "String", into = "String")]
+        pub struct $name(String);
+
+        impl $name {
+            /// Wrap a value produced by this node's own hashing/derivation (no validation)
+            pub(crate) fn new_unchecked(value: String) -> Self {
+                Self(value)
+            }
+
+            pub fn as_str(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = ParseIdError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                if $is_valid(s) {
+                    Ok(Self(s.to_string()))
+                } else {
+                    Err($error(s.to_string()))
+                }
+            }
+        }
+
+        impl TryFrom<String> for $name {
+            type Error = ParseIdError;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                if $is_valid(&value) {
+                    Ok(Self(value))
+                } else {
+                    Err($error(value))
+                }
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(id: $name) -> Self {
+                id.0
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        impl Deref for $name {
+            type Target = str;
+
+            fn deref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        // Hashes like the inner String, so maps keyed by the newtype can be queried by &str
+        impl Borrow<str> for $name {
+            fn borrow(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl PartialEq<str> for $name {
+            fn eq(&self, other: &str) -> bool {
+                self.0 == other
+            }
+        }
+
+        impl PartialEq<&str> for $name {
+            fn eq(&self, other: &&str) -> bool {
+                self.0 == *other
+            }
+        }
+
+        impl PartialEq<String> for $name {
+            fn eq(&self, other: &String) -> bool {
+                &self.0 == other
+            }
+        }
+
+        impl PartialEq<$name> for String {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+
+        impl PartialEq<$name> for &str {
+            fn eq(&self, other: &$name) -> bool {
+                *self == other.0
+            }
+        }
+    };
+}
+
+string_id!(
+    /// Account address: 0x + 40 lowercase hex chars (see FalconKeypair::get_address)
+    Address,
+    is_valid_address,
+    ParseIdError::Address
+);
+
+string_id!(
+    /// Transaction hash (Transaction::hash), lowercase hex SHA3-256
+    TxHash,
+    is_valid_hash,
+    ParseIdError::Hash
+);
+
+string_id!(
+    /// Block hash (Block::calculate_hash), lowercase hex double SHA3-256
+    BlockHash,
+    is_valid_hash,
+    ParseIdError::Hash
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_validates_and_round_trips_as_strings() {
+        let address = "0x1111111111111111111111111111111111111111";
+        let parsed: Address = address.parse().unwrap();
+        assert_eq!(parsed.to_string(), address);
+        assert_eq!(serde_json::to_string(&parsed).unwrap(), format!("\"{}\"", address));
+        assert_eq!(serde_json::from_str::<Address>(&format!("\"{}\"", address)).unwrap(), parsed);
+
+        let hash = "ab".repeat(32);
+        let tx_hash: TxHash = hash.parse().unwrap();
+        assert_eq!(tx_hash, hash);
+        assert_eq!(bincode::deserialize::<TxHash>(&bincode::serialize(&tx_hash).unwrap()).unwrap(), tx_hash);
+        assert_eq!(bincode::serialize(&tx_hash).unwrap(), bincode::serialize(&hash).unwrap());
+
+        // Wrong shape for the type is rejected, including a hash where an address belongs
+        assert!(matches!(hash.parse::<Address>(), Err(ParseIdError::Address(_))));
+        assert!(matches!(address.parse::<BlockHash>(), Err(ParseIdError::Hash(_))));
+        assert!("0x1111111111111111111111111111111111111AAA".parse::<Address>().is_err());
+        assert!("AB".repeat(32).parse::<TxHash>().is_err());
+        assert!(serde_json::from_str::<TxHash>("\"not-a-hash\"").is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use thiserror::Error;
+
+/// CONSENSUS CONSTANT: 1 QUA = 1_000_000 microunits
+pub const MICROUNITS_PER_QUA: u64 = 1_000_000;
+/// Decimal places of one microunit in QUA
+pub const QUA_DECIMALS: usize = 6;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum UnitsError {
+    #[error("Invalid QUA amount: {0:?}")]
+    Invalid(String),
+    #[error("QUA amount has more than {} decimal places: {0:?}", QUA_DECIMALS)]
+    TooPrecise(String),
+    #[error("QUA amount too large: {0:?}")]
+    Overflow(String),
+}
+
+/// Parse a decimal QUA amount ("1.234567") into microunits without going through f64
+pub fn parse_qua(s: &str) -> Result<u64, UnitsError> {
+    let s = s.trim();
+    let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+    let is_digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    if whole.is_empty() || !is_digits(whole) || !is_digits(frac) || s.ends_with('.') {
+        return Err(UnitsError::Invalid(s.to_string()));
+    }
+    if frac.len() > QUA_DECIMALS {
+        return Err(UnitsError::TooPrecise(s.to_string()));
+    }
+
+    let overflow = || UnitsError::Overflow(s.to_string());
+    let whole: u64 = whole.parse().map_err(|_| overflow())?;
+    let frac: u64 = if frac.is_empty() {
+        0
+    } else {
+        format!("{:0<width$}", frac, width = QUA_DECIMALS).parse().map_err(|_| overflow())?
+    };
+    whole
+        .checked_mul(MICROUNITS_PER_QUA)
+        .and_then(|units| units.checked_add(frac))
+        .ok_or_else(overflow)
+}
+
+/// Exact decimal QUA string for a microunit amount, always with 6 decimals ("1.234567")
+pub fn format_qua(microunits: u64) -> String {
+    format!(
+        "{}.{:0width$}",
+        microunits / MICROUNITS_PER_QUA,
+        microunits % MICROUNITS_PER_QUA,
+        width = QUA_DECIMALS
+    )
+}
+
+/// Convert microunits to QUA as f64 (display only; never feed the result back into amounts)
+pub fn microunits_to_qua(microunits: u64) -> f64 {
+    microunits as f64 / MICROUNITS_PER_QUA as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_and_format_round_trip_exactly() {
+        assert_eq!(parse_qua("1.234567"), Ok(1_234_567));
+        assert_eq!(parse_qua("0.1"), Ok(100_000));
+        assert_eq!(parse_qua("25"), Ok(25_000_000));
+        assert_eq!(parse_qua(" 0.000001 "), Ok(1));
+        assert_eq!(format_qua(1_234_567), "1.234567");
+        assert_eq!(format_qua(1), "0.000001");
+
+        // Every representable amount survives format -> parse
+        for units in [0, 1, 100_000, 300_000, 1_234_567, 999_999_999, u64::MAX] {
+            assert_eq!(parse_qua(&format_qua(units)), Ok(units));
+        }
+        assert_eq!((8.2 * MICROUNITS_PER_QUA as f64) as u64, 8_199_999); // why amounts must not go through f64
+        assert_eq!(parse_qua("8.2"), Ok(8_200_000));
+    }
+
+    #[test]
+    fn rejects_malformed_and_over_precise_amounts() {
+        assert!(matches!(parse_qua("1.2345678"), Err(UnitsError::TooPrecise(_))));
+        assert!(matches!(parse_qua("18446744073709.551616"), Err(UnitsError::Overflow(_))));
+        for bad in ["", ".5", "1.", "-1", "+1", "1e3", "1.2.3", "abc", "1,5"] {
+            assert!(matches!(parse_qua(bad), Err(UnitsError::Invalid(_))), "{:?}", bad);
+        }
+    }
+}
@@ -0,0 +1,86 @@
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Sha3_256};
+use std::fmt;
+use std::str::FromStr;
+
+/// Hash functions the node can compute. SHA3-256 is the only one today and every
+/// consensus hash (transactions, blocks, merkle roots, addresses) uses it; the enum
+/// exists so a successor can be added behind a new id without touching call sites
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum HashAlgorithm {
+    #[default]
+    #[serde(rename = "sha3-256")]
+    Sha3_256,
+}
+
+impl HashAlgorithm {
+    /// Algorithm behind all current consensus hashes
+    pub const CONSENSUS: Self = Self::Sha3_256;
+
+    /// Stable one-byte id recorded wherever the algorithm needs to be identified
+    pub fn id(self) -> u8 {
+        match self {
+            Self::Sha3_256 => 1,
+        }
+    }
+
+    pub fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(Self::Sha3_256),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sha3_256 => "sha3-256",
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "sha3-256" => Ok(Self::Sha3_256),
+            other => Err(format!("Unknown hash algorithm: {}", other)),
+        }
+    }
+}
+
+/// Hash `data` with `algorithm`. Every supported algorithm has a 256-bit output,
+/// matching the 32-byte hashes stored in blocks and transactions
+pub fn hash_with(algorithm: HashAlgorithm, data: &[u8]) -> [u8; 32] {
+    match algorithm {
+        HashAlgorithm::Sha3_256 => Sha3_256::digest(data).into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sha3_path_matches_direct_calls() {
+        let data = b"quanta block preimage";
+        assert_eq!(hash_with(HashAlgorithm::Sha3_256, data).as_slice(), Sha3_256::digest(data).as_slice());
+        assert_eq!(hash_with(HashAlgorithm::CONSENSUS, data), crate::crypto::sha3_hash(data));
+
+        // Block hashes are unchanged: double SHA3-256, hex encoded
+        let twice = Sha3_256::digest(Sha3_256::digest(data));
+        assert_eq!(crate::crypto::double_sha3(data), hex::encode(twice));
+
+        let algorithm = HashAlgorithm::from_id(HashAlgorithm::CONSENSUS.id()).unwrap();
+        assert_eq!(algorithm, HashAlgorithm::Sha3_256);
+        assert_eq!(algorithm.name().parse::<HashAlgorithm>().unwrap(), algorithm);
+        assert_eq!(serde_json::to_string(&algorithm).unwrap(), "\"sha3-256\"");
+        assert_eq!(HashAlgorithm::from_id(0), None);
+    }
+}
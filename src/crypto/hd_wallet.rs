@@ -1,9 +1,10 @@
 use bip39::{Mnemonic, Language};
-use sha3::{Sha3_256, Digest};
+use sha3::Sha3_256;
 use hmac::{Hmac, Mac};
 use serde::{Deserialize, Serialize};
 use rand::RngCore;
-use zeroize::Zeroize;
+use zeroize::{Zeroize, Zeroizing};
+use crate::crypto::FalconKeypair;
 
 type HmacSha256 = Hmac<Sha3_256>;
 
@@ -83,32 +84,22 @@ impl HDWallet {
         mac.finalize().into_bytes().to_vec()
     }
 
-    /// Derive address from account key
-    fn derive_address(account_key: &[u8]) -> String {
-        let mut hasher = Sha3_256::new();
-        hasher.update(account_key);
-        let hash = hasher.finalize();
-        hex::encode(&hash[..20]) // Use first 20 bytes like Ethereum
+    /// Falcon keypair for an account, regenerated from its child key on each use
+    /// (secret keys are never kept in the wallet)
+    fn account_keypair(&self, index: u32) -> FalconKeypair {
+        let account_key = Zeroizing::new(self.derive_account_key(index));
+        FalconKeypair::from_seed(&account_key).expect("32-byte account key is a valid Falcon seed")
     }
 
     /// Generate a new account/address
     pub fn generate_account(&mut self, label: Option<String>) -> HDAccount {
         let index = self.accounts.len() as u32;
-        let account_key = self.derive_account_key(index);
-        let address = Self::derive_address(&account_key);
-        
-        // TODO: SECURITY - Generate real Falcon-512 keypair per account
-        // This is a PLACEHOLDER. In production:
-        // 1. Use account_key as seed for Falcon key generation
-        // 2. Generate actual Falcon private + public key pair
-        // 3. Store private key encrypted, public key here
-        // 4. Sign with actual Falcon key, not this derived stub
-        let public_key = account_key[..32].to_vec();
-        
+        let keypair = self.account_keypair(index);
+
         let account = HDAccount {
             index,
-            address,
-            public_key,
+            address: keypair.get_address().to_string(),
+            public_key: keypair.public_key.clone(),
             label,
         };
         
@@ -121,6 +112,14 @@ impl HDWallet {
         self.accounts.iter().find(|a| a.index == index)
     }
 
+    /// Sign `data` with the Falcon key of generated account `index`
+    pub fn sign_with_account(&self, index: u32, data: &[u8]) -> Result<Vec<u8>, String> {
+        if self.get_account(index).is_none() {
+            return Err(format!("No account #{} in this wallet", index));
+        }
+        Ok(self.account_keypair(index).sign(data))
+    }
+
     /// Get all accounts
     pub fn get_accounts(&self) -> &[HDAccount] {
         &self.accounts
@@ -137,6 +136,24 @@ impl HDWallet {
         wallet
     }
 
+    /// Re-derive every stored account from the master key and check it still has the
+    /// address it was saved with; the stored public keys are replaced by derived ones
+    fn rederive_accounts(&mut self) -> Result<(), String> {
+        for i in 0..self.accounts.len() {
+            let keypair = self.account_keypair(self.accounts[i].index);
+            let account = &mut self.accounts[i];
+            let address = keypair.get_address().to_string();
+            if address != account.address {
+                return Err(format!(
+                    "Account #{} derives to {} but was saved as {} (wrong passphrase or incompatible key derivation)",
+                    account.index, address, account.address
+                ));
+            }
+            account.public_key = keypair.public_key.clone();
+        }
+        Ok(())
+    }
+
     /// Export wallet data (encrypted with proper format)
     /// Format: [salt_len:4][salt][nonce:12][ciphertext]
     pub fn export_encrypted(&self, password: &str) -> Result<Vec<u8>, String> {
@@ -180,8 +197,9 @@ impl HDWallet {
         Ok(result)
     }
     
-    /// Import wallet from encrypted data
-    pub fn import_encrypted(encrypted_data: &[u8], password: &str) -> Result<Self, String> {
+    /// Import wallet from encrypted data; `passphrase` is the one the wallet was created
+    /// with (it is not stored), and every account is re-derived and checked against it
+    pub fn import_encrypted(encrypted_data: &[u8], password: &str, passphrase: &str) -> Result<Self, String> {
         use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
         use chacha20poly1305::aead::Aead;
         use argon2::{Argon2, PasswordHasher};
@@ -237,8 +255,10 @@ impl HDWallet {
         // Regenerate seed and master_key (they were skipped in serialization)
         let mnemonic = Mnemonic::parse_in_normalized(Language::English, &wallet.mnemonic)
             .map_err(|e| format!("Invalid mnemonic: {}", e))?;
+        wallet.passphrase = passphrase.to_string();
         wallet.seed = mnemonic.to_seed(&wallet.passphrase).to_vec();
         wallet.master_key = Self::derive_master_key(&wallet.seed);
+        wallet.rederive_accounts()?;
         
         Ok(wallet)
     }
@@ -279,3 +299,61 @@ impl Default for HDWallet {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::{sha3_hash, verify_signature};
+    use crate::crypto::signatures::address_from_public_key;
+
+    #[test]
+    fn restored_accounts_match_and_sign_with_real_falcon_keys() {
+        let mut wallet = HDWallet::new();
+        let first = wallet.generate_account(None);
+        let second = wallet.generate_account(None);
+        assert_ne!(first.address, second.address);
+
+        // Same mnemonic, same accounts
+        let restored = HDWallet::restore(wallet.mnemonic.clone(), "", 2);
+        for (original, again) in wallet.get_accounts().iter().zip(restored.get_accounts()) {
+            assert_eq!(original.address, again.address);
+            assert_eq!(original.public_key, again.public_key);
+        }
+
+        // The stored key is a Falcon public key whose address is the account address
+        assert_eq!(first.public_key.len(), 897);
+        assert_eq!(address_from_public_key(&first.public_key), first.address);
+        let message = sha3_hash(b"hd account payment");
+        let signature = restored.sign_with_account(first.index, &message).unwrap();
+        assert!(verify_signature(&message, &signature, &first.public_key));
+        assert!(!verify_signature(&message, &signature, &second.public_key));
+        assert!(restored.sign_with_account(2, &message).is_err());
+
+        // A passphrase derives a different wallet
+        let other = HDWallet::restore(wallet.mnemonic.clone(), "hunter2", 1);
+        assert_ne!(other.get_accounts()[0].address, first.address);
+    }
+
+    #[test]
+    fn loaded_wallet_rederives_its_accounts() {
+        // Known answer for the standard all-"abandon" test mnemonic
+        let mnemonic = format!("{} art", ["abandon"; 23].join(" "));
+        let wallet = HDWallet::restore(mnemonic.clone(), "", 2);
+        assert_eq!(wallet.get_accounts()[0].address, "0xf4f1d6f03760ff5721939e485e972e69de305896");
+
+        // Loading re-derives the keys rather than trusting the stored ones
+        let mut tampered = wallet.clone();
+        tampered.accounts[1].public_key = vec![0u8; 897];
+        let loaded = HDWallet::import_encrypted(&tampered.export_encrypted("pw").unwrap(), "pw", "").unwrap();
+        for (original, again) in wallet.get_accounts().iter().zip(loaded.get_accounts()) {
+            assert_eq!(original.public_key, again.public_key);
+        }
+        let message = sha3_hash(b"after load");
+        let signature = loaded.sign_with_account(1, &message).unwrap();
+        assert!(verify_signature(&message, &signature, &wallet.get_accounts()[1].public_key));
+
+        // Accounts that no longer derive to their saved address refuse to load
+        let saved = HDWallet::restore(mnemonic, "hunter2", 1).export_encrypted("pw").unwrap();
+        assert!(HDWallet::import_encrypted(&saved, "pw", "").is_err());
+        assert!(HDWallet::import_encrypted(&saved, "pw", "hunter2").is_ok());
+    }
+}
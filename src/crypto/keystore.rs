@@ -0,0 +1,102 @@
+use crate::crypto::password;
+use crate::crypto::signatures::{sign_with_secret_key, FalconKeypair};
+use crate::crypto::wallet::{QuantumWallet, WalletError};
+use zeroize::Zeroize;
+
+/// In-memory hot key for the running node (validator/staking messages)
+///
+/// SECURITY:
+/// - Decrypted once at startup from a quantum-safe wallet file
+/// - Never written back to disk; only the encrypted wallet exists on disk
+/// - Secret bytes are zeroized in place on drop, and the copy made for each signature is wiped
+pub struct NodeKeyStore {
+    address: String,
+    public_key: Vec<u8>,
+    secret_key: Box<[u8]>,
+}
+
+impl NodeKeyStore {
+    /// Load and decrypt the key from an encrypted wallet file
+    pub fn load(wallet_file: &str, password: &str) -> Result<Self, WalletError> {
+        let wallet = QuantumWallet::load_quantum_safe(wallet_file, password)?;
+        Ok(Self::from_keypair(&wallet.keypair))
+        // `wallet` drops here, zeroizing its copy of the secret
+    }
+
+    /// Load using `--password-file` or QUANTA_WALLET_PASSWORD if given, otherwise prompt on the terminal
+    pub fn load_interactive(wallet_file: &str, password_file: Option<&str>) -> Result<Self, WalletError> {
+        let prompt = format!("Enter password for node key wallet {}:", wallet_file);
+        let password = password::read_password(password_file, &prompt)?;
+        Self::load(wallet_file, &password)
+    }
+
+    /// Copy a keypair into the store
+    pub fn from_keypair(keypair: &FalconKeypair) -> Self {
+        Self {
+            address: keypair.get_address().into(),
+            public_key: keypair.public_key.clone(),
+            secret_key: keypair.secret_key_bytes().to_vec().into_boxed_slice(),
+        }
+    }
+
+    /// Address derived from the stored public key
+    pub fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Falcon public key bytes
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Sign a validator message with the hot key
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>, WalletError> {
+        sign_with_secret_key(&self.secret_key, message).map_err(|_| WalletError::Encryption)
+    }
+
+    /// Overwrite the secret bytes with zeros (also run on drop)
+    fn wipe(&mut self) {
+        self.secret_key.zeroize();
+    }
+}
+
+impl Drop for NodeKeyStore {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
+impl std::fmt::Debug for NodeKeyStore {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NodeKeyStore")
+            .field("address", &self.address)
+            .field("secret_key", &"<redacted>")
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::verify_signature;
+
+    #[test]
+    fn signs_with_loaded_key() {
+        let keypair = FalconKeypair::generate();
+        let store = NodeKeyStore::from_keypair(&keypair);
+
+        assert_eq!(store.address(), keypair.get_address());
+        let signature = store.sign(b"validator vote").unwrap();
+        assert!(verify_signature(b"validator vote", &signature, store.public_key()));
+    }
+
+    #[test]
+    fn drop_zeroizes_secret_key() {
+        let mut store = NodeKeyStore::from_keypair(&FalconKeypair::generate());
+        assert!(store.secret_key.iter().any(|b| *b != 0));
+
+        // Same path Drop takes; inspect the buffer before it is freed
+        store.wipe();
+        assert!(store.secret_key.iter().all(|b| *b == 0));
+    }
+}
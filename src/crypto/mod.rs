@@ -1,8 +1,12 @@
+pub mod hash;
 pub mod signatures;
 pub mod wallet;
 pub mod hd_wallet;
 pub mod multisig;
+pub mod keystore;
+pub mod password;
 
+pub use hash::{hash_with, HashAlgorithm};
 pub use signatures::{FalconKeypair, verify_signature, sha3_hash, double_sha3};
 pub use wallet::QuantumWallet;
 pub use hd_wallet::HDWallet;
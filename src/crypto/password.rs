@@ -0,0 +1,98 @@
+use std::io;
+use zeroize::Zeroizing;
+
+/// Environment variable checked before prompting for the wallet password
+pub const PASSWORD_ENV: &str = "QUANTA_WALLET_PASSWORD";
+
+/// Environment variable holding the database passphrase (node.encrypt_db)
+pub const DB_PASSPHRASE_ENV: &str = "QUANTA_DB_PASSPHRASE";
+
+/// Read a wallet password from a file (one trailing newline is not part of the password)
+pub fn read_password_file(path: &str) -> io::Result<Zeroizing<String>> {
+    let mut password = Zeroizing::new(std::fs::read_to_string(path)?);
+    let len = password.trim_end_matches(['\r', '\n']).len();
+    password.truncate(len);
+    if password.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, format!("password file {} is empty", path)));
+    }
+    Ok(password)
+}
+
+/// Password from `--password-file`, else QUANTA_WALLET_PASSWORD, else None
+pub fn non_interactive_password(password_file: Option<&str>) -> io::Result<Option<Zeroizing<String>>> {
+    if let Some(path) = password_file {
+        return read_password_file(path).map(Some);
+    }
+    match std::env::var(PASSWORD_ENV) {
+        Ok(password) => {
+            eprintln!(
+                "WARNING: using wallet password from {} (the environment of a process can be read by other local users); prefer --password-file",
+                PASSWORD_ENV
+            );
+            Ok(Some(Zeroizing::new(password)))
+        }
+        Err(_) => Ok(None),
+    }
+}
+
+/// Password to unlock an existing wallet; prompts on the terminal as a fallback
+pub fn read_password(password_file: Option<&str>, prompt: &str) -> io::Result<Zeroizing<String>> {
+    if let Some(password) = non_interactive_password(password_file)? {
+        return Ok(password);
+    }
+    println!("{}", prompt);
+    Ok(Zeroizing::new(rpassword::read_password()?))
+}
+
+/// Database passphrase from QUANTA_DB_PASSPHRASE, else prompted on the terminal
+pub fn read_db_passphrase() -> io::Result<Zeroizing<String>> {
+    if let Ok(passphrase) = std::env::var(DB_PASSPHRASE_ENV) {
+        return Ok(Zeroizing::new(passphrase));
+    }
+    println!("Enter database passphrase:");
+    Ok(Zeroizing::new(rpassword::read_password()?))
+}
+
+/// Password to encrypt a new wallet; interactive entry must be typed twice
+pub fn read_new_password(password_file: Option<&str>) -> io::Result<Zeroizing<String>> {
+    if let Some(password) = non_interactive_password(password_file)? {
+        return Ok(password);
+    }
+    println!("\nEnter password to encrypt wallet:");
+    let password = Zeroizing::new(rpassword::read_password()?);
+    println!("Confirm password:");
+    let confirm = Zeroizing::new(rpassword::read_password()?);
+    if password != confirm {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "Passwords don't match!"));
+    }
+    Ok(password)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::QuantumWallet;
+
+    #[test]
+    fn password_file_unlocks_wallet() {
+        let dir = tempfile::tempdir().unwrap();
+        let wallet_path = dir.path().join("wallet.qua");
+        let wallet_path = wallet_path.to_str().unwrap();
+        let password_path = dir.path().join("password");
+        let password_path = password_path.to_str().unwrap();
+
+        let wallet = QuantumWallet::new();
+        wallet.save_quantum_safe(wallet_path, "correct horse battery").unwrap();
+        std::fs::write(password_path, "correct horse battery\n").unwrap();
+
+        // The file wins over the environment and never reaches the prompt
+        let password = read_password(Some(password_path), "unused prompt").unwrap();
+        assert_eq!(password.as_str(), "correct horse battery");
+        let loaded = QuantumWallet::load_quantum_safe(wallet_path, &password).unwrap();
+        assert_eq!(loaded.address, wallet.address);
+
+        std::fs::write(password_path, "\n").unwrap();
+        assert!(read_password_file(password_path).is_err());
+        assert!(read_password_file(&format!("{}.missing", password_path)).is_err());
+    }
+}
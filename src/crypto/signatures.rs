@@ -3,6 +3,8 @@ use pqcrypto_traits::sign::{PublicKey, SecretKey, SignedMessage};
 use sha3::{Digest, Sha3_256};
 use serde::{Serialize, Deserialize};
 use zeroize::Zeroize;
+use crate::core::types::Address;
+use crate::crypto::hash::{hash_with, HashAlgorithm};
 
 /// Secure secret key wrapper - zeroizes on drop
 #[derive(Zeroize)]
@@ -49,6 +51,11 @@ impl FalconKeypair {
         self.secret_key.len()
     }
 
+    /// Raw secret key bytes (for moving into a zeroizing key store)
+    pub(crate) fn secret_key_bytes(&self) -> &[u8] {
+        &self.secret_key
+    }
+
     /// Generate a new Falcon-512 keypair
     pub fn generate() -> Self {
         let (pk, sk) = keypair();
@@ -58,14 +65,21 @@ impl FalconKeypair {
         }
     }
 
+    /// Deterministic Falcon-512 keypair from `seed` (at least 32 bytes of secret material):
+    /// the same seed always gives the same keys, so HD wallets can re-derive accounts
+    pub fn from_seed(seed: &[u8]) -> Result<Self, String> {
+        if seed.len() < MIN_KEYGEN_SEED_LEN {
+            return Err(format!("Seed is {} bytes (min {})", seed.len(), MIN_KEYGEN_SEED_LEN));
+        }
+        let (public_key, secret_key) = seeded_keygen::keypair(seed)?;
+        Ok(Self { public_key, secret_key })
+    }
+
     /// Sign a message with Falcon private key
     /// SECURITY: Message is typically a HASH, not raw data
     /// For transactions, use sign_hash() instead
     pub fn sign(&self, message: &[u8]) -> Vec<u8> {
-        let sk = SecretKey::from_bytes(&self.secret_key)
-            .expect("Invalid secret key");
-        let signed = sign(message, &sk);
-        signed.as_bytes().to_vec()
+        sign_with_secret_key(&self.secret_key, message).expect("Invalid secret key")
     }
     
     /// Sign a hash (PREFERRED for transactions)
@@ -84,11 +98,23 @@ impl FalconKeypair {
     /// Derive quantum-resistant address from public key
     /// Uses first 20 bytes of SHA3-256 hash (Ethereum-style)
     /// Format: 0x + 40 hex chars = 42 chars total
-    pub fn get_address(&self) -> String {
-        let mut hasher = Sha3_256::new();
-        hasher.update(&self.public_key);
-        let hash = hasher.finalize();
-        format!("0x{}", hex::encode(&hash[..20]))
+    pub fn get_address(&self) -> Address {
+        Address::new_unchecked(address_from_public_key(&self.public_key))
+    }
+
+    /// Public key as lowercase hex
+    pub fn public_key_hex(&self) -> String {
+        self.public().to_hex()
+    }
+
+    /// Secret key as lowercase hex, UNENCRYPTED (only for `keygen` output; wiped on drop)
+    pub fn secret_key_hex(&self) -> zeroize::Zeroizing<String> {
+        zeroize::Zeroizing::new(hex::encode(&self.secret_key))
+    }
+
+    /// Public half of this keypair
+    pub fn public(&self) -> FalconPublicKey {
+        FalconPublicKey(self.public_key.clone())
     }
     
     /// Get address without 0x prefix (for backwards compatibility)
@@ -101,6 +127,168 @@ impl FalconKeypair {
     }
 }
 
+const MIN_KEYGEN_SEED_LEN: usize = 32;
+
+/// Sign `message` with raw Falcon secret key bytes. pqcrypto's SecretKey is a plain
+/// `Copy` array with no zeroize-on-drop, so the copy is overwritten once signing is done
+pub(crate) fn sign_with_secret_key(secret_key: &[u8], message: &[u8]) -> Result<Vec<u8>, String> {
+    type FalconSecretKey = pqcrypto_falcon::falcon512::SecretKey;
+    let mut sk = <FalconSecretKey as SecretKey>::from_bytes(secret_key)
+        .map_err(|_| "Invalid Falcon secret key".to_string())?;
+    let signed = sign(message, &sk);
+    sk = <FalconSecretKey as SecretKey>::from_bytes(&[0u8; secret_key_bytes()]).expect("secret key length");
+    std::hint::black_box(&mut sk);
+    Ok(signed.as_bytes().to_vec())
+}
+
+/// Falcon-512 key generation driven by a caller-supplied seed
+///
+/// pqcrypto only exposes keypair(), which seeds from the OS RNG. This is the same
+/// routine (PQClean's crypto_sign_keypair, linked in through pqcrypto-falcon) with
+/// the 48 random bytes replaced by `seed`, producing keys in the standard encoding.
+mod seeded_keygen {
+    use zeroize::Zeroize;
+
+    const LOGN: u32 = 9;
+    const N: usize = 1 << LOGN;
+    const KEYGEN_TEMP_BYTES: usize = 14336; // FALCON_KEYGEN_TEMP_9
+    const PUBLIC_KEY_BYTES: usize = 897;
+    const SECRET_KEY_BYTES: usize = 1281;
+
+    #[repr(C)]
+    struct Shake256Context {
+        ctx: [u64; 26],
+    }
+
+    extern "C" {
+        fn shake256_inc_init(state: *mut Shake256Context);
+        fn shake256_inc_absorb(state: *mut Shake256Context, input: *const u8, inlen: usize);
+        fn shake256_inc_finalize(state: *mut Shake256Context);
+        fn shake256_inc_ctx_release(state: *mut Shake256Context);
+        fn PQCLEAN_FALCON512_CLEAN_keygen(
+            rng: *mut Shake256Context,
+            f: *mut i8,
+            g: *mut i8,
+            big_f: *mut i8,
+            big_g: *mut i8,
+            h: *mut u16,
+            logn: u32,
+            tmp: *mut u8,
+        );
+        fn PQCLEAN_FALCON512_CLEAN_trim_i8_encode(out: *mut u8, max_out_len: usize, x: *const i8, logn: u32, bits: u32) -> usize;
+        fn PQCLEAN_FALCON512_CLEAN_modq_encode(out: *mut u8, max_out_len: usize, x: *const u16, logn: u32) -> usize;
+        static PQCLEAN_FALCON512_CLEAN_max_fg_bits: [u8; 11];
+        static PQCLEAN_FALCON512_CLEAN_max_FG_bits: [u8; 11];
+    }
+
+    /// (public key, secret key) bytes, as pqcrypto's PublicKey/SecretKey::as_bytes
+    pub fn keypair(seed: &[u8]) -> Result<(Vec<u8>, Vec<u8>), String> {
+        let (mut f, mut g, mut big_f) = ([0i8; N], [0i8; N], [0i8; N]);
+        let mut h = [0u16; N];
+        let mut tmp = vec![0u64; KEYGEN_TEMP_BYTES / 8]; // 8-byte aligned, as keygen requires
+        let mut secret_key = vec![0u8; SECRET_KEY_BYTES];
+        let mut public_key = vec![0u8; PUBLIC_KEY_BYTES];
+
+        // SAFETY: every buffer has the size PQClean expects for logn = 9, and the
+        // SHAKE context is initialised before use and released after
+        let encoded = unsafe {
+            let mut rng = Shake256Context { ctx: [0; 26] };
+            shake256_inc_init(&mut rng);
+            shake256_inc_absorb(&mut rng, seed.as_ptr(), seed.len());
+            shake256_inc_finalize(&mut rng);
+            PQCLEAN_FALCON512_CLEAN_keygen(
+                &mut rng,
+                f.as_mut_ptr(),
+                g.as_mut_ptr(),
+                big_f.as_mut_ptr(),
+                std::ptr::null_mut(),
+                h.as_mut_ptr(),
+                LOGN,
+                tmp.as_mut_ptr().cast(),
+            );
+            shake256_inc_ctx_release(&mut rng);
+
+            let fg_bits = PQCLEAN_FALCON512_CLEAN_max_fg_bits[LOGN as usize] as u32;
+            let big_fg_bits = PQCLEAN_FALCON512_CLEAN_max_FG_bits[LOGN as usize] as u32;
+            secret_key[0] = 0x50 + LOGN as u8;
+            let mut used = 1;
+            for (poly, bits) in [(&f, fg_bits), (&g, fg_bits), (&big_f, big_fg_bits)] {
+                let written = PQCLEAN_FALCON512_CLEAN_trim_i8_encode(
+                    secret_key[used..].as_mut_ptr(),
+                    SECRET_KEY_BYTES - used,
+                    poly.as_ptr(),
+                    LOGN,
+                    bits,
+                );
+                used += written;
+                if written == 0 {
+                    break;
+                }
+            }
+
+            public_key[0] = LOGN as u8;
+            let written = PQCLEAN_FALCON512_CLEAN_modq_encode(
+                public_key[1..].as_mut_ptr(),
+                PUBLIC_KEY_BYTES - 1,
+                h.as_ptr(),
+                LOGN,
+            );
+            used == SECRET_KEY_BYTES && written == PUBLIC_KEY_BYTES - 1
+        };
+
+        f.zeroize();
+        g.zeroize();
+        big_f.zeroize();
+        tmp.zeroize();
+        if !encoded {
+            secret_key.zeroize();
+            return Err("Falcon key encoding failed".to_string());
+        }
+        Ok((public_key, secret_key))
+    }
+}
+
+/// Falcon-512 public key with the canonical hex encoding and address derivation
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FalconPublicKey(Vec<u8>);
+
+#[allow(dead_code)]
+impl FalconPublicKey {
+    /// Wrap raw bytes (rejects anything that is not a Falcon-512 public key)
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let _: pqcrypto_falcon::falcon512::PublicKey = PublicKey::from_bytes(bytes)
+            .map_err(|e| format!("Invalid Falcon public key: {}", e))?;
+        Ok(Self(bytes.to_vec()))
+    }
+
+    /// Parse from hex (an optional 0x prefix is accepted)
+    pub fn from_hex(s: &str) -> Result<Self, String> {
+        let s = s.strip_prefix("0x").unwrap_or(s);
+        let bytes = hex::decode(s).map_err(|e| format!("Invalid public key hex: {}", e))?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Lowercase hex, no prefix
+    pub fn to_hex(&self) -> String {
+        hex::encode(&self.0)
+    }
+
+    /// Address derived from this key (see address_from_public_key)
+    pub fn address(&self) -> String {
+        address_from_public_key(&self.0)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+/// Single code path for address derivation: 0x + hex(SHA3-256(pubkey)[..20])
+pub fn address_from_public_key(public_key: &[u8]) -> String {
+    let hash = sha3_hash(public_key);
+    format!("0x{}", hex::encode(&hash[..20]))
+}
+
 /// Verify a Falcon signature
 /// 
 /// NOTE: For blockchain transactions, 'message' should be the HASH of the transaction,
@@ -131,12 +319,7 @@ pub fn verify_hash(hash: &[u8; 32], signature: &[u8], public_key: &[u8]) -> bool
 /// Calculate SHA3-256 hash (quantum-resistant)
 /// Returns exactly 32 bytes for type safety
 pub fn sha3_hash(data: &[u8]) -> [u8; 32] {
-    let mut hasher = Sha3_256::new();
-    hasher.update(data);
-    let result = hasher.finalize();
-    let mut hash = [0u8; 32];
-    hash.copy_from_slice(&result);
-    hash
+    hash_with(HashAlgorithm::Sha3_256, data)
 }
 
 /// Calculate double SHA3-256 hash for block hashing
@@ -146,4 +329,75 @@ pub fn double_sha3(data: &[u8]) -> String {
     hex::encode(&hash2)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_key_hex_round_trip() {
+        let keypair = FalconKeypair::generate();
+        let hex_key = keypair.public_key_hex();
+
+        let parsed = FalconPublicKey::from_hex(&hex_key).unwrap();
+        assert_eq!(parsed, keypair.public());
+        assert_eq!(parsed.to_hex(), hex_key);
+        assert_eq!(FalconPublicKey::from_hex(&format!("0x{}", hex_key)).unwrap(), parsed);
+        assert_eq!(parsed.address(), keypair.get_address());
 
+        assert!(FalconPublicKey::from_hex("zz").is_err());
+        assert!(FalconPublicKey::from_hex("abcd").is_err()); // wrong length
+    }
+
+    #[test]
+    fn exported_hex_keys_sign_and_verify() {
+        let keypair = FalconKeypair::generate();
+        let public_key = hex::decode(keypair.public_key_hex()).unwrap();
+        let secret_key = hex::decode(keypair.secret_key_hex().as_str()).unwrap();
+        assert_eq!(address_from_public_key(&public_key), keypair.get_address());
+
+        // A signature made from the exported secret verifies under the exported public key
+        let message = sha3_hash(b"keygen");
+        let sk = SecretKey::from_bytes(&secret_key).unwrap();
+        let signed = sign(&message, &sk).as_bytes().to_vec();
+        assert!(verify_signature(&message, &signed, &public_key));
+        assert!(verify_signature(&message, &keypair.sign(&message), &public_key));
+        assert!(!verify_signature(&sha3_hash(b"other"), &signed, &public_key));
+    }
+
+    #[test]
+    fn seeded_keypair_is_deterministic_and_signs() {
+        let seed = [7u8; 32];
+        let keypair = FalconKeypair::from_seed(&seed).unwrap();
+        let again = FalconKeypair::from_seed(&seed).unwrap();
+        assert_eq!(keypair.public_key, again.public_key);
+        assert_eq!(keypair.secret_key, again.secret_key);
+        assert_ne!(FalconKeypair::from_seed(&[8u8; 32]).unwrap().public_key, keypair.public_key);
+        assert!(FalconKeypair::from_seed(&[7u8; 16]).is_err());
+
+        // Same encoding as pqcrypto's own keys
+        assert!(<pqcrypto_falcon::falcon512::PublicKey as PublicKey>::from_bytes(&keypair.public_key).is_ok());
+        assert!(<pqcrypto_falcon::falcon512::SecretKey as SecretKey>::from_bytes(&keypair.secret_key).is_ok());
+        let message = sha3_hash(b"seeded");
+        assert!(verify_signature(&message, &keypair.sign(&message), &keypair.public_key));
+
+        // Known answer: keys from a fixed seed must never change, or every HD wallet's
+        // addresses would move (pqcrypto-falcon and pqcrypto-internals are pinned for this)
+        assert_eq!(
+            hex::encode(sha3_hash(&keypair.public_key)),
+            "9f7be28c9db8e386c77aca1e2c588b03aad40eb03a43ba21850c81f624d81790"
+        );
+        assert_eq!(
+            hex::encode(sha3_hash(&keypair.secret_key)),
+            "baadf24ab1517de5e1d00f8843e1a9f138280b62f012be1c40745fbd990880d5"
+        );
+        assert_eq!(keypair.get_address().to_string(), "0x9f7be28c9db8e386c77aca1e2c588b03aad40eb0");
+    }
+
+    #[test]
+    fn address_derivation_is_stable() {
+        // SHA3-256 of 897 zero bytes, first 20 bytes
+        let key = FalconPublicKey::from_bytes(&[0u8; 897]).unwrap();
+        assert_eq!(key.address(), "0x12ecd52c3818e3b68859f6f1f2a6c954ed1da49e");
+        assert_eq!(address_from_public_key(&[0u8; 897]), key.address());
+    }
+}
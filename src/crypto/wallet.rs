@@ -5,7 +5,10 @@ use chacha20poly1305::{
     ChaCha20Poly1305, Nonce,
 };
 use argon2::Argon2;
+use crate::consensus::fee_estimator::FeeEstimate;
 use crate::crypto::signatures::FalconKeypair;
+use crate::core::transaction::Transaction;
+use crate::core::Address;
 use serde::{Serialize, Deserialize};
 use std::fs;
 use std::path::Path;
@@ -29,6 +32,38 @@ pub enum WalletError {
     HexDecode(#[from] hex::FromHexError),
 }
 
+/// Buffer reserved for a serialized wallet: the Falcon keys come to ~9KB as JSON
+/// arrays, so serialization never reallocates and leaves no unwiped copy behind
+const WALLET_JSON_CAPACITY: usize = 16 * 1024;
+
+/// Owner read/write only - wallet files are encrypted, but there is no reason
+/// to let other local users copy the ciphertext for offline attacks
+#[cfg(unix)]
+const WALLET_FILE_MODE: u32 = 0o600;
+
+/// Write a wallet file that is owner-only from the moment it exists (Unix only).
+/// An existing file is tightened before it is truncated and rewritten
+pub fn write_wallet_file<P: AsRef<Path>>(path: P, contents: &[u8]) -> std::io::Result<()> {
+    use std::io::Write;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(WALLET_FILE_MODE);
+    }
+    let mut file = options.open(path)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        file.set_permissions(fs::Permissions::from_mode(WALLET_FILE_MODE))?;
+    }
+    file.set_len(0)?;
+    file.write_all(contents)?;
+    file.sync_all()
+}
+
 /// Fully quantum-resistant encrypted wallet structure
 /// Uses Kyber-1024 (NIST PQC KEM) + ChaCha20-Poly1305
 /// 
@@ -53,11 +88,49 @@ struct QuantumSafeWallet {
     salt: Vec<u8>,
 }
 
+/// Kyber secret key or shared secret that is zeroed on drop
+///
+/// pqcrypto's types are `Copy` byte arrays with no zeroize-on-drop and no mutable
+/// access, so the drop overwrites the value with an all-zero one; `black_box` keeps
+/// the compiler from discarding that dead store. Never copy the inner value out.
+struct KyberSecret<T: KyberBytes>(T);
+
+/// pqcrypto secret types that can be rebuilt from all-zero bytes
+trait KyberBytes: Copy {
+    fn zeroed() -> Self;
+}
+
+impl KyberBytes for pqcrypto_kyber::kyber1024::SecretKey {
+    fn zeroed() -> Self {
+        <Self as SecretKey>::from_bytes(&[0u8; secret_key_bytes()]).expect("secret key length")
+    }
+}
+
+impl KyberBytes for pqcrypto_kyber::kyber1024::SharedSecret {
+    fn zeroed() -> Self {
+        <Self as SharedSecret>::from_bytes(&[0u8; shared_secret_bytes()]).expect("shared secret length")
+    }
+}
+
+impl<T: KyberBytes> KyberSecret<T> {
+    /// Overwrite the secret with zeros (also run on drop)
+    fn wipe(&mut self) {
+        self.0 = T::zeroed();
+        std::hint::black_box(&mut self.0);
+    }
+}
+
+impl<T: KyberBytes> Drop for KyberSecret<T> {
+    fn drop(&mut self) {
+        self.wipe();
+    }
+}
+
 /// Production-grade quantum-resistant wallet
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct QuantumWallet {
     pub keypair: FalconKeypair,
-    pub address: String,
+    pub address: Address,
 }
 
 impl QuantumWallet {
@@ -89,9 +162,15 @@ impl QuantumWallet {
     /// - Password compromise ≠ wallet compromise (Kyber still protects)
     /// - Quantum adversary needs BOTH password AND break Kyber
     /// - "Harvest now, decrypt later" mitigated
+    ///
+    /// MEMORY HYGIENE: the serialized wallet (holds the Falcon secret key), master key,
+    /// Kyber secret key and shared secret are zeroized before returning, on every path
+    /// including errors. The wallet is serialized into a buffer sized so it never
+    /// reallocates. Copies made inside the AEAD cipher are out of our control.
     pub fn save_quantum_safe(&self, filename: &str, password: &str) -> Result<(), WalletError> {
-        // Serialize wallet data
-        let wallet_json = serde_json::to_vec(self)?;
+        // Serialize wallet data (plaintext includes the Falcon secret key)
+        let mut wallet_json = Zeroizing::new(Vec::with_capacity(WALLET_JSON_CAPACITY));
+        serde_json::to_writer(&mut *wallet_json, self)?;
         
         // Generate random salt for Argon2
         let mut salt = [0u8; 32];
@@ -105,12 +184,14 @@ impl QuantumWallet {
         
         // Generate Kyber-1024 keypair for this wallet file
         let (kyber_pk, kyber_sk) = keypair();
+        let kyber_sk = KyberSecret(kyber_sk);
         
         // Encapsulate to get shared secret (this is the actual encryption key)
         let (shared_secret, kyber_ciphertext) = encapsulate(&kyber_pk);
+        let shared_secret = KyberSecret(shared_secret);
         
         // Derive wallet encryption key from shared secret
-        let wallet_key = &shared_secret.as_bytes()[..32];
+        let wallet_key = &shared_secret.0.as_bytes()[..32];
         
         // Encrypt wallet data with Kyber-derived key
         let mut data_nonce_bytes = [0u8; 12];
@@ -119,7 +200,7 @@ impl QuantumWallet {
         
         let wallet_cipher = ChaCha20Poly1305::new_from_slice(wallet_key)
             .map_err(|_| WalletError::Encryption)?;
-        let encrypted_data = wallet_cipher.encrypt(data_nonce, wallet_json.as_ref())
+        let encrypted_data = wallet_cipher.encrypt(data_nonce, wallet_json.as_slice())
             .map_err(|_| WalletError::Encryption)?;
         
         // Encrypt Kyber secret key with password-derived master key
@@ -129,7 +210,7 @@ impl QuantumWallet {
         
         let sk_cipher = ChaCha20Poly1305::new_from_slice(&*master_key)
             .map_err(|_| WalletError::Encryption)?;
-        let encrypted_kyber_sk = sk_cipher.encrypt(sk_nonce, kyber_sk.as_bytes())
+        let encrypted_kyber_sk = sk_cipher.encrypt(sk_nonce, kyber_sk.0.as_bytes())
             .map_err(|_| WalletError::Encryption)?;
         
         // Create quantum-safe wallet structure
@@ -144,7 +225,7 @@ impl QuantumWallet {
         };
         
         let json = serde_json::to_string_pretty(&quantum_wallet)?;
-        fs::write(filename, json)?;
+        write_wallet_file(filename, json.as_bytes())?;
         
         tracing::info!(" Quantum-safe wallet saved: {}", filename);
         tracing::info!(" Two-layer encryption: Argon2 + Kyber-1024");
@@ -161,6 +242,10 @@ impl QuantumWallet {
     /// 3. Decapsulate ciphertext → shared secret
     /// 4. Derive wallet decryption key
     /// 5. Decrypt wallet data
+    ///
+    /// MEMORY HYGIENE: the master key, Kyber secret key, shared secret and decrypted
+    /// wallet JSON are zeroized. The Falcon secret key is parsed by serde, which can leave
+    /// partial copies while its buffer grows; only the final key is wiped (on drop).
     pub fn load_quantum_safe(filename: &str, password: &str) -> Result<Self, WalletError> {
         if !Path::new(filename).exists() {
             return Err(WalletError::NotFound);
@@ -186,29 +271,30 @@ impl QuantumWallet {
         
         // Reconstruct Kyber secret key (wrap in Zeroizing for safety)
         let mut kyber_sk_zeroizing = Zeroizing::new(kyber_sk_bytes);
-        let kyber_sk = pqcrypto_kyber::kyber1024::SecretKey::from_bytes(&kyber_sk_zeroizing)
-            .map_err(|_| WalletError::Encryption)?;
+        let kyber_sk = KyberSecret(pqcrypto_kyber::kyber1024::SecretKey::from_bytes(&kyber_sk_zeroizing)
+            .map_err(|_| WalletError::Encryption)?);
         
         // Reconstruct ciphertext
         let kyber_ct = pqcrypto_kyber::kyber1024::Ciphertext::from_bytes(&quantum_wallet.kyber_ciphertext)
             .map_err(|_| WalletError::Encryption)?;
         
         // Decapsulate to get shared secret (CRITICAL: actual PQ crypto happens here)
-        let shared_secret = decapsulate(&kyber_ct, &kyber_sk);
+        let shared_secret = KyberSecret(decapsulate(&kyber_ct, &kyber_sk.0));
         
         // Zeroize Kyber SK now that we're done with it
         kyber_sk_zeroizing.zeroize();
+        drop(kyber_sk);
         
         // Derive wallet decryption key from shared secret
-        let wallet_key = &shared_secret.as_bytes()[..32];
+        let wallet_key = &shared_secret.0.as_bytes()[..32];
         
         // Decrypt wallet data
         let wallet_cipher = ChaCha20Poly1305::new_from_slice(wallet_key)
             .map_err(|_| WalletError::Encryption)?;
         let data_nonce = Nonce::from_slice(&quantum_wallet.data_nonce);
         
-        let decrypted_data = wallet_cipher.decrypt(data_nonce, quantum_wallet.encrypted_data.as_ref())
-            .map_err(|_| WalletError::InvalidPassword)?;
+        let decrypted_data = Zeroizing::new(wallet_cipher.decrypt(data_nonce, quantum_wallet.encrypted_data.as_ref())
+            .map_err(|_| WalletError::InvalidPassword)?);
         
         let wallet: Self = serde_json::from_slice(&decrypted_data)?;
         
@@ -219,6 +305,30 @@ impl QuantumWallet {
         Ok(wallet)
     }
 
+    /// Sign a transaction with this wallet's Falcon key
+    /// Fills in an empty sender and the public key, then signs the signing data
+    pub fn sign_transaction(&self, tx: &mut Transaction) {
+        if tx.sender.is_empty() {
+            tx.sender = self.address.to_string();
+        }
+        tx.public_key = self.keypair.public_key.clone();
+        tx.signature = self.keypair.sign(&tx.get_signing_data());
+    }
+
+    /// Sign a transaction paying the fee `estimate` quotes for its signed weight
+    /// Re-signs until the fee covers the weight, since Falcon signature lengths vary
+    pub fn sign_transaction_with_fee(&self, tx: &mut Transaction, estimate: &FeeEstimate) {
+        tx.fee = 0;
+        loop {
+            self.sign_transaction(tx);
+            let fee = estimate.fee_for_weight(tx.weight());
+            if tx.fee >= fee {
+                return;
+            }
+            tx.fee = fee;
+        }
+    }
+
     /// Display comprehensive wallet information
     pub fn display_info(&self, balance: f64) {
         println!("\n");
@@ -257,3 +367,113 @@ impl QuantumWallet {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_transactions_sign_and_verify() {
+        let wallet = QuantumWallet::new();
+
+        let mut transfer = Transaction::builder()
+            .recipient("0x2222222222222222222222222222222222222222")
+            .amount(5_000)
+            .fee(1_000)
+            .nonce(1)
+            .build();
+        assert!(!transfer.verify());
+        wallet.sign_transaction(&mut transfer);
+        assert_eq!(transfer.sender, wallet.address);
+        assert!(transfer.verify());
+
+        let contract = "0x3333333333333333333333333333333333333333";
+        let mut call = Transaction::builder()
+            .sender(wallet.address.clone())
+            .call_contract(contract, "transfer", vec![7, 8])
+            .nonce(2)
+            .build();
+        wallet.sign_transaction(&mut call);
+        assert_eq!(call.recipient, contract);
+        assert!(matches!(
+            &call.tx_type,
+            crate::core::transaction::TransactionType::CallContract { function, .. } if function == "transfer"
+        ));
+        assert!(call.verify());
+    }
+
+    #[test]
+    fn fee_covers_the_signed_weight() {
+        let wallet = QuantumWallet::new();
+        let estimate = FeeEstimate::from_transactions(&[], 100, 500);
+
+        let mut tx = Transaction::builder()
+            .recipient("0x2222222222222222222222222222222222222222")
+            .amount(5_000)
+            .nonce(1)
+            .build();
+        tx.memo = Some(vec![0u8; 256]);
+        wallet.sign_transaction_with_fee(&mut tx, &estimate);
+
+        assert!(tx.verify());
+        assert!(tx.fee >= estimate.fee_for_weight(tx.weight()));
+        // ~2KB signed at the 0.5 microunit/byte floor is well above the flat minimum
+        assert!(tx.fee > estimate.min_fee);
+    }
+
+    #[test]
+    fn kyber_secrets_are_wiped() {
+        // The save/load paths hold the Kyber SK and shared secret only inside KyberSecret
+        let (pk, sk) = keypair();
+        let mut sk = KyberSecret(sk);
+        let (ss, _) = encapsulate(&pk);
+        let mut ss = KyberSecret(ss);
+        assert!(sk.0.as_bytes().iter().any(|b| *b != 0));
+        assert!(ss.0.as_bytes().iter().any(|b| *b != 0));
+
+        // Same path Drop takes; inspect the value before it goes away
+        sk.wipe();
+        ss.wipe();
+        assert!(sk.0.as_bytes().iter().all(|b| *b == 0));
+        assert!(ss.0.as_bytes().iter().all(|b| *b == 0));
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.qua");
+        let path = path.to_str().unwrap();
+
+        let wallet = QuantumWallet::new();
+        // The plaintext fits the reserved buffer, so saving never reallocates it
+        assert!(serde_json::to_vec(&wallet).unwrap().len() <= WALLET_JSON_CAPACITY);
+        wallet.save_quantum_safe(path, "hunter2").unwrap();
+        let loaded = QuantumWallet::load_quantum_safe(path, "hunter2").unwrap();
+        assert_eq!(loaded.address, wallet.address);
+        assert_eq!(loaded.keypair.secret_key_bytes(), wallet.keypair.secret_key_bytes());
+        assert!(matches!(
+            QuantumWallet::load_quantum_safe(path, "wrong"),
+            Err(WalletError::InvalidPassword)
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn saved_wallet_is_owner_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.qua");
+        let path = path.to_str().unwrap();
+
+        QuantumWallet::new().save_quantum_safe(path, "test-password").unwrap();
+
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        // Overwriting a file left world-readable tightens it too
+        fs::set_permissions(path, fs::Permissions::from_mode(0o644)).unwrap();
+        QuantumWallet::new().save_quantum_safe(path, "test-password").unwrap();
+        let mode = fs::metadata(path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+}
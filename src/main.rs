@@ -9,31 +9,22 @@ mod rpc;
 
 
 
+use consensus::blockchain::MinFees;
 use consensus::Blockchain;
-use crypto::QuantumWallet;
+use crypto::{FalconKeypair, QuantumWallet};
 use storage::BlockchainStorage;
 use network::{Network, NetworkConfig};
 use consensus::MetricsCollector;
 use config::QuantaConfig;
+use config::types::LogFormat;
 use rpc::{RpcServer, RpcClient};
-use chrono::Utc;
 use clap::{Parser, Subcommand};
 use std::sync::Arc;
 use tokio::sync::RwLock;
-use tracing_subscriber;
+use core::units::{format_qua, microunits_to_qua, parse_qua, MICROUNITS_PER_QUA};
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::util::SubscriberInitExt;
 
-// CONSENSUS CONSTANTS: 1 QUA = 1_000_000 microunits
-const MICROUNITS_PER_QUA: u64 = 1_000_000;
-
-/// Convert QUA (f64 for CLI UX) to microunits (u64 for consensus)
-fn qua_to_microunits(qua: f64) -> u64 {
-    (qua * MICROUNITS_PER_QUA as f64) as u64
-}
-
-/// Convert microunits (u64) to QUA (f64 for display)
-fn microunits_to_qua(microunits: u64) -> f64 {
-    microunits as f64 / MICROUNITS_PER_QUA as f64
-}
 
 #[derive(Parser)]
 #[command(name = "quanta")]
@@ -41,6 +32,11 @@ fn microunits_to_qua(microunits: u64) -> f64 {
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Read the wallet password from this file instead of prompting
+    /// (QUANTA_WALLET_PASSWORD is used when this is not given)
+    #[arg(long, global = true)]
+    password_file: Option<String>,
 }
 
 #[derive(Subcommand)]
@@ -83,6 +79,14 @@ enum Commands {
         /// Run in background as daemon
         #[arg(long)]
         detach: bool,
+        
+        /// Encrypted wallet holding the node's hot signing key (decrypted once, kept in memory only)
+        #[arg(long = "node-key")]
+        node_key: Option<String>,
+        
+        /// Log format: text or json (overrides config)
+        #[arg(long, value_enum)]
+        log_format: Option<LogFormat>,
     },
     
     /// Check node status (requires running node)
@@ -133,6 +137,17 @@ enum Commands {
         rpc_port: u16,
     },
     
+    /// Find the first block height where this node's chain differs from a peer's (requires running node)
+    VerifyChainAgainstPeer {
+        /// Peer RPC URL (e.g. http://10.0.0.5:7782)
+        #[arg(short, long)]
+        peer: String,
+        
+        /// RPC port (default: 7782)
+        #[arg(short = 'r', long = "rpc-port", default_value = "7782")]
+        rpc_port: u16,
+    },
+    
     /// Get list of connected peers (requires running node)
     Peers {
         /// RPC port (default: 7782)
@@ -154,6 +169,13 @@ enum Commands {
         file: String,
     },
     
+    /// Generate a Falcon keypair and print it UNENCRYPTED (for scripts and tests)
+    Keygen {
+        /// Print the keys as JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    
     /// Create a new HD wallet with 24-word mnemonic
     NewHdWallet {
         /// Wallet file name
@@ -213,9 +235,9 @@ enum Commands {
         /// Recipient address
         #[arg(short, long)]
         to: String,
-        /// Amount to send
-        #[arg(short, long)]
-        amount: f64,
+        /// Amount to send in QUA (up to 6 decimal places)
+        #[arg(short, long, value_parser = parse_qua)]
+        amount: u64,
         /// Database path
         #[arg(short, long, default_value = "./quanta_data")]
         db: String,
@@ -235,6 +257,21 @@ enum Commands {
         db: String,
     },
     
+    /// Check that this machine can run a node with the given configuration
+    Doctor {
+        /// Configuration file path
+        #[arg(short = 'c', long)]
+        config: Option<String>,
+        
+        /// Network type (mainnet or testnet; default: from config)
+        #[arg(long)]
+        network: Option<String>,
+        
+        /// Database path (overrides config)
+        #[arg(short, long)]
+        db: Option<String>,
+    },
+    
     /// Run demo with sample transactions
     Demo {
         /// Database path
@@ -245,15 +282,20 @@ enum Commands {
 
 #[tokio::main]
 async fn main() {
-    println!("");
-    println!("        QUANTA - Quantum-Resistant Blockchain                  ");
-    println!("         Falcon Signatures | Post-Quantum Cryptography         ");
-    println!("\n");
-
     let cli = Cli::parse();
 
+    // Keep stdout machine-readable for JSON output
+    if !matches!(cli.command, Commands::Keygen { json: true }) {
+        println!("");
+        println!("        QUANTA - Quantum-Resistant Blockchain                  ");
+        println!("         Falcon Signatures | Post-Quantum Cryptography         ");
+        println!("\n");
+    }
+
+    let password_file = cli.password_file.as_deref();
+
     match cli.command {
-        Commands::Start { config, network, port, network_port, rpc_port, db, bootstrap, no_network, detach } => {
+        Commands::Start { config, network, port, network_port, rpc_port, db, bootstrap, no_network, detach, node_key, log_format } => {
             // Load configuration with RPC port override
             let mut cfg = QuantaConfig::load_with_overrides(
                 config,
@@ -264,6 +306,9 @@ async fn main() {
                 Some(network),
                 no_network
             ).expect("Failed to load configuration");
+            if let Some(format) = log_format {
+                cfg.node.log_format = format;
+            }
             
             // Set RPC port from CLI or default
             let rpc_port = rpc_port.unwrap_or(7782);
@@ -294,11 +339,22 @@ async fn main() {
                             rpc_port.to_string(),
                             "--db".to_string(),
                             cfg.node.db_path.clone(),
+                            "--log-format".to_string(),
+                            cfg.node.log_format.as_str().to_string(),
                         ];
                         
                         if cfg.node.no_network {
                             args.push("--no-network".to_string());
                         }
+                        if let Some(ref key_file) = node_key {
+                            // Child has no terminal: password must come from --password-file or QUANTA_WALLET_PASSWORD
+                            args.push("--node-key".to_string());
+                            args.push(key_file.clone());
+                            if let Some(path) = password_file {
+                                args.push("--password-file".to_string());
+                                args.push(path.to_string());
+                            }
+                        }
                         
                         // Open log file for the child process
                         let log_file_path = format!("logs/quanta_node_{}.log", cfg.node.network_port);
@@ -356,12 +412,7 @@ async fn main() {
                     .open(&log_file)
                     .expect("Failed to open log file");
                 
-                tracing_subscriber::fmt()
-                    .with_writer(Arc::new(file))
-                    .with_ansi(false)
-                    .with_target(false)
-                    .with_level(true)
-                    .init();
+                log_subscriber(cfg.node.log_format, Arc::new(file), false).init();
                 
                 // Write PID file
                 let pid_file = format!("logs/quanta_{}.pid", cfg.node.network_port);
@@ -373,20 +424,65 @@ async fn main() {
                 tracing::info!("PID file: {}", pid_file);
             } else {
                 // Initialize console logging for non-detached mode
-                tracing_subscriber::fmt()
-                    .with_target(false)
-                    .with_level(true)
-                    .init();
+                let ansi = cfg.node.log_format == LogFormat::Text;
+                log_subscriber(cfg.node.log_format, std::io::stdout, ansi).init();
             }
             
+            core::redact::set_address_redaction(cfg.security.redact_log_addresses);
+            
             tracing::info!("Starting QUANTA node with configuration:");
             tracing::info!("  API Port: {}", cfg.node.api_port);
             tracing::info!("  Network Port: {}", cfg.node.network_port);
             tracing::info!("  RPC Port: {}", rpc_port);
             tracing::info!("  Database: {}", cfg.node.db_path);
             
-            let storage = Arc::new(BlockchainStorage::new(&cfg.node.db_path).expect("Failed to open database"));
-            let blockchain = Arc::new(RwLock::new(Blockchain::new(storage, cfg.network_type).expect("Failed to initialize blockchain")));
+            // Hot signing key for validator messages (zeroized when the node exits)
+            let node_key = match node_key {
+                Some(ref key_file) => match crypto::keystore::NodeKeyStore::load_interactive(key_file, password_file) {
+                    Ok(store) => {
+                        tracing::info!("  Node Key: {}", core::redact::redact_address(store.address()));
+                        Some(Arc::new(store))
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to load node key from {}: {}", key_file, e);
+                        std::process::exit(1);
+                    }
+                },
+                None => None,
+            };
+            
+            let storage = if cfg.node.encrypt_db {
+                // Child has no terminal in daemon mode: the passphrase must come from QUANTA_DB_PASSPHRASE
+                let passphrase = crypto::password::read_db_passphrase().expect("Failed to read database passphrase");
+                BlockchainStorage::open_encrypted(&cfg.node.db_path, &passphrase)
+            } else {
+                BlockchainStorage::new(&cfg.node.db_path)
+            };
+            let storage = match storage {
+                Ok(storage) => Arc::new(storage),
+                Err(e) => {
+                    tracing::error!("Failed to open database {}: {}", cfg.node.db_path, e);
+                    std::process::exit(1);
+                }
+            };
+            if let Err(e) = storage.check_chain_height(cfg.node.repair_chain_height) {
+                tracing::error!("Chain consistency check failed: {}", e);
+                std::process::exit(1);
+            }
+            let blockchain = Arc::new(RwLock::new(Blockchain::new(storage, cfg.network_type)
+                .expect("Failed to initialize blockchain")
+                .with_contracts_enabled(cfg.node.contracts_enabled)
+                .with_min_fees(MinFees {
+                    transfer: cfg.consensus.min_transaction_fee_microunits,
+                    deploy: cfg.consensus.min_deploy_fee_microunits,
+                    call: cfg.consensus.min_call_fee_microunits,
+                })
+                .with_min_fee_per_kb(cfg.consensus.min_fee_per_kb_microunits)
+                .with_max_call_data_bytes(cfg.consensus.max_call_data_bytes)
+                .with_fee_estimate_window(cfg.node.fee_estimate_window_blocks)
+                .with_block_time_window(cfg.node.block_time_window_blocks)
+                .with_max_orphan_blocks(cfg.security.max_orphan_blocks)
+                .with_max_pending_per_sender(cfg.security.max_pending_per_sender)));
             
             let metrics = Arc::new(MetricsCollector::new());
             
@@ -424,9 +520,48 @@ async fn main() {
                     node_id: uuid::Uuid::new_v4().to_string(),
                     bootstrap_nodes,
                     dns_seeds: cfg.network.dns_seeds.clone(),
+                    max_inbound_per_ip_per_sec: cfg.network.max_inbound_per_ip_per_sec,
+                    max_inbound_per_sec: cfg.network.max_inbound_per_sec,
+                    snapshot_sync: cfg.network.snapshot_sync,
+                    encryption: cfg.network.p2p_encryption,
+                    max_orphan_requests_per_peer: cfg.network.max_orphan_requests_per_peer,
+                    min_peers_to_mine: cfg.network.min_peers_to_mine,
+                    ban_policy: network::discovery::BanPolicy {
+                        strike_failures: cfg.security.peer_ban_strike_failures,
+                        strike_reputation: cfg.security.peer_ban_strike_reputation,
+                        max_failures: cfg.security.peer_ban_max_failures,
+                        base_ban_secs: cfg.security.peer_ban_duration_secs,
+                    },
                 };
                 
-                let network = Arc::new(Network::new(network_config, Arc::clone(&blockchain)));
+                let encryption = match network::noise::PeerEncryption::load_or_create(
+                    cfg.network.p2p_encryption,
+                    std::path::Path::new(&cfg.network.p2p_noise_key_file),
+                ) {
+                    Ok(encryption) => encryption,
+                    Err(e) => {
+                        tracing::error!("Failed to load Noise static key: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+                let mut pinned_keys = std::collections::HashMap::new();
+                for (addr, key) in &cfg.network.peer_static_keys {
+                    match (addr.parse(), hex::decode(key)) {
+                        (Ok(addr), Ok(key)) => {
+                            pinned_keys.insert(addr, key);
+                        }
+                        _ => {
+                            tracing::error!("Invalid peer_static_keys entry '{}'", addr);
+                            std::process::exit(1);
+                        }
+                    }
+                }
+                tracing::info!("  Noise Static Key: {}", hex::encode(encryption.public_key()));
+
+                let network = Arc::new(
+                    Network::new(network_config, Arc::clone(&blockchain))
+                        .with_encryption(encryption, network::noise::KeyPins::new(pinned_keys)),
+                );
                 
                 // Start P2P network
                 let network_clone = Arc::clone(&network);
@@ -494,7 +629,10 @@ async fn main() {
                 cfg.node.api_port,
                 cfg.node.network_port,
                 rpc_port,
-            );
+            )
+            .with_admin_rpc(cfg.security.enable_admin_rpc)
+            .with_max_body_bytes(cfg.security.max_request_body_bytes)
+            .with_node_key(node_key);
             
             let rpc_handle = {
                 let rpc_port_clone = rpc_port;
@@ -511,8 +649,15 @@ async fn main() {
                 let metrics_clone = Some(metrics.clone());
                 let network_clone = network.clone();
                 let port = cfg.node.api_port;
+                let max_concurrent = cfg.security.max_concurrent_api_requests;
+                let max_body_bytes = cfg.security.max_request_body_bytes;
+                let replica = if cfg.node.api_read_replica {
+                    Some(blockchain.read().await.replica())
+                } else {
+                    None
+                };
                 tokio::spawn(async move {
-                    api::start_server(blockchain_clone, port, metrics_clone, network_clone).await;
+                    api::start_server(blockchain_clone, port, metrics_clone, network_clone, max_concurrent, max_body_bytes, replica).await;
                 })
             };
             
@@ -548,6 +693,7 @@ async fn main() {
                     println!("");
                     println!("  Status:         {}                              ", if status.running { "RUNNING " } else { "STOPPED" });
                     println!("  Version:        {}                                     ", status.version);
+                    println!("  Protocol:       v{} ({:?})                             ", status.protocol_version, status.network);
                     println!("  Uptime:         {} seconds                             ", status.uptime_seconds);
                     println!("                                                          ");
                     println!("  Chain Height:   {} blocks                              ", status.chain_height);
@@ -583,9 +729,9 @@ async fn main() {
                     }
                     println!("  Blocks Mined:   {}                                      ", status.blocks_mined);
                     println!("  Difficulty:     {}                                      ", status.difficulty);
-                    println!("  Block Reward:   {} microunits ({:.6} QUA)         ", 
+                    println!("  Block Reward:   {} microunits ({} QUA)         ", 
                         status.mining_reward, 
-                        status.mining_reward as f64 / 1_000_000.0
+                        format_qua(status.mining_reward)
                     );
                     if let Some(last_time) = status.last_block_time {
                         use chrono::{DateTime, Utc as ChronoUtc};
@@ -593,6 +739,10 @@ async fn main() {
                             .unwrap_or_else(|| ChronoUtc::now());
                         println!("  Last Block:     {}                        ", dt.format("%Y-%m-%d %H:%M:%S UTC"));
                     }
+                    if let (Some(avg), Some(ahead)) = (status.avg_block_time_secs, status.blocks_ahead_of_target) {
+                        println!("  Avg Block Time: {:.1}s                                  ", avg);
+                        println!("  vs Target:      {:+.1} blocks                          ", ahead);
+                    }
                     println!("\n");
                 }
                 Err(e) => {
@@ -619,6 +769,11 @@ async fn main() {
                     println!("  Timestamp:      {}                        ", dt.format("%Y-%m-%d %H:%M:%S UTC"));
                     println!("  Transactions:   {}                                      ", block.transactions);
                     println!("  Difficulty:     {}                                      ", block.difficulty);
+                    println!("  Nonce:          {}                                      ", block.nonce);
+                    println!("  Merkle Root:    {}...", block.merkle_root.get(..24).unwrap_or(&block.merkle_root));
+                    println!("  Total Fees:     {} QUA                               ", format_qua(block.total_fees));
+                    println!("  Coinbase:       {} QUA                               ", format_qua(block.coinbase_reward));
+                    println!("  Size:           {} bytes                                ", block.size_bytes);
                     println!("\n");
                 }
                 Err(e) => {
@@ -628,6 +783,29 @@ async fn main() {
             }
         }
         
+        Commands::VerifyChainAgainstPeer { peer, rpc_port } => {
+            let client = RpcClient::new(rpc_port);
+            let peer_client = RpcClient::with_url(peer.clone());
+            
+            match client.find_divergence(&peer_client).await {
+                Ok(comparison) => {
+                    println!("  Local height:   {}", comparison.local_height);
+                    println!("  Peer height:    {} ({})", comparison.peer_height, peer);
+                    match comparison.first_divergence {
+                        Some(height) => {
+                            println!("  Chains diverge at height {}", height);
+                            std::process::exit(2);
+                        }
+                        None => println!("  Chains agree on all {} common blocks", comparison.local_height.min(comparison.peer_height)),
+                    }
+                }
+                Err(e) => {
+                    eprintln!(" Failed to compare chains: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        
         Commands::Peers { rpc_port } => {
             let client = RpcClient::new(rpc_port);
             
@@ -641,7 +819,11 @@ async fn main() {
                         println!("  No peers connected                                      ");
                     } else {
                         for (i, peer) in peers.iter().enumerate() {
-                            println!("  {}. {}                                    ", i + 1, peer.address);
+                            println!(
+                                "  {}. {} ({:?}, sent {} B, recv {} B{})",
+                                i + 1, peer.address, peer.direction, peer.bytes_sent, peer.bytes_received,
+                                if peer.encrypted { ", encrypted" } else { "" }
+                            );
                         }
                     }
                     
@@ -655,6 +837,11 @@ async fn main() {
         }
         
         Commands::StartMining { address, rpc_port } => {
+            if !core::transaction::is_valid_address(&address) {
+                eprintln!(" Invalid miner address: {} (expected 0x + 40 hex chars)", address);
+                std::process::exit(1);
+            }
+            
             let client = RpcClient::new(rpc_port);
             
             println!("Starting mining to address: {}", address);
@@ -735,29 +922,38 @@ async fn main() {
             
             let wallet = QuantumWallet::new();
             
-            let password = if let Ok(p) = std::env::var("QUANTA_WALLET_PASSWORD") {
-                p
-            } else {
-                println!("\nEnter password to encrypt wallet:");
-                rpassword::read_password().expect("Failed to read password")
-            };
-            
-            let password_confirm = if let Ok(p) = std::env::var("QUANTA_WALLET_PASSWORD") {
-                p
-            } else {
-                println!("Confirm password:");
-                rpassword::read_password().expect("Failed to read password")
+            let password = match crypto::password::read_new_password(password_file) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
             };
             
-            if password != password_confirm {
-                eprintln!("Passwords don't match!");
-                return;
-            }
-            
             wallet.save_quantum_safe(&file, &password).expect("Failed to save wallet");
             println!("Wallet created and encrypted successfully!");
         }
 
+        Commands::Keygen { json } => {
+            let keypair = FalconKeypair::generate();
+            let secret_key = keypair.secret_key_hex();
+
+            eprintln!("WARNING: the private key below is NOT ENCRYPTED.");
+            eprintln!("WARNING: anyone who sees it can spend this address's funds; never paste it into logs or chats.");
+            if json {
+                let output = zeroize::Zeroizing::new(serde_json::json!({
+                    "address": keypair.get_address(),
+                    "public_key": keypair.public_key_hex(),
+                    "private_key": secret_key.as_str(),
+                }).to_string());
+                println!("{}", output.as_str());
+            } else {
+                println!("Address:     {}", keypair.get_address());
+                println!("Public key:  {}", keypair.public_key_hex());
+                println!("Private key: {}", secret_key.as_str());
+            }
+        }
+
         Commands::NewHdWallet { file, accounts } => {
             use crate::crypto::HDWallet;
             
@@ -770,20 +966,17 @@ async fn main() {
             
             wallet.display_info();
             
-            println!("\nEnter password to encrypt wallet:");
-            let password = rpassword::read_password().expect("Failed to read password");
-            
-            println!("Confirm password:");
-            let password_confirm = rpassword::read_password().expect("Failed to read password");
-            
-            if password != password_confirm {
-                eprintln!("Passwords don't match!");
-                return;
-            }
+            let password = match crypto::password::read_new_password(password_file) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("{}", e);
+                    return;
+                }
+            };
             
             // Save encrypted wallet
             let encrypted = wallet.export_encrypted(&password).expect("Failed to encrypt wallet");
-            std::fs::write(&file, encrypted).expect("Failed to save wallet");
+            crypto::wallet::write_wallet_file(&file, &encrypted).expect("Failed to save wallet");
             
             println!("\n HD Wallet created and encrypted successfully!");
             println!(" Saved to: {}", file);
@@ -792,21 +985,28 @@ async fn main() {
         }
 
         Commands::HdWallet { file } => {
-            println!("Enter wallet password:");
-            let _password = rpassword::read_password().expect("Failed to read password");
+            use crate::crypto::HDWallet;
+
+            let password = crypto::password::read_password(password_file, "Enter wallet password:")
+                .expect("Failed to read password");
+            
+            let wallet = match std::fs::read(&file)
+                .map_err(|e| e.to_string())
+                .and_then(|data| HDWallet::import_encrypted(&data, &password, ""))
+            {
+                Ok(w) => w,
+                Err(e) => {
+                    eprintln!("Failed to load HD wallet: {}", e);
+                    return;
+                }
+            };
             
-            // For now, we'll need to implement proper loading
-            println!("HD wallet info display - implementation needed for encrypted load");
-            println!("Wallet file: {}", file);
+            wallet.display_info();
         }
 
         Commands::Wallet { file, network, db } => {
-            let password = if let Ok(p) = std::env::var("QUANTA_WALLET_PASSWORD") {
-                p
-            } else {
-                println!("Enter wallet password:");
-                rpassword::read_password().expect("Failed to read password")
-            };
+            let password = crypto::password::read_password(password_file, "Enter wallet password:")
+                .expect("Failed to read password");
             
             let wallet = match QuantumWallet::load_quantum_safe(&file, &password) {
                 Ok(w) => w,
@@ -831,12 +1031,8 @@ async fn main() {
         }
 
         Commands::WalletAddress { file } => {
-            let password = if let Ok(p) = std::env::var("QUANTA_WALLET_PASSWORD") {
-                p
-            } else {
-                println!("Enter wallet password:");
-                rpassword::read_password().expect("Failed to read password")
-            };
+            let password = crypto::password::read_password(password_file, "Enter wallet password:")
+                .expect("Failed to read password");
             
             let wallet = match QuantumWallet::load_quantum_safe(&file, &password) {
                 Ok(w) => w,
@@ -850,12 +1046,8 @@ async fn main() {
         }
 
         Commands::Mine { wallet: wallet_file, db } => {
-            let password = if let Ok(p) = std::env::var("QUANTA_WALLET_PASSWORD") {
-                p
-            } else {
-                println!("Enter wallet password:");
-                rpassword::read_password().expect("Failed to read password")
-            };
+            let password = crypto::password::read_password(password_file, "Enter wallet password:")
+                .expect("Failed to read password");
             
             let wallet = match QuantumWallet::load_quantum_safe(&wallet_file, &password) {
                 Ok(w) => w,
@@ -869,24 +1061,20 @@ async fn main() {
             let blockchain = Arc::new(RwLock::new(Blockchain::new(storage, core::ChainNetwork::Mainnet).expect("Failed to initialize blockchain")));
             
             println!("  Mining new block...");
-            let mine_result = blockchain.write().await.mine_pending_transactions(wallet.address.clone());
+            let mine_result = blockchain.write().await.mine_pending_transactions(wallet.address.to_string());
             match mine_result {
                 Ok(_) => {
                     println!(" Block mined successfully!");
                     let balance_microunits = blockchain.read().await.get_balance(&wallet.address);
-                    println!(" New balance: {:.6} QUA", microunits_to_qua(balance_microunits));
+                    println!(" New balance: {} QUA", format_qua(balance_microunits));
                 }
                 Err(e) => eprintln!(" Mining failed: {}", e),
             }
         }
 
-        Commands::Send { wallet: wallet_file, to, amount, db } => {
-            let password = if let Ok(p) = std::env::var("QUANTA_WALLET_PASSWORD") {
-                p
-            } else {
-                println!("Enter wallet password:");
-                rpassword::read_password().expect("Failed to read password")
-            };
+        Commands::Send { wallet: wallet_file, to, amount: amount_microunits, db } => {
+            let password = crypto::password::read_password(password_file, "Enter wallet password:")
+                .expect("Failed to read password");
             
             let wallet = match QuantumWallet::load_quantum_safe(&wallet_file, &password) {
                 Ok(w) => w,
@@ -899,9 +1087,6 @@ async fn main() {
             let storage = Arc::new(BlockchainStorage::new(&db).expect("Failed to open database"));
             let blockchain = Arc::new(RwLock::new(Blockchain::new(storage, core::ChainNetwork::Mainnet).expect("Failed to initialize blockchain")));
             
-            // Convert QUA to microunits
-            let amount_microunits = qua_to_microunits(amount);
-            
             // Get current nonce for sender
             let current_nonce = {
                 let bc = blockchain.read().await;
@@ -911,29 +1096,23 @@ async fn main() {
             };
             let next_nonce = current_nonce + 1;
             
-            use crate::core::transaction::{Transaction, TransactionType};
-            let mut tx = Transaction {
-                sender: wallet.address.clone(),
-                recipient: to.clone(),
-                amount: amount_microunits,
-                timestamp: Utc::now().timestamp(),
-                signature: vec![],
-                public_key: wallet.keypair.public_key.clone(),
-                fee: 1000, // 0.001 QUA default fee
-                nonce: next_nonce,
-                tx_type: TransactionType::Transfer,
-            };
-            
-            // Sign transaction
-            let signing_data = tx.get_signing_data();
-            tx.signature = wallet.keypair.sign(&signing_data);
+            let mut tx = crate::core::transaction::Transaction::builder()
+                .recipient(to.clone())
+                .amount(amount_microunits)
+                .nonce(next_nonce)
+                .build();
+            // Fee at the recent fee rate for the signed size
+            let estimate = blockchain.read().await.estimate_fee_rate();
+            wallet.sign_transaction_with_fee(&mut tx, &estimate);
             
+            let tx_fee = tx.fee;
             let add_result = blockchain.write().await.add_transaction(tx);
             match add_result {
                 Ok(_) => {
                     println!(" Transaction added to mempool");
-                    println!(" Sending {:.6} QUA to {}", amount, to);
+                    println!(" Sending {} QUA to {}", format_qua(amount_microunits), to);
                     println!(" Nonce: {}", next_nonce);
+                    println!(" Fee: {} QUA", format_qua(tx_fee));
                 }
                 Err(e) => eprintln!(" Transaction failed: {}", e),
             }
@@ -944,8 +1123,8 @@ async fn main() {
             let blockchain = Arc::new(RwLock::new(Blockchain::new(storage, core::ChainNetwork::Mainnet).expect("Failed to initialize blockchain")));
             let stats = blockchain.read().await.get_stats();
             
-            let reward_qua = microunits_to_qua(stats.mining_reward);
-            let supply_qua = microunits_to_qua(stats.total_supply);
+            let reward_qua = format_qua(stats.mining_reward);
+            let supply_qua = format_qua(stats.total_supply);
             
             println!("");
             println!("                QUANTA BLOCKCHAIN STATISTICS                   ");
@@ -953,9 +1132,10 @@ async fn main() {
             println!(" Chain Length: {} blocks                                  ", stats.chain_length);
             println!(" Total Transactions: {}                                    ", stats.total_transactions);
             println!(" Current Difficulty: {}                                     ", stats.current_difficulty);
-            println!(" Mining Reward: {:.6} QUA                                 ", reward_qua);
-            println!(" Total Supply: {:.6} QUA                                  ", supply_qua);
+            println!(" Mining Reward: {} QUA                                 ", reward_qua);
+            println!(" Total Supply: {} QUA                                  ", supply_qua);
             println!(" Pending Transactions: {}                                   ", stats.pending_transactions);
+            println!(" Accounts: {}                                               ", stats.account_count);
             println!("");
             println!(" Quantum Resistance: ACTIVE                                  ");
             println!(" Signature Algorithm: Falcon-512 (NIST PQC)                   ");
@@ -982,6 +1162,24 @@ async fn main() {
             }
         }
 
+        Commands::Doctor { config, network, db } => {
+            let cfg = match QuantaConfig::load_with_overrides(config, None, None, db, None, network, false) {
+                Ok(cfg) => cfg,
+                Err(e) => {
+                    println!("[FAIL] config: failed to load configuration: {}", e);
+                    println!("       -> check the file path and TOML syntax");
+                    std::process::exit(1);
+                }
+            };
+            
+            println!("Checking QUANTA node setup ({:?}, data directory {})\n", cfg.network_type, cfg.node.db_path);
+            let report = config::doctor::run(&cfg);
+            report.print();
+            if !report.passed() {
+                std::process::exit(1);
+            }
+        }
+
         Commands::Demo { db } => {
             println!("Running Production Demo...\n");
             run_demo(&db).await;
@@ -989,8 +1187,49 @@ async fn main() {
     }
 }
 
+/// Node log subscriber writing to `writer`: human-readable text, or one JSON object
+/// per line with `timestamp`, `level`, `message` and the event's fields at top level
+fn log_subscriber<W>(format: LogFormat, writer: W, ansi: bool) -> Box<dyn tracing::Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt()
+        .with_writer(writer)
+        .with_ansi(ansi)
+        .with_target(false)
+        .with_level(true);
+    match format {
+        LogFormat::Text => Box::new(builder.finish()),
+        LogFormat::Json => Box::new(builder.json().flatten_event(true).finish()),
+    }
+}
+
+// WARNING: Insecure password for demo ONLY! Never use in production!
+const DEMO_PASSWORD: &str = "INSECURE_DEMO_PASSWORD_DO_NOT_USE_IN_PRODUCTION";
+
+/// Demo wallets live inside the demo database directory, never the working directory
+fn demo_wallet_dir(db_path: &str) -> std::path::PathBuf {
+    std::path::Path::new(db_path).join("demo_wallets")
+}
+
+/// Create three demo wallets, replacing any left over from a previous demo run
+fn create_demo_wallets(db_path: &str) -> Result<(std::path::PathBuf, [QuantumWallet; 3]), Box<dyn std::error::Error>> {
+    let dir = demo_wallet_dir(db_path);
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)?;
+    }
+    std::fs::create_dir_all(&dir)?;
+
+    let wallets = [QuantumWallet::new(), QuantumWallet::new(), QuantumWallet::new()];
+    for (i, wallet) in wallets.iter().enumerate() {
+        let path = dir.join(format!("demo_wallet{}.qua", i + 1));
+        wallet.save_quantum_safe(&path.to_string_lossy(), DEMO_PASSWORD)?;
+    }
+    Ok((dir, wallets))
+}
+
 async fn run_demo(db_path: &str) {
-    use crate::core::transaction::{Transaction, TransactionType};
+    use crate::core::transaction::Transaction;
     let storage = Arc::new(BlockchainStorage::new(db_path).expect("Failed to open database"));
     
     // Clear old demo data
@@ -1000,94 +1239,76 @@ async fn run_demo(db_path: &str) {
     
     // Create demo wallets
     println!(" Creating quantum-safe encrypted demo wallets...");
-    let wallet1 = QuantumWallet::new();
-    let wallet2 = QuantumWallet::new();
-    let wallet3 = QuantumWallet::new();
-    
-    // WARNING: Insecure password for demo ONLY! Never use in production!
-    const DEMO_PASSWORD: &str = "INSECURE_DEMO_PASSWORD_DO_NOT_USE_IN_PRODUCTION";
     println!("  Demo wallets use INSECURE password - FOR TESTING ONLY!");
-    
-    wallet1.save_quantum_safe("demo_wallet1.qua", DEMO_PASSWORD).unwrap();
-    wallet2.save_quantum_safe("demo_wallet2.qua", DEMO_PASSWORD).unwrap();
-    wallet3.save_quantum_safe("demo_wallet3.qua", DEMO_PASSWORD).unwrap();
+    let (wallet_dir, wallets) = create_demo_wallets(db_path).expect("Failed to create demo wallets");
+    let [wallet1, wallet2, wallet3] = wallets;
+    println!("  Demo wallets written to {}", wallet_dir.display());
     
     println!("\n  Mining genesis rewards...");
-    blockchain.write().await.mine_pending_transactions(wallet1.address.clone()).unwrap();
-    blockchain.write().await.mine_pending_transactions(wallet1.address.clone()).unwrap();
+    blockchain.write().await.mine_pending_transactions(wallet1.address.to_string()).unwrap();
+    blockchain.write().await.mine_pending_transactions(wallet1.address.to_string()).unwrap();
     
     println!("\n Creating transactions...");
     
     // Transaction 1: 25 QUA = 25_000_000 microunits
-    let amount1_microunits = qua_to_microunits(25.0);
+    let amount1_microunits = 25 * MICROUNITS_PER_QUA;
     let nonce1 = {
         let bc = blockchain.read().await;
         let nonce = bc.get_account_state_mut().get_nonce(&wallet1.address);
         nonce + 1
     };
     
-    let mut tx1 = Transaction {
-        sender: wallet1.address.clone(),
-        recipient: wallet2.address.clone(),
-        amount: amount1_microunits,
-        timestamp: Utc::now().timestamp(),
-        signature: vec![],
-        public_key: wallet1.keypair.public_key.clone(),
-        fee: 1000, // 0.001 QUA
-        nonce: nonce1,
-        tx_type: TransactionType::Transfer,
-    };
-    let signing_data1 = tx1.get_signing_data();
-    tx1.signature = wallet1.keypair.sign(&signing_data1);
+    let mut tx1 = Transaction::builder()
+        .recipient(wallet2.address.clone())
+        .amount(amount1_microunits)
+        .fee(1000) // 0.001 QUA
+        .nonce(nonce1)
+        .build();
+    wallet1.sign_transaction(&mut tx1);
     blockchain.write().await.add_transaction(tx1).unwrap();
     println!("   Tx 1: 25 QUA to wallet2 (nonce {})", nonce1);
     
     println!("\n  Mining first transaction...");
-    blockchain.write().await.mine_pending_transactions(wallet2.address.clone()).unwrap();
+    blockchain.write().await.mine_pending_transactions(wallet2.address.to_string()).unwrap();
     
     // Transaction 2: 15 QUA = 15_000_000 microunits
-    let amount2_microunits = qua_to_microunits(15.0);
+    let amount2_microunits = 15 * MICROUNITS_PER_QUA;
     let nonce2 = {
         let bc = blockchain.read().await;
         let nonce = bc.get_account_state_mut().get_nonce(&wallet1.address);
         nonce + 1
     };
     
-    let mut tx2 = Transaction {
-        sender: wallet1.address.clone(),
-        recipient: wallet3.address.clone(),
-        amount: amount2_microunits,
-        timestamp: Utc::now().timestamp(),
-        signature: vec![],
-        public_key: wallet1.keypair.public_key.clone(),
-        fee: 1000,
-        nonce: nonce2,
-        tx_type: TransactionType::Transfer,
-    };
-    let signing_data2 = tx2.get_signing_data();
-    tx2.signature = wallet1.keypair.sign(&signing_data2);
+    let mut tx2 = Transaction::builder()
+        .recipient(wallet3.address.clone())
+        .amount(amount2_microunits)
+        .fee(1000)
+        .nonce(nonce2)
+        .build();
+    wallet1.sign_transaction(&mut tx2);
     blockchain.write().await.add_transaction(tx2).unwrap();
     println!("   Tx 2: 15 QUA to wallet3 (nonce {})", nonce2);
     
     println!("\n  Mining second transaction...");
-    blockchain.write().await.mine_pending_transactions(wallet3.address.clone()).unwrap();
+    blockchain.write().await.mine_pending_transactions(wallet3.address.to_string()).unwrap();
     
     // Show final balances
     println!("\n Final Balances:");
     let bc = blockchain.read().await;
-    let bal1 = microunits_to_qua(bc.get_balance(&wallet1.address));
-    let bal2 = microunits_to_qua(bc.get_balance(&wallet2.address));
-    let bal3 = microunits_to_qua(bc.get_balance(&wallet3.address));
-    println!("  Wallet 1: {:.6} QUA", bal1);
-    println!("  Wallet 2: {:.6} QUA", bal2);
-    println!("  Wallet 3: {:.6} QUA", bal3);
+    let bal1 = format_qua(bc.get_balance(&wallet1.address));
+    let bal2 = format_qua(bc.get_balance(&wallet2.address));
+    let bal3 = format_qua(bc.get_balance(&wallet3.address));
+    println!("  Wallet 1: {} QUA", bal1);
+    println!("  Wallet 2: {} QUA", bal2);
+    println!("  Wallet 3: {} QUA", bal3);
     
     // Show stats
     let stats = bc.get_stats();
     println!("\n Blockchain Stats:");
     println!("  Blocks: {}", stats.chain_length);
     println!("  Transactions: {}", stats.total_transactions);
-    println!("  Total Supply: {:.6} QUA ({} microunits)", microunits_to_qua(stats.total_supply), stats.total_supply);
+    println!("  Accounts: {}", stats.account_count);
+    println!("  Total Supply: {} QUA ({} microunits)", format_qua(stats.total_supply), stats.total_supply);
     println!("  Current Difficulty: {}", stats.current_difficulty);
     
     // Validate
@@ -1116,8 +1337,68 @@ async fn run_demo(db_path: &str) {
     println!(" Blockchain persisted to: {}", db_path);
     println!(" All amounts stored as u64 microunits (deterministic)");
     println!(" Nonce-based replay protection enabled");
-    println!("  Demo wallets password: INSECURE_DEMO_PASSWORD_DO_NOT_USE_IN_PRODUCTION");
+    println!("  Demo wallets: {} (replaced on every demo run)", demo_wallet_dir(db_path).display());
+    println!("  Demo wallets password: {}", DEMO_PASSWORD);
     println!("  WARNING: Demo password is PUBLIC - delete wallets after testing!");
     println!("\n To start API server:");
     println!("   cargo run --release -- start --db {} --port 3000", db_path);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_entries(path: &std::path::Path) -> Vec<std::path::PathBuf> {
+        let mut entries: Vec<_> = std::fs::read_dir(path).unwrap().map(|e| e.unwrap().path()).collect();
+        entries.sort();
+        entries
+    }
+
+    #[test]
+    fn demo_wallets_stay_inside_demo_directory() {
+        let demo = tempfile::tempdir().unwrap();
+        let db_path = demo.path().join("quanta_demo");
+
+        // Run twice: the second run replaces the first run's wallets
+        create_demo_wallets(db_path.to_str().unwrap()).unwrap();
+        let (dir, _) = create_demo_wallets(db_path.to_str().unwrap()).unwrap();
+
+        // Nothing lands beside the database directory, only inside it
+        assert!(dir.starts_with(&db_path));
+        assert_eq!(dir_entries(&dir).len(), 3);
+        assert_eq!(dir_entries(demo.path()), vec![db_path]);
+    }
+
+    #[test]
+    fn json_log_format_writes_parseable_lines() {
+        #[derive(Clone, Default)]
+        struct Capture(Arc<std::sync::Mutex<Vec<u8>>>);
+        impl std::io::Write for Capture {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(buf);
+                Ok(buf.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let capture = Capture::default();
+        let writer = capture.clone();
+        let subscriber = log_subscriber(LogFormat::Json, move || writer.clone(), false);
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(height = 5, "Added new block");
+            tracing::warn!(peer = "203.0.113.1:8333", "Peer failed");
+        });
+
+        let output = String::from_utf8(capture.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["level"], "INFO");
+        assert_eq!(lines[0]["message"], "Added new block");
+        assert_eq!(lines[0]["height"], 5);
+        assert!(lines[0]["timestamp"].is_string());
+        assert_eq!(lines[1]["level"], "WARN");
+        assert_eq!(lines[1]["peer"], "203.0.113.1:8333");
+    }
+}
@@ -1,7 +1,8 @@
 use std::collections::HashMap;
-use std::net::{SocketAddr, ToSocketAddrs};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs};
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use crate::network::rng::{os_rng, NetworkRng};
 use tracing::{info, warn};
 
 /// Peer metadata for tracking peer health and source
@@ -22,11 +23,49 @@ pub enum PeerSource {
     Manual,
 }
 
+/// When repeated failures get a peer banned (see `security.peer_ban_*` in the config)
+#[derive(Clone, Debug, PartialEq)]
+pub struct BanPolicy {
+    /// Failures after which a peer with low reputation is banned
+    pub strike_failures: u32,
+    /// Reputation a peer must drop below for the strike rule to apply
+    pub strike_reputation: i32,
+    /// Failures after which a peer is banned regardless of reputation
+    pub max_failures: u32,
+    /// Ban length per `strike_failures` failures (longer bans for repeat offenders)
+    pub base_ban_secs: i64,
+}
+
+impl Default for BanPolicy {
+    fn default() -> Self {
+        Self {
+            strike_failures: 3,
+            strike_reputation: -20,
+            max_failures: 10,
+            base_ban_secs: 3600,
+        }
+    }
+}
+
+const DIAL_BACKOFF_BASE_SECS: i64 = 10; // Wait after the first failed dial
+const DIAL_BACKOFF_MAX_SECS: i64 = 30 * 60; // Cap for repeatedly failing addresses
+
+/// Redial schedule for one address: doubles after each consecutive failed dial
+#[derive(Clone, Debug, PartialEq)]
+pub struct DialBackoff {
+    pub failures: u32,
+    pub next_attempt: i64, // Unix timestamp before which the address is not redialed
+}
+
 /// Peer discovery mechanism
 pub struct PeerDiscovery {
     known_peers: Arc<RwLock<HashMap<SocketAddr, PeerMeta>>>,
     seed_nodes: Vec<SocketAddr>,
     dns_seeds: Vec<String>,
+    rng: NetworkRng,
+    ban_policy: BanPolicy,
+    dial_backoff: RwLock<HashMap<SocketAddr, DialBackoff>>,
+    banned_ips: RwLock<HashMap<IpAddr, i64>>, // Whole-IP bans (inbound floods) -> Unix expiry
 }
 
 impl PeerDiscovery {
@@ -36,6 +75,10 @@ impl PeerDiscovery {
             known_peers: Arc::new(RwLock::new(HashMap::new())),
             seed_nodes,
             dns_seeds: Vec::new(),
+            rng: os_rng(),
+            ban_policy: BanPolicy::default(),
+            dial_backoff: RwLock::new(HashMap::new()),
+            banned_ips: RwLock::new(HashMap::new()),
         }
     }
     
@@ -45,9 +88,26 @@ impl PeerDiscovery {
             known_peers: Arc::new(RwLock::new(HashMap::new())),
             seed_nodes,
             dns_seeds,
+            rng: os_rng(),
+            ban_policy: BanPolicy::default(),
+            dial_backoff: RwLock::new(HashMap::new()),
+            banned_ips: RwLock::new(HashMap::new()),
         }
     }
 
+    /// Replace the RNG used for peer selection (e.g. a seeded one in tests)
+    #[cfg(test)]
+    pub fn with_rng(mut self, rng: NetworkRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Replace the thresholds used by mark_peer_failed
+    pub fn with_ban_policy(mut self, ban_policy: BanPolicy) -> Self {
+        self.ban_policy = ban_policy;
+        self
+    }
+
     /// Resolve DNS seeds to socket addresses
     pub async fn resolve_dns_seeds(&self) -> Vec<SocketAddr> {
         let mut resolved = Vec::new();
@@ -134,11 +194,15 @@ impl PeerDiscovery {
             let reputation = meta.reputation;
             let is_seed = meta.source == PeerSource::Seed;
             
-            // Ban logic: 3 strikes with low reputation
-            if (failures > 3 && reputation < -20) || failures > 10 {
+            // Ban logic: strikes with low reputation, or too many failures outright
+            let policy = &self.ban_policy;
+            if (failures > policy.strike_failures && reputation < policy.strike_reputation)
+                || failures > policy.max_failures
+            {
                 if !is_seed {
-                    // Temporary ban: 1 hour for first ban, exponential backoff
-                    let ban_duration = 3600 * (failures as i64 / 3);
+                    // Temporary ban: one base duration per strike_failures failures
+                    let strikes = failures / policy.strike_failures.max(1);
+                    let ban_duration = policy.base_ban_secs * strikes as i64;
                     let ban_until = chrono::Utc::now().timestamp() + ban_duration;
                     meta.banned_until = Some(ban_until);
                     warn!("Peer {} BANNED until {} (reputation: {}, failures: {})", 
@@ -203,12 +267,56 @@ impl PeerDiscovery {
             healthy.extend(self.seed_nodes.iter().copied());
         }
         
-        let mut rng = rand::thread_rng();
-        healthy.shuffle(&mut rng);
+        // Fix the input order (HashMap iteration is arbitrary) so a seeded RNG is reproducible
+        healthy.sort();
+        healthy.dedup();
+        healthy.shuffle(&mut *self.rng.lock());
         
         healthy.into_iter().take(count).collect()
     }
     
+    /// Ban a peer for `duration_secs` (operator action: applies to seeds too)
+    pub async fn ban_peer(&self, addr: SocketAddr, duration_secs: i64) {
+        let mut peers = self.known_peers.write().await;
+        let now = chrono::Utc::now().timestamp();
+        let meta = peers.entry(addr).or_insert_with(|| PeerMeta {
+            address: addr,
+            last_seen: now,
+            failures: 0,
+            source: PeerSource::Manual,
+            reputation: 0,
+            banned_until: None,
+        });
+        meta.banned_until = Some(now + duration_secs);
+        warn!("Peer {} BANNED for {}s by operator", addr, duration_secs);
+    }
+
+    /// Ban every connection from `ip` for the policy's base ban duration (inbound
+    /// floods, whose source ports are ephemeral); returns the ban expiry
+    pub async fn ban_ip(&self, ip: IpAddr) -> i64 {
+        let now = chrono::Utc::now().timestamp();
+        let ban_until = now + self.ban_policy.base_ban_secs;
+        let mut banned = self.banned_ips.write().await;
+        banned.retain(|_, until| *until > now);
+        banned.insert(ip, ban_until);
+        warn!("IP {} BANNED until {} (connection flood)", ip, ban_until);
+        ban_until
+    }
+
+    /// Check if `ip` is banned, directly or through any banned peer at that IP
+    /// (inbound connections use ephemeral ports)
+    pub async fn is_ip_banned(&self, ip: IpAddr) -> bool {
+        let now = chrono::Utc::now().timestamp();
+        if self.banned_ips.read().await.get(&ip).is_some_and(|until| now < *until) {
+            return true;
+        }
+        self.known_peers
+            .read()
+            .await
+            .values()
+            .any(|meta| meta.address.ip() == ip && meta.banned_until.is_some_and(|until| now < until))
+    }
+
     /// Check if peer is currently banned
     pub async fn is_banned(&self, addr: &SocketAddr) -> bool {
         let peers = self.known_peers.read().await;
@@ -221,6 +329,39 @@ impl PeerDiscovery {
         false
     }
 
+    /// Whether `addr` may be dialed now: not banned and not waiting out a dial backoff
+    pub async fn dial_due(&self, addr: &SocketAddr) -> bool {
+        self.dial_due_at(addr, chrono::Utc::now().timestamp()).await
+    }
+
+    async fn dial_due_at(&self, addr: &SocketAddr, now: i64) -> bool {
+        if self.is_banned(addr).await {
+            return false;
+        }
+        self.dial_backoff.read().await.get(addr).is_none_or(|backoff| now >= backoff.next_attempt)
+    }
+
+    /// Push back the next dial of `addr` after a failed attempt; returns the delay in seconds
+    pub async fn record_dial_failure(&self, addr: SocketAddr) -> i64 {
+        self.record_dial_failure_at(addr, chrono::Utc::now().timestamp()).await
+    }
+
+    async fn record_dial_failure_at(&self, addr: SocketAddr, now: i64) -> i64 {
+        let mut backoffs = self.dial_backoff.write().await;
+        let backoff = backoffs.entry(addr).or_insert(DialBackoff { failures: 0, next_attempt: now });
+        backoff.failures += 1;
+        let delay = DIAL_BACKOFF_BASE_SECS
+            .saturating_mul(1i64 << (backoff.failures - 1).min(20))
+            .min(DIAL_BACKOFF_MAX_SECS);
+        backoff.next_attempt = now + delay;
+        delay
+    }
+
+    /// A successful dial clears the backoff, so the next reconnect is immediate
+    pub async fn record_dial_success(&self, addr: SocketAddr) {
+        self.dial_backoff.write().await.remove(&addr);
+    }
+
     /// Bootstrap discovery from seed nodes (deduplicated)
     pub async fn bootstrap(&self) -> Vec<SocketAddr> {
         let mut peers = self.known_peers.write().await;
@@ -301,3 +442,114 @@ fn is_routable_addr(addr: &SocketAddr) -> bool {
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::rng::seeded_rng;
+
+    async fn discovery_with_seed(seed: u64) -> PeerDiscovery {
+        let discovery = PeerDiscovery::new(Vec::new()).with_rng(seeded_rng(seed));
+        for port in 9000..9020 {
+            discovery.add_peer(SocketAddr::from(([127, 0, 0, 1], port))).await;
+        }
+        discovery
+    }
+
+    #[tokio::test]
+    async fn seeded_rng_gives_deterministic_peer_selection() {
+        let first = discovery_with_seed(42).await;
+        let second = discovery_with_seed(42).await;
+
+        let picks_a = first.get_random_peers(8).await;
+        let picks_b = second.get_random_peers(8).await;
+        assert_eq!(picks_a.len(), 8);
+        assert_eq!(picks_a, picks_b);
+
+        // Subsequent draws stay in lockstep too
+        assert_eq!(first.get_random_peers(8).await, second.get_random_peers(8).await);
+    }
+
+    #[tokio::test]
+    async fn tightened_ban_policy_bans_after_fewer_failures() {
+        let addr = SocketAddr::from(([10, 0, 0, 1], 8333));
+        let strict = PeerDiscovery::new(Vec::new()).with_ban_policy(BanPolicy {
+            strike_failures: 1,
+            strike_reputation: -5,
+            max_failures: 3,
+            base_ban_secs: 60,
+        });
+        let default = PeerDiscovery::new(Vec::new());
+
+        for discovery in [&strict, &default] {
+            discovery.add_peer(addr).await;
+            discovery.mark_peer_failed(addr).await;
+            discovery.mark_peer_failed(addr).await;
+        }
+
+        // Two failures put reputation at -10: banned under the strict policy only
+        assert!(strict.is_banned(&addr).await);
+        assert!(!default.is_banned(&addr).await);
+
+        let now = chrono::Utc::now().timestamp();
+        let banned_until = strict.get_peer_meta(&addr).await.unwrap().banned_until.unwrap();
+        assert!(banned_until > now && banned_until <= now + 2 * 60);
+    }
+
+    #[tokio::test]
+    async fn failing_bootstrap_is_retried_with_growing_delays() {
+        let addr = SocketAddr::from(([203, 0, 113, 5], 8333));
+        let discovery = PeerDiscovery::new(vec![addr]);
+        let mut now = 1_000_000;
+
+        // Never failed: dialed right away
+        assert!(discovery.dial_due_at(&addr, now).await);
+
+        let mut delays = Vec::new();
+        for _ in 0..12 {
+            let delay = discovery.record_dial_failure_at(addr, now).await;
+            assert!(!discovery.dial_due_at(&addr, now + delay - 1).await);
+            now += delay;
+            assert!(discovery.dial_due_at(&addr, now).await);
+            delays.push(delay);
+        }
+        assert_eq!(&delays[..4], &[10, 20, 40, 80]);
+        assert!(delays.windows(2).all(|pair| pair[1] >= pair[0]));
+        assert_eq!(*delays.last().unwrap(), DIAL_BACKOFF_MAX_SECS);
+
+        // One successful dial makes the next reconnect prompt again
+        discovery.record_dial_failure_at(addr, now).await;
+        discovery.record_dial_success(addr).await;
+        assert!(discovery.dial_due_at(&addr, now).await);
+
+        // A banned address is not dialed whatever its backoff
+        discovery.ban_peer(addr, 3600).await;
+        assert!(!discovery.dial_due(&addr).await);
+    }
+
+    #[tokio::test]
+    async fn flood_ban_follows_the_ban_policy() {
+        let flooder = IpAddr::from([198, 51, 100, 7]);
+        let discovery = PeerDiscovery::new(Vec::new()).with_ban_policy(BanPolicy {
+            base_ban_secs: 120,
+            ..BanPolicy::default()
+        });
+
+        let now = chrono::Utc::now().timestamp();
+        let ban_until = discovery.ban_ip(flooder).await;
+        assert!(ban_until >= now + 120 && ban_until <= now + 121);
+        assert!(discovery.is_ip_banned(flooder).await);
+        assert!(!discovery.is_ip_banned(IpAddr::from([198, 51, 100, 8])).await);
+    }
+
+    #[tokio::test]
+    async fn resolved_dns_seeds_are_recorded_as_seed_peers() {
+        let discovery = PeerDiscovery::with_dns_seeds(Vec::new(), vec!["127.0.0.1".to_string(), "127.0.0.2:9333".to_string()]);
+
+        let resolved = discovery.resolve_dns_seeds().await;
+        let expected = [SocketAddr::from(([127, 0, 0, 1], 8333)), SocketAddr::from(([127, 0, 0, 2], 9333))];
+        assert_eq!(resolved, expected);
+        for addr in expected {
+            assert_eq!(discovery.get_peer_meta(&addr).await.unwrap().source, PeerSource::Seed);
+        }
+    }
+}
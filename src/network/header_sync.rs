@@ -0,0 +1,369 @@
+use crate::consensus::activation::ActivationHeights;
+use crate::core::block::{Block, BlockHeader};
+use std::net::SocketAddr;
+use thiserror::Error;
+
+/// Blocks requested per GetBlocks batch once the header chain is trusted
+/// (below the 100-block window handle_new_block accepts ahead of our tip)
+pub const SYNC_BLOCK_BATCH: u64 = 64;
+/// How far below our tip headers are re-requested to find where a diverged peer forks off
+pub const FORK_SEARCH_DEPTH: u64 = 100;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum HeaderSyncError {
+    #[error("header chain does not extend our tip at height {height} (peer is on another branch)")]
+    Diverged { height: u64 },
+    #[error("header at height {got} where {expected} was expected")]
+    NotContiguous { expected: u64, got: u64 },
+    #[error("header {height} does not link to the header before it")]
+    BrokenLink { height: u64 },
+    #[error("header {height} has version {got} where version {expected} is required")]
+    UnexpectedVersion { height: u64, expected: u32, got: u32 },
+    #[error("header {height} states difficulty {got} where the schedule requires {expected}")]
+    UnexpectedDifficulty { height: u64, expected: u32, got: u32 },
+    #[error("header {height} hash is not the hash of its fields")]
+    WrongHash { height: u64 },
+    #[error("header {height} hash does not meet its stated difficulty")]
+    InsufficientWork { height: u64 },
+    #[error("block {height} hash {got} does not match the trusted header {expected}")]
+    HashMismatch { height: u64, expected: String, got: String },
+}
+
+/// Headers-first sync from one peer: the header chain is downloaded and checked
+/// (linkage, version, difficulty schedule and hash) before any block body is
+/// requested, and every body is then matched against its already-trusted header
+///
+/// From the header_hash activation height a header's hash is recomputed from its
+/// fields, so meeting the target is real proof of work. Below it the hash covers the
+/// transactions and is only the peer's word until the body arrives: the target check
+/// there only catches headers inconsistent with their stated difficulty
+#[derive(Debug)]
+pub struct HeaderSync {
+    peer: SocketAddr,
+    peer_height: u64,           // Chain height the peer advertised; headers past it are dropped
+    base: u64,                  // Height of the first header from the peer (our chain height at the start)
+    headers: Vec<BlockHeader>,  // Our recent headers, then the validated ones from the peer, contiguous
+    own: usize,                 // How many of `headers` are ours
+    next_difficulty: fn(&[BlockHeader]) -> u32,
+    activations: ActivationHeights, // Which header version (hash format) each height must carry
+    next_body: u64,             // Lowest height whose body has not been requested yet
+    locating_fork: bool,        // Diverged: waiting for headers from below our tip
+}
+
+impl HeaderSync {
+    /// Start syncing from `peer`, which advertised `peer_height`, on top of `recent`: our
+    /// headers up to the tip, from genesis or spanning at least one adjustment window.
+    /// `next_difficulty` gives the difficulty due after a header run (next_difficulty_after)
+    pub fn new(
+        peer: SocketAddr,
+        recent: Vec<BlockHeader>,
+        peer_height: u64,
+        next_difficulty: fn(&[BlockHeader]) -> u32,
+        activations: ActivationHeights,
+    ) -> Self {
+        let base = recent.last().map_or(0, |tip| tip.index + 1);
+        Self {
+            peer,
+            peer_height,
+            base,
+            own: recent.len(),
+            headers: recent,
+            next_difficulty,
+            activations,
+            next_body: base,
+            locating_fork: false,
+        }
+    }
+
+    pub fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+
+    /// Height the next GetHeaders should start from
+    pub fn next_header_height(&self) -> u64 {
+        self.base + (self.headers.len() - self.own) as u64
+    }
+
+    /// Every header up to the peer's advertised height is in
+    pub fn has_all_headers(&self) -> bool {
+        self.next_header_height() >= self.peer_height
+    }
+
+    /// Validate `headers` as the continuation of the trusted chain and append them,
+    /// up to the peer's advertised height. The whole batch is rejected if any header is
+    /// out of sequence, fails to link, has the wrong version for its height, states a
+    /// difficulty other than the schedule's, isn't the hash of its fields (where that
+    /// can be recomputed), or has a hash above its difficulty target
+    pub fn accept_headers(&mut self, headers: Vec<BlockHeader>) -> Result<usize, HeaderSyncError> {
+        let before = self.headers.len();
+        let room = self.peer_height.saturating_sub(self.next_header_height());
+
+        for header in headers.into_iter().take(room as usize) {
+            if let Err(e) = self.check_header(&header) {
+                self.headers.truncate(before);
+                return Err(e);
+            }
+            self.headers.push(header);
+        }
+        Ok(self.headers.len() - before)
+    }
+
+    fn check_header(&self, header: &BlockHeader) -> Result<(), HeaderSyncError> {
+        let height = self.next_header_height();
+        if header.index != height {
+            return Err(HeaderSyncError::NotContiguous { expected: height, got: header.index });
+        }
+        if self.headers.last().map(|parent| parent.hash.as_str()) != Some(header.previous_hash.as_str()) {
+            return Err(if height == self.base {
+                HeaderSyncError::Diverged { height }
+            } else {
+                HeaderSyncError::BrokenLink { height }
+            });
+        }
+        let expected = self.activations.block_version(height);
+        if header.version != expected {
+            return Err(HeaderSyncError::UnexpectedVersion { height, expected, got: header.version });
+        }
+        let expected = (self.next_difficulty)(&self.headers);
+        if header.difficulty != expected {
+            return Err(HeaderSyncError::UnexpectedDifficulty { height, expected, got: header.difficulty });
+        }
+        if header.calculate_hash().is_some_and(|hash| hash != header.hash) {
+            return Err(HeaderSyncError::WrongHash { height });
+        }
+        if !header.meets_difficulty() {
+            return Err(HeaderSyncError::InsufficientWork { height });
+        }
+        Ok(())
+    }
+
+    /// The peer's chain forks below our tip: switch to locating the fork point and
+    /// return the height to request headers from
+    pub fn start_fork_search(&mut self) -> u64 {
+        self.locating_fork = true;
+        self.base.saturating_sub(FORK_SEARCH_DEPTH)
+    }
+
+    pub fn is_locating_fork(&self) -> bool {
+        self.locating_fork
+    }
+
+    /// Inclusive range of blocks to fetch from the peer given its headers from below
+    /// our tip: from the first one `our_hash_at` doesn't have, up to the peer's height
+    /// but no further than `max_ahead` past our tip. None if no header differs
+    pub fn fork_blocks(
+        &self,
+        headers: &[BlockHeader],
+        our_hash_at: impl Fn(u64) -> Option<String>,
+        max_ahead: u64,
+    ) -> Option<(u64, u64)> {
+        let fork = headers
+            .iter()
+            .find(|header| our_hash_at(header.index).as_deref() != Some(header.hash.as_str()))?;
+        let last = self.peer_height.checked_sub(1)?.min(self.base.saturating_sub(1) + max_ahead);
+        (fork.index <= last).then_some((fork.index, last))
+    }
+
+    /// Check a block body from the sync peer against its trusted header (blocks
+    /// outside the downloaded range are left to normal validation)
+    pub fn check_block(&self, block: &Block) -> Result<(), HeaderSyncError> {
+        let Some(header) = block
+            .index
+            .checked_sub(self.base)
+            .and_then(|offset| self.headers.get(self.own + offset as usize))
+        else {
+            return Ok(());
+        };
+        if header.hash != block.hash {
+            return Err(HeaderSyncError::HashMismatch {
+                height: block.index,
+                expected: header.hash.clone(),
+                got: block.hash.to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Next inclusive range of bodies to request, once everything requested so far has
+    /// connected (`chain_height` has caught up); None while a batch is in flight or when done
+    pub fn next_body_batch(&mut self, chain_height: u64) -> Option<(u64, u64)> {
+        let end = self.next_header_height();
+        if chain_height < self.next_body || chain_height >= end {
+            return None;
+        }
+        let last = (chain_height + SYNC_BLOCK_BATCH).min(end) - 1;
+        self.next_body = last + 1;
+        Some((chain_height, last))
+    }
+
+    /// Every header's body has connected
+    pub fn is_complete(&self, chain_height: u64) -> bool {
+        chain_height >= self.next_header_height()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::blockchain::next_difficulty_after;
+    use crate::core::block::{HEADER_HASH_BLOCK_VERSION, LEGACY_BLOCK_VERSION};
+    use crate::core::ChainNetwork;
+
+    /// Heights for tests: every header carries a recomputable hash
+    const HEADER_HASHES: ActivationHeights = ActivationHeights::all_at(0);
+
+    /// Header linked to `parent`, mined (by hash grinding) to meet `difficulty`
+    fn mined_header(index: u64, parent: &str, difficulty: u32) -> BlockHeader {
+        mine(BlockHeader {
+            index,
+            timestamp: 1_700_000_000 + index as i64,
+            previous_hash: parent.to_string(),
+            hash: String::new(),
+            nonce: 0,
+            difficulty,
+            merkle_root: "0".repeat(64),
+            version: HEADER_HASH_BLOCK_VERSION,
+        })
+    }
+
+    fn mine(mut header: BlockHeader) -> BlockHeader {
+        loop {
+            header.hash = header.calculate_hash().unwrap().into();
+            if header.meets_difficulty() {
+                return header;
+            }
+            header.nonce += 1;
+        }
+    }
+
+    fn header_chain(base: u64, parent: &str, count: u64, difficulty: u32) -> Vec<BlockHeader> {
+        let mut parent = parent.to_string();
+        (base..base + count)
+            .map(|index| {
+                let header = mined_header(index, &parent, difficulty);
+                parent = header.hash.clone();
+                header
+            })
+            .collect()
+    }
+
+    /// Difficulty schedule for chains mined at difficulty 1 in tests
+    fn flat(_: &[BlockHeader]) -> u32 {
+        1
+    }
+
+    #[test]
+    fn header_chain_with_insufficient_pow_is_rejected_before_bodies() {
+        let genesis = BlockHeader::from(&Block::genesis(ChainNetwork::Testnet));
+        let peer: SocketAddr = "10.0.0.9:8333".parse().unwrap();
+        let new_sync = |peer_height| HeaderSync::new(peer, vec![genesis.clone()], peer_height, flat, HEADER_HASHES);
+
+        // A header claiming more work than the schedule asks for: nothing is trusted, no body requested
+        let mut sync = new_sync(1_000);
+        let mut forged = header_chain(1, &genesis.hash, 3, 1);
+        forged[1].difficulty = 60;
+        assert_eq!(
+            sync.accept_headers(forged),
+            Err(HeaderSyncError::UnexpectedDifficulty { height: 2, expected: 1, got: 60 })
+        );
+        assert_eq!(sync.next_header_height(), 1);
+        assert_eq!(sync.next_body_batch(1), None);
+
+        // The right difficulty with a hash that doesn't meet it
+        let mut unmined = header_chain(1, &genesis.hash, 3, 1);
+        while unmined[2].meets_difficulty() {
+            unmined[2].nonce += 1;
+            unmined[2].hash = unmined[2].calculate_hash().unwrap().into();
+        }
+        assert_eq!(sync.accept_headers(unmined), Err(HeaderSyncError::InsufficientWork { height: 3 }));
+
+        // Gaps and broken links are refused too, and a chain off another tip is a divergence
+        let mut gapped = header_chain(1, &genesis.hash, 3, 1);
+        gapped.remove(1);
+        assert_eq!(sync.accept_headers(gapped), Err(HeaderSyncError::NotContiguous { expected: 2, got: 3 }));
+        assert_eq!(
+            sync.accept_headers(header_chain(1, &"ab".repeat(32), 2, 1)),
+            Err(HeaderSyncError::Diverged { height: 1 })
+        );
+
+        // A valid chain is trusted and its bodies fetched in batches, one batch at a time
+        let headers = header_chain(1, &genesis.hash, SYNC_BLOCK_BATCH + 10, 1);
+        assert_eq!(sync.accept_headers(headers.clone()).unwrap(), headers.len());
+        assert_eq!(sync.next_body_batch(1), Some((1, SYNC_BLOCK_BATCH)));
+        assert_eq!(sync.next_body_batch(10), None);
+        assert_eq!(sync.next_body_batch(SYNC_BLOCK_BATCH + 1), Some((SYNC_BLOCK_BATCH + 1, SYNC_BLOCK_BATCH + 10)));
+        assert!(sync.is_complete(SYNC_BLOCK_BATCH + 11));
+
+        // A body whose hash differs from its trusted header is caught
+        let mut body = Block::genesis(ChainNetwork::Testnet);
+        body.index = 2;
+        assert!(matches!(sync.check_block(&body), Err(HeaderSyncError::HashMismatch { height: 2, .. })));
+
+        // Headers past the peer's advertised height are dropped
+        let mut capped = new_sync(4);
+        assert_eq!(capped.accept_headers(header_chain(1, &genesis.hash, 10, 1)).unwrap(), 3);
+        assert_eq!(capped.next_header_height(), 4);
+        assert!(capped.has_all_headers());
+    }
+
+    #[test]
+    fn stated_difficulty_must_follow_the_retarget_schedule() {
+        // Ten easy headers from genesis: the eleventh falls on a retarget, which never
+        // goes below the minimum difficulty of 4
+        let recent = header_chain(0, "", 10, 1);
+        let peer: SocketAddr = "10.0.0.9:8333".parse().unwrap();
+        let tip = recent.last().unwrap().hash.clone();
+        let mut sync = HeaderSync::new(peer, recent, 1_000, next_difficulty_after, HEADER_HASHES);
+
+        assert_eq!(
+            sync.accept_headers(header_chain(10, &tip, 1, 1)),
+            Err(HeaderSyncError::UnexpectedDifficulty { height: 10, expected: 4, got: 1 })
+        );
+        assert_eq!(sync.next_header_height(), 10);
+    }
+
+    #[test]
+    fn diverged_peer_is_fetched_from_the_fork_point() {
+        let ours = header_chain(0, "", 8, 1);
+        let peer: SocketAddr = "10.0.0.9:8333".parse().unwrap();
+        let mut sync = HeaderSync::new(peer, ours.clone(), 12, flat, HEADER_HASHES);
+
+        // The peer's chain shares our first five blocks and then goes its own way
+        let mut theirs = ours[..5].to_vec();
+        theirs.push(mine(BlockHeader { timestamp: ours[5].timestamp + 1, nonce: 0, ..ours[5].clone() }));
+        theirs.extend(header_chain(6, &theirs[5].hash, 6, 1));
+        assert_eq!(sync.accept_headers(theirs[8..].to_vec()), Err(HeaderSyncError::Diverged { height: 8 }));
+
+        assert_eq!(sync.start_fork_search(), 0);
+        assert!(sync.is_locating_fork());
+        let our_hash_at = |height: u64| ours.get(height as usize).map(|header| header.hash.clone());
+        assert_eq!(sync.fork_blocks(&theirs, our_hash_at, 100), Some((5, 11)));
+        assert_eq!(sync.fork_blocks(&theirs, our_hash_at, 2), Some((5, 9)));
+        assert_eq!(sync.fork_blocks(&ours, our_hash_at, 100), None);
+    }
+
+    #[test]
+    fn made_up_hashes_are_rejected_once_headers_commit_to_their_fields() {
+        let genesis = BlockHeader::from(&Block::genesis(ChainNetwork::Testnet));
+        let peer: SocketAddr = "10.0.0.9:8333".parse().unwrap();
+
+        // A hash that meets the target but isn't the hash of the header costs nothing to make
+        let mut sync = HeaderSync::new(peer, vec![genesis.clone()], 1_000, flat, HEADER_HASHES);
+        let mut forged = header_chain(1, &genesis.hash, 2, 1);
+        forged[1].hash = "0".repeat(64);
+        assert_eq!(sync.accept_headers(forged), Err(HeaderSyncError::WrongHash { height: 2 }));
+        assert_eq!(sync.next_header_height(), 1);
+
+        // Nor can a peer fall back to legacy headers, whose hash can't be recomputed
+        let legacy = BlockHeader { version: LEGACY_BLOCK_VERSION, ..header_chain(1, &genesis.hash, 1, 1).remove(0) };
+        assert_eq!(
+            sync.accept_headers(vec![legacy.clone()]),
+            Err(HeaderSyncError::UnexpectedVersion { height: 1, expected: HEADER_HASH_BLOCK_VERSION, got: LEGACY_BLOCK_VERSION })
+        );
+
+        // Below the activation height the stated hash is only checked against the target
+        let mut legacy_sync = HeaderSync::new(peer, vec![genesis.clone()], 1_000, flat, ActivationHeights::all_at(100));
+        let made_up = BlockHeader { hash: format!("0{}", "f".repeat(63)), ..legacy };
+        assert_eq!(legacy_sync.accept_headers(vec![made_up]), Ok(1));
+    }
+}
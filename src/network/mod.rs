@@ -1,7 +1,12 @@
 pub mod peer;
 pub mod discovery;
+pub mod header_sync;
 pub mod network;
+pub mod noise;
 pub mod protocol;
+pub mod rng;
+pub mod snapshot_sync;
+pub mod throttle;
 
 pub use peer::{Peer, PeerManager};
 pub use discovery::PeerDiscovery;
@@ -1,15 +1,30 @@
-use crate::core::block::Block;
-use crate::consensus::blockchain::Blockchain;
-use crate::network::peer::{Peer, PeerManager};
-use crate::network::protocol::{P2PMessage, PROTOCOL_VERSION};
+use crate::core::block::{Block, BlockHeader};
+use crate::core::snapshot::{latest_checkpoint_at, StateSnapshot};
+use crate::consensus::blockchain::{next_difficulty_after, run_blocking, Blockchain};
+use crate::network::discovery::{BanPolicy, PeerDiscovery, PeerSource};
+use crate::network::noise::{EncryptionMode, KeyPins, PeerEncryption};
+use crate::network::peer::{Peer, PeerDirection, PeerManager};
+use crate::network::protocol::{
+    Capabilities, P2PMessage, RejectReason, MAX_ADDR_PER_MESSAGE, MAX_HEADERS_PER_MESSAGE, PROTOCOL_VERSION,
+};
+use crate::network::rng::{os_rng, NetworkRng};
+use crate::network::header_sync::{HeaderSync, HeaderSyncError};
+use crate::network::snapshot_sync::{chunk, chunk_count, SnapshotDownload};
+use crate::network::throttle::{AcceptDecision, AcceptThrottle, OrphanRequestDecision, OrphanRequestThrottle};
 use crate::core::transaction::Transaction;
-use std::net::{SocketAddr, ToSocketAddrs};
+use crate::core::TxHash;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use tokio::net::{TcpListener, TcpStream};
 use tokio::sync::{mpsc, RwLock};
 use tokio::time::{interval, Duration};
 use tracing::{debug, error, info, warn};
 use uuid::Uuid;
+use rand::RngCore;
+
+/// How far past our tip a block from a peer may be (farther ones are rejected)
+const MAX_BLOCKS_AHEAD: u64 = 100;
 
 /// Network configuration
 #[derive(Clone, Debug)]
@@ -19,6 +34,19 @@ pub struct NetworkConfig {
     pub node_id: String,
     pub bootstrap_nodes: Vec<SocketAddr>,
     pub dns_seeds: Vec<String>,
+    pub max_inbound_per_ip_per_sec: u32,
+    pub max_inbound_per_sec: u32,
+    /// Fetch a verified state snapshot at the latest snapshot checkpoint instead of
+    /// downloading every block from genesis
+    pub snapshot_sync: bool,
+    /// When repeatedly failing peers get banned
+    pub ban_policy: BanPolicy,
+    /// Whether connections negotiate Noise encryption
+    pub encryption: EncryptionMode,
+    /// Orphan-driven parent requests a single peer may have outstanding
+    pub max_orphan_requests_per_peer: u32,
+    /// Connected peers required before mining (0 = mine regardless)
+    pub min_peers_to_mine: usize,
 }
 
 impl Default for NetworkConfig {
@@ -29,6 +57,13 @@ impl Default for NetworkConfig {
             node_id: Uuid::new_v4().to_string(),
             bootstrap_nodes: Vec::new(),
             dns_seeds: Vec::new(),
+            max_inbound_per_ip_per_sec: 5,
+            max_inbound_per_sec: 50,
+            snapshot_sync: false,
+            ban_policy: BanPolicy::default(),
+            encryption: EncryptionMode::default(),
+            max_orphan_requests_per_peer: 8,
+            min_peers_to_mine: 0,
         }
     }
 }
@@ -40,22 +75,66 @@ pub struct Network {
     peer_manager: Arc<PeerManager>,
     message_tx: mpsc::UnboundedSender<(SocketAddr, P2PMessage)>,
     message_rx: Arc<RwLock<mpsc::UnboundedReceiver<(SocketAddr, P2PMessage)>>>,
+    rng: NetworkRng,
+    accept_throttle: parking_lot::Mutex<AcceptThrottle>,
+    orphan_requests: parking_lot::Mutex<OrphanRequestThrottle>,
+    discovery: PeerDiscovery, // Known peers and bans (manual add/ban via admin RPC)
+    encryption: PeerEncryption, // Noise static key (fresh unless set by with_encryption)
+    key_pins: KeyPins, // Expected static keys of outbound peers
+    mining_paused: AtomicBool, // Below min_peers_to_mine at the last ready_to_mine check
+    header_sync: parking_lot::Mutex<Option<HeaderSync>>, // Headers-first sync in progress, if any
+    snapshot_cache: tokio::sync::Mutex<Option<(u64, Arc<Vec<u8>>)>>, // Serialized snapshot we serve, by height
+    snapshot_download: parking_lot::Mutex<Option<SnapshotDownload>>, // Snapshot being fetched, if any
 }
 
 impl Network {
     /// Create a new network instance
     pub fn new(config: NetworkConfig, blockchain: Arc<RwLock<Blockchain>>) -> Self {
         let (message_tx, message_rx) = mpsc::unbounded_channel();
+        let accept_throttle = AcceptThrottle::new(
+            config.max_inbound_per_ip_per_sec,
+            config.max_inbound_per_sec,
+        );
         
         Self {
-            config,
             blockchain,
             peer_manager: Arc::new(PeerManager::new(125)),
             message_tx,
             message_rx: Arc::new(RwLock::new(message_rx)),
+            rng: os_rng(),
+            accept_throttle: parking_lot::Mutex::new(accept_throttle),
+            orphan_requests: parking_lot::Mutex::new(OrphanRequestThrottle::new(config.max_orphan_requests_per_peer)),
+            discovery: PeerDiscovery::with_dns_seeds(config.bootstrap_nodes.clone(), config.dns_seeds.clone())
+                .with_ban_policy(config.ban_policy.clone()),
+            encryption: PeerEncryption::new(config.encryption),
+            key_pins: KeyPins::default(),
+            mining_paused: AtomicBool::new(false),
+            header_sync: parking_lot::Mutex::new(None),
+            snapshot_cache: tokio::sync::Mutex::new(None),
+            snapshot_download: parking_lot::Mutex::new(None),
+            config,
         }
     }
 
+    /// Replace the RNG used for ping nonces (e.g. a seeded one in tests)
+    #[cfg(test)]
+    pub fn with_rng(mut self, rng: NetworkRng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    /// Use a persistent Noise static key and pinned peer keys
+    pub fn with_encryption(mut self, encryption: PeerEncryption, key_pins: KeyPins) -> Self {
+        self.encryption = encryption;
+        self.key_pins = key_pins;
+        self
+    }
+
+    /// Draw the next ping nonce from the network RNG
+    fn next_nonce(&self) -> u64 {
+        self.rng.lock().next_u64()
+    }
+
     /// Start the network node
     pub async fn start(self: Arc<Self>) -> Result<(), String> {
         info!("Starting network node on {}", self.config.listen_addr);
@@ -82,7 +161,7 @@ impl Network {
         let maintenance_handle = {
             let network = Arc::clone(&self);
             tokio::spawn(async move {
-                network.maintain_peers().await;
+                Arc::clone(&network).maintain_peers().await;
             })
         };
         
@@ -99,28 +178,17 @@ impl Network {
             })
         };
 
-        // Resolve DNS seeds to get additional bootstrap nodes
+        // Resolve DNS seeds to get additional bootstrap nodes (recorded as Seed peers)
         if !self.config.dns_seeds.is_empty() {
             info!("Resolving {} DNS seeds...", self.config.dns_seeds.len());
-            for dns_seed in &self.config.dns_seeds {
-                let network = Arc::clone(&self);
-                let seed = dns_seed.clone();
-                tokio::spawn(async move {
-                    match network.resolve_dns_seed(&seed).await {
-                        Ok(addrs) => {
-                            info!("DNS seed {} resolved to {} peers", seed, addrs.len());
-                            for addr in addrs {
-                                if let Err(e) = network.connect_to_peer(addr).await {
-                                    debug!("Failed to connect to DNS peer {}: {}", addr, e);
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            warn!("Failed to resolve DNS seed {}: {}", seed, e);
-                        }
+            let network = Arc::clone(&self);
+            tokio::spawn(async move {
+                for addr in network.discovery.resolve_dns_seeds().await {
+                    if let Err(e) = network.connect_to_peer(addr).await {
+                        debug!("Failed to connect to DNS peer {}: {}", addr, e);
                     }
-                });
-            }
+                }
+            });
         }
 
         // Connect to bootstrap nodes
@@ -153,21 +221,41 @@ impl Network {
         loop {
             match listener.accept().await {
                 Ok((stream, addr)) => {
+                    // Drop floods before spending a task or handshake on them
+                    let decision = self.accept_throttle.lock().check(addr.ip());
+                    if decision == AcceptDecision::Flood {
+                        self.discovery.ban_ip(addr.ip()).await;
+                    }
+                    if decision != AcceptDecision::Allow {
+                        debug!("Dropping inbound connection from {} ({:?})", addr, decision);
+                        drop(stream);
+                        continue;
+                    }
+                    if self.discovery.is_ip_banned(addr.ip()).await {
+                        debug!("Dropping inbound connection from banned {}", addr);
+                        drop(stream);
+                        continue;
+                    }
+                    
                     info!("Incoming connection from {}", addr);
                     
                     let message_tx = self.message_tx.clone();
                     let peer_manager = Arc::clone(&self.peer_manager);
                     let blockchain = Arc::clone(&self.blockchain);
                     let node_id = self.config.node_id.clone();
+                    let encryption = self.encryption.clone();
                     
                     tokio::spawn(async move {
-                        match Peer::new(stream, addr).await {
+                        match Peer::negotiate(stream, addr, PeerDirection::Inbound, &encryption).await {
                             Ok(peer) => {
                                 let peer = Arc::new(peer);
                                 
                                 // Perform handshake
-                                let height = blockchain.read().await.get_chain().len() as u64;
-                                if let Ok(_) = peer.handshake(PROTOCOL_VERSION, height, node_id).await {
+                                let (height, earliest) = {
+                                    let bc = blockchain.read().await;
+                                    (bc.get_height(), bc.earliest_available_height())
+                                };
+                                if let Ok(_) = peer.handshake(PROTOCOL_VERSION, height, earliest, node_id, Capabilities::LOCAL).await {
                                     // Add peer and start receive task
                                     if peer_manager.add_peer(Arc::clone(&peer)).await.is_ok() {
                                         Self::start_peer_receive_task(peer, message_tx, peer_manager).await;
@@ -214,40 +302,27 @@ impl Network {
         });
     }
 
-    /// Resolve DNS seed to socket addresses
-    async fn resolve_dns_seed(&self, dns_seed: &str) -> Result<Vec<SocketAddr>, String> {
-        let lookup_addr = if dns_seed.contains(':') {
-            dns_seed.to_string()
-        } else {
-            format!("{}:8333", dns_seed) // Default Quanta P2P port
-        };
-        
-        tokio::task::spawn_blocking(move || {
-            lookup_addr
-                .to_socket_addrs()
-                .map(|addrs| addrs.collect())
-                .map_err(|e| format!("DNS resolution failed: {}", e))
-        })
-        .await
-        .map_err(|e| format!("Task join error: {}", e))?
-    }
-
     /// Connect to a peer
     pub async fn connect_to_peer(&self, addr: SocketAddr) -> Result<(), String> {
+        if self.discovery.is_banned(&addr).await {
+            return Err(format!("Peer {} is banned", addr));
+        }
         info!("Connecting to peer {}", addr);
         
         let stream = TcpStream::connect(addr)
             .await
             .map_err(|e| format!("Failed to connect: {}", e))?;
         
-        let peer = Arc::new(Peer::new(stream, addr).await?);
+        let peer = Arc::new(Peer::negotiate(stream, addr, PeerDirection::Outbound, &self.encryption).await?);
+        self.key_pins.check(addr, peer.get_info().await.remote_static_key.as_deref())?;
         
         // Perform handshake
         let blockchain = self.blockchain.read().await;
-        let height = blockchain.get_chain().len() as u64;
+        let height = blockchain.get_height();
+        let earliest = blockchain.earliest_available_height();
         drop(blockchain);
         
-        peer.handshake(PROTOCOL_VERSION, height, self.config.node_id.clone()).await?;
+        peer.handshake(PROTOCOL_VERSION, height, earliest, self.config.node_id.clone(), Capabilities::LOCAL).await?;
         
         // Add to peer manager
         self.peer_manager.add_peer(Arc::clone(&peer)).await?;
@@ -284,7 +359,7 @@ impl Network {
                 self.handle_new_transaction(tx).await?;
             }
             P2PMessage::Block(block) => {
-                self.handle_new_block(block).await?;
+                self.handle_new_block(addr, block).await?;
             }
             P2PMessage::GetBlocks { start_height, end_height } => {
                 self.handle_get_blocks(addr, start_height, end_height).await?;
@@ -312,20 +387,66 @@ impl Network {
             P2PMessage::Disconnect => {
                 self.peer_manager.remove_peer(addr).await;
             }
-            _ => {
-                debug!("Unhandled message type from {}", addr);
+            P2PMessage::Reject { reason, message } => {
+                warn!("Peer {} rejected request: {:?} ({})", addr, reason, message);
+            }
+            P2PMessage::GetSnapshot { height, chunk } => {
+                self.handle_get_snapshot(addr, height, chunk).await?;
+            }
+            P2PMessage::SnapshotChunk { height, chunk, total_chunks, data } => {
+                self.handle_snapshot_chunk(addr, height, chunk, total_chunks, data).await?;
+            }
+            P2PMessage::GetAddr => {
+                let peers: Vec<SocketAddr> = self
+                    .discovery
+                    .get_random_peers(MAX_ADDR_PER_MESSAGE)
+                    .await
+                    .into_iter()
+                    .filter(|peer| *peer != addr)
+                    .collect();
+                self.send_to_peer(addr, P2PMessage::Addr(peers)).await?;
+            }
+            P2PMessage::Addr(peers) => {
+                if peers.len() > MAX_ADDR_PER_MESSAGE {
+                    return self.penalize_violation(addr, &format!("Addr with {} entries", peers.len())).await;
+                }
+                self.discovery.process_addr_message(peers, MAX_ADDR_PER_MESSAGE).await;
+            }
+            P2PMessage::GetHeaders { start_height } => {
+                self.handle_get_headers(addr, start_height).await?;
+            }
+            P2PMessage::Headers(headers) => {
+                self.handle_headers(addr, headers).await?;
+            }
+            P2PMessage::Error(message) => {
+                warn!("Peer {} reported an error: {}", addr, message);
+            }
+            // The handshake is over once a peer is connected; a second one is a violation
+            P2PMessage::Version { .. } => {
+                return self.penalize_violation(addr, "Version after handshake").await;
+            }
+            P2PMessage::VerAck => {
+                return self.penalize_violation(addr, "VerAck after handshake").await;
             }
         }
         Ok(())
     }
 
+    /// Count a protocol violation against `addr` (reputation hit, possible ban)
+    async fn penalize_violation(&self, addr: SocketAddr, violation: &str) -> Result<(), String> {
+        // Inbound peers are only tracked once they misbehave
+        self.discovery.add_peer(addr).await;
+        self.discovery.mark_peer_failed(addr).await;
+        Err(format!("Protocol violation from {}: {}", addr, violation))
+    }
+
     /// Handle new transaction
     async fn handle_new_transaction(&self, tx: Transaction) -> Result<(), String> {
         let blockchain = self.blockchain.write().await;
         
         // Check for duplicates (replay attack prevention)
         let tx_hash = tx.hash();
-        let pending_txs: Vec<String> = {
+        let pending_txs: Vec<TxHash> = {
             let pending = blockchain.get_pending_transactions();
             pending.iter().map(|t| t.hash()).collect()
         };
@@ -345,7 +466,17 @@ impl Network {
     }
 
     /// Handle new block (WITH HARDENED VALIDATION)
-    async fn handle_new_block(&self, block: Block) -> Result<(), String> {
+    async fn handle_new_block(&self, addr: SocketAddr, block: Block) -> Result<(), String> {
+        // Bodies from the sync peer must match the header chain it already committed to
+        let mismatch = match self.header_sync.lock().as_ref() {
+            Some(sync) if sync.peer() == addr => sync.check_block(&block).err(),
+            _ => None,
+        };
+        if let Some(e) = mismatch {
+            *self.header_sync.lock() = None;
+            return self.penalize_violation(addr, &e.to_string()).await;
+        }
+
         let blockchain = self.blockchain.write().await;
         
         // Check if we already have this block
@@ -355,7 +486,7 @@ impl Network {
         
         // SECURITY: Reject blocks that are too far ahead (prevents time-warp attacks)
         let latest = blockchain.get_latest_block();
-        if block.index > latest.index + 100 {
+        if block.index > latest.index + MAX_BLOCKS_AHEAD {
             return Err(format!("Block too far ahead: {} vs our {}", block.index, latest.index));
         }
         
@@ -364,8 +495,8 @@ impl Network {
         if block.index <= latest.index {
             // Block is older than our chain tip - might fill a gap, let add_network_block decide
             drop(blockchain);
-            let bc = self.blockchain.write().await;
-            return match bc.add_network_block(block) {
+            let bc = Arc::clone(&self.blockchain).write_owned().await;
+            return match run_blocking(bc, move |bc| bc.add_network_block(block)).await {
                 Ok(_) => Ok(()),
                 Err(e) => Err(format!("Failed to add historical block: {}", e)),
             };
@@ -385,10 +516,39 @@ impl Network {
         
         // Add block to chain (use add_network_block for full validation)
         drop(blockchain);
-        let bc = self.blockchain.write().await;
-        match bc.add_network_block(block.clone()) {
+        let bc = Arc::clone(&self.blockchain).write_owned().await;
+        let submitted = block.clone();
+        match run_blocking(bc, move |bc| bc.add_network_block(submitted)).await {
             Ok(_) => {
+                let (orphaned, height) = {
+                    let bc = self.blockchain.read().await;
+                    (bc.has_orphan(&block.hash), bc.get_height())
+                };
+                if orphaned {
+                    let decision = self.orphan_requests.lock().on_orphan(addr);
+                    return match decision {
+                        OrphanRequestDecision::Request => {
+                            // Ask the sender for the gap up to the orphan's parent
+                            info!("Holding orphan block {} at height {}, requesting {}..{} from {}",
+                                &block.hash[..8], block.index, height, block.index - 1, addr);
+                            self.send_to_peer(addr, P2PMessage::GetBlocks {
+                                start_height: height,
+                                end_height: block.index - 1,
+                            }).await
+                        }
+                        OrphanRequestDecision::Throttled => {
+                            debug!("Holding orphan block {} from {} without requesting parents (throttled)",
+                                &block.hash[..8], addr);
+                            Ok(())
+                        }
+                        OrphanRequestDecision::Penalize => {
+                            self.penalize_violation(addr, "keeps sending unconnectable orphan blocks").await
+                        }
+                    };
+                }
+                self.orphan_requests.lock().on_block_connected(addr);
                 info!("Added new block {} at height {}", &block.hash[..8], block.index);
+                self.advance_header_sync(height).await?;
                 
                 // NOTE: Do NOT re-broadcast - block came from peer who already broadcast it
                 // self.broadcast_block(block).await;
@@ -401,11 +561,25 @@ impl Network {
     /// Handle get blocks request
     async fn handle_get_blocks(&self, addr: SocketAddr, start: u64, end: u64) -> Result<(), String> {
         let blockchain = self.blockchain.read().await;
-        let blocks: Vec<Block> = (start..=end)
-            .filter_map(|i| blockchain.get_chain().get(i as usize).cloned())
-            .collect();
+        let earliest = blockchain.earliest_available_height();
+        let tip = blockchain.get_height().saturating_sub(1);
+        let (pruned, serve) = split_block_request(start, end, earliest, tip);
+        let blocks: Vec<Block> = match serve {
+            Some((from, to)) => (from..=to)
+                .filter_map(|i| blockchain.get_block_by_height(i))
+                .collect(),
+            None => Vec::new(),
+        };
         drop(blockchain);
         
+        // Tell the peer up front so it can fetch the pruned part elsewhere
+        if let Some(reason) = pruned {
+            self.send_to_peer(addr, P2PMessage::Reject {
+                reason,
+                message: format!("Blocks {}..{} are pruned", start, earliest),
+            }).await?;
+        }
+        
         for block in blocks {
             self.send_to_peer(addr, P2PMessage::Block(block)).await?;
         }
@@ -413,6 +587,116 @@ impl Network {
         Ok(())
     }
 
+    /// Serve up to MAX_HEADERS_PER_MESSAGE headers from `start_height` (empty past our tip)
+    async fn handle_get_headers(&self, addr: SocketAddr, start_height: u64) -> Result<(), String> {
+        let blockchain = self.blockchain.read().await;
+        let start = start_height.max(blockchain.earliest_available_height());
+        let headers: Vec<BlockHeader> = (start..start.saturating_add(MAX_HEADERS_PER_MESSAGE))
+            .map_while(|height| blockchain.get_block_by_height(height))
+            .map(|block| BlockHeader::from(&block))
+            .collect();
+        drop(blockchain);
+
+        self.send_to_peer(addr, P2PMessage::Headers(headers)).await
+    }
+
+    /// Headers from the sync peer: validate and extend the trusted header chain, then ask
+    /// for more headers or, once the peer has none left, start fetching bodies. A peer on
+    /// another branch is asked for headers from below our tip to find the fork point
+    async fn handle_headers(&self, addr: SocketAddr, headers: Vec<BlockHeader>) -> Result<(), String> {
+        let received = headers.len() as u64;
+        let locating = {
+            let mut sync = self.header_sync.lock();
+            match sync.as_ref() {
+                Some(active) if active.peer() == addr && active.is_locating_fork() => sync.take(),
+                _ => None,
+            }
+        };
+        if let Some(located) = locating {
+            return self.fetch_fork(located, headers).await;
+        }
+
+        let next = {
+            let mut sync = self.header_sync.lock();
+            match sync.as_mut() {
+                Some(active) if active.peer() == addr => match active.accept_headers(headers) {
+                    Ok(_) if received == MAX_HEADERS_PER_MESSAGE && !active.has_all_headers() => {
+                        Ok(Some(active.next_header_height()))
+                    }
+                    Ok(_) => {
+                        info!("Header chain from {} verified up to height {}, fetching blocks", addr, active.next_header_height());
+                        Ok(None)
+                    }
+                    Err(e @ HeaderSyncError::Diverged { .. }) => {
+                        info!("Locating fork point with {}: {}", addr, e);
+                        Ok(Some(active.start_fork_search()))
+                    }
+                    Err(e) => {
+                        *sync = None;
+                        Err(e)
+                    }
+                },
+                _ => {
+                    debug!("Ignoring {} unrequested header(s) from {}", received, addr);
+                    return Ok(());
+                }
+            }
+        };
+
+        match next {
+            Ok(Some(start_height)) => self.send_to_peer(addr, P2PMessage::GetHeaders { start_height }).await,
+            Ok(None) => {
+                let height = self.blockchain.read().await.get_height();
+                self.advance_header_sync(height).await
+            }
+            Err(e) => self.penalize_violation(addr, &format!("invalid header chain: {}", e)).await,
+        }
+    }
+
+    /// Fetch a diverged peer's blocks from the first of its `headers` (from below our
+    /// tip) that we don't have; fork resolution then weighs its branch against ours
+    async fn fetch_fork(&self, sync: HeaderSync, headers: Vec<BlockHeader>) -> Result<(), String> {
+        let range = {
+            let blockchain = self.blockchain.read().await;
+            sync.fork_blocks(&headers, |height| blockchain.get_block_by_height(height).map(|b| b.hash), MAX_BLOCKS_AHEAD)
+        };
+        match range {
+            Some((start_height, end_height)) => {
+                info!("Fetching blocks {}..={} from {} from its fork point", start_height, end_height, sync.peer());
+                self.send_to_peer(sync.peer(), P2PMessage::GetBlocks { start_height, end_height }).await
+            }
+            None => {
+                debug!("No fork point within the headers from {}", sync.peer());
+                Ok(())
+            }
+        }
+    }
+
+    /// Request the next batch of bodies once the previous one has connected, and end
+    /// the sync when every trusted header has its block
+    async fn advance_header_sync(&self, chain_height: u64) -> Result<(), String> {
+        let request = {
+            let mut sync = self.header_sync.lock();
+            let Some(active) = sync.as_mut() else {
+                return Ok(());
+            };
+            if active.is_complete(chain_height) {
+                info!("Headers-first sync complete at height {}", chain_height);
+                *sync = None;
+                return Ok(());
+            }
+            active.next_body_batch(chain_height).map(|range| (active.peer(), range))
+        };
+
+        match request {
+            Some((peer, (start_height, end_height))) => {
+                debug!("Requesting blocks {}..={} from {}", start_height, end_height, peer);
+                self.send_to_peer(peer, P2PMessage::GetBlocks { start_height, end_height }).await
+            }
+            None => Ok(()),
+        }
+    }
+
     /// Handle get height request
     async fn handle_get_height(&self, addr: SocketAddr) -> Result<(), String> {
         let blockchain = self.blockchain.read().await;
@@ -421,6 +705,81 @@ impl Network {
         self.send_to_peer(addr, P2PMessage::Height(height)).await
     }
 
+    /// Serve one chunk of the state snapshot at a checkpoint height
+    async fn handle_get_snapshot(&self, addr: SocketAddr, height: u64, index: u32) -> Result<(), String> {
+        let Some(data) = self.serialized_snapshot(height).await else {
+            return self.send_to_peer(addr, P2PMessage::Error(format!("No snapshot at height {}", height))).await;
+        };
+        match chunk(&data, index) {
+            Some(part) => {
+                let msg = P2PMessage::SnapshotChunk {
+                    height,
+                    chunk: index,
+                    total_chunks: chunk_count(data.len()),
+                    data: part.to_vec(),
+                };
+                self.send_to_peer(addr, msg).await
+            }
+            None => self.send_to_peer(addr, P2PMessage::Error(format!("No snapshot chunk {}", index))).await,
+        }
+    }
+
+    /// Serialized snapshot at `height`, replayed from genesis (off the async runtime)
+    /// on first request only. Checkpointed blocks can't be reorganized away, so the
+    /// cached bytes stay valid; the lock makes concurrent requests wait for one replay
+    async fn serialized_snapshot(&self, height: u64) -> Option<Arc<Vec<u8>>> {
+        let mut cache = self.snapshot_cache.lock().await;
+        if let Some((cached_height, data)) = cache.as_ref() {
+            if *cached_height == height {
+                return Some(Arc::clone(data));
+            }
+        }
+        let guard = Arc::clone(&self.blockchain).read_owned().await;
+        let snapshot = run_blocking(guard, move |bc| bc.state_snapshot(height)).await?;
+        let data = Arc::new(bincode::serialize(&snapshot).ok()?);
+        *cache = Some((height, Arc::clone(&data)));
+        Some(data)
+    }
+
+    /// Collect a chunk of the snapshot we requested; ask for the next one, or verify
+    /// and store the snapshot once it is complete
+    async fn handle_snapshot_chunk(
+        &self,
+        addr: SocketAddr,
+        height: u64,
+        index: u32,
+        total_chunks: u32,
+        data: Vec<u8>,
+    ) -> Result<(), String> {
+        let (outcome, next_chunk) = {
+            let mut download = self.snapshot_download.lock();
+            let Some(current) = download.as_mut() else {
+                debug!("Ignoring unsolicited snapshot chunk from {}", addr);
+                return Ok(());
+            };
+            let outcome = current.accept_chunk(addr, height, index, total_chunks, data);
+            let next_chunk = current.next_chunk();
+            match outcome {
+                Ok(Some(_)) | Err(_) if current.peer() == addr => *download = None,
+                _ => {}
+            }
+            (outcome, next_chunk)
+        };
+
+        match outcome {
+            Ok(None) => self.send_to_peer(addr, P2PMessage::GetSnapshot { height, chunk: next_chunk }).await,
+            Ok(Some(bytes)) => {
+                let snapshot: StateSnapshot = bincode::deserialize(&bytes)
+                    .map_err(|e| format!("Snapshot from {} is malformed: {}", addr, e))?;
+                let guard = Arc::clone(&self.blockchain).read_owned().await;
+                run_blocking(guard, move |bc| bc.accept_snapshot(&snapshot))
+                    .await
+                    .map_err(|e| format!("Snapshot from {} rejected: {}", addr, e))
+            }
+            Err(e) => Err(format!("Snapshot chunk from {} refused: {}", addr, e)),
+        }
+    }
+
     /// Handle get mempool request
     async fn handle_get_mempool(&self, addr: SocketAddr) -> Result<(), String> {
         let blockchain = self.blockchain.read().await;
@@ -430,12 +789,19 @@ impl Network {
     }
 
     /// Send message to specific peer
+    /// A peer whose connection failed (or that keeps failing sends) is dropped right away
     async fn send_to_peer(&self, addr: SocketAddr, msg: P2PMessage) -> Result<(), String> {
         let peers = self.peer_manager.get_peers().await;
         
         for peer in peers {
             if peer.address().await == addr {
-                return peer.send_message(msg).await;
+                let result = peer.send_message(msg).await;
+                if result.is_err() && peer.should_drop() {
+                    warn!("Dropping peer {} after failed send", addr);
+                    self.peer_manager.remove_peer(addr).await;
+                    self.penalize_failed_sends(&[addr]).await;
+                }
+                return result;
             }
         }
         
@@ -444,12 +810,21 @@ impl Network {
 
     /// Broadcast transaction to all peers
     pub async fn broadcast_transaction(&self, tx: Transaction) {
-        self.peer_manager.broadcast(P2PMessage::NewTx(tx)).await;
+        let dropped = self.peer_manager.broadcast(P2PMessage::NewTx(tx)).await;
+        self.penalize_failed_sends(&dropped).await;
     }
 
     /// Broadcast block to all peers
     pub async fn broadcast_block(&self, block: Block) {
-        self.peer_manager.broadcast(P2PMessage::Block(block)).await;
+        let dropped = self.peer_manager.broadcast(P2PMessage::Block(block)).await;
+        self.penalize_failed_sends(&dropped).await;
+    }
+
+    /// Count a dropped connection against the peers' reputation (repeat offenders get banned)
+    async fn penalize_failed_sends(&self, addrs: &[SocketAddr]) {
+        for &addr in addrs {
+            self.discovery.mark_peer_failed(addr).await;
+        }
     }
 
     /// Synchronize blockchain from peers
@@ -485,25 +860,36 @@ impl Network {
         }
         
         if let Some(peer) = best_peer {
+            // Fresh node: ask for the latest snapshot checkpoint the peer has reached
+            if self.config.snapshot_sync && our_height <= 1 {
+                if let Some(checkpoint) = latest_checkpoint_at(max_height.saturating_sub(1)) {
+                    info!("Requesting state snapshot at checkpoint height {}", checkpoint.height);
+                    // Peers that don't serve snapshots get plain block sync below
+                    let download = SnapshotDownload::new(peer.address().await, checkpoint.height);
+                    match peer.send_message(P2PMessage::GetSnapshot { height: checkpoint.height, chunk: 0 }).await {
+                        Ok(()) => *self.snapshot_download.lock() = Some(download),
+                        Err(e) => info!("Snapshot unavailable from best peer ({}), syncing blocks", e),
+                    }
+                }
+            }
+
             info!("Syncing from peer with height {}", max_height);
-            
-            // Request missing blocks
-            let _ = peer.send_message(P2PMessage::GetBlocks {
-                start_height: our_height,
-                end_height: max_height,
-            }).await;
-            
-            // Wait for blocks to arrive
-            tokio::time::sleep(Duration::from_secs(5)).await;
-            
-            info!("Blockchain sync complete");
+
+            // Headers first: bodies are only requested once the header chain checks out
+            let (recent, activations) = {
+                let blockchain = self.blockchain.read().await;
+                (blockchain.recent_headers(), blockchain.activations())
+            };
+            let peer_addr = peer.address().await;
+            *self.header_sync.lock() = Some(HeaderSync::new(peer_addr, recent, max_height, next_difficulty_after, activations));
+            peer.send_message(P2PMessage::GetHeaders { start_height: our_height }).await?;
         }
         
         Ok(())
     }
 
     /// Maintain peer connections
-    async fn maintain_peers(&self) {
+    async fn maintain_peers(self: Arc<Self>) {
         let mut ticker = interval(Duration::from_secs(10));
         ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
         
@@ -516,31 +902,30 @@ impl Network {
             // Send ping to all peers
             let peers = self.peer_manager.get_peers().await;
             for peer in peers {
-                let nonce = rand::random();
+                let nonce = self.next_nonce();
                 let _ = peer.send_message(P2PMessage::Ping(nonce)).await;
             }
             
             // Try to maintain minimum peer count
             let peer_count = self.peer_manager.peer_count().await;
             if peer_count < 3 && !self.config.bootstrap_nodes.is_empty() {
-                // Try reconnecting to bootstrap nodes
+                let mut connected = Vec::new();
+                for peer in self.peer_manager.get_peers().await {
+                    connected.push(peer.address().await);
+                }
+
+                // Reconnect to bootstrap nodes, backing off from ones that keep failing
                 for &bootstrap_addr in &self.config.bootstrap_nodes {
-                    let message_tx = self.message_tx.clone();
-                    let peer_manager = Arc::clone(&self.peer_manager);
-                    let blockchain = Arc::clone(&self.blockchain);
-                    let node_id = self.config.node_id.clone();
-                    
+                    if connected.contains(&bootstrap_addr) || !self.discovery.dial_due(&bootstrap_addr).await {
+                        continue;
+                    }
+                    let network = Arc::clone(&self);
                     tokio::spawn(async move {
-                        if let Ok(stream) = TcpStream::connect(bootstrap_addr).await {
-                            if let Ok(peer) = Peer::new(stream, bootstrap_addr).await {
-                                let peer = Arc::new(peer);
-                                let height = blockchain.read().await.get_chain().len() as u64;
-                                if peer.handshake(PROTOCOL_VERSION, height, node_id).await.is_ok() {
-                                    if peer_manager.add_peer(Arc::clone(&peer)).await.is_ok() {
-                                        // Use centralized receive task instead of inline loop
-                                        Self::start_peer_receive_task(peer, message_tx, peer_manager).await;
-                                    }
-                                }
+                        match network.connect_to_peer(bootstrap_addr).await {
+                            Ok(()) => network.discovery.record_dial_success(bootstrap_addr).await,
+                            Err(e) => {
+                                let delay = network.discovery.record_dial_failure(bootstrap_addr).await;
+                                debug!("Bootstrap node {} unreachable ({}); retrying in {}s", bootstrap_addr, e, delay);
                             }
                         }
                     });
@@ -549,11 +934,52 @@ impl Network {
         }
     }
 
+    /// Operator: remember `addr` as a manual peer and dial it
+    pub async fn add_peer(&self, addr: SocketAddr) -> Result<(), String> {
+        self.discovery.add_peer_with_source(addr, PeerSource::Manual).await;
+        self.connect_to_peer(addr).await
+    }
+
+    /// Operator: disconnect `addr` (it may reconnect; use ban_peer to keep it out)
+    /// Returns whether the peer was connected
+    pub async fn remove_peer(&self, addr: SocketAddr) -> bool {
+        let mut found = false;
+        for peer in self.peer_manager.get_peers().await {
+            if peer.address().await == addr {
+                peer.disconnect().await;
+                found = true;
+            }
+        }
+        self.peer_manager.remove_peer(addr).await;
+        found
+    }
+
+    /// Operator: disconnect `addr` and refuse connections to/from it for `duration_secs`
+    pub async fn ban_peer(&self, addr: SocketAddr, duration_secs: i64) {
+        self.discovery.ban_peer(addr, duration_secs).await;
+        self.remove_peer(addr).await;
+    }
+
     /// Get connected peer count
     pub async fn peer_count(&self) -> usize {
         self.peer_manager.peer_count().await
     }
     
+    /// Whether enough peers are connected to mine (min_peers_to_mine)
+    /// Logs once when mining pauses and once when it resumes
+    pub async fn ready_to_mine(&self) -> bool {
+        let required = self.config.min_peers_to_mine;
+        let connected = self.peer_count().await;
+        let ready = connected >= required;
+        let was_paused = self.mining_paused.swap(!ready, Ordering::Relaxed);
+        if !ready && !was_paused {
+            warn!("Mining paused: {} of {} required peers connected (min_peers_to_mine)", connected, required);
+        } else if ready && was_paused {
+            info!("Mining resumed: {} peers connected", connected);
+        }
+        ready
+    }
+
     /// Get peer count (alias for health check)
     pub async fn get_peer_count(&self) -> usize {
         self.peer_count().await
@@ -578,10 +1004,154 @@ impl Network {
             info!("Heartbeat: Pinging {} peers", peers.len());
         }
         for peer in peers {
-            let nonce = rand::random();
+            let nonce = self.next_nonce();
             if let Err(e) = peer.send_message(P2PMessage::Ping(nonce)).await {
                 warn!("Heartbeat ping failed: {}", e);
             }
         }
     }
 }
+
+/// Split a GetBlocks range into the pruned part (reported via Reject) and the
+/// part this node can still serve, clamped to our tip
+fn split_block_request(start: u64, end: u64, earliest: u64, tip: u64) -> (Option<RejectReason>, Option<(u64, u64)>) {
+    let pruned = if start < earliest {
+        Some(RejectReason::BlocksPruned { earliest_available: earliest })
+    } else {
+        None
+    };
+
+    let from = start.max(earliest);
+    let to = end.min(tip);
+    let serve = if from <= to { Some((from, to)) } else { None };
+
+    (pruned, serve)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pruned_range_yields_reject() {
+        let (pruned, serve) = split_block_request(0, 3, 10, 50);
+        assert_eq!(pruned, Some(RejectReason::BlocksPruned { earliest_available: 10 }));
+        assert_eq!(serve, None);
+    }
+
+    #[test]
+    fn partially_pruned_range_serves_remainder() {
+        let (pruned, serve) = split_block_request(5, 20, 10, 50);
+        assert_eq!(pruned, Some(RejectReason::BlocksPruned { earliest_available: 10 }));
+        assert_eq!(serve, Some((10, 20)));
+    }
+
+    #[test]
+    fn unpruned_range_is_clamped_to_tip() {
+        let (pruned, serve) = split_block_request(0, u64::MAX, 0, 50);
+        assert_eq!(pruned, None);
+        assert_eq!(serve, Some((0, 50)));
+    }
+
+    #[tokio::test]
+    async fn admin_add_peer_connects_and_ban_blocks_reconnection() {
+        // Remote end: accept connections and complete the handshake, keeping peers alive
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let remote_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let encryption = PeerEncryption::new(EncryptionMode::Optional);
+            let mut peers = Vec::new();
+            while let Ok((stream, from)) = listener.accept().await {
+                let peer = Peer::negotiate(stream, from, PeerDirection::Inbound, &encryption).await.unwrap();
+                let _ = peer.handshake(PROTOCOL_VERSION, 1, 0, "remote".to_string(), Capabilities::LOCAL).await;
+                peers.push(peer);
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+        let network = Network::new(NetworkConfig::default(), Arc::new(RwLock::new(blockchain)));
+
+        network.add_peer(remote_addr).await.unwrap();
+        assert_eq!(network.peer_count().await, 1);
+
+        network.ban_peer(remote_addr, 3600).await;
+        assert_eq!(network.peer_count().await, 0);
+        assert!(network.add_peer(remote_addr).await.unwrap_err().contains("banned"));
+        assert_eq!(network.peer_count().await, 0);
+    }
+
+    #[tokio::test]
+    async fn mining_waits_for_min_peers() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let remote_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let encryption = PeerEncryption::new(EncryptionMode::Optional);
+            let mut peers = Vec::new();
+            while let Ok((stream, from)) = listener.accept().await {
+                let peer = Peer::negotiate(stream, from, PeerDirection::Inbound, &encryption).await.unwrap();
+                let _ = peer.handshake(PROTOCOL_VERSION, 1, 0, "remote".to_string(), Capabilities::LOCAL).await;
+                peers.push(peer);
+            }
+        });
+
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+        let config = NetworkConfig { min_peers_to_mine: 1, ..NetworkConfig::default() };
+        let network = Network::new(config, Arc::new(RwLock::new(blockchain)));
+
+        // Paused with no peers, resumed once one connects, paused again when it goes
+        assert!(!network.ready_to_mine().await);
+        network.add_peer(remote_addr).await.unwrap();
+        assert!(network.ready_to_mine().await);
+        network.remove_peer(remote_addr).await;
+        assert!(!network.ready_to_mine().await);
+
+        // The default (0) never pauses
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+        let network = Network::new(NetworkConfig::default(), Arc::new(RwLock::new(blockchain)));
+        assert!(network.ready_to_mine().await);
+    }
+
+    #[test]
+    fn seeded_rng_gives_deterministic_ping_nonces() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Arc::new(RwLock::new(Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap()));
+        let nonces = |seed| {
+            let network = Network::new(NetworkConfig::default(), Arc::clone(&blockchain))
+                .with_rng(crate::network::rng::seeded_rng(seed));
+            (0..3).map(|_| network.next_nonce()).collect::<Vec<_>>()
+        };
+        assert_eq!(nonces(7), nonces(7));
+        assert_ne!(nonces(7), nonces(8));
+    }
+
+    #[tokio::test]
+    async fn mid_session_version_is_a_protocol_violation() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = Arc::new(crate::storage::BlockchainStorage::new(dir.path()).unwrap());
+        let blockchain = Blockchain::new(storage, crate::core::ChainNetwork::Testnet).unwrap();
+        let network = Network::new(NetworkConfig::default(), Arc::new(RwLock::new(blockchain)));
+        let addr: SocketAddr = "10.0.0.7:8333".parse().unwrap();
+
+        let version = P2PMessage::Version {
+            version: PROTOCOL_VERSION,
+            height: 0,
+            earliest_height: 0,
+            timestamp: chrono::Utc::now().timestamp(),
+            node_id: "replayed".to_string(),
+            capabilities: Capabilities::LOCAL,
+        };
+        let err = network.handle_message(addr, version).await.unwrap_err();
+        assert!(err.contains("Protocol violation"));
+
+        let meta = network.discovery.get_peer_meta(&addr).await.unwrap();
+        assert_eq!(meta.failures, 1);
+        assert!(meta.reputation < 0);
+    }
+}
@@ -0,0 +1,345 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::path::Path;
+use snow::{Builder, HandshakeState, TransportState};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+use zeroize::Zeroizing;
+
+/// Noise pattern: both sides send their static key, so each peer learns (and the
+/// handshake proves possession of) the other's long-term key
+pub const NOISE_PARAMS: &str = "Noise_XX_25519_ChaChaPoly_BLAKE2s";
+/// Binds the handshake to this protocol (different prologues can't complete a handshake)
+const NOISE_PROLOGUE: &[u8] = b"quanta-p2p-noise-v1";
+/// Sent by both sides right after TCP connect, followed by one EncryptionMode byte
+const PREAMBLE_MAGIC: &[u8; 4] = b"QNSE";
+/// Largest Noise transport message; longer frames are encrypted in chunks
+const NOISE_MAX_MESSAGE: usize = 65535;
+const NOISE_TAG_LEN: usize = 16;
+const HANDSHAKE_TIMEOUT_SECS: u64 = 10;
+/// X25519 key length; the key file holds the private key followed by the public key
+const NOISE_KEY_LEN: usize = 32;
+
+/// Whether P2P connections are encrypted (config: network.p2p_encryption)
+///
+/// Both sides announce their mode before the Version handshake. The connection is
+/// encrypted when neither side is `Disabled`; a `Required` side refuses plaintext.
+/// Every mode sends the preamble, so even `Disabled` can't connect to nodes from
+/// before encryption was added, which expect the Version message first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptionMode {
+    Disabled,
+    #[default]
+    Optional,
+    Required,
+}
+
+impl EncryptionMode {
+    fn to_byte(self) -> u8 {
+        match self {
+            EncryptionMode::Disabled => 0,
+            EncryptionMode::Optional => 1,
+            EncryptionMode::Required => 2,
+        }
+    }
+
+    fn from_byte(byte: u8) -> Result<Self, String> {
+        match byte {
+            0 => Ok(EncryptionMode::Disabled),
+            1 => Ok(EncryptionMode::Optional),
+            2 => Ok(EncryptionMode::Required),
+            other => Err(format!("Unknown encryption mode {}", other)),
+        }
+    }
+}
+
+/// This node's encryption policy and Noise static key
+#[derive(Clone)]
+pub struct PeerEncryption {
+    pub mode: EncryptionMode,
+    private_key: std::sync::Arc<Zeroizing<Vec<u8>>>,
+    public_key: Vec<u8>,
+}
+
+impl PeerEncryption {
+    /// Use a fresh static key (tests; a node keeps its key with `load_or_create`)
+    pub fn new(mode: EncryptionMode) -> Self {
+        let keypair = Builder::new(NOISE_PARAMS.parse().expect("valid Noise params"))
+            .generate_keypair()
+            .expect("Noise keypair generation");
+        Self {
+            mode,
+            private_key: std::sync::Arc::new(Zeroizing::new(keypair.private)),
+            public_key: keypair.public,
+        }
+    }
+
+    /// Load the static key from `path`, creating it (owner-only) on first start, so
+    /// peers see the same identity across restarts and can pin it
+    pub fn load_or_create(mode: EncryptionMode, path: &Path) -> Result<Self, String> {
+        if path.exists() {
+            let bytes = Zeroizing::new(
+                std::fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+            );
+            if bytes.len() != NOISE_KEY_LEN * 2 {
+                return Err(format!("{} is not a Noise key file", path.display()));
+            }
+            return Ok(Self {
+                mode,
+                private_key: std::sync::Arc::new(Zeroizing::new(bytes[..NOISE_KEY_LEN].to_vec())),
+                public_key: bytes[NOISE_KEY_LEN..].to_vec(),
+            });
+        }
+
+        let encryption = Self::new(mode);
+        let mut contents = Zeroizing::new(encryption.private_key.to_vec());
+        contents.extend_from_slice(&encryption.public_key);
+        crate::crypto::wallet::write_wallet_file(path, &contents)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(encryption)
+    }
+
+    /// Our Noise static public key (what peers see as our identity)
+    pub fn public_key(&self) -> &[u8] {
+        &self.public_key
+    }
+
+    /// Exchange encryption modes and, if both sides allow it, run the Noise handshake
+    /// The outbound side initiates. Returns None for an agreed plaintext connection.
+    pub async fn negotiate(&self, stream: &mut TcpStream, initiator: bool) -> Result<Option<NoiseSession>, String> {
+        timeout(Duration::from_secs(HANDSHAKE_TIMEOUT_SECS), self.negotiate_inner(stream, initiator))
+            .await
+            .map_err(|_| "Encryption negotiation timed out".to_string())?
+    }
+
+    async fn negotiate_inner(&self, stream: &mut TcpStream, initiator: bool) -> Result<Option<NoiseSession>, String> {
+        let mut preamble = [0u8; 5];
+        preamble[..4].copy_from_slice(PREAMBLE_MAGIC);
+        preamble[4] = self.mode.to_byte();
+        stream.write_all(&preamble).await.map_err(|e| format!("Failed to send preamble: {}", e))?;
+
+        let mut theirs = [0u8; 5];
+        stream.read_exact(&mut theirs).await.map_err(|e| format!("Failed to read preamble: {}", e))?;
+        if &theirs[..4] != PREAMBLE_MAGIC {
+            return Err("Peer did not send an encryption preamble".to_string());
+        }
+        let their_mode = EncryptionMode::from_byte(theirs[4])?;
+
+        match (self.mode, their_mode) {
+            (EncryptionMode::Disabled, EncryptionMode::Required) => {
+                Err("Peer requires encryption but it is disabled here".to_string())
+            }
+            (EncryptionMode::Required, EncryptionMode::Disabled) => {
+                Err("Encryption required but peer only speaks plaintext".to_string())
+            }
+            (EncryptionMode::Disabled, _) | (_, EncryptionMode::Disabled) => Ok(None),
+            _ => self.handshake(stream, initiator).await.map(Some),
+        }
+    }
+
+    /// Noise XX: -> e; <- e, ee, s, es; -> s, se
+    async fn handshake(&self, stream: &mut TcpStream, initiator: bool) -> Result<NoiseSession, String> {
+        let builder = Builder::new(NOISE_PARAMS.parse().expect("valid Noise params"))
+            .local_private_key(&self.private_key)
+            .prologue(NOISE_PROLOGUE);
+        let mut state = if initiator { builder.build_initiator() } else { builder.build_responder() }
+            .map_err(|e| format!("Noise setup failed: {}", e))?;
+
+        let mut buf = vec![0u8; NOISE_MAX_MESSAGE];
+        while !state.is_handshake_finished() {
+            if state.is_my_turn() {
+                let len = state.write_message(&[], &mut buf).map_err(|e| format!("Noise handshake failed: {}", e))?;
+                write_handshake_frame(stream, &buf[..len]).await?;
+            } else {
+                let frame = read_handshake_frame(stream).await?;
+                state.read_message(&frame, &mut buf).map_err(|e| format!("Noise handshake failed: {}", e))?;
+            }
+        }
+        NoiseSession::from_handshake(state)
+    }
+}
+
+/// Expected Noise static keys of outbound peers, by address
+///
+/// Operator-configured pins (network.peer_static_keys) are loaded at startup; any other
+/// address is pinned to the key it presents on its first encrypted connection. A pinned
+/// peer must present the same key and may not fall back to plaintext.
+#[derive(Debug, Default)]
+pub struct KeyPins {
+    pins: parking_lot::Mutex<HashMap<SocketAddr, Vec<u8>>>,
+}
+
+impl KeyPins {
+    pub fn new(pins: HashMap<SocketAddr, Vec<u8>>) -> Self {
+        Self { pins: parking_lot::Mutex::new(pins) }
+    }
+
+    /// Check the key `addr` presented (None = plaintext) and pin it if it is the first
+    pub fn check(&self, addr: SocketAddr, remote_static: Option<&[u8]>) -> Result<(), String> {
+        let mut pins = self.pins.lock();
+        match (pins.get(&addr), remote_static) {
+            (Some(pinned), Some(key)) if pinned.as_slice() == key => Ok(()),
+            (Some(_), Some(_)) => Err(format!("Peer {} presented a different static key than pinned", addr)),
+            (Some(_), None) => Err(format!("Peer {} has a pinned key but connected in plaintext", addr)),
+            (None, Some(key)) => {
+                pins.insert(addr, key.to_vec());
+                Ok(())
+            }
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+async fn write_handshake_frame(stream: &mut TcpStream, data: &[u8]) -> Result<(), String> {
+    stream.write_all(&(data.len() as u16).to_be_bytes()).await.map_err(|e| e.to_string())?;
+    stream.write_all(data).await.map_err(|e| e.to_string())
+}
+
+async fn read_handshake_frame(stream: &mut TcpStream) -> Result<Vec<u8>, String> {
+    let mut len = [0u8; 2];
+    stream.read_exact(&mut len).await.map_err(|e| e.to_string())?;
+    let mut frame = vec![0u8; u16::from_be_bytes(len) as usize];
+    stream.read_exact(&mut frame).await.map_err(|e| e.to_string())?;
+    Ok(frame)
+}
+
+/// Established Noise session: encrypts/decrypts whole message frames
+pub struct NoiseSession {
+    transport: TransportState,
+    remote_static: Vec<u8>,
+}
+
+impl NoiseSession {
+    fn from_handshake(state: HandshakeState) -> Result<Self, String> {
+        let remote_static = state
+            .get_remote_static()
+            .ok_or("Peer sent no static key")?
+            .to_vec();
+        let transport = state.into_transport_mode().map_err(|e| format!("Noise handshake failed: {}", e))?;
+        Ok(Self { transport, remote_static })
+    }
+
+    /// The peer's authenticated Noise static public key
+    pub fn remote_static(&self) -> &[u8] {
+        &self.remote_static
+    }
+
+    /// Encrypt a frame of any length (split into Noise-sized chunks)
+    pub fn encrypt(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, String> {
+        let chunk_len = NOISE_MAX_MESSAGE - NOISE_TAG_LEN;
+        let mut out = Vec::with_capacity(plaintext.len() + NOISE_TAG_LEN * (plaintext.len() / chunk_len + 1));
+        let mut buf = vec![0u8; NOISE_MAX_MESSAGE];
+        // An empty frame still produces one (tag-only) chunk
+        let chunks: Vec<&[u8]> = if plaintext.is_empty() { vec![&[]] } else { plaintext.chunks(chunk_len).collect() };
+        for chunk in chunks {
+            let len = self
+                .transport
+                .write_message(chunk, &mut buf)
+                .map_err(|e| format!("Encryption failed: {}", e))?;
+            out.extend_from_slice(&buf[..len]);
+        }
+        Ok(out)
+    }
+
+    /// Decrypt a frame produced by `encrypt` (fails on any tampering or reordering)
+    pub fn decrypt(&mut self, ciphertext: &[u8]) -> Result<Vec<u8>, String> {
+        let mut out = Vec::with_capacity(ciphertext.len());
+        let mut buf = vec![0u8; NOISE_MAX_MESSAGE];
+        for chunk in ciphertext.chunks(NOISE_MAX_MESSAGE) {
+            let len = self
+                .transport
+                .read_message(chunk, &mut buf)
+                .map_err(|e| format!("Decryption failed: {}", e))?;
+            out.extend_from_slice(&buf[..len]);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::peer::{Peer, PeerDirection};
+    use crate::network::protocol::P2PMessage;
+    use tokio::net::TcpListener;
+
+    /// Connect two peers with the given modes; returns (outbound, inbound) results
+    async fn connect(
+        outbound: &PeerEncryption,
+        inbound: &PeerEncryption,
+    ) -> (Result<Peer, String>, Result<Peer, String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let inbound = inbound.clone();
+        let accept = tokio::spawn(async move {
+            let (stream, peer_addr) = listener.accept().await.unwrap();
+            Peer::negotiate(stream, peer_addr, PeerDirection::Inbound, &inbound).await
+        });
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let out = Peer::negotiate(stream, addr, PeerDirection::Outbound, outbound).await;
+        (out, accept.await.unwrap())
+    }
+
+    #[tokio::test]
+    async fn encrypted_peers_exchange_messages_and_learn_static_keys() {
+        let a = PeerEncryption::new(EncryptionMode::Required);
+        let b = PeerEncryption::new(EncryptionMode::Optional);
+        let (out, inb) = connect(&a, &b).await;
+        let (out, inb) = (out.unwrap(), inb.unwrap());
+
+        let out_info = out.get_info().await;
+        let in_info = inb.get_info().await;
+        assert_eq!(out_info.remote_static_key.as_deref(), Some(b.public_key()));
+        assert_eq!(in_info.remote_static_key.as_deref(), Some(a.public_key()));
+
+        // Larger than one Noise message, so it is chunked on the wire
+        let big = P2PMessage::Error("x".repeat(200_000));
+        out.send_message(big.clone()).await.unwrap();
+        out.send_message(P2PMessage::Ping(7)).await.unwrap();
+        assert!(matches!(inb.receive_message().await.unwrap(), P2PMessage::Error(s) if s.len() == 200_000));
+        assert!(matches!(inb.receive_message().await.unwrap(), P2PMessage::Ping(7)));
+        inb.send_message(P2PMessage::Pong(7)).await.unwrap();
+        assert!(matches!(out.receive_message().await.unwrap(), P2PMessage::Pong(7)));
+    }
+
+    #[tokio::test]
+    async fn plaintext_peer_is_rejected_when_encryption_is_required() {
+        let required = PeerEncryption::new(EncryptionMode::Required);
+        let plaintext = PeerEncryption::new(EncryptionMode::Disabled);
+        let (out, inb) = connect(&plaintext, &required).await;
+        assert!(out.is_err());
+        assert!(inb.err().unwrap().contains("plaintext"));
+
+        // Optional falls back to plaintext with a plaintext-only peer
+        let optional = PeerEncryption::new(EncryptionMode::Optional);
+        let (out, inb) = connect(&plaintext, &optional).await;
+        assert!(out.unwrap().get_info().await.remote_static_key.is_none());
+        assert!(inb.unwrap().get_info().await.remote_static_key.is_none());
+    }
+
+    #[test]
+    fn static_key_survives_restarts_and_pins_reject_a_changed_key() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("p2p_noise.key");
+        let first = PeerEncryption::load_or_create(EncryptionMode::Optional, &path).unwrap();
+        let again = PeerEncryption::load_or_create(EncryptionMode::Optional, &path).unwrap();
+        assert_eq!(first.public_key(), again.public_key());
+        assert_eq!(first.private_key.as_slice(), again.private_key.as_slice());
+
+        let addr: SocketAddr = "10.0.0.1:8333".parse().unwrap();
+        let pins = KeyPins::default();
+        assert!(pins.check(addr, Some(first.public_key())).is_ok());
+        assert!(pins.check(addr, Some(first.public_key())).is_ok());
+        let other = PeerEncryption::new(EncryptionMode::Optional);
+        assert!(pins.check(addr, Some(other.public_key())).is_err());
+        assert!(pins.check(addr, None).is_err());
+
+        // Addresses never seen encrypted stay unpinned
+        let plaintext: SocketAddr = "10.0.0.2:8333".parse().unwrap();
+        assert!(pins.check(plaintext, None).is_ok());
+        assert!(pins.check(plaintext, Some(other.public_key())).is_ok());
+    }
+}
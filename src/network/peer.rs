@@ -1,5 +1,8 @@
-use crate::network::protocol::{P2PMessage, serialize_message, deserialize_message};
+use crate::network::noise::{NoiseSession, PeerEncryption};
+use crate::network::protocol::{Capabilities, P2PMessage, serialize_message, deserialize_message};
+use serde::{Deserialize, Serialize};
 use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use tokio::io::{AsyncReadExt, AsyncWriteExt, ReadHalf, WriteHalf};
 use tokio::net::TcpStream;
@@ -7,6 +10,20 @@ use tokio::sync::{mpsc, RwLock};
 use tokio::time::{timeout, Duration};
 use tracing::{debug, info, warn};
 
+/// Frames queued per peer before it is treated as stalled and dropped
+pub const SEND_QUEUE_CAPACITY: usize = 256;
+
+/// Consecutive send errors tolerated before a peer whose connection hasn't
+/// failed outright is dropped anyway
+pub const MAX_SEND_FAILURES: u32 = 3;
+
+/// Which side initiated the connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PeerDirection {
+    Inbound,
+    Outbound,
+}
+
 /// Information about a connected peer
 #[derive(Debug, Clone)]
 pub struct PeerInfo {
@@ -14,73 +31,243 @@ pub struct PeerInfo {
     pub node_id: String,
     pub version: u32,
     pub height: u64,
+    pub earliest_height: u64,
     pub connected_at: i64,
     pub last_seen: i64,
+    pub direction: PeerDirection,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub remote_static_key: Option<Vec<u8>>, // Noise static key (None = plaintext connection)
+    pub capabilities: Capabilities,          // Advertised in the peer's Version message
+}
+
+impl PeerInfo {
+    /// Seconds connected as of `now` (0 if connected_at is ahead of `now` through clock skew)
+    pub fn connected_for(&self, now: i64) -> u64 {
+        now.saturating_sub(self.connected_at).max(0) as u64
+    }
+}
+
+/// A recorded timestamp for display, clamped to [0, now] so clock skew never shows a future time
+pub fn clamp_to_past(timestamp: i64, now: i64) -> u64 {
+    timestamp.min(now).max(0) as u64
 }
 
 /// Represents a connection to a peer in the network
+/// Outgoing frames go through a bounded queue drained by a dedicated writer task
 pub struct Peer {
     info: Arc<RwLock<PeerInfo>>,
     read_half: Arc<RwLock<ReadHalf<TcpStream>>>,
-    write_half: Arc<RwLock<WriteHalf<TcpStream>>>,
+    send_queue: mpsc::Sender<Vec<u8>>,
+    failed: Arc<AtomicBool>, // Set on queue overflow or write error
+    send_failures: AtomicU32, // Consecutive send_serialized errors (reset on success)
     shutdown_tx: mpsc::Sender<()>,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    session: Option<parking_lot::Mutex<NoiseSession>>, // Encrypts/decrypts every frame when set
 }
 
 impl Peer {
-    /// Create a new peer connection
+    /// Create a new plaintext peer connection (no encryption negotiation)
+    #[cfg(test)]
     pub async fn new(
         stream: TcpStream,
         address: SocketAddr,
+        direction: PeerDirection,
     ) -> Result<Self, String> {
-        let (shutdown_tx, _) = mpsc::channel(1);
+        Self::with_send_queue_capacity(stream, address, direction, SEND_QUEUE_CAPACITY).await
+    }
+
+    /// Negotiate encryption on a fresh connection (outbound initiates), then create the peer
+    pub async fn negotiate(
+        mut stream: TcpStream,
+        address: SocketAddr,
+        direction: PeerDirection,
+        encryption: &PeerEncryption,
+    ) -> Result<Self, String> {
+        let session = encryption
+            .negotiate(&mut stream, direction == PeerDirection::Outbound)
+            .await
+            .map_err(|e| format!("Encryption negotiation with {} failed: {}", address, e))?;
+        Ok(Self::build(stream, address, direction, SEND_QUEUE_CAPACITY, session))
+    }
+
+    /// Create a peer connection with a custom send queue bound
+    #[cfg(test)]
+    pub async fn with_send_queue_capacity(
+        stream: TcpStream,
+        address: SocketAddr,
+        direction: PeerDirection,
+        capacity: usize,
+    ) -> Result<Self, String> {
+        Ok(Self::build(stream, address, direction, capacity, None))
+    }
+
+    fn build(
+        stream: TcpStream,
+        address: SocketAddr,
+        direction: PeerDirection,
+        capacity: usize,
+        session: Option<NoiseSession>,
+    ) -> Self {
+        let (shutdown_tx, shutdown_rx) = mpsc::channel(1);
+        let (send_queue, send_rx) = mpsc::channel(capacity.max(1));
+        let failed = Arc::new(AtomicBool::new(false));
         
         let info = PeerInfo {
             address,
             node_id: String::new(),
             version: 0,
             height: 0,
+            earliest_height: 0,
             connected_at: chrono::Utc::now().timestamp(),
             last_seen: chrono::Utc::now().timestamp(),
+            direction,
+            bytes_sent: 0,
+            bytes_received: 0,
+            remote_static_key: session.as_ref().map(|s| s.remote_static().to_vec()),
+            capabilities: Capabilities::NONE, // Until the handshake says otherwise
         };
 
         // CRITICAL: Split stream to avoid read/write lock contention
         let (read_half, write_half) = tokio::io::split(stream);
+        tokio::spawn(Self::run_writer(write_half, send_rx, shutdown_rx, Arc::clone(&failed), address));
 
-        Ok(Self {
+        Self {
             info: Arc::new(RwLock::new(info)),
             read_half: Arc::new(RwLock::new(read_half)),
-            write_half: Arc::new(RwLock::new(write_half)),
+            send_queue,
+            failed,
+            send_failures: AtomicU32::new(0),
             shutdown_tx,
-        })
+            bytes_sent: AtomicU64::new(0),
+            bytes_received: AtomicU64::new(0),
+            session: session.map(parking_lot::Mutex::new),
+        }
+    }
+
+    /// Writer task: drains the send queue onto the socket until shutdown or a write error
+    async fn run_writer(
+        mut write: WriteHalf<TcpStream>,
+        mut send_rx: mpsc::Receiver<Vec<u8>>,
+        mut shutdown_rx: mpsc::Receiver<()>,
+        failed: Arc<AtomicBool>,
+        address: SocketAddr,
+    ) {
+        loop {
+            // Biased: frames queued before a shutdown (e.g. Disconnect) still go out
+            let frame = tokio::select! {
+                biased;
+                frame = send_rx.recv() => match frame {
+                    Some(frame) => frame,
+                    None => break,
+                },
+                _ = shutdown_rx.recv() => break,
+            };
+
+            // A stalled peer blocks here, so shutdown must be able to interrupt the write
+            let result = tokio::select! {
+                result = async {
+                    write.write_all(&frame).await?;
+                    write.flush().await
+                } => result,
+                _ = shutdown_rx.recv() => break,
+            };
+
+            if let Err(e) = result {
+                warn!("Failed to write to peer {}: {}", address, e);
+                failed.store(true, Ordering::Relaxed);
+                break;
+            }
+        }
+        let _ = write.shutdown().await;
     }
 
-    /// Send a message to this peer
+    /// Send a message to this peer (queued; fails if the peer is stalled or gone, or
+    /// did not advertise the capability the message needs)
     pub async fn send_message(&self, msg: P2PMessage) -> Result<(), String> {
+        if !self.supports(&msg).await {
+            return Err(format!("Peer does not support {:?}", msg.required_capability().unwrap_or_default()));
+        }
         let data = serialize_message(&msg)?;
-        let len = data.len() as u32;
-        
-        let mut write = self.write_half.write().await;
-        
-        // Write length prefix (4 bytes) then message data
-        write
-            .write_all(&len.to_be_bytes())
-            .await
-            .map_err(|e| format!("Failed to write message length: {}", e))?;
-        
-        write
-            .write_all(&data)
-            .await
-            .map_err(|e| format!("Failed to write message data: {}", e))?;
-        
-        write
-            .flush()
-            .await
-            .map_err(|e| format!("Failed to flush stream: {}", e))?;
-
-        debug!("Sent message to {}: {:?}", self.info.read().await.address, msg);
+        self.send_serialized(&data)?;
+        debug!("Queued message to {}: {:?}", self.info.read().await.address, msg);
         Ok(())
     }
 
+    /// Queue an already serialized message without waiting for the socket
+    /// A full queue means the peer is not keeping up: it is marked failed and shut down
+    pub fn send_serialized(&self, data: &[u8]) -> Result<(), String> {
+        let result = self.queue_frame(data);
+        if result.is_ok() {
+            self.send_failures.store(0, Ordering::Relaxed);
+        } else {
+            self.send_failures.fetch_add(1, Ordering::Relaxed);
+        }
+        result
+    }
+
+    /// Consecutive failed sends; past MAX_SEND_FAILURES the peer is dropped
+    pub fn send_failures(&self) -> u32 {
+        self.send_failures.load(Ordering::Relaxed)
+    }
+
+    /// Whether the last send errors mean the peer should be disconnected: its
+    /// connection failed, or sends kept failing past MAX_SEND_FAILURES
+    pub fn should_drop(&self) -> bool {
+        self.has_failed() || self.send_failures() >= MAX_SEND_FAILURES
+    }
+
+    fn queue_frame(&self, data: &[u8]) -> Result<(), String> {
+        if self.has_failed() {
+            return Err("Peer connection failed".to_string());
+        }
+
+        // Held until the frame is queued: frames must hit the wire in encryption order
+        let mut session = self.session.as_ref().map(|s| s.lock());
+        let encrypted;
+        let data = match session.as_mut() {
+            Some(session) => {
+                encrypted = session.encrypt(data)?;
+                &encrypted[..]
+            }
+            None => data,
+        };
+
+        // Length prefix (4 bytes) then message data
+        let mut frame = Vec::with_capacity(4 + data.len());
+        frame.extend_from_slice(&(data.len() as u32).to_be_bytes());
+        frame.extend_from_slice(data);
+        let frame_len = frame.len() as u64;
+
+        match self.send_queue.try_send(frame) {
+            Ok(()) => {
+                // Counted when queued: the writer sends frames in order until it fails
+                self.bytes_sent.fetch_add(frame_len, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(mpsc::error::TrySendError::Full(_)) => {
+                self.mark_failed();
+                Err("Send queue full (peer stalled)".to_string())
+            }
+            Err(mpsc::error::TrySendError::Closed(_)) => {
+                self.failed.store(true, Ordering::Relaxed);
+                Err("Peer connection closed".to_string())
+            }
+        }
+    }
+
+    /// Mark the connection failed and stop the writer (closes our side of the socket)
+    fn mark_failed(&self) {
+        self.failed.store(true, Ordering::Relaxed);
+        let _ = self.shutdown_tx.try_send(());
+    }
+
+    /// Whether the connection was dropped for overflow or a write error
+    pub fn has_failed(&self) -> bool {
+        self.failed.load(Ordering::Relaxed)
+    }
+
     /// Receive a message from this peer with timeout
     pub async fn receive_message(&self) -> Result<P2PMessage, String> {
         let result = timeout(
@@ -123,20 +310,46 @@ impl Peer {
             .await
             .map_err(|e| format!("Failed to read message data: {}", e))?;
         
+        self.bytes_received.fetch_add(4 + len as u64, Ordering::Relaxed);
+        
+        if let Some(session) = &self.session {
+            let data = session.lock().decrypt(&data)?;
+            return deserialize_message(&data);
+        }
         deserialize_message(&data)
     }
 
     /// Update peer information after handshake
-    pub async fn update_info(&self, node_id: String, version: u32, height: u64) {
+    pub async fn update_info(
+        &self,
+        node_id: String,
+        version: u32,
+        height: u64,
+        earliest_height: u64,
+        capabilities: Capabilities,
+    ) {
         let mut info = self.info.write().await;
         info.node_id = node_id;
         info.version = version;
         info.height = height;
+        info.earliest_height = earliest_height;
+        info.capabilities = capabilities;
     }
 
-    /// Get peer information
+    /// Whether the peer advertised what `msg` needs (plain messages always qualify)
+    pub async fn supports(&self, msg: &P2PMessage) -> bool {
+        match msg.required_capability() {
+            Some(capability) => self.info.read().await.capabilities.contains(capability),
+            None => true,
+        }
+    }
+
+    /// Get peer information (including current traffic counters)
     pub async fn get_info(&self) -> PeerInfo {
-        self.info.read().await.clone()
+        let mut info = self.info.read().await.clone();
+        info.bytes_sent = self.bytes_sent.load(Ordering::Relaxed);
+        info.bytes_received = self.bytes_received.load(Ordering::Relaxed);
+        info
     }
 
     /// Get peer address
@@ -146,27 +359,39 @@ impl Peer {
 
     /// Check if peer is alive
     pub async fn is_alive(&self) -> bool {
+        if self.has_failed() {
+            return false;
+        }
         let info = self.info.read().await;
         let now = chrono::Utc::now().timestamp();
         now - info.last_seen < 180 // 3 minutes timeout
     }
 
     /// Perform handshake with peer
-    pub async fn handshake(&self, our_version: u32, our_height: u64, our_node_id: String) -> Result<(), String> {
-        // Send our version
+    pub async fn handshake(
+        &self,
+        our_version: u32,
+        our_height: u64,
+        our_earliest_height: u64,
+        our_node_id: String,
+        our_capabilities: Capabilities,
+    ) -> Result<(), String> {
+        // Send our version (earliest height tells peers which blocks we can serve)
         let version_msg = P2PMessage::Version {
             version: our_version,
             height: our_height,
+            earliest_height: our_earliest_height,
             timestamp: chrono::Utc::now().timestamp(),
             node_id: our_node_id,
+            capabilities: our_capabilities,
         };
         
         self.send_message(version_msg).await?;
         
         // Wait for their version
         match self.receive_message().await? {
-            P2PMessage::Version { version, height, node_id, .. } => {
-                self.update_info(node_id, version, height).await;
+            P2PMessage::Version { version, height, earliest_height, node_id, capabilities, .. } => {
+                self.update_info(node_id, version, height, earliest_height, capabilities).await;
                 
                 // Send verack
                 self.send_message(P2PMessage::VerAck).await?;
@@ -245,19 +470,40 @@ impl PeerManager {
         self.peers.read().await.len()
     }
 
-    /// Broadcast message to all peers (PARALLELIZED)
-    pub async fn broadcast(&self, msg: P2PMessage) {
+    /// Broadcast message to all peers
+    /// Serializes once and queues per peer; a peer whose queue overflows or whose
+    /// connection failed is dropped instead of holding up (or piling tasks onto) everyone
+    /// else. Returns the dropped peers so the caller can count it against them
+    pub async fn broadcast(&self, msg: P2PMessage) -> Vec<SocketAddr> {
+        let data = match serialize_message(&msg) {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Failed to serialize broadcast: {}", e);
+                return Vec::new();
+            }
+        };
         let peers = self.peers.read().await.clone();
         
-        // Spawn concurrent sends - don't let one slow peer block everyone
+        let mut dropped = Vec::new();
         for peer in peers {
-            let msg_clone = msg.clone();
-            tokio::spawn(async move {
-                if let Err(e) = peer.send_message(msg_clone).await {
-                    warn!("Failed to send message to peer: {}", e);
+            if !peer.supports(&msg).await {
+                continue;
+            }
+            if let Err(e) = peer.send_serialized(&data) {
+                let addr = peer.address().await;
+                if peer.should_drop() {
+                    warn!("Dropping peer {}: {}", addr, e);
+                    dropped.push(addr);
+                } else {
+                    debug!("Send to peer {} failed ({} in a row): {}", addr, peer.send_failures(), e);
                 }
-            });
+            }
+        }
+        
+        for addr in &dropped {
+            self.remove_peer(*addr).await;
         }
+        dropped
     }
 
     /// Clean up dead peers
@@ -281,3 +527,162 @@ impl PeerManager {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    /// Connected pair: our Peer plus the raw remote end
+    async fn connected_peer(capacity: usize) -> (Arc<Peer>, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let remote = TcpStream::connect(addr).await.unwrap();
+        let (stream, peer_addr) = listener.accept().await.unwrap();
+        let peer = Peer::with_send_queue_capacity(stream, peer_addr, PeerDirection::Inbound, capacity)
+            .await
+            .unwrap();
+        (Arc::new(peer), remote)
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn stalled_peer_overflow_disconnects_only_that_peer() {
+        let manager = PeerManager::new(8);
+
+        // Healthy remote drains everything; stalled remote never reads
+        let (healthy, mut healthy_remote) = connected_peer(4).await;
+        tokio::spawn(async move {
+            let _ = tokio::io::copy(&mut healthy_remote, &mut tokio::io::sink()).await;
+        });
+        let (stalled, _stalled_remote) = connected_peer(4).await;
+        manager.add_peer(Arc::clone(&healthy)).await.unwrap();
+        manager.add_peer(Arc::clone(&stalled)).await.unwrap();
+
+        // ~1MB messages fill the stalled socket's kernel buffers, then its queue
+        let big = P2PMessage::Error("x".repeat(1024 * 1024));
+        for _ in 0..200 {
+            manager.broadcast(big.clone()).await;
+            if manager.peer_count().await == 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+
+        assert!(stalled.has_failed());
+        assert!(!healthy.has_failed());
+        let remaining = manager.get_peers().await;
+        assert_eq!(remaining.len(), 1);
+        assert!(Arc::ptr_eq(&remaining[0], &healthy));
+    }
+
+    #[tokio::test]
+    async fn outbound_peer_reports_direction_and_traffic() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listen_addr = listener.local_addr().unwrap();
+
+        let inbound = tokio::spawn(async move {
+            let (stream, addr) = listener.accept().await.unwrap();
+            let peer = Peer::new(stream, addr, PeerDirection::Inbound).await.unwrap();
+            peer.handshake(1, 0, 0, "inbound-node".to_string(), Capabilities::LOCAL).await.unwrap();
+            peer.get_info().await
+        });
+
+        let stream = TcpStream::connect(listen_addr).await.unwrap();
+        let peer = Peer::new(stream, listen_addr, PeerDirection::Outbound).await.unwrap();
+        peer.handshake(1, 0, 0, "outbound-node".to_string(), Capabilities::LOCAL).await.unwrap();
+
+        let info = peer.get_info().await;
+        assert_eq!(info.direction, PeerDirection::Outbound);
+        assert_eq!(info.node_id, "inbound-node");
+        assert!(info.bytes_sent > 0);
+        assert!(info.bytes_received > 0);
+
+        let inbound_info = inbound.await.unwrap();
+        assert_eq!(inbound_info.direction, PeerDirection::Inbound);
+        assert_eq!(inbound_info.bytes_received, info.bytes_sent);
+    }
+
+    #[tokio::test]
+    async fn clock_skew_never_reports_negative_durations() {
+        let (peer, _remote) = connected_peer(4).await;
+        let mut info = peer.get_info().await;
+        let now = chrono::Utc::now().timestamp();
+
+        // Peer clock (or ours, since) jumped: connected_at is in the future
+        info.connected_at = now + 300;
+        info.last_seen = now + 300;
+        assert_eq!(info.connected_for(now), 0);
+        assert_eq!(clamp_to_past(info.connected_at, now), now as u64);
+        assert_eq!(clamp_to_past(info.last_seen, now), now as u64);
+
+        info.connected_at = now - 42;
+        assert_eq!(info.connected_for(now), 42);
+        assert_eq!(clamp_to_past(info.connected_at, now), (now - 42) as u64);
+        assert_eq!(clamp_to_past(-5, now), 0);
+    }
+
+    #[tokio::test]
+    async fn capability_gated_messages_skip_peers_without_support() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let listen_addr = listener.local_addr().unwrap();
+
+        // Remote advertises nothing and reports the first message it receives after VerAck
+        let remote = tokio::spawn(async move {
+            let (stream, addr) = listener.accept().await.unwrap();
+            let peer = Peer::new(stream, addr, PeerDirection::Inbound).await.unwrap();
+            peer.handshake(1, 0, 0, "legacy-node".to_string(), Capabilities::NONE).await.unwrap();
+            let first = peer.receive_message().await.unwrap();
+            (peer.get_info().await.capabilities, first)
+        });
+
+        let stream = TcpStream::connect(listen_addr).await.unwrap();
+        let peer = Arc::new(Peer::new(stream, listen_addr, PeerDirection::Outbound).await.unwrap());
+        peer.handshake(1, 0, 0, "new-node".to_string(), Capabilities::LOCAL).await.unwrap();
+        assert_eq!(peer.get_info().await.capabilities, Capabilities::NONE);
+
+        let snapshot_request = P2PMessage::GetSnapshot { height: 10, chunk: 0 };
+        assert!(!peer.supports(&snapshot_request).await);
+        assert!(peer.send_message(snapshot_request.clone()).await.is_err());
+
+        // Broadcast skips the peer (without dropping it); plain messages still flow
+        let manager = PeerManager::new(8);
+        manager.add_peer(Arc::clone(&peer)).await.unwrap();
+        manager.broadcast(snapshot_request).await;
+        assert_eq!(manager.peer_count().await, 1);
+        peer.send_message(P2PMessage::Ping(9)).await.unwrap();
+
+        let (remote_sees, first) = remote.await.unwrap();
+        assert_eq!(remote_sees, Capabilities::LOCAL);
+        assert!(matches!(first, P2PMessage::Ping(9)));
+    }
+
+    #[tokio::test]
+    async fn peer_with_dead_connection_is_dropped_on_broadcast() {
+        let manager = PeerManager::new(8);
+        let (healthy, mut healthy_remote) = connected_peer(SEND_QUEUE_CAPACITY).await;
+        tokio::spawn(async move {
+            let _ = tokio::io::copy(&mut healthy_remote, &mut tokio::io::sink()).await;
+        });
+        let (dead, dead_remote) = connected_peer(SEND_QUEUE_CAPACITY).await;
+        let dead_addr = dead.address().await;
+        manager.add_peer(Arc::clone(&healthy)).await.unwrap();
+        manager.add_peer(Arc::clone(&dead)).await.unwrap();
+        drop(dead_remote);
+
+        // Writes to the closed socket fail within a few frames; the next broadcast drops the peer
+        let mut dropped = Vec::new();
+        for _ in 0..100 {
+            dropped = manager.broadcast(P2PMessage::Ping(1)).await;
+            if !dropped.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(dropped, vec![dead_addr]);
+        assert!(dead.should_drop());
+        let remaining = manager.get_peers().await;
+        assert_eq!(remaining.len(), 1);
+        assert!(Arc::ptr_eq(&remaining[0], &healthy));
+        assert_eq!(healthy.send_failures(), 0);
+    }
+}
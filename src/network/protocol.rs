@@ -1,3 +1,4 @@
+pub use crate::core::block::BlockHeader;
 use crate::core::block::Block;
 use crate::core::transaction::Transaction;
 use serde::{Deserialize, Serialize};
@@ -10,8 +11,10 @@ pub enum P2PMessage {
     Version {
         version: u32,
         height: u64,
+        earliest_height: u64, // Lowest block height this node can still serve
         timestamp: i64,
         node_id: String,
+        capabilities: Capabilities, // Optional features this node supports
     },
     VerAck,
 
@@ -44,44 +47,98 @@ pub enum P2PMessage {
     // Error handling
     Error(String),
     Disconnect,
+    Reject {
+        reason: RejectReason,
+        message: String,
+    },
+
+    // Snapshot sync (only served at hardcoded snapshot checkpoints). The bincode
+    // StateSnapshot is sent in SNAPSHOT_CHUNK_BYTES pieces, one per request
+    GetSnapshot {
+        height: u64,
+        chunk: u32,
+    },
+    SnapshotChunk {
+        height: u64,
+        chunk: u32,
+        total_chunks: u32,
+        data: Vec<u8>,
+    },
 }
 
-/// Network message wrapper with magic bytes for network identification
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct NetworkMessage {
-    pub magic: [u8; 4], // Network identifier
-    pub message: P2PMessage,
+/// Optional protocol features a node advertises in its Version message
+///
+/// Messages that need a feature are only sent to peers that advertised it (see
+/// `P2PMessage::required_capability`). Unknown bits from newer peers are kept and
+/// ignored, so features can roll out one node at a time.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Capabilities(u64);
+
+impl Capabilities {
+    pub const NONE: Self = Self(0);
+    /// Answers GetSnapshot at snapshot checkpoints
+    pub const SNAPSHOTS: Self = Self(1 << 0);
+
+    /// Every feature this build supports (advertised in our handshake)
+    pub const LOCAL: Self = Self::SNAPSHOTS;
+
+    /// Names for display (RPC/API); unknown bits are skipped
+    const NAMED: [(Capabilities, &'static str); 1] = [(Self::SNAPSHOTS, "snapshots")];
+
+    pub fn contains(self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    pub fn names(self) -> Vec<&'static str> {
+        Self::NAMED.iter().filter(|(cap, _)| self.contains(*cap)).map(|(_, name)| *name).collect()
+    }
 }
 
-/// Simplified block header for efficient sync
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct BlockHeader {
-    pub index: u64,
-    pub timestamp: i64,
-    pub previous_hash: String,
-    pub hash: String,
-    pub nonce: u64,
-    pub difficulty: u32,
+impl std::ops::BitOr for Capabilities {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self {
+        Self(self.0 | rhs.0)
+    }
 }
 
-impl From<&Block> for BlockHeader {
-    fn from(block: &Block) -> Self {
-        Self {
-            index: block.index,
-            timestamp: block.timestamp,
-            previous_hash: block.previous_hash.clone(),
-            hash: block.hash.clone(),
-            nonce: block.nonce,
-            difficulty: block.difficulty,
+impl P2PMessage {
+    /// Capability the receiving peer must advertise before we send this message
+    pub fn required_capability(&self) -> Option<Capabilities> {
+        match self {
+            P2PMessage::GetSnapshot { .. } => Some(Capabilities::SNAPSHOTS),
+            _ => None,
         }
     }
 }
 
+/// Why a peer request was refused
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum RejectReason {
+    /// Requested blocks are below the node's earliest available height;
+    /// the requester should fetch them from another peer
+    BlocksPruned { earliest_available: u64 },
+}
+
+/// Network message wrapper with magic bytes for network identification
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct NetworkMessage {
+    pub magic: [u8; 4], // Network identifier
+    pub message: P2PMessage,
+}
+
+
 /// Protocol constants
 pub const PROTOCOL_VERSION: u32 = 1;
+/// Wire format version byte prefixed to every serialized message
+/// Bump on any change to the P2PMessage encoding (variant order, fields) or to
+/// the transaction hash preimage (peers would disagree on every signature)
+pub const WIRE_FORMAT_VERSION: u8 = 8;
 pub const MAX_MESSAGE_SIZE: usize = 2 * 1024 * 1024; // 2MB
 pub const PING_INTERVAL_SECS: u64 = 60;
 pub const PEER_TIMEOUT_SECS: u64 = 180;
+pub const MAX_ADDR_PER_MESSAGE: usize = 1000; // Larger Addr messages are a protocol violation
+pub const MAX_HEADERS_PER_MESSAGE: u64 = 2000; // Headers served per GetHeaders request
 
 /// Network magic bytes (prevents testnet/mainnet message mixing)
 pub const TESTNET_MAGIC: [u8; 4] = *b"QUAX"; // Quanta Testnet
@@ -116,16 +173,171 @@ pub trait MessageHandler: Send + Sync {
     async fn handle_get_mempool(&self) -> Result<Vec<Transaction>, String>;
 }
 
-/// Serialize a message for network transmission
+/// Serialize a message for network transmission (version byte + bincode payload)
 pub fn serialize_message(msg: &P2PMessage) -> Result<Vec<u8>, String> {
-    bincode::serialize(msg).map_err(|e| format!("Serialization error: {}", e))
+    let payload = bincode::serialize(msg).map_err(|e| format!("Serialization error: {}", e))?;
+    let mut data = Vec::with_capacity(payload.len() + 1);
+    data.push(WIRE_FORMAT_VERSION);
+    data.extend_from_slice(&payload);
+    Ok(data)
 }
 
-/// Deserialize a message from network data
+/// Deserialize a message from network data (rejects unknown wire format versions)
 pub fn deserialize_message(data: &[u8]) -> Result<P2PMessage, String> {
     if data.len() > MAX_MESSAGE_SIZE {
         return Err("Message too large".to_string());
     }
-    bincode::deserialize(data).map_err(|e| format!("Deserialization error: {}", e))
+    let (&version, payload) = data.split_first().ok_or("Empty message")?;
+    if version != WIRE_FORMAT_VERSION {
+        return Err(format!(
+            "Unsupported wire format version {} (expected {})",
+            version, WIRE_FORMAT_VERSION
+        ));
+    }
+    let msg: P2PMessage =
+        bincode::deserialize(payload).map_err(|e| format!("Deserialization error: {}", e))?;
+    check_transaction_bounds(&msg)?;
+    Ok(msg)
+}
+
+/// Reject messages carrying transactions with oversized address/call fields
+fn check_transaction_bounds(msg: &P2PMessage) -> Result<(), String> {
+    let txs: &[Transaction] = match msg {
+        P2PMessage::NewTx(tx) => std::slice::from_ref(tx),
+        P2PMessage::Mempool(txs) => txs,
+        P2PMessage::Block(block) => &block.transactions,
+        _ => return Ok(()),
+    };
+    txs.iter()
+        .try_for_each(|tx| tx.check_field_bounds())
+        .map_err(|e| format!("Oversized transaction field: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::ChainNetwork;
+
+    /// Bincode variant index for each message. The match is exhaustive, so adding a
+    /// variant fails to compile until it is listed here; reordering fails the test.
+    fn expected_tag(msg: &P2PMessage) -> u32 {
+        match msg {
+            P2PMessage::Version { .. } => 0,
+            P2PMessage::VerAck => 1,
+            P2PMessage::GetAddr => 2,
+            P2PMessage::Addr(_) => 3,
+            P2PMessage::GetBlocks { .. } => 4,
+            P2PMessage::Block(_) => 5,
+            P2PMessage::GetHeaders { .. } => 6,
+            P2PMessage::Headers(_) => 7,
+            P2PMessage::GetHeight => 8,
+            P2PMessage::Height(_) => 9,
+            P2PMessage::NewTx(_) => 10,
+            P2PMessage::GetMempool => 11,
+            P2PMessage::Mempool(_) => 12,
+            P2PMessage::Ping(_) => 13,
+            P2PMessage::Pong(_) => 14,
+            P2PMessage::Error(_) => 15,
+            P2PMessage::Disconnect => 16,
+            P2PMessage::Reject { .. } => 17,
+            P2PMessage::GetSnapshot { .. } => 18,
+            P2PMessage::SnapshotChunk { .. } => 19,
+        }
+    }
+
+    fn sample_messages() -> Vec<P2PMessage> {
+        let block = Block::genesis(ChainNetwork::Testnet);
+        let mut tx = Transaction::new(
+            "0x1111111111111111111111111111111111111111".to_string(),
+            "0x2222222222222222222222222222222222222222".to_string(),
+            5_000,
+            1_700_000_000,
+        );
+        tx.nonce = 3;
+        tx.signature = vec![1, 2, 3];
+        tx.public_key = vec![4, 5, 6];
+
+        vec![
+            P2PMessage::Version {
+                version: PROTOCOL_VERSION,
+                height: 42,
+                earliest_height: 0,
+                timestamp: 1_700_000_000,
+                node_id: "node-a".to_string(),
+                capabilities: Capabilities::LOCAL,
+            },
+            P2PMessage::VerAck,
+            P2PMessage::GetAddr,
+            P2PMessage::Addr(vec!["127.0.0.1:8333".parse().unwrap()]),
+            P2PMessage::GetBlocks { start_height: 1, end_height: 10 },
+            P2PMessage::Block(block.clone()),
+            P2PMessage::GetHeaders { start_height: 7 },
+            P2PMessage::Headers(vec![BlockHeader::from(&block)]),
+            P2PMessage::GetHeight,
+            P2PMessage::Height(99),
+            P2PMessage::NewTx(tx.clone()),
+            P2PMessage::GetMempool,
+            P2PMessage::Mempool(vec![tx]),
+            P2PMessage::Ping(11),
+            P2PMessage::Pong(12),
+            P2PMessage::Error("boom".to_string()),
+            P2PMessage::Disconnect,
+            P2PMessage::Reject {
+                reason: RejectReason::BlocksPruned { earliest_available: 5 },
+                message: "pruned".to_string(),
+            },
+            P2PMessage::GetSnapshot { height: 100, chunk: 2 },
+            P2PMessage::SnapshotChunk { height: 100, chunk: 2, total_chunks: 3, data: vec![7; 16] },
+        ]
+    }
+
+    #[test]
+    fn every_variant_round_trips() {
+        for msg in sample_messages() {
+            let bytes = serialize_message(&msg).unwrap();
+            assert_eq!(bytes[0], WIRE_FORMAT_VERSION);
+
+            let tag = u32::from_le_bytes(bytes[1..5].try_into().unwrap());
+            assert_eq!(tag, expected_tag(&msg), "variant tag changed for {:?}", msg);
+
+            let decoded = deserialize_message(&bytes).unwrap();
+            assert_eq!(serialize_message(&decoded).unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn oversized_transaction_fields_are_rejected() {
+        let mut tx = Transaction::new(
+            "0x1111111111111111111111111111111111111111".to_string(),
+            "0x".to_string() + &"2".repeat(10_000),
+            5_000,
+            1_700_000_000,
+        );
+        let bytes = serialize_message(&P2PMessage::NewTx(tx.clone())).unwrap();
+        assert!(deserialize_message(&bytes).unwrap_err().contains("recipient"));
+
+        tx.recipient = "0x2222222222222222222222222222222222222222".to_string();
+        tx.tx_type = crate::core::transaction::TransactionType::CallContract {
+            contract: tx.recipient.clone(),
+            function: "f".to_string(),
+            args: vec![0u8; crate::core::transaction::MAX_CALL_ARGS_BYTES + 1],
+        };
+        let bytes = serialize_message(&P2PMessage::Mempool(vec![tx])).unwrap();
+        assert!(deserialize_message(&bytes).unwrap_err().contains("args"));
+    }
+
+    #[test]
+    fn wrong_wire_format_version_is_rejected() {
+        let mut bytes = serialize_message(&P2PMessage::Ping(1)).unwrap();
+        bytes[0] = WIRE_FORMAT_VERSION.wrapping_add(1);
+
+        let err = deserialize_message(&bytes).unwrap_err();
+        assert!(err.contains("wire format version"));
+        assert!(deserialize_message(&[]).is_err());
+
+        // Version 5 transactions had no memo field
+        bytes[0] = 5;
+        assert!(deserialize_message(&bytes).is_err());
+    }
 }
 
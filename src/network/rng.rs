@@ -0,0 +1,20 @@
+use parking_lot::Mutex;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::sync::Arc;
+
+/// Shared RNG used for peer selection and ping nonces
+/// Production uses the OS RNG; tests can inject a seeded one for reproducibility
+pub type NetworkRng = Arc<Mutex<Box<dyn RngCore + Send>>>;
+
+/// OS-backed RNG (default)
+pub fn os_rng() -> NetworkRng {
+    Arc::new(Mutex::new(Box::new(OsRng)))
+}
+
+/// Deterministic RNG from a fixed seed
+#[cfg(test)]
+pub fn seeded_rng(seed: u64) -> NetworkRng {
+    use rand::SeedableRng;
+    Arc::new(Mutex::new(Box::new(rand::rngs::StdRng::seed_from_u64(seed))))
+}
@@ -0,0 +1,129 @@
+use std::net::SocketAddr;
+use thiserror::Error;
+
+/// Bytes of serialized snapshot per SnapshotChunk message (well under MAX_MESSAGE_SIZE)
+pub const SNAPSHOT_CHUNK_BYTES: usize = 1024 * 1024;
+/// Chunks a snapshot may span (512 MiB); larger announced totals are refused
+pub const MAX_SNAPSHOT_CHUNKS: u32 = 512;
+
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotSyncError {
+    #[error("chunk from {0}, which is not the snapshot peer")]
+    WrongPeer(SocketAddr),
+    #[error("chunk {got} for height {height} where chunk {expected} was expected")]
+    OutOfOrder { height: u64, expected: u32, got: u32 },
+    #[error("snapshot of {0} chunks exceeds the limit")]
+    TooLarge(u32),
+    #[error("chunk {0} is empty or over the chunk size")]
+    BadChunk(u32),
+}
+
+/// Number of chunks `len` serialized bytes are served in
+pub fn chunk_count(len: usize) -> u32 {
+    len.div_ceil(SNAPSHOT_CHUNK_BYTES) as u32
+}
+
+/// Chunk `index` of a serialized snapshot (None past the end)
+pub fn chunk(data: &[u8], index: u32) -> Option<&[u8]> {
+    let start = (index as usize).checked_mul(SNAPSHOT_CHUNK_BYTES)?;
+    if start >= data.len() {
+        return None;
+    }
+    Some(&data[start..(start + SNAPSHOT_CHUNK_BYTES).min(data.len())])
+}
+
+/// Snapshot download from one peer: chunks are requested one at a time, in order,
+/// and only deserialized and verified once all of them have arrived
+#[derive(Debug)]
+pub struct SnapshotDownload {
+    peer: SocketAddr,
+    height: u64,
+    next_chunk: u32,
+    total: Option<u32>, // Set by the first chunk
+    data: Vec<u8>,
+}
+
+impl SnapshotDownload {
+    pub fn new(peer: SocketAddr, height: u64) -> Self {
+        Self { peer, height, next_chunk: 0, total: None, data: Vec::new() }
+    }
+
+    pub fn peer(&self) -> SocketAddr {
+        self.peer
+    }
+
+    /// Chunk to request next
+    pub fn next_chunk(&self) -> u32 {
+        self.next_chunk
+    }
+
+    /// Append the next chunk. Returns the whole serialized snapshot once the last
+    /// chunk is in, None while more are needed
+    pub fn accept_chunk(
+        &mut self,
+        peer: SocketAddr,
+        height: u64,
+        index: u32,
+        total: u32,
+        data: Vec<u8>,
+    ) -> Result<Option<Vec<u8>>, SnapshotSyncError> {
+        if peer != self.peer {
+            return Err(SnapshotSyncError::WrongPeer(peer));
+        }
+        if height != self.height || index != self.next_chunk || self.total.is_some_and(|t| t != total) {
+            return Err(SnapshotSyncError::OutOfOrder { height, expected: self.next_chunk, got: index });
+        }
+        if total == 0 || total > MAX_SNAPSHOT_CHUNKS {
+            return Err(SnapshotSyncError::TooLarge(total));
+        }
+        if data.is_empty() || data.len() > SNAPSHOT_CHUNK_BYTES {
+            return Err(SnapshotSyncError::BadChunk(index));
+        }
+
+        self.total = Some(total);
+        self.data.extend(data);
+        self.next_chunk += 1;
+        if self.next_chunk < total {
+            return Ok(None);
+        }
+        Ok(Some(std::mem::take(&mut self.data)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chunks_reassemble_in_order_and_strays_are_refused() {
+        let peer: SocketAddr = "10.0.0.1:8333".parse().unwrap();
+        let data: Vec<u8> = (0..SNAPSHOT_CHUNK_BYTES * 2 + 10).map(|i| i as u8).collect();
+        let total = chunk_count(data.len());
+        assert_eq!(total, 3);
+        assert_eq!(chunk(&data, 2).unwrap().len(), 10);
+        assert!(chunk(&data, 3).is_none());
+
+        let mut download = SnapshotDownload::new(peer, 100);
+        let stranger: SocketAddr = "10.0.0.2:8333".parse().unwrap();
+        assert_eq!(
+            download.accept_chunk(stranger, 100, 0, total, chunk(&data, 0).unwrap().to_vec()),
+            Err(SnapshotSyncError::WrongPeer(stranger))
+        );
+        assert!(matches!(
+            download.accept_chunk(peer, 100, 1, total, chunk(&data, 1).unwrap().to_vec()),
+            Err(SnapshotSyncError::OutOfOrder { expected: 0, got: 1, .. })
+        ));
+        assert_eq!(
+            download.accept_chunk(peer, 100, 0, MAX_SNAPSHOT_CHUNKS + 1, vec![0]),
+            Err(SnapshotSyncError::TooLarge(MAX_SNAPSHOT_CHUNKS + 1))
+        );
+
+        for index in 0..total - 1 {
+            let part = chunk(&data, index).unwrap().to_vec();
+            assert_eq!(download.accept_chunk(peer, 100, index, total, part), Ok(None));
+            assert_eq!(download.next_chunk(), index + 1);
+        }
+        let last = chunk(&data, total - 1).unwrap().to_vec();
+        assert_eq!(download.accept_chunk(peer, 100, total - 1, total, last), Ok(Some(data)));
+    }
+}
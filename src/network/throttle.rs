@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+
+/// Length of one rate-limiting window
+const WINDOW: Duration = Duration::from_secs(1);
+/// An IP that keeps connecting past this multiple of its limit within one window is flooding
+const BAN_MULTIPLIER: u32 = 4;
+/// Prune idle per-IP entries once the table grows past this size
+const MAX_TRACKED_IPS: usize = 10_000;
+/// Minimum gap between orphan-driven GetBlocks requests to one peer
+const ORPHAN_REQUEST_INTERVAL: Duration = Duration::from_secs(2);
+/// Peers with no orphan activity for this long are dropped from the orphan table
+const ORPHAN_PEER_IDLE: Duration = Duration::from_secs(600);
+/// Prune idle per-peer orphan entries once the table grows past this size
+const MAX_TRACKED_ORPHAN_PEERS: usize = 1_000;
+
+/// Outcome of an inbound connection attempt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptDecision {
+    Allow,
+    Throttled,
+    /// Sustained flood: the caller bans the IP (through PeerDiscovery, with the other bans)
+    Flood,
+}
+
+struct IpWindow {
+    started: Instant,
+    count: u32,
+}
+
+/// Accept-rate limiter for inbound P2P connections
+/// Runs before a handler task is spawned, so floods are dropped cheaply
+pub struct AcceptThrottle {
+    per_ip_limit: u32,
+    global_limit: u32,
+    per_ip: HashMap<IpAddr, IpWindow>,
+    global_started: Instant,
+    global_count: u32,
+}
+
+impl AcceptThrottle {
+    /// Create a throttle allowing `per_ip_limit` connections per IP and
+    /// `global_limit` connections overall per second
+    pub fn new(per_ip_limit: u32, global_limit: u32) -> Self {
+        Self {
+            per_ip_limit,
+            global_limit,
+            per_ip: HashMap::new(),
+            global_started: Instant::now(),
+            global_count: 0,
+        }
+    }
+
+    /// Decide whether to accept a connection from `ip` now
+    pub fn check(&mut self, ip: IpAddr) -> AcceptDecision {
+        self.check_at(ip, Instant::now())
+    }
+
+    /// Decide whether to accept a connection from `ip` at time `now`
+    pub fn check_at(&mut self, ip: IpAddr, now: Instant) -> AcceptDecision {
+        if self.per_ip.len() > MAX_TRACKED_IPS {
+            self.per_ip.retain(|_, w| now.duration_since(w.started) < WINDOW);
+        }
+
+        // Per-IP window
+        let window = self.per_ip.entry(ip).or_insert(IpWindow { started: now, count: 0 });
+        if now.duration_since(window.started) >= WINDOW {
+            window.started = now;
+            window.count = 0;
+        }
+        window.count = window.count.saturating_add(1);
+
+        if window.count > self.per_ip_limit {
+            if window.count > self.per_ip_limit.saturating_mul(BAN_MULTIPLIER) {
+                self.per_ip.remove(&ip);
+                return AcceptDecision::Flood;
+            }
+            return AcceptDecision::Throttled;
+        }
+
+        // Global window
+        if now.duration_since(self.global_started) >= WINDOW {
+            self.global_started = now;
+            self.global_count = 0;
+        }
+        if self.global_count >= self.global_limit {
+            return AcceptDecision::Throttled;
+        }
+        self.global_count += 1;
+
+        AcceptDecision::Allow
+    }
+}
+
+/// What to do about an orphan block received from a peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrphanRequestDecision {
+    /// Ask the peer for the missing parents
+    Request,
+    /// Hold the orphan without asking (too soon, or too many requests outstanding)
+    Throttled,
+    /// The peer keeps sending orphans past its budget: mark it failed
+    Penalize,
+}
+
+struct PeerOrphans {
+    outstanding: u32,
+    last_request: Option<Instant>,
+    throttled: u32,
+    last_seen: Instant,
+}
+
+/// Per-peer budget for orphan-driven parent requests, so a peer streaming
+/// unconnectable blocks can't make us spam it with GetBlocks
+pub struct OrphanRequestThrottle {
+    max_outstanding: u32,
+    peers: HashMap<SocketAddr, PeerOrphans>,
+}
+
+impl OrphanRequestThrottle {
+    /// Allow each peer `max_outstanding` parent requests not yet answered by a connecting block
+    pub fn new(max_outstanding: u32) -> Self {
+        Self {
+            max_outstanding: max_outstanding.max(1),
+            peers: HashMap::new(),
+        }
+    }
+
+    /// Decide how to handle an orphan block just received from `addr`
+    pub fn on_orphan(&mut self, addr: SocketAddr) -> OrphanRequestDecision {
+        self.on_orphan_at(addr, Instant::now())
+    }
+
+    /// Decide how to handle an orphan block received from `addr` at time `now`
+    pub fn on_orphan_at(&mut self, addr: SocketAddr, now: Instant) -> OrphanRequestDecision {
+        if self.peers.len() > MAX_TRACKED_ORPHAN_PEERS {
+            self.peers.retain(|_, p| now.duration_since(p.last_seen) < ORPHAN_PEER_IDLE);
+        }
+
+        let peer = self.peers.entry(addr).or_insert(PeerOrphans {
+            outstanding: 0,
+            last_request: None,
+            throttled: 0,
+            last_seen: now,
+        });
+        peer.last_seen = now;
+
+        let too_soon = peer.last_request.is_some_and(|last| now.duration_since(last) < ORPHAN_REQUEST_INTERVAL);
+        if !too_soon && peer.outstanding < self.max_outstanding {
+            peer.outstanding += 1;
+            peer.last_request = Some(now);
+            return OrphanRequestDecision::Request;
+        }
+
+        // Each penalty costs the peer one failure; repeat offenders hit the ban policy
+        peer.throttled += 1;
+        if peer.throttled >= self.max_outstanding {
+            peer.throttled = 0;
+            return OrphanRequestDecision::Penalize;
+        }
+        OrphanRequestDecision::Throttled
+    }
+
+    /// A block from `addr` connected to the chain, answering one outstanding request.
+    /// Throttled orphans before it are forgiven, so only an unbroken flood is penalized
+    pub fn on_block_connected(&mut self, addr: SocketAddr) {
+        if let Some(peer) = self.peers.get_mut(&addr) {
+            peer.outstanding = peer.outstanding.saturating_sub(1);
+            peer.throttled = 0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(last: u8) -> IpAddr {
+        IpAddr::from([203, 0, 113, last])
+    }
+
+    #[test]
+    fn burst_from_one_ip_is_throttled() {
+        let mut throttle = AcceptThrottle::new(3, 100);
+        let now = Instant::now();
+
+        let decisions: Vec<_> = (0..5).map(|_| throttle.check_at(ip(1), now)).collect();
+        assert_eq!(&decisions[..3], &[AcceptDecision::Allow; 3]);
+        assert_eq!(&decisions[3..], &[AcceptDecision::Throttled; 2]);
+
+        // Other IPs are unaffected, and the window resets after a second
+        assert_eq!(throttle.check_at(ip(2), now), AcceptDecision::Allow);
+        assert_eq!(throttle.check_at(ip(1), now + WINDOW), AcceptDecision::Allow);
+    }
+
+    #[test]
+    fn sustained_flood_is_reported_for_banning() {
+        let mut throttle = AcceptThrottle::new(2, 100);
+        let now = Instant::now();
+
+        for _ in 0..(2 * BAN_MULTIPLIER) {
+            assert_ne!(throttle.check_at(ip(1), now), AcceptDecision::Flood);
+        }
+        assert_eq!(throttle.check_at(ip(1), now), AcceptDecision::Flood);
+
+        // The ban itself lives in PeerDiscovery; the throttle starts the IP afresh
+        assert_eq!(throttle.check_at(ip(1), now), AcceptDecision::Allow);
+    }
+
+    #[test]
+    fn global_limit_applies_across_ips() {
+        let mut throttle = AcceptThrottle::new(10, 3);
+        let now = Instant::now();
+
+        for i in 0..3 {
+            assert_eq!(throttle.check_at(ip(i), now), AcceptDecision::Allow);
+        }
+        assert_eq!(throttle.check_at(ip(9), now), AcceptDecision::Throttled);
+    }
+
+    #[test]
+    fn orphan_flood_is_throttled_then_penalized() {
+        let peer: SocketAddr = "203.0.113.1:8333".parse().unwrap();
+        let mut throttle = OrphanRequestThrottle::new(2);
+        let now = Instant::now();
+
+        // One request per interval, then further orphans in the interval are held
+        assert_eq!(throttle.on_orphan_at(peer, now), OrphanRequestDecision::Request);
+        assert_eq!(throttle.on_orphan_at(peer, now), OrphanRequestDecision::Throttled);
+        let later = now + ORPHAN_REQUEST_INTERVAL;
+        assert_eq!(throttle.on_orphan_at(peer, later), OrphanRequestDecision::Request);
+
+        // Budget of 2 outstanding is spent: waiting no longer helps, and the flood is penalized
+        let much_later = later + ORPHAN_REQUEST_INTERVAL;
+        assert_eq!(throttle.on_orphan_at(peer, much_later), OrphanRequestDecision::Penalize);
+        assert_eq!(throttle.on_orphan_at(peer, much_later), OrphanRequestDecision::Throttled);
+        assert_eq!(throttle.on_orphan_at(peer, much_later), OrphanRequestDecision::Penalize);
+
+        // Other peers have their own budget; a connecting block frees one request
+        let other: SocketAddr = "203.0.113.2:8333".parse().unwrap();
+        assert_eq!(throttle.on_orphan_at(other, much_later), OrphanRequestDecision::Request);
+        throttle.on_block_connected(peer);
+        let after = much_later + ORPHAN_REQUEST_INTERVAL;
+        assert_eq!(throttle.on_orphan_at(peer, after), OrphanRequestDecision::Request);
+
+        // Throttled orphans don't add up across connecting blocks
+        assert_eq!(throttle.on_orphan_at(peer, after), OrphanRequestDecision::Throttled);
+        throttle.on_block_connected(peer);
+        assert_eq!(throttle.on_orphan_at(peer, after), OrphanRequestDecision::Throttled);
+        throttle.on_block_connected(peer);
+        assert_eq!(throttle.on_orphan_at(peer, after), OrphanRequestDecision::Throttled);
+    }
+}
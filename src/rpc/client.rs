@@ -14,6 +14,14 @@ impl RpcClient {
         }
     }
 
+    /// Client for a node at an explicit RPC URL (e.g. a remote peer)
+    pub fn with_url(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::new(),
+        }
+    }
+
     pub async fn call(
         &self,
         method: &str,
@@ -48,6 +56,18 @@ impl RpcClient {
         Ok(status)
     }
 
+    #[allow(dead_code)]
+    pub async fn get_version(&self) -> Result<VersionInfo, Box<dyn Error>> {
+        let response = self.call("get_version", serde_json::json!({})).await?;
+
+        if let Some(error) = response.error {
+            return Err(format!("RPC Error: {}", error.message).into());
+        }
+
+        let info: VersionInfo = serde_json::from_value(response.result.unwrap())?;
+        Ok(info)
+    }
+
     pub async fn get_mining_status(&self) -> Result<MiningStatus, Box<dyn Error>> {
         let response = self.call("mining_status", serde_json::json!({})).await?;
         
@@ -72,6 +92,30 @@ impl RpcClient {
         Ok(block)
     }
 
+    /// Find the first height where this node's chain and `peer`'s differ
+    /// Block hashes commit to their parent, so once the chains differ they differ at
+    /// every later height; binary search needs O(log n) get_block calls per node.
+    pub async fn find_divergence(&self, peer: &RpcClient) -> Result<ChainComparison, Box<dyn Error>> {
+        let local_height = self.get_node_status().await?.chain_height;
+        let peer_height = peer.get_node_status().await?.chain_height;
+
+        let (mut low, mut high) = (0, local_height.min(peer_height));
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if self.get_block(mid).await?.hash == peer.get_block(mid).await?.hash {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        Ok(ChainComparison {
+            local_height,
+            peer_height,
+            first_divergence: (low < local_height.min(peer_height)).then_some(low),
+        })
+    }
+
     pub async fn get_balance(&self, address: &str) -> Result<serde_json::Value, Box<dyn Error>> {
         let response = self
             .call("get_balance", serde_json::json!({ "address": address }))
@@ -117,6 +161,64 @@ impl RpcClient {
         Ok(())
     }
 
+    /// Full block at `height`, fetched raw (hex/base64 bincode) and decoded locally
+    #[cfg(test)]
+    pub async fn get_raw_block(
+        &self,
+        height: u64,
+        encoding: ResultEncoding,
+    ) -> Result<crate::core::block::Block, Box<dyn Error>> {
+        let response = self
+            .call("get_block", serde_json::json!({ "height": height, "encoding": encoding }))
+            .await?;
+
+        if let Some(error) = response.error {
+            return Err(format!("RPC Error: {}", error.message).into());
+        }
+
+        let result = response.result.unwrap_or_default();
+        let data = result.get("data").and_then(|d| d.as_str()).ok_or("RPC result has no raw data")?;
+        Ok(encoding.decode_raw(data)?)
+    }
+
+    /// Full signed transaction by hash (mempool or chain) with its block height (None = pending)
+    #[cfg(test)]
+    pub async fn get_transaction(
+        &self,
+        hash: &str,
+    ) -> Result<(crate::core::transaction::Transaction, Option<u64>), Box<dyn Error>> {
+        let response = self.call("get_transaction", serde_json::json!({ "hash": hash })).await?;
+
+        if let Some(error) = response.error {
+            return Err(format!("RPC Error: {}", error.message).into());
+        }
+
+        let mut result = response.result.unwrap_or_default();
+        let block_height = result.get("block_height").and_then(|h| h.as_u64());
+        let transaction = serde_json::from_value(result["transaction"].take())?;
+        Ok((transaction, block_height))
+    }
+
+    /// Full signed transaction by hash, fetched raw (hex/base64 bincode) and decoded locally
+    #[cfg(test)]
+    pub async fn get_raw_transaction(
+        &self,
+        hash: &str,
+        encoding: ResultEncoding,
+    ) -> Result<crate::core::transaction::Transaction, Box<dyn Error>> {
+        let response = self
+            .call("get_transaction", serde_json::json!({ "hash": hash, "encoding": encoding }))
+            .await?;
+
+        if let Some(error) = response.error {
+            return Err(format!("RPC Error: {}", error.message).into());
+        }
+
+        let result = response.result.unwrap_or_default();
+        let data = result.get("data").and_then(|d| d.as_str()).ok_or("RPC result has no raw data")?;
+        Ok(encoding.decode_raw(data)?)
+    }
+
     pub async fn stop_mining(&self) -> Result<(), Box<dyn Error>> {
         let response = self.call("stop_mining", serde_json::json!({})).await?;
         
@@ -127,3 +229,219 @@ impl RpcClient {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::Blockchain;
+    use crate::consensus::blockchain::AccountStatePage;
+    use crate::core::ChainNetwork;
+    use crate::rpc::RpcServer;
+    use crate::storage::BlockchainStorage;
+    use std::sync::Arc;
+    use tokio::sync::RwLock;
+
+    /// Serve `blockchain` over JSON-RPC on an ephemeral port
+    async fn serve(blockchain: Blockchain) -> RpcClient {
+        serve_router(RpcServer::new(Arc::new(RwLock::new(blockchain)), None, 0, 0, 0).into_router()).await
+    }
+
+    async fn serve_router(router: axum::Router) -> RpcClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await });
+        RpcClient::with_url(url)
+    }
+
+    #[tokio::test]
+    async fn reports_first_divergent_height() {
+        let (dir_a, dir_b) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let open = |dir: &tempfile::TempDir| {
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet).unwrap()
+        };
+        let (a, b) = (open(&dir_a), open(&dir_b));
+
+        // Shared history through height 2, then each side mines its own block 3 and 4
+        for _ in 0..2 {
+            a.mine_pending_transactions("0x1111111111111111111111111111111111111111".to_string()).unwrap();
+            b.add_network_block(a.get_latest_block()).unwrap();
+        }
+        for _ in 0..2 {
+            a.mine_pending_transactions("0x1111111111111111111111111111111111111111".to_string()).unwrap();
+        }
+        b.mine_pending_transactions("0x2222222222222222222222222222222222222222".to_string()).unwrap();
+
+        let (local, peer) = (serve(a).await, serve(b).await);
+        let comparison = local.find_divergence(&peer).await.unwrap();
+        assert_eq!(
+            comparison,
+            ChainComparison { local_height: 5, peer_height: 4, first_divergence: Some(3) }
+        );
+
+        // A node compared with itself never diverges
+        assert_eq!(local.find_divergence(&local).await.unwrap().first_divergence, None);
+    }
+
+    #[tokio::test]
+    async fn raw_block_encodings_match_json_form() {
+        let dir = tempfile::tempdir().unwrap();
+        let blockchain =
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet).unwrap();
+        blockchain.mine_pending_transactions("0x1111111111111111111111111111111111111111".to_string()).unwrap();
+        let client = serve(blockchain).await;
+
+        let response = client.call("get_block", serde_json::json!({ "height": 1 })).await.unwrap();
+        let info: BlockInfo = serde_json::from_value(response.result.unwrap()).unwrap();
+
+        for encoding in [ResultEncoding::Hex, ResultEncoding::Base64] {
+            let block = client.get_raw_block(1, encoding).await.unwrap();
+            assert_eq!(BlockInfo::from(&block), info);
+            assert_eq!(block.hash, block.calculate_hash());
+        }
+
+        let response = client.call("get_block", serde_json::json!({ "height": 1, "encoding": "xml" })).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn fee_histogram_covers_the_pending_pool() {
+        let dir = tempfile::tempdir().unwrap();
+        let blockchain =
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet).unwrap();
+        let pending = |fee, nonce| {
+            crate::core::transaction::Transaction::builder()
+                .sender("0x1111111111111111111111111111111111111111")
+                .recipient("0x2222222222222222222222222222222222222222")
+                .amount(1_000)
+                .fee(fee)
+                .nonce(nonce)
+                .build()
+        };
+        blockchain.get_pending_transactions_mut().extend([pending(0, 1), pending(1_000_000, 2), pending(2_000_000, 3)]);
+        let client = serve(blockchain).await;
+
+        let response = client.call("get_fee_histogram", serde_json::json!({ "boundaries": [1.0] })).await.unwrap();
+        let histogram: crate::consensus::mempool::FeeHistogram = serde_json::from_value(response.result.unwrap()).unwrap();
+        assert_eq!(histogram.tx_count, 3);
+        assert_eq!(histogram.buckets.iter().map(|b| b.tx_count).collect::<Vec<_>>(), vec![2, 1]);
+        assert_eq!(histogram.buckets[1].cumulative_bytes, histogram.total_bytes);
+
+        let response = client.call("get_fee_histogram", serde_json::json!({ "boundaries": [2.0, 1.0] })).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn fee_estimate_treats_target_zero_as_the_next_block() {
+        let dir = tempfile::tempdir().unwrap();
+        let blockchain =
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet).unwrap();
+        let client = serve(blockchain).await;
+
+        let next = client.call("estimate_fee", serde_json::json!({ "target": 1 })).await.unwrap().result.unwrap();
+        let zero = client.call("estimate_fee", serde_json::json!({ "target": 0 })).await.unwrap().result.unwrap();
+        assert_eq!(zero, next);
+        assert_eq!(zero["target_blocks"], 1);
+
+        let response = client.call("estimate_fee", serde_json::json!({ "target": "soon" })).await.unwrap();
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+
+    #[tokio::test]
+    async fn confirmed_transaction_is_returned_signed_and_verifiable() {
+        let dir = tempfile::tempdir().unwrap();
+        let blockchain =
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet).unwrap();
+        let keypair = crate::crypto::FalconKeypair::generate();
+        let credit = crate::core::transaction::Transaction::new("TREASURY".to_string(), keypair.get_address(), 1_000_000, 0);
+        blockchain.get_account_state_mut().credit_account(&credit, 0, 0);
+
+        let mut tx = crate::core::transaction::Transaction::builder()
+            .sender(keypair.get_address())
+            .recipient("0x2222222222222222222222222222222222222222")
+            .amount(1_000)
+            .fee(1_000)
+            .nonce(1)
+            .build();
+        tx.public_key = keypair.public_key.clone();
+        tx.signature = keypair.sign(&tx.get_signing_data());
+        let hash = tx.hash();
+        blockchain.add_transaction(tx.clone()).unwrap();
+        blockchain.mine_pending_transactions("0x1111111111111111111111111111111111111111".to_string()).unwrap();
+        let client = serve(blockchain).await;
+
+        let (fetched, block_height) = client.get_transaction(&hash).await.unwrap();
+        assert_eq!(block_height, Some(1));
+        assert_eq!(fetched, tx);
+        assert!(fetched.verify());
+        for encoding in [ResultEncoding::Hex, ResultEncoding::Base64] {
+            let raw = client.get_raw_transaction(&hash, encoding).await.unwrap();
+            assert!(raw.verify());
+            assert_eq!(raw.hash(), hash);
+        }
+    }
+
+    #[tokio::test]
+    async fn oversized_request_body_is_rejected() {
+        let dir = tempfile::tempdir().unwrap();
+        let blockchain =
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet).unwrap();
+        let server = RpcServer::new(Arc::new(RwLock::new(blockchain)), None, 0, 0, 0).with_max_body_bytes(1024);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, server.into_router()).await });
+
+        let oversized = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "get_version",
+            "params": {"padding": "x".repeat(2048)},
+            "id": 1,
+        });
+        let response = reqwest::Client::new().post(&url).json(&oversized).send().await.unwrap();
+        assert_eq!(response.status(), reqwest::StatusCode::PAYLOAD_TOO_LARGE);
+
+        // Requests under the limit are still served
+        RpcClient::with_url(url).get_version().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn account_state_dump_sums_to_supply() {
+        let dir = tempfile::tempdir().unwrap();
+        let blockchain =
+            Blockchain::new(Arc::new(BlockchainStorage::new(dir.path()).unwrap()), ChainNetwork::Testnet).unwrap();
+        for miner in ["0x1111111111111111111111111111111111111111", "0x2222222222222222222222222222222222222222"] {
+            blockchain.mine_pending_transactions(miner.to_string()).unwrap();
+        }
+        let supply = blockchain.get_supply();
+        let blockchain = Arc::new(RwLock::new(blockchain));
+
+        // Admin-only: a default server refuses the dump
+        let public = serve_router(RpcServer::new(blockchain.clone(), None, 0, 0, 0).into_router()).await;
+        let refused = public.call("get_account_state", serde_json::json!({})).await.unwrap();
+        assert_eq!(refused.error.unwrap().code, -32601);
+
+        let admin = serve_router(RpcServer::new(blockchain, None, 0, 0, 0).with_admin_rpc(true).into_router()).await;
+        let (mut after, mut entries) = (None, Vec::new());
+        loop {
+            let response = admin
+                .call("get_account_state", serde_json::json!({ "after": after, "limit": 2 }))
+                .await
+                .unwrap();
+            let page: AccountStatePage = serde_json::from_value(response.result.unwrap()).unwrap();
+            assert!(page.accounts.len() <= 2);
+            assert_eq!((page.total_spendable, page.total_locked), (supply.circulating, supply.locked));
+            entries.extend(page.accounts);
+            match page.next_after {
+                Some(next) => after = Some(next),
+                None => break,
+            }
+        }
+
+        // Every account exactly once, in address order, and the balances add up to circulating + locked
+        assert!(entries.windows(2).all(|pair| pair[0].address < pair[1].address));
+        assert!(entries.iter().any(|entry| entry.address == "0x2222222222222222222222222222222222222222"));
+        let spendable: u64 = entries.iter().map(|entry| entry.balance).sum();
+        let locked: u64 = entries.iter().map(|entry| entry.locked).sum();
+        assert_eq!(spendable + locked, supply.circulating + supply.locked);
+        assert!(locked > 0);
+    }
+}
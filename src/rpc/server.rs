@@ -1,14 +1,23 @@
 use super::types::*;
 use crate::consensus::Blockchain;
+use crate::consensus::blockchain::{mine_until_stale, run_blocking};
 use crate::core::block::Block;
+use crate::core::block::Target;
+use crate::consensus::mempool::{FeeHistogram, DEFAULT_FEE_HISTOGRAM_BOUNDARIES};
+use crate::core::transaction::is_valid_address;
+use crate::core::units::microunits_to_qua;
+use crate::crypto::keystore::NodeKeyStore;
 use crate::network::Network;
+use crate::network::peer::clamp_to_past;
+use crate::api::handlers::{TransactionView, DEFAULT_MAX_REQUEST_BODY_BYTES};
 use axum::{
-    extract::State,
+    extract::{DefaultBodyLimit, State},
     http::StatusCode,
     response::Json,
     routing::post,
     Router,
 };
+use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use std::time::Instant;
@@ -22,6 +31,9 @@ pub struct RpcServer {
     pub api_port: u16,
     pub network_port: u16,
     pub rpc_port: u16,
+    pub admin_enabled: bool,
+    pub max_body_bytes: usize,
+    pub node_key: Option<Arc<NodeKeyStore>>,
 }
 
 pub struct MiningState {
@@ -40,8 +52,17 @@ struct AppState {
     api_port: u16,
     network_port: u16,
     rpc_port: u16,
+    admin_enabled: bool,
+    account_dump_window: Arc<parking_lot::Mutex<(Instant, u32)>>,
+    node_key: Option<Arc<NodeKeyStore>>,
 }
 
+/// Default and maximum accounts per get_account_state page
+const DEFAULT_ACCOUNT_PAGE_SIZE: usize = 500;
+const MAX_ACCOUNT_PAGE_SIZE: usize = 5_000;
+/// get_account_state pages served per minute (each one sorts the whole state)
+const ACCOUNT_DUMP_PAGES_PER_MINUTE: u32 = 60;
+
 impl RpcServer {
     pub fn new(
         blockchain: Arc<RwLock<Blockchain>>,
@@ -58,10 +79,44 @@ impl RpcServer {
             api_port,
             network_port,
             rpc_port,
+            admin_enabled: false,
+            max_body_bytes: DEFAULT_MAX_REQUEST_BODY_BYTES,
+            node_key: None,
         }
     }
 
+    /// Hot key loaded with `--node-key`, used by the sign_message admin method
+    pub fn with_node_key(mut self, node_key: Option<Arc<NodeKeyStore>>) -> Self {
+        self.node_key = node_key;
+        self
+    }
+
+    /// Allow admin methods (add_peer, remove_peer, ban_peer, get_account_state, sign_message)
+    pub fn with_admin_rpc(mut self, enabled: bool) -> Self {
+        self.admin_enabled = enabled;
+        self
+    }
+
+    /// Reject request bodies over `limit` bytes with 413 before they are deserialized
+    pub fn with_max_body_bytes(mut self, limit: usize) -> Self {
+        self.max_body_bytes = limit;
+        self
+    }
+
     pub async fn start(self, port: u16) -> Result<(), Box<dyn std::error::Error>> {
+        let app = self.into_router();
+
+        let addr = format!("127.0.0.1:{}", port);
+        let listener = tokio::net::TcpListener::bind(&addr).await?;
+        
+        tracing::info!("RPC server listening on {}", addr);
+        
+        axum::serve(listener, app).await?;
+        Ok(())
+    }
+
+    /// JSON-RPC router without a listener (start() binds it to the RPC port)
+    pub fn into_router(self) -> Router {
         let state = AppState {
             blockchain: self.blockchain,
             network: self.network,
@@ -70,19 +125,15 @@ impl RpcServer {
             api_port: self.api_port,
             network_port: self.network_port,
             rpc_port: self.rpc_port,
+            admin_enabled: self.admin_enabled,
+            account_dump_window: Arc::new(parking_lot::Mutex::new((Instant::now(), 0))),
+            node_key: self.node_key,
         };
 
-        let app = Router::new()
+        Router::new()
             .route("/", post(handle_rpc_request))
-            .with_state(state);
-
-        let addr = format!("127.0.0.1:{}", port);
-        let listener = tokio::net::TcpListener::bind(&addr).await?;
-        
-        tracing::info!("RPC server listening on {}", addr);
-        
-        axum::serve(listener, app).await?;
-        Ok(())
+            .layer(DefaultBodyLimit::max(self.max_body_bytes))
+            .with_state(state)
     }
 }
 
@@ -94,14 +145,32 @@ async fn handle_rpc_request(
 
     let response = match request.method.as_str() {
         "node_status" => handle_node_status(&state).await,
+        "get_version" => handle_get_version(&state).await,
         "start_mining" => handle_start_mining(&state, &request.params).await,
         "stop_mining" => handle_stop_mining(&state).await,
         "mining_status" => handle_mining_status(&state).await,
         "get_block" => handle_get_block(&state, &request.params).await,
+        "get_transaction" => handle_get_transaction(&state, &request.params).await,
         "get_balance" => handle_get_balance(&state, &request.params).await,
+        "get_next_nonce" => handle_get_next_nonce(&state, &request.params).await,
         "get_peers" => handle_get_peers(&state).await,
-        "get_mempool" => handle_get_mempool(&state).await,
+        "get_mempool" => handle_get_mempool(&state, &request.params).await,
+        "get_mempool_info" => handle_get_mempool_info(&state).await,
+        "get_fee_histogram" => handle_get_fee_histogram(&state, &request.params).await,
+        "estimate_fee" => handle_estimate_fee(&state, &request.params).await,
+        "preview_next_block" => handle_preview_next_block(&state).await,
+        "get_supply" => handle_get_supply(&state).await,
         "shutdown" => handle_shutdown(&state).await,
+        "add_peer" | "remove_peer" | "ban_peer" | "get_account_state" | "sign_message" if !state.admin_enabled => JsonRpcResponse::error(
+            request.id,
+            -32601,
+            format!("Admin method {} disabled (set security.enable_admin_rpc)", request.method),
+        ),
+        "add_peer" => handle_add_peer(&state, &request.params).await,
+        "remove_peer" => handle_remove_peer(&state, &request.params).await,
+        "ban_peer" => handle_ban_peer(&state, &request.params).await,
+        "get_account_state" => handle_get_account_state(&state, &request.params).await,
+        "sign_message" => handle_sign_message(&state, &request.params),
         _ => JsonRpcResponse::error(
             request.id,
             -32601,
@@ -113,10 +182,12 @@ async fn handle_rpc_request(
 }
 
 async fn handle_node_status(state: &AppState) -> JsonRpcResponse {
-    let blockchain = state.blockchain.read().await;
-    let chain_height = blockchain.get_height();
-    let mempool_size = blockchain.get_pending_transactions().len();
-    drop(blockchain);
+    let (snapshot, network) = {
+        let blockchain = state.blockchain.read().await;
+        (blockchain.snapshot(), blockchain.network())
+    };
+    let chain_height = snapshot.height;
+    let mempool_size = snapshot.stats.pending_transactions;
 
     let peer_count = if let Some(ref network) = state.network {
         network.peer_count().await
@@ -137,16 +208,25 @@ async fn handle_node_status(state: &AppState) -> JsonRpcResponse {
         rpc_port: state.rpc_port,
         uptime_seconds: uptime,
         version: env!("CARGO_PKG_VERSION").to_string(),
+        protocol_version: crate::network::protocol::PROTOCOL_VERSION,
+        network,
     };
 
     JsonRpcResponse::success(1, serde_json::to_value(status).unwrap())
 }
 
-async fn handle_mining_status(state: &AppState) -> JsonRpcResponse {
+async fn handle_get_version(state: &AppState) -> JsonRpcResponse {
     let blockchain = state.blockchain.read().await;
-    let latest_block = blockchain.get_latest_block();
-    let stats = blockchain.get_stats();
-    drop(blockchain);
+    let info = VersionInfo::new(blockchain.network(), blockchain.contracts_enabled());
+    JsonRpcResponse::success(1, serde_json::to_value(info).unwrap())
+}
+
+async fn handle_mining_status(state: &AppState) -> JsonRpcResponse {
+    let (snapshot, timing) = {
+        let blockchain = state.blockchain.read().await;
+        (blockchain.snapshot(), blockchain.block_timing())
+    };
+    let stats = snapshot.stats;
 
     let mining_state = state.mining_state.read().await;
     let is_mining = mining_state.as_ref().map(|m| m.is_active).unwrap_or(false);
@@ -155,10 +235,13 @@ async fn handle_mining_status(state: &AppState) -> JsonRpcResponse {
     let mining_status = MiningStatus {
         is_mining,
         mining_address,
-        last_block_time: Some(latest_block.timestamp),
+        last_block_time: Some(snapshot.tip_timestamp),
         blocks_mined: stats.chain_length as u64,
         difficulty: stats.current_difficulty as u64,
+        target: Target::from_difficulty(stats.current_difficulty),
         mining_reward: stats.mining_reward,
+        avg_block_time_secs: timing.as_ref().map(|t| t.avg_block_time_secs),
+        blocks_ahead_of_target: timing.as_ref().map(|t| t.blocks_ahead_of_target),
     };
 
     JsonRpcResponse::success(1, serde_json::to_value(mining_status).unwrap())
@@ -176,6 +259,14 @@ async fn handle_start_mining(state: &AppState, params: &serde_json::Value) -> Js
         }
     };
 
+    if !is_valid_address(&address) {
+        return JsonRpcResponse::error(
+            1,
+            -32602,
+            format!("Invalid params: malformed miner address {}", address),
+        );
+    }
+
     let mut mining_state = state.mining_state.write().await;
     
     // Check if already mining
@@ -208,7 +299,8 @@ async fn handle_start_mining(state: &AppState, params: &serde_json::Value) -> Js
     let network = state.network.clone();
     
     tokio::spawn(async move {
-        tracing::info!("Mining task started for address: {}", mining_address);
+        tracing::info!("Mining task started for address: {}", crate::core::redact::redact_address(&mining_address));
+        tracing::debug!("Mining address (full): {}", mining_address);
         
         let mut consecutive_failures = 0;
         const MAX_CONSECUTIVE_FAILURES: u32 = 10;
@@ -219,30 +311,43 @@ async fn handle_start_mining(state: &AppState, params: &serde_json::Value) -> Js
                 tracing::info!("Mining task stopped by cancellation");
                 break;
             }
+
+            // A node that has lost its peers may be off on a fork; wait for them to come back
+            if let Some(ref net) = network {
+                if !net.ready_to_mine().await {
+                    tokio::time::sleep(tokio::time::Duration::from_secs(1)).await;
+                    continue;
+                }
+            }
             
             // Mine a block
             // 1. Create template (Lock held briefly)
-            let template_result = blockchain.read().await.create_block_template(mining_address.clone());
+            let (template_result, tip) = {
+                let bc = blockchain.read().await;
+                (bc.create_block_template(mining_address.clone()), bc.subscribe_tip())
+            };
             
             match template_result {
-                Ok(mut block) => {
-                    // 2. Mine (NO LOCK held on blockchain)
-                    // Run CPU-intensive mining in a blocking task to avoid starving async runtime
-                    
-                    let mined_block_res = tokio::task::spawn_blocking(move || {
-                        block.mine();
-                        block
-                    }).await;
+                Ok(block) => {
+                    // 2. Mine (NO LOCK held on blockchain) in a blocking task to avoid starving
+                    // the async runtime; abandoned if a new tip arrives or mining is stopped
+                    let mined_block_res = mine_until_stale(block, tip, &cancel_token).await;
                     
                     if let Ok(mined_block) = mined_block_res {
                         // Check cancellation again before submitting
                         if cancel_token.is_cancelled() {
                             break;
                         }
+                        let Some(mined_block) = mined_block else {
+                            tracing::info!("Chain tip moved while mining, restarting on the new tip");
+                            continue;
+                        };
 
                         // 3. Submit (Lock held briefly to commit)
-                        // add_network_block handles validation and saving
-                        match blockchain.read().await.add_network_block(mined_block.clone()) {
+                        // add_network_block handles validation and saving (off the async runtime)
+                        let guard = Arc::clone(&blockchain).read_owned().await;
+                        let submitted = mined_block.clone();
+                        match run_blocking(guard, move |bc| bc.add_network_block(submitted)).await {
                             Ok(_) => {
                                 consecutive_failures = 0; // Reset on success
                                 let mut count = blocks_mined.write().await;
@@ -338,22 +443,79 @@ async fn handle_get_block(state: &AppState, params: &serde_json::Value) -> JsonR
         }
     };
 
+    let encoding = match ResultEncoding::from_params(params) {
+        Ok(encoding) => encoding,
+        Err(e) => return JsonRpcResponse::error(1, -32602, e),
+    };
+
     let blockchain = state.blockchain.read().await;
     
     if let Some(block) = blockchain.get_block_by_height(height) {
-        let block_info = BlockInfo {
-            height: block.index,
-            hash: block.hash.clone(),
-            timestamp: block.timestamp,
-            transactions: block.transactions.len(),
-            difficulty: block.difficulty as u64,
-        };
-        JsonRpcResponse::success(1, serde_json::to_value(block_info).unwrap())
+        match encoding.encode_raw(&block) {
+            None => JsonRpcResponse::success(1, serde_json::to_value(BlockInfo::from(&block)).unwrap()),
+            Some(Ok(data)) => JsonRpcResponse::success(1, serde_json::json!({ "encoding": encoding, "data": data })),
+            Some(Err(e)) => JsonRpcResponse::error(1, -32603, e),
+        }
     } else {
         JsonRpcResponse::error(1, -32000, format!("Block not found at height {}", height))
     }
 }
 
+/// Largest JSON `get_transaction` result; JSON spells each signature/key/code byte as
+/// a number, so big deploys must be fetched raw (hex/base64) instead
+const MAX_JSON_TRANSACTION_BYTES: usize = 256 * 1024;
+
+/// Full signed transaction by hash (signature and public key included, so the
+/// result can be re-broadcast or verified independently)
+async fn handle_get_transaction(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let hash = match params.get("hash").and_then(|v| v.as_str()) {
+        Some(hash) => hash,
+        None => {
+            return JsonRpcResponse::error(
+                1,
+                -32602,
+                "Invalid params: hash required".to_string(),
+            )
+        }
+    };
+    let encoding = match ResultEncoding::from_params(params) {
+        Ok(encoding) => encoding,
+        Err(e) => return JsonRpcResponse::error(1, -32602, e),
+    };
+
+    // Mempool first, then the chain's transaction index (block_height null = pending)
+    let found = {
+        let blockchain = state.blockchain.read().await;
+        let pending = blockchain.get_pending_transactions().iter().find(|tx| tx.hash() == hash).cloned();
+        pending
+            .map(|tx| (tx, None))
+            .or_else(|| blockchain.get_transaction(hash).map(|(tx, height)| (tx, Some(height))))
+    };
+    let Some((tx, block_height)) = found else {
+        return JsonRpcResponse::error(1, -32000, format!("Transaction not found: {}", hash));
+    };
+
+    match encoding.encode_raw(&tx) {
+        None => {
+            let result = serde_json::json!({ "transaction": TransactionView::from(tx), "block_height": block_height });
+            let size = serde_json::to_vec(&result).map(|bytes| bytes.len()).unwrap_or(usize::MAX);
+            if size > MAX_JSON_TRANSACTION_BYTES {
+                return JsonRpcResponse::error(
+                    1,
+                    -32000,
+                    format!("Transaction too large for a json result ({} bytes); request encoding hex or base64", size),
+                );
+            }
+            JsonRpcResponse::success(1, result)
+        }
+        Some(Ok(data)) => JsonRpcResponse::success(
+            1,
+            serde_json::json!({ "encoding": encoding, "data": data, "block_height": block_height }),
+        ),
+        Some(Err(e)) => JsonRpcResponse::error(1, -32603, e),
+    }
+}
+
 async fn handle_get_balance(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
     let address = match params.get("address").and_then(|v| v.as_str()) {
         Some(addr) => addr,
@@ -374,7 +536,33 @@ async fn handle_get_balance(state: &AppState, params: &serde_json::Value) -> Jso
         serde_json::json!({
             "address": address,
             "balance": balance,
-            "balance_qua": balance as f64 / 1_000_000.0
+            "balance_qua": microunits_to_qua(balance),
+            "locked_microunits": blockchain.get_locked_balance(address),
+            "unlock_height": blockchain.get_unlock_height(address),
+        }),
+    )
+}
+
+async fn handle_get_next_nonce(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let address = match params.get("address").and_then(|v| v.as_str()) {
+        Some(addr) => addr,
+        None => {
+            return JsonRpcResponse::error(
+                1,
+                -32602,
+                "Invalid params: address required".to_string(),
+            )
+        }
+    };
+
+    let blockchain = state.blockchain.read().await;
+    let next_nonce = blockchain.get_next_nonce(address);
+
+    JsonRpcResponse::success(
+        1,
+        serde_json::json!({
+            "address": address,
+            "next_nonce": next_nonce
         }),
     )
 }
@@ -382,12 +570,18 @@ async fn handle_get_balance(state: &AppState, params: &serde_json::Value) -> Jso
 async fn handle_get_peers(state: &AppState) -> JsonRpcResponse {
     if let Some(ref network) = state.network {
         let peers = network.get_peers_info().await;
+        let now = chrono::Utc::now().timestamp();
         let peer_infos: Vec<PeerInfo> = peers
             .iter()
             .map(|p| PeerInfo {
                 address: p.address.to_string(),
-                connected_since: p.connected_at,
-                last_seen: p.last_seen,
+                connected_since: clamp_to_past(p.connected_at, now),
+                last_seen: clamp_to_past(p.last_seen, now),
+                direction: p.direction,
+                bytes_sent: p.bytes_sent,
+                bytes_received: p.bytes_received,
+                encrypted: p.remote_static_key.is_some(),
+                capabilities: p.capabilities.names().into_iter().map(String::from).collect(),
             })
             .collect();
         JsonRpcResponse::success(1, serde_json::to_value(peer_infos).unwrap())
@@ -396,9 +590,89 @@ async fn handle_get_peers(state: &AppState) -> JsonRpcResponse {
     }
 }
 
-async fn handle_get_mempool(state: &AppState) -> JsonRpcResponse {
+/// Default operator ban: 24 hours
+const DEFAULT_BAN_SECS: i64 = 86_400;
+
+/// Parse the `addr` param and require a running network (Err: code, message)
+fn admin_peer_target<'a>(state: &'a AppState, params: &serde_json::Value) -> Result<(&'a Arc<Network>, SocketAddr), (i32, String)> {
+    let addr = params
+        .get("addr")
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse::<SocketAddr>().ok())
+        .ok_or((-32602, "Invalid params: addr (ip:port) required".to_string()))?;
+    let network = state.network.as_ref().ok_or((-32000, "Network disabled".to_string()))?;
+    Ok((network, addr))
+}
+
+async fn handle_add_peer(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let (network, addr) = match admin_peer_target(state, params) {
+        Ok(target) => target,
+        Err((code, message)) => return JsonRpcResponse::error(1, code, message),
+    };
+    match network.add_peer(addr).await {
+        Ok(()) => JsonRpcResponse::success(1, serde_json::json!({ "connected": addr.to_string() })),
+        Err(e) => JsonRpcResponse::error(1, -32000, format!("Failed to add peer {}: {}", addr, e)),
+    }
+}
+
+async fn handle_remove_peer(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let (network, addr) = match admin_peer_target(state, params) {
+        Ok(target) => target,
+        Err((code, message)) => return JsonRpcResponse::error(1, code, message),
+    };
+    let removed = network.remove_peer(addr).await;
+    JsonRpcResponse::success(1, serde_json::json!({ "removed": removed }))
+}
+
+async fn handle_ban_peer(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let (network, addr) = match admin_peer_target(state, params) {
+        Ok(target) => target,
+        Err((code, message)) => return JsonRpcResponse::error(1, code, message),
+    };
+    let duration = params.get("duration").and_then(|v| v.as_i64()).unwrap_or(DEFAULT_BAN_SECS);
+    if duration <= 0 {
+        return JsonRpcResponse::error(1, -32602, "Invalid params: duration must be > 0 seconds".to_string());
+    }
+    network.ban_peer(addr, duration).await;
+    JsonRpcResponse::success(1, serde_json::json!({ "banned": addr.to_string(), "duration": duration }))
+}
+
+fn handle_sign_message(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let Some(ref node_key) = state.node_key else {
+        return JsonRpcResponse::error(1, -32000, "No node key loaded (start the node with --node-key)".to_string());
+    };
+    let message = match params.get("message").and_then(|v| v.as_str()).map(hex::decode) {
+        Some(Ok(message)) => message,
+        _ => return JsonRpcResponse::error(1, -32602, "Invalid params: message (hex) required".to_string()),
+    };
+    match node_key.sign(&message) {
+        Ok(signature) => JsonRpcResponse::success(1, serde_json::json!({
+            "address": node_key.address(),
+            "public_key": hex::encode(node_key.public_key()),
+            "signature": hex::encode(signature),
+        })),
+        Err(e) => JsonRpcResponse::error(1, -32000, format!("Signing failed: {}", e)),
+    }
+}
+
+async fn handle_get_mempool(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let encoding = match ResultEncoding::from_params(params) {
+        Ok(encoding) => encoding,
+        Err(e) => return JsonRpcResponse::error(1, -32602, e),
+    };
     let blockchain = state.blockchain.read().await;
     let transactions = blockchain.get_pending_transactions();
+
+    if encoding != ResultEncoding::Json {
+        let raw: Result<Vec<String>, String> = transactions
+            .iter()
+            .filter_map(|tx| encoding.encode_raw(tx))
+            .collect();
+        return match raw {
+            Ok(raw) => JsonRpcResponse::success(1, serde_json::json!({ "encoding": encoding, "transactions": raw })),
+            Err(e) => JsonRpcResponse::error(1, -32603, e),
+        };
+    }
     
     let tx_data: Vec<serde_json::Value> = transactions
         .iter()
@@ -417,6 +691,126 @@ async fn handle_get_mempool(state: &AppState) -> JsonRpcResponse {
     JsonRpcResponse::success(1, serde_json::json!({ "transactions": tx_data }))
 }
 
+async fn handle_get_mempool_info(state: &AppState) -> JsonRpcResponse {
+    let blockchain = state.blockchain.read().await;
+    let info = blockchain.mempool_info();
+
+    match serde_json::to_value(info) {
+        Ok(value) => JsonRpcResponse::success(1, value),
+        Err(e) => JsonRpcResponse::error(1, -32603, format!("Serialization error: {}", e)),
+    }
+}
+
+async fn handle_get_fee_histogram(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let boundaries = match params.get("boundaries") {
+        Some(value) => match serde_json::from_value::<Vec<f64>>(value.clone()) {
+            Ok(boundaries) => boundaries,
+            Err(_) => return JsonRpcResponse::error(1, -32602, "boundaries must be an array of numbers".to_string()),
+        },
+        None => DEFAULT_FEE_HISTOGRAM_BOUNDARIES.to_vec(),
+    };
+    if let Err(e) = FeeHistogram::check_boundaries(&boundaries) {
+        return JsonRpcResponse::error(1, -32602, e);
+    }
+
+    let histogram = state.blockchain.read().await.fee_histogram(&boundaries);
+    match serde_json::to_value(histogram) {
+        Ok(value) => JsonRpcResponse::success(1, value),
+        Err(e) => JsonRpcResponse::error(1, -32603, format!("Serialization error: {}", e)),
+    }
+}
+
+async fn handle_estimate_fee(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let target_blocks = match params.get("target") {
+        Some(value) => match value.as_u64().and_then(|target| u32::try_from(target).ok()) {
+            // 0 asks for the next block, like 1 (as on the REST endpoint)
+            Some(target) => target.max(1),
+            None => return JsonRpcResponse::error(1, -32602, "target must be a number of blocks".to_string()),
+        },
+        None => 1,
+    };
+
+    let fee = state.blockchain.read().await.estimate_fee(target_blocks);
+    JsonRpcResponse::success(1, serde_json::json!({ "target_blocks": target_blocks, "fee_microunits": fee }))
+}
+
+async fn handle_preview_next_block(state: &AppState) -> JsonRpcResponse {
+    let preview = state.blockchain.read().await.preview_next_block();
+    let transactions: Vec<serde_json::Value> = preview
+        .transactions
+        .iter()
+        .map(|tx| {
+            serde_json::json!({
+                "hash": tx.hash(),
+                "sender": tx.sender,
+                "recipient": tx.recipient,
+                "amount": tx.amount,
+                "fee": tx.fee,
+                "nonce": tx.nonce,
+            })
+        })
+        .collect();
+
+    JsonRpcResponse::success(1, serde_json::json!({
+        "height": preview.height,
+        "tx_count": transactions.len(),
+        "total_fees": preview.total_fees,
+        "transactions": transactions,
+    }))
+}
+
+async fn handle_get_supply(state: &AppState) -> JsonRpcResponse {
+    let blockchain = state.blockchain.read().await;
+    let supply = blockchain.get_supply();
+
+    match serde_json::to_value(supply) {
+        Ok(value) => JsonRpcResponse::success(1, value),
+        Err(e) => JsonRpcResponse::error(1, -32603, format!("Serialization error: {}", e)),
+    }
+}
+
+async fn handle_get_account_state(state: &AppState, params: &serde_json::Value) -> JsonRpcResponse {
+    let after = match params.get("after") {
+        None | Some(serde_json::Value::Null) => None,
+        Some(serde_json::Value::String(address)) => Some(address.clone()),
+        Some(_) => return JsonRpcResponse::error(1, -32602, "Invalid params: after must be an address".to_string()),
+    };
+    let limit = match params.get("limit") {
+        None => DEFAULT_ACCOUNT_PAGE_SIZE,
+        Some(value) => match value.as_u64() {
+            Some(limit) if limit > 0 && limit as usize <= MAX_ACCOUNT_PAGE_SIZE => limit as usize,
+            _ => {
+                return JsonRpcResponse::error(
+                    1,
+                    -32602,
+                    format!("Invalid params: limit must be between 1 and {}", MAX_ACCOUNT_PAGE_SIZE),
+                )
+            }
+        },
+    };
+
+    {
+        let mut window = state.account_dump_window.lock();
+        if window.0.elapsed().as_secs() >= 60 {
+            *window = (Instant::now(), 0);
+        }
+        if window.1 >= ACCOUNT_DUMP_PAGES_PER_MINUTE {
+            return JsonRpcResponse::error(
+                1,
+                -32005,
+                format!("Rate limited: at most {} get_account_state pages per minute", ACCOUNT_DUMP_PAGES_PER_MINUTE),
+            );
+        }
+        window.1 += 1;
+    }
+
+    let page = state.blockchain.read().await.account_state_page(after.as_deref(), limit);
+    match serde_json::to_value(page) {
+        Ok(value) => JsonRpcResponse::success(1, value),
+        Err(e) => JsonRpcResponse::error(1, -32603, format!("Serialization error: {}", e)),
+    }
+}
+
 async fn handle_shutdown(state: &AppState) -> JsonRpcResponse {
     tracing::info!("Shutdown requested via RPC");
     
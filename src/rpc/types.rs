@@ -1,3 +1,11 @@
+use crate::core::block::{Block, Target};
+use crate::core::ChainNetwork;
+use crate::crypto::HashAlgorithm;
+use crate::network::protocol::PROTOCOL_VERSION;
+use crate::network::peer::PeerDirection;
+use base64::Engine;
+#[cfg(test)]
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -37,6 +45,41 @@ pub struct NodeStatus {
     pub rpc_port: u16,
     pub uptime_seconds: u64,
     pub version: String,
+    pub protocol_version: u32,
+    pub network: ChainNetwork,
+}
+
+/// Software/protocol version and capabilities (clients use this for compatibility checks)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionInfo {
+    pub version: String,
+    pub protocol_version: u32,
+    pub network: ChainNetwork,
+    pub features: Vec<String>,
+    /// Hash function behind block, transaction and merkle hashes
+    #[serde(default)]
+    pub hash_algorithm: HashAlgorithm,
+}
+
+impl VersionInfo {
+    pub fn new(network: ChainNetwork, contracts_enabled: bool) -> Self {
+        let mut features = vec![
+            "falcon512-signatures".to_string(),
+            "fee-rate-floor".to_string(),
+            "mempool-pagination".to_string(),
+            "stats-since".to_string(),
+        ];
+        if contracts_enabled {
+            features.push("contracts".to_string());
+        }
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            protocol_version: PROTOCOL_VERSION,
+            network,
+            features,
+            hash_algorithm: HashAlgorithm::CONSENSUS,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,24 +88,121 @@ pub struct MiningStatus {
     pub mining_address: Option<String>,
     pub last_block_time: Option<i64>,
     pub blocks_mined: u64,
-    pub difficulty: u64,
+    pub difficulty: u64, // Leading zero hex digits (display); `target` is the exact threshold
+    /// Threshold the next block's hash must not exceed (64 hex chars)
+    pub target: Target,
     pub mining_reward: u64,
+    /// Average seconds between recent blocks (None on a chain too young to measure)
+    #[serde(default)]
+    pub avg_block_time_secs: Option<f64>,
+    /// Smoothed schedule offset: blocks ahead (+) or behind (-) the target block time
+    #[serde(default)]
+    pub blocks_ahead_of_target: Option<f64>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Outcome of comparing block hashes between this node and a peer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChainComparison {
+    pub local_height: u64,
+    pub peer_height: u64,
+    /// Lowest height whose hashes differ (None: one chain is a prefix of the other)
+    pub first_divergence: Option<u64>,
+}
+
+/// Encoding of block/transaction results (`encoding` param of get_block, get_transaction
+/// and get_mempool). `hex` and `base64` carry the raw bincode bytes, the same pinned
+/// serialization peers exchange, for integrators who prefer compactness over readability.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ResultEncoding {
+    #[default]
+    Json,
+    Hex,
+    Base64,
+}
+
+impl ResultEncoding {
+    /// Read the optional `encoding` param (absent = json)
+    pub fn from_params(params: &serde_json::Value) -> Result<Self, String> {
+        match params.get("encoding") {
+            None | Some(serde_json::Value::Null) => Ok(Self::Json),
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|_| format!("Invalid params: unknown encoding {} (json, hex or base64)", value)),
+        }
+    }
+
+    /// Raw bincode bytes of `value` in this encoding (None for json)
+    pub fn encode_raw<T: Serialize>(self, value: &T) -> Option<Result<String, String>> {
+        let bytes = || bincode::serialize(value).map_err(|e| format!("Serialization error: {}", e));
+        match self {
+            Self::Json => None,
+            Self::Hex => Some(bytes().map(hex::encode)),
+            Self::Base64 => Some(bytes().map(|b| base64::engine::general_purpose::STANDARD.encode(b))),
+        }
+    }
+
+    /// Decode a value produced by `encode_raw`
+    #[cfg(test)]
+    pub fn decode_raw<T: DeserializeOwned>(self, data: &str) -> Result<T, String> {
+        let bytes = match self {
+            Self::Json => return Err("json results are not raw-encoded".to_string()),
+            Self::Hex => hex::decode(data).map_err(|e| e.to_string())?,
+            Self::Base64 => base64::engine::general_purpose::STANDARD.decode(data).map_err(|e| e.to_string())?,
+        };
+        bincode::deserialize(&bytes).map_err(|e| format!("Deserialization error: {}", e))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct BlockInfo {
     pub height: u64,
     pub hash: String,
     pub timestamp: i64,
     pub transactions: usize,
     pub difficulty: u64,
+    pub target: Target, // Exact threshold for this block's hash (see Target)
+    pub merkle_root: String,
+    pub nonce: u64,
+    pub total_fees: u64,      // microunits, non-coinbase txs
+    pub coinbase_reward: u64, // microunits paid to the miner by the coinbase tx
+    pub size_bytes: usize,    // serialized (bincode) block size
+}
+
+impl From<&Block> for BlockInfo {
+    fn from(block: &Block) -> Self {
+        Self {
+            height: block.index,
+            hash: block.hash.clone(),
+            timestamp: block.timestamp,
+            transactions: block.transactions.len(),
+            difficulty: block.difficulty as u64,
+            target: block.target(),
+            merkle_root: block.merkle_root.clone(),
+            nonce: block.nonce,
+            total_fees: block.get_total_fees(),
+            coinbase_reward: block
+                .transactions
+                .iter()
+                .find(|tx| tx.is_coinbase())
+                .map(|tx| tx.amount)
+                .unwrap_or(0),
+            size_bytes: bincode::serialized_size(block).map(|s| s as usize).unwrap_or(0),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PeerInfo {
     pub address: String,
-    pub connected_since: i64,
-    pub last_seen: i64,
+    pub connected_since: u64,
+    pub last_seen: u64,
+    pub direction: PeerDirection,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    #[serde(default)]
+    pub encrypted: bool,
+    #[serde(default)]
+    pub capabilities: Vec<String>,
 }
 
 impl JsonRpcResponse {
@@ -88,3 +228,38 @@ impl JsonRpcResponse {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transaction::Transaction;
+
+    #[test]
+    fn block_info_totals_non_coinbase_fees() {
+        let mut coinbase = Transaction::new(
+            "COINBASE".to_string(),
+            "0x1111111111111111111111111111111111111111".to_string(),
+            50_000_000,
+            1_700_000_000,
+        );
+        coinbase.fee = 0;
+        let mut txs = vec![coinbase];
+        for fee in [1_000, 2_500, 400] {
+            let mut tx = Transaction::new(
+                "0x2222222222222222222222222222222222222222".to_string(),
+                "0x3333333333333333333333333333333333333333".to_string(),
+                10,
+                1_700_000_000,
+            );
+            tx.fee = fee;
+            txs.push(tx);
+        }
+        let block = Block::new(1, txs, "ab".repeat(32), 1);
+
+        let info = BlockInfo::from(&block);
+        assert_eq!(info.total_fees, 3_900);
+        assert_eq!(info.coinbase_reward, 50_000_000);
+        assert_eq!(info.merkle_root, block.merkle_root);
+        assert_eq!(info.size_bytes, bincode::serialize(&block).unwrap().len());
+    }
+}
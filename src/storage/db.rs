@@ -1,8 +1,24 @@
 use sled::Db;
 use crate::core::block::Block;
 use crate::core::transaction::AccountState;
+use crate::core::snapshot::StateSnapshot;
+use crate::crypto::HashAlgorithm;
+use argon2::Argon2;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Nonce};
+use rand::RngCore;
 use std::path::Path;
 use thiserror::Error;
+use zeroize::Zeroizing;
+
+/// Argon2 salt for the database key (stored in the clear; the passphrase never is)
+const ENCRYPTION_SALT_KEY: &[u8] = b"encryption_salt";
+/// Known value sealed with the database key, so a wrong passphrase fails at open
+const ENCRYPTION_CHECK_KEY: &[u8] = b"encryption_check";
+const ENCRYPTION_CHECK_VALUE: &[u8] = b"quanta-db";
+const NONCE_LEN: usize = 12;
+/// Id of the hash algorithm the stored chain was built with (see HashAlgorithm::id)
+const HASH_ALGORITHM_KEY: &[u8] = b"hash_algorithm";
 
 #[derive(Error, Debug)]
 pub enum StorageError {
@@ -12,26 +28,149 @@ pub enum StorageError {
     Serialization(#[from] serde_json::Error),
     #[error("Block not found: {0}")]
     BlockNotFound(u64),
+    #[error("Stored chain height {stored} does not match {found} blocks on disk (set node.repair_chain_height = true to repair)")]
+    HeightMismatch { stored: u64, found: u64 },
+    #[error("Database is encrypted (set node.encrypt_db = true and supply the passphrase)")]
+    Encrypted,
+    #[error("Database holds unencrypted data; encryption can only be enabled on a new data directory")]
+    NotEncrypted,
+    #[error("Could not decrypt the database (wrong passphrase or corrupted data)")]
+    Decryption,
+    #[error("Database encryption failed")]
+    Encryption,
+    #[error("Database was built with hash algorithm id {0}, which this node does not support")]
+    UnsupportedHashAlgorithm(u8),
 }
 
 /// Persistent storage for blockchain data
+///
+/// Values can be encrypted at rest with ChaCha20-Poly1305 (see `open_encrypted`).
+/// Keys stay in the clear, so the layout (block count) is visible but not the data.
 pub struct BlockchainStorage {
     db: Db,
+    cipher: Option<ChaCha20Poly1305>, // Derived from the passphrase at open; never written to disk
 }
 
 impl BlockchainStorage {
     /// Open or create blockchain database
     pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, StorageError> {
         let db = sled::open(path)?;
+        if db.contains_key(ENCRYPTION_SALT_KEY)? {
+            return Err(StorageError::Encrypted);
+        }
+        Self::check_hash_algorithm(&db)?;
         tracing::info!("Blockchain database opened");
-        Ok(Self { db })
+        Ok(Self { db, cipher: None })
+    }
+
+    /// Open or create a database whose values are encrypted with a key derived from
+    /// `passphrase` (Argon2). A new database gets a random salt; an existing unencrypted
+    /// one is refused rather than left half-encrypted
+    pub fn open_encrypted<P: AsRef<Path>>(path: P, passphrase: &str) -> Result<Self, StorageError> {
+        let db = sled::open(path)?;
+        let salt = match db.get(ENCRYPTION_SALT_KEY)? {
+            Some(salt) => salt.to_vec(),
+            None if !db.is_empty() => return Err(StorageError::NotEncrypted),
+            None => {
+                let mut salt = vec![0u8; 16];
+                OsRng.fill_bytes(&mut salt);
+                salt
+            }
+        };
+
+        let mut key = Zeroizing::new([0u8; 32]);
+        Argon2::default()
+            .hash_password_into(passphrase.as_bytes(), &salt, &mut *key)
+            .map_err(|_| StorageError::Encryption)?;
+        let storage = Self {
+            cipher: Some(ChaCha20Poly1305::new_from_slice(&*key).map_err(|_| StorageError::Encryption)?),
+            db,
+        };
+
+        match storage.db.get(ENCRYPTION_CHECK_KEY)? {
+            Some(check) => {
+                if storage.open_value(ENCRYPTION_CHECK_KEY, &check)? != ENCRYPTION_CHECK_VALUE {
+                    return Err(StorageError::Decryption);
+                }
+            }
+            None => {
+                let check = storage.seal(ENCRYPTION_CHECK_KEY, ENCRYPTION_CHECK_VALUE.to_vec())?;
+                storage.db.insert(ENCRYPTION_SALT_KEY, salt)?;
+                storage.db.insert(ENCRYPTION_CHECK_KEY, check)?;
+                storage.db.flush()?;
+            }
+        }
+        Self::check_hash_algorithm(&storage.db)?;
+        tracing::info!("Encrypted blockchain database opened");
+        Ok(storage)
+    }
+
+    /// Refuse a database recorded with another hash algorithm (databases without the
+    /// record are SHA3-256, the only algorithm there has been). Opening never writes it,
+    /// so an untouched database stays empty; see `record_hash_algorithm`
+    fn check_hash_algorithm(db: &Db) -> Result<(), StorageError> {
+        match db.get(HASH_ALGORITHM_KEY)? {
+            Some(id) => {
+                let id = id.first().copied().unwrap_or(0);
+                match HashAlgorithm::from_id(id) {
+                    Some(HashAlgorithm::CONSENSUS) => Ok(()),
+                    _ => Err(StorageError::UnsupportedHashAlgorithm(id)),
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Record the hash algorithm once the database holds a chain
+    fn record_hash_algorithm(&self) -> Result<(), StorageError> {
+        if !self.db.contains_key(HASH_ALGORITHM_KEY)? {
+            self.db.insert(HASH_ALGORITHM_KEY, &[HashAlgorithm::CONSENSUS.id()])?;
+        }
+        Ok(())
+    }
+
+    /// Encrypt `value` for `key` (nonce || ciphertext); the key is authenticated too,
+    /// so a value can't be moved under another key. Plaintext databases store it as is
+    fn seal(&self, key: &[u8], value: Vec<u8>) -> Result<Vec<u8>, StorageError> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(value);
+        };
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(&nonce, Payload { msg: &value, aad: key })
+            .map_err(|_| StorageError::Encryption)?;
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Reverse of `seal`
+    fn open_value(&self, key: &[u8], value: &[u8]) -> Result<Vec<u8>, StorageError> {
+        let Some(cipher) = &self.cipher else {
+            return Ok(value.to_vec());
+        };
+        if value.len() < NONCE_LEN {
+            return Err(StorageError::Decryption);
+        }
+        let (nonce, ciphertext) = value.split_at(NONCE_LEN);
+        cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: key })
+            .map_err(|_| StorageError::Decryption)
+    }
+
+    fn put(&self, key: &[u8], value: Vec<u8>) -> Result<(), StorageError> {
+        self.db.insert(key, self.seal(key, value)?)?;
+        Ok(())
+    }
+
+    fn fetch(&self, key: &[u8]) -> Result<Option<Vec<u8>>, StorageError> {
+        self.db.get(key)?.map(|value| self.open_value(key, &value)).transpose()
     }
 
     /// Save a block to disk
     pub fn save_block(&self, block: &Block) -> Result<(), StorageError> {
         let key = format!("block:{}", block.index);
-        let value = serde_json::to_vec(block)?;
-        self.db.insert(key.as_bytes(), value)?;
+        self.put(key.as_bytes(), serde_json::to_vec(block)?)?;
         self.db.flush()?;
         tracing::debug!("Block {} saved to database", block.index);
         Ok(())
@@ -40,7 +179,7 @@ impl BlockchainStorage {
     /// Load a block from disk
     pub fn load_block(&self, index: u64) -> Result<Block, StorageError> {
         let key = format!("block:{}", index);
-        let value = self.db.get(key.as_bytes())?
+        let value = self.fetch(key.as_bytes())?
             .ok_or(StorageError::BlockNotFound(index))?;
         let block: Block = serde_json::from_slice(&value)?;
         Ok(block)
@@ -49,8 +188,8 @@ impl BlockchainStorage {
     /// Get the height of the blockchain (number of blocks)
     pub fn get_chain_height(&self) -> Result<u64, StorageError> {
         let height_key = b"chain_height";
-        if let Some(value) = self.db.get(height_key)? {
-            let height_bytes: [u8; 8] = value.as_ref().try_into()
+        if let Some(value) = self.fetch(height_key)? {
+            let height_bytes: [u8; 8] = value.as_slice().try_into()
                 .map_err(|_| StorageError::Database(sled::Error::Unsupported("Invalid height data".into())))?;
             Ok(u64::from_be_bytes(height_bytes))
         } else {
@@ -61,15 +200,35 @@ impl BlockchainStorage {
     /// Update the chain height
     pub fn set_chain_height(&self, height: u64) -> Result<(), StorageError> {
         let height_key = b"chain_height";
-        self.db.insert(height_key, &height.to_be_bytes())?;
+        self.put(height_key, height.to_be_bytes().to_vec())?;
+        self.record_hash_algorithm()
+    }
+
+    /// Replace the blocks from height `fork_height` on with `blocks` (a chain reorganization),
+    /// along with the chain height and account state, in one atomic batch
+    pub fn replace_chain_suffix(&self, fork_height: u64, blocks: &[Block], account_state: &AccountState) -> Result<(), StorageError> {
+        let old_height = self.get_chain_height()?;
+        let new_height = fork_height + blocks.len() as u64;
+        let mut batch = sled::Batch::default();
+        for index in new_height..old_height {
+            batch.remove(format!("block:{}", index).as_bytes());
+        }
+        for block in blocks {
+            let key = format!("block:{}", block.index);
+            batch.insert(key.as_bytes(), self.seal(key.as_bytes(), serde_json::to_vec(block)?)?);
+        }
+        batch.insert(&b"chain_height"[..], self.seal(b"chain_height", new_height.to_be_bytes().to_vec())?);
+        batch.insert(&b"account_state"[..], self.seal(b"account_state", serde_json::to_vec(account_state)?)?);
+        self.db.apply_batch(batch)?;
+        self.db.flush()?;
+        tracing::debug!("Replaced blocks {}..{} with {} block(s)", fork_height, old_height, blocks.len());
         Ok(())
     }
 
     /// Save account state (formerly "UTXO set")
     pub fn save_account_state(&self, account_state: &AccountState) -> Result<(), StorageError> {
         let key = b"account_state";
-        let value = serde_json::to_vec(account_state)?;
-        self.db.insert(key, value)?;
+        self.put(key, serde_json::to_vec(account_state)?)?;
         self.db.flush()?;
         tracing::debug!("Account state saved to database");
         Ok(())
@@ -78,7 +237,7 @@ impl BlockchainStorage {
     /// Load account state (formerly "UTXO set")
     pub fn load_account_state(&self) -> Result<Option<AccountState>, StorageError> {
         let key = b"account_state";
-        if let Some(value) = self.db.get(key)? {
+        if let Some(value) = self.fetch(key)? {
             let account_state: AccountState = serde_json::from_slice(&value)?;
             Ok(Some(account_state))
         } else {
@@ -86,6 +245,22 @@ impl BlockchainStorage {
         }
     }
 
+    /// Save a verified state snapshot (replaces any previous one)
+    pub fn save_snapshot(&self, snapshot: &StateSnapshot) -> Result<(), StorageError> {
+        self.put(b"state_snapshot", serde_json::to_vec(snapshot)?)?;
+        self.db.flush()?;
+        tracing::debug!("State snapshot at height {} saved to database", snapshot.height);
+        Ok(())
+    }
+
+    /// Load the stored state snapshot, if any
+    pub fn load_snapshot(&self) -> Result<Option<StateSnapshot>, StorageError> {
+        match self.fetch(b"state_snapshot")? {
+            Some(value) => Ok(Some(serde_json::from_slice(&value)?)),
+            None => Ok(None),
+        }
+    }
+
     /// Load entire blockchain from disk
     pub fn load_chain(&self) -> Result<Vec<Block>, StorageError> {
         let height = self.get_chain_height()?;
@@ -105,12 +280,122 @@ impl BlockchainStorage {
         Ok(chain)
     }
 
+    /// Compare the stored chain height with the contiguous blocks on disk
+    ///
+    /// A crash between save_block and set_chain_height leaves a block past the
+    /// stored height; a lost block leaves the height pointing past the chain.
+    /// With `repair`, stray blocks are dropped (account state was never updated
+    /// for them) and a height past the chain is lowered; otherwise the mismatch
+    /// is an error. Returns the (possibly repaired) height.
+    pub fn check_chain_height(&self, repair: bool) -> Result<u64, StorageError> {
+        let stored = self.get_chain_height()?;
+        let mut found = 0;
+        while self.db.contains_key(format!("block:{}", found).as_bytes())? {
+            found += 1;
+        }
+        if found == stored {
+            return Ok(stored);
+        }
+        if !repair {
+            return Err(StorageError::HeightMismatch { stored, found });
+        }
+
+        if found > stored {
+            tracing::warn!("Dropping {} uncommitted block(s) past stored height {}", found - stored, stored);
+            for index in stored..found {
+                self.db.remove(format!("block:{}", index).as_bytes())?;
+            }
+        } else {
+            tracing::warn!(
+                "Stored height {} points past the last block on disk; lowering to {} (account state may need a resync)",
+                stored, found
+            );
+            self.set_chain_height(found)?;
+        }
+        self.db.flush()?;
+        Ok(found.min(stored))
+    }
+
     /// Clear all data (use with caution!)
     pub fn clear(&self) -> Result<(), StorageError> {
+        // An encrypted database stays encrypted under the same key
+        let salt = self.db.get(ENCRYPTION_SALT_KEY)?;
+        let check = self.db.get(ENCRYPTION_CHECK_KEY)?;
+        let algorithm = self.db.get(HASH_ALGORITHM_KEY)?;
         self.db.clear()?;
+        if let Some(algorithm) = algorithm {
+            self.db.insert(HASH_ALGORITHM_KEY, algorithm)?;
+        }
+        if let (Some(salt), Some(check)) = (salt, check) {
+            self.db.insert(ENCRYPTION_SALT_KEY, salt)?;
+            self.db.insert(ENCRYPTION_CHECK_KEY, check)?;
+        }
         self.db.flush()?;
         tracing::warn!("Database cleared");
         Ok(())
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::transaction::Transaction;
+    use crate::core::ChainNetwork;
+
+    #[test]
+    fn encrypted_db_needs_its_passphrase() {
+        let dir = tempfile::tempdir().unwrap();
+        let address = "0x1111111111111111111111111111111111111111";
+        let genesis = Block::genesis(ChainNetwork::Testnet);
+        let mut state = AccountState::new();
+        state.credit_account(&Transaction::new("TREASURY".to_string(), address.to_string(), 5_000, 0), 0, 100);
+
+        let storage = BlockchainStorage::open_encrypted(dir.path(), "correct horse battery").unwrap();
+        storage.save_block(&genesis).unwrap();
+        storage.set_chain_height(1).unwrap();
+        storage.save_account_state(&state).unwrap();
+        drop(storage);
+
+        // Nothing readable on disk, and no way in without the right passphrase
+        let raw = sled::open(dir.path()).unwrap();
+        let stored_state = raw.get(b"account_state").unwrap().unwrap();
+        let stored_block = raw.get(b"block:0").unwrap().unwrap();
+        assert!(!stored_state.windows(address.len()).any(|w| w == address.as_bytes()));
+        assert!(!stored_block.windows(genesis.hash.len()).any(|w| w == genesis.hash.as_bytes()));
+        drop(raw);
+        assert!(matches!(BlockchainStorage::new(dir.path()), Err(StorageError::Encrypted)));
+        assert!(matches!(BlockchainStorage::open_encrypted(dir.path(), "wrong"), Err(StorageError::Decryption)));
+
+        let storage = BlockchainStorage::open_encrypted(dir.path(), "correct horse battery").unwrap();
+        assert_eq!(storage.load_chain().unwrap()[0].hash, genesis.hash);
+        assert_eq!(storage.load_account_state().unwrap().unwrap().get_balance(address), 5_000);
+        drop(storage);
+
+        // An existing plaintext database is not silently switched to encryption
+        let plain = tempfile::tempdir().unwrap();
+        BlockchainStorage::new(plain.path()).unwrap().save_block(&genesis).unwrap();
+        assert!(matches!(BlockchainStorage::open_encrypted(plain.path(), "pass"), Err(StorageError::NotEncrypted)));
+    }
+
+    #[test]
+    fn hash_algorithm_is_recorded_with_the_chain_not_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        drop(BlockchainStorage::new(dir.path()).unwrap());
+        assert!(sled::open(dir.path()).unwrap().is_empty());
+
+        // A plaintext open that wrote nothing leaves the directory free for encryption
+        let storage = BlockchainStorage::open_encrypted(dir.path(), "pass").unwrap();
+        storage.set_chain_height(1).unwrap();
+        drop(storage);
+        let raw = sled::open(dir.path()).unwrap();
+        assert_eq!(raw.get(HASH_ALGORITHM_KEY).unwrap().unwrap().as_ref(), [HashAlgorithm::CONSENSUS.id()]);
+
+        raw.insert(HASH_ALGORITHM_KEY, &[0xee]).unwrap();
+        drop(raw);
+        assert!(matches!(
+            BlockchainStorage::open_encrypted(dir.path(), "pass"),
+            Err(StorageError::UnsupportedHashAlgorithm(0xee))
+        ));
+    }
+}